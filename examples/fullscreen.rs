@@ -1,7 +1,7 @@
 extern crate winit;
 
 use std::io::{self, Write};
-use winit::{ControlFlow, Event, WindowEvent};
+use winit::{ControlFlow, Event, Fullscreen, WindowEvent};
 
 fn main() {
     let mut events_loop = winit::EventsLoop::new();
@@ -25,12 +25,45 @@ fn main() {
         monitor
     };
 
+    // choosing between borderless and exclusive fullscreen
+    let mut fullscreen = {
+        for (num, video_mode) in monitor.get_video_modes().enumerate() {
+            println!(
+                "Video mode #{}: {:?} {}-bit {}Hz",
+                num,
+                video_mode.get_dimensions(),
+                video_mode.get_bit_depth(),
+                video_mode.get_refresh_rate(),
+            );
+        }
+
+        print!("Please write the number of the video mode to use, or nothing for borderless: ");
+        io::stdout().flush().unwrap();
+
+        let mut num = String::new();
+        io::stdin().read_line(&mut num).unwrap();
+        match num.trim() {
+            "" => Fullscreen::Borderless(Some(monitor)),
+            num => {
+                let num = num.parse().ok().expect("Please enter a number");
+                let video_mode = monitor.get_video_modes().nth(num).expect("Please enter a valid ID");
+                Fullscreen::Exclusive(monitor, video_mode)
+            },
+        }
+    };
+
     let window = winit::WindowBuilder::new()
         .with_title("Hello world!")
-        .with_fullscreen(Some(monitor))
         .build(&events_loop)
         .unwrap();
 
+    // Not every platform can switch the video mode.
+    if let Err(err) = window.set_fullscreen(Some(fullscreen.clone())) {
+        println!("{}, falling back to borderless fullscreen", err);
+        fullscreen = Fullscreen::Borderless(fullscreen.get_monitor().cloned());
+        window.set_fullscreen(Some(fullscreen.clone())).unwrap();
+    }
+
     let mut is_fullscreen = true;
     let mut is_maximized = false;
     let mut decorations = true;
@@ -44,7 +77,7 @@ fn main() {
                 WindowEvent::KeyboardInput {
                     input:
                         winit::KeyboardInput {
-                            virtual_keycode: Some(virtual_code),
+                            logical_key: Some(winit::LogicalKey::Named(virtual_code)),
                             state,
                             ..
                         },
@@ -54,9 +87,9 @@ fn main() {
                     (winit::VirtualKeyCode::F, winit::ElementState::Pressed) => {
                         is_fullscreen = !is_fullscreen;
                         if !is_fullscreen {
-                            window.set_fullscreen(None);
+                            window.set_fullscreen(None).unwrap();
                         } else {
-                            window.set_fullscreen(Some(window.get_current_monitor()));
+                            window.set_fullscreen(Some(fullscreen.clone())).unwrap();
                         }
                     }
                     (winit::VirtualKeyCode::M, winit::ElementState::Pressed) => {
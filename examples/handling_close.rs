@@ -37,7 +37,7 @@ fn main() {
                 KeyboardInput {
                     input:
                         winit::KeyboardInput {
-                            virtual_keycode: Some(virtual_code),
+                            logical_key: Some(winit::LogicalKey::Named(virtual_code)),
                             state: Released,
                             ..
                         },
@@ -29,5 +29,5 @@ fn main() {
             _ => (),
         }
         winit::ControlFlow::Continue
-    })
+    });
 }
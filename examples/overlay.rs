@@ -0,0 +1,51 @@
+extern crate winit;
+
+fn main() {
+    let mut events_loop = winit::EventsLoop::new();
+
+    // An overlay is a borderless, transparent window that stays above all the others.
+    let window = winit::WindowBuilder::new()
+        .with_title("An overlay window")
+        .with_decorations(false)
+        .with_transparency(true)
+        .with_always_on_top(true)
+        .build(&events_loop)
+        .unwrap();
+
+    let mut always_on_top = true;
+
+    events_loop.run_forever(|event| {
+        println!("{:?}", event);
+
+        match event {
+            winit::Event::WindowEvent { event: winit::WindowEvent::CloseRequested, .. } => {
+                return winit::ControlFlow::Break;
+            },
+            // Toggling the other attributes mustn't clobber the overlay's stacking.
+            winit::Event::WindowEvent {
+                event: winit::WindowEvent::KeyboardInput {
+                    input: winit::KeyboardInput {
+                        state: winit::ElementState::Released,
+                        logical_key: Some(winit::LogicalKey::Named(key)),
+                        ..
+                    },
+                    ..
+                },
+                ..
+            } => match key {
+                winit::VirtualKeyCode::T => {
+                    always_on_top = !always_on_top;
+                    window.set_always_on_top(always_on_top).unwrap();
+                },
+                winit::VirtualKeyCode::F => {
+                    window.set_fullscreen(Some(winit::Fullscreen::Borderless(None))).unwrap();
+                },
+                winit::VirtualKeyCode::Escape => window.set_fullscreen(None).unwrap(),
+                _ => (),
+            },
+            _ => (),
+        }
+
+        winit::ControlFlow::Continue
+    });
+}
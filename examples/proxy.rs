@@ -1,7 +1,7 @@
 extern crate winit;
 
 fn main() {
-    let mut events_loop = winit::EventsLoop::new();
+    let mut events_loop = winit::EventsLoop::<u32>::new_user_event();
 
     let _window = winit::WindowBuilder::new()
         .with_title("A fantastic window!")
@@ -11,10 +11,10 @@ fn main() {
     let proxy = events_loop.create_proxy();
 
     std::thread::spawn(move || {
-        // Wake up the `events_loop` once every second.
-        loop {
+        // Send an event to the `events_loop` once every second.
+        for count in 0.. {
             std::thread::sleep(std::time::Duration::from_secs(1));
-            proxy.wakeup().unwrap();
+            proxy.send_event(count).unwrap();
         }
     });
 
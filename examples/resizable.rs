@@ -19,7 +19,7 @@ fn main() {
                 winit::WindowEvent::KeyboardInput {
                     input:
                         winit::KeyboardInput {
-                            virtual_keycode: Some(winit::VirtualKeyCode::Space),
+                            logical_key: Some(winit::LogicalKey::Named(winit::VirtualKeyCode::Space)),
                             state: winit::ElementState::Released,
                             ..
                         },
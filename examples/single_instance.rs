@@ -0,0 +1,28 @@
+extern crate winit;
+
+fn main() {
+    let mut events_loop = match winit::EventsLoop::new_single_instance("org.example.SingleInstance").unwrap() {
+        winit::SingleInstance::Primary(events_loop) => events_loop,
+        winit::SingleInstance::Secondary => {
+            println!("Already running, the arguments were sent to the first instance");
+            return;
+        },
+    };
+
+    let _window = winit::WindowBuilder::new()
+        .with_title("Launch this example again")
+        .build(&events_loop)
+        .unwrap();
+
+    events_loop.run_forever(|event| {
+        match event {
+            winit::Event::InstanceLaunched(args) => {
+                println!("Launched again with {:?}", args);
+                winit::ControlFlow::Continue
+            },
+            winit::Event::WindowEvent { event: winit::WindowEvent::CloseRequested, .. } =>
+                winit::ControlFlow::Break,
+            _ => winit::ControlFlow::Continue,
+        }
+    });
+}
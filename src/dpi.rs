@@ -54,7 +54,16 @@
 //! [`HiDpiFactorChanged`](../enum.WindowEvent.html#variant.HiDpiFactorChanged) if you're only listening for size.
 //!
 //! Your GPU has no awareness of the concept of logical pixels, and unless you like wasting pixel density, your
-//! framebuffer's size should be in physical pixels.
+//! framebuffer's size should be in physical pixels. Use
+//! [`Window::get_inner_size_physical`](../struct.Window.html#method.get_inner_size_physical) for that, which returns
+//! the size as reported by the system, rather than converting the logical size yourself. Logical sizes remain the
+//! right choice for laying out UI.
+//!
+//! Converting between logical and physical pixels generally produces fractional values, which have to be rounded
+//! before they can be used as a number of pixels. The `Into` implementations for integer tuples round to the
+//! nearest integer. When you need a different policy, for example to make sure a buffer covers the whole window,
+//! use `cast` with the [`Rounding`](enum.Rounding.html) you want, or `to_physical_rounded`/`to_physical_floored`
+//! to get a physical value that's already whole.
 
 /// Checks that the DPI factor is a normal positive `f64`.
 ///
@@ -66,12 +75,73 @@ pub fn validate_hidpi_factor(dpi_factor: f64) -> bool {
     dpi_factor.is_sign_positive() && dpi_factor.is_normal()
 }
 
+// Computes the dots-per-inch of a monitor from its resolution and physical size. Returns `None` if the physical
+// size is unknown, which is commonly reported as 0.
+pub(crate) fn calc_dpi((width_px, height_px): (u32, u32), (width_mm, height_mm): (f64, f64)) -> Option<f64> {
+    if width_mm <= 0.0 || height_mm <= 0.0 {
+        return None;
+    }
+    let ppmm = ((width_px as f64 * height_px as f64) / (width_mm * height_mm)).sqrt();
+    Some(ppmm * 25.4)
+}
+
+/// How a fractional number of pixels is turned into a whole one.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Rounding {
+    /// Rounds to the nearest integer, and away from zero when halfway between two integers.
+    Round,
+    /// Rounds down.
+    Floor,
+    /// Rounds up.
+    Ceil,
+}
+
+impl Rounding {
+    #[inline]
+    pub fn apply(self, pixels: f64) -> f64 {
+        match self {
+            Rounding::Round => pixels.round(),
+            Rounding::Floor => pixels.floor(),
+            Rounding::Ceil => pixels.ceil(),
+        }
+    }
+}
+
+/// A type that a number of pixels can be converted to.
+pub trait Pixel: Copy {
+    /// Converts `pixels` using `rounding`, which is ignored if `Self` can represent fractional pixels.
+    fn from_f64(pixels: f64, rounding: Rounding) -> Self;
+}
+
+impl Pixel for u32 {
+    /// Negative values become 0.
+    #[inline]
+    fn from_f64(pixels: f64, rounding: Rounding) -> Self {
+        rounding.apply(pixels).max(0.0) as u32
+    }
+}
+
+impl Pixel for i32 {
+    #[inline]
+    fn from_f64(pixels: f64, rounding: Rounding) -> Self {
+        rounding.apply(pixels) as i32
+    }
+}
+
+impl Pixel for f64 {
+    #[inline]
+    fn from_f64(pixels: f64, _rounding: Rounding) -> Self {
+        pixels
+    }
+}
+
 /// A position represented in logical pixels.
 ///
 /// The position is stored as floats, so please be careful. Casting floats to integers truncates the fractional part,
 /// which can cause noticable issues. To help with that, an `Into<(i32, i32)>` implementation is provided which
 /// does the rounding for you.
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct LogicalPosition {
     pub x: f64,
     pub y: f64,
@@ -95,6 +165,25 @@ impl LogicalPosition {
         let y = self.y * dpi_factor;
         PhysicalPosition::new(x, y)
     }
+
+    /// Same as `to_physical`, but rounds the result to the nearest physical pixel.
+    #[inline]
+    pub fn to_physical_rounded(&self, dpi_factor: f64) -> PhysicalPosition {
+        let (x, y) = self.to_physical(dpi_factor).cast(Rounding::Round);
+        PhysicalPosition::new(x, y)
+    }
+
+    /// Same as `to_physical`, but rounds the result down to a whole physical pixel.
+    #[inline]
+    pub fn to_physical_floored(&self, dpi_factor: f64) -> PhysicalPosition {
+        let (x, y) = self.to_physical(dpi_factor).cast(Rounding::Floor);
+        PhysicalPosition::new(x, y)
+    }
+
+    #[inline]
+    pub fn cast<P: Pixel>(&self, rounding: Rounding) -> (P, P) {
+        (P::from_f64(self.x, rounding), P::from_f64(self.y, rounding))
+    }
 }
 
 impl From<(f64, f64)> for LogicalPosition {
@@ -122,7 +211,7 @@ impl Into<(i32, i32)> for LogicalPosition {
     /// Note that this rounds instead of truncating.
     #[inline]
     fn into(self) -> (i32, i32) {
-        (self.x.round() as _, self.y.round() as _)
+        self.cast(Rounding::Round)
     }
 }
 
@@ -132,6 +221,7 @@ impl Into<(i32, i32)> for LogicalPosition {
 /// which can cause noticable issues. To help with that, an `Into<(i32, i32)>` implementation is provided which
 /// does the rounding for you.
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PhysicalPosition {
     pub x: f64,
     pub y: f64,
@@ -155,6 +245,11 @@ impl PhysicalPosition {
         let y = self.y / dpi_factor;
         LogicalPosition::new(x, y)
     }
+
+    #[inline]
+    pub fn cast<P: Pixel>(&self, rounding: Rounding) -> (P, P) {
+        (P::from_f64(self.x, rounding), P::from_f64(self.y, rounding))
+    }
 }
 
 impl From<(f64, f64)> for PhysicalPosition {
@@ -182,7 +277,7 @@ impl Into<(i32, i32)> for PhysicalPosition {
     /// Note that this rounds instead of truncating.
     #[inline]
     fn into(self) -> (i32, i32) {
-        (self.x.round() as _, self.y.round() as _)
+        self.cast(Rounding::Round)
     }
 }
 
@@ -192,6 +287,7 @@ impl Into<(i32, i32)> for PhysicalPosition {
 /// which can cause noticable issues. To help with that, an `Into<(u32, u32)>` implementation is provided which
 /// does the rounding for you.
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct LogicalSize {
     pub width: f64,
     pub height: f64,
@@ -215,6 +311,25 @@ impl LogicalSize {
         let height = self.height * dpi_factor;
         PhysicalSize::new(width, height)
     }
+
+    /// Same as `to_physical`, but rounds the result to the nearest physical pixel.
+    #[inline]
+    pub fn to_physical_rounded(&self, dpi_factor: f64) -> PhysicalSize {
+        let (width, height) = self.to_physical(dpi_factor).cast(Rounding::Round);
+        PhysicalSize::new(width, height)
+    }
+
+    /// Same as `to_physical`, but rounds the result down to a whole physical pixel.
+    #[inline]
+    pub fn to_physical_floored(&self, dpi_factor: f64) -> PhysicalSize {
+        let (width, height) = self.to_physical(dpi_factor).cast(Rounding::Floor);
+        PhysicalSize::new(width, height)
+    }
+
+    #[inline]
+    pub fn cast<P: Pixel>(&self, rounding: Rounding) -> (P, P) {
+        (P::from_f64(self.width, rounding), P::from_f64(self.height, rounding))
+    }
 }
 
 impl From<(f64, f64)> for LogicalSize {
@@ -242,7 +357,7 @@ impl Into<(u32, u32)> for LogicalSize {
     /// Note that this rounds instead of truncating.
     #[inline]
     fn into(self) -> (u32, u32) {
-        (self.width.round() as _, self.height.round() as _)
+        self.cast(Rounding::Round)
     }
 }
 
@@ -252,6 +367,7 @@ impl Into<(u32, u32)> for LogicalSize {
 /// which can cause noticable issues. To help with that, an `Into<(u32, u32)>` implementation is provided which
 /// does the rounding for you.
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PhysicalSize {
     pub width: f64,
     pub height: f64,
@@ -275,6 +391,11 @@ impl PhysicalSize {
         let height = self.height / dpi_factor;
         LogicalSize::new(width, height)
     }
+
+    #[inline]
+    pub fn cast<P: Pixel>(&self, rounding: Rounding) -> (P, P) {
+        (P::from_f64(self.width, rounding), P::from_f64(self.height, rounding))
+    }
 }
 
 impl From<(f64, f64)> for PhysicalSize {
@@ -302,6 +423,23 @@ impl Into<(u32, u32)> for PhysicalSize {
     /// Note that this rounds instead of truncating.
     #[inline]
     fn into(self) -> (u32, u32) {
-        (self.width.round() as _, self.height.round() as _)
+        self.cast(Rounding::Round)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LogicalSize, PhysicalPosition, Rounding};
+
+    #[test]
+    fn rounding_is_explicit() {
+        let size = LogicalSize::new(100.5, 33.3);
+        assert_eq!(size.to_physical_rounded(1.5), (151.0, 50.0).into());
+        assert_eq!(size.to_physical_floored(1.5), (150.0, 49.0).into());
+        assert_eq!(size.to_physical(1.5).cast::<u32>(Rounding::Ceil), (151, 50));
+        let position = PhysicalPosition::new(-1.5, 2.5);
+        assert_eq!(position.cast::<i32>(Rounding::Floor), (-2, 2));
+        assert_eq!(position.cast::<u32>(Rounding::Round), (0, 3));
+        assert_eq!(position.cast::<f64>(Rounding::Round), (-1.5, 2.5));
     }
 }
@@ -1,10 +1,18 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
-use {DeviceId, LogicalPosition, LogicalSize, WindowId};
+use {DeviceId, LogicalPosition, LogicalSize, PhysicalPosition, WindowId};
 
 /// Describes a generic event.
+///
+/// With the `serde` feature, events can be serialized, to record them for instance. They can't be deserialized, since
+/// window and device IDs are only meaningful while the window or device they came from exists, and winit relies on
+/// the IDs it's given being real.
+///
+/// `T` is the type of the events sent with `EventsLoopProxy::send_event`.
 #[derive(Clone, Debug)]
-pub enum Event {
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum Event<T = ()> {
     WindowEvent {
         window_id: WindowId,
         event: WindowEvent,
@@ -13,18 +21,75 @@ pub enum Event {
         device_id: DeviceId,
         event: DeviceEvent,
     },
+    /// The `EventsLoop` was woken up by an `EventsLoopProxy`, or the deadline given with
+    /// `ControlFlow::WaitUntil` was reached.
     Awakened,
 
     /// The application has been suspended or resumed.
     ///
     /// The parameter is true if app was suspended, and false if it has been resumed.
     Suspended(bool),
+
+    /// A file descriptor registered with `EventsLoopExt::wakeup_on_fd` is ready.
+    ///
+    /// This is only emitted on Linux and the BSDs.
+    FdReady(i32),
+
+    /// Emitted after the `WindowEvent::RedrawRequested` events of an iteration of the events loop
+    /// have all been delivered, which is the time to do the work that follows rendering, such as
+    /// presenting.
+    ///
+    /// Within an iteration, the input and other events are delivered first, then the
+    /// `RedrawRequested` events, then this one, and the events loop waits for more events after
+    /// that. It isn't emitted for iterations without any redraw.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Emitted once there are no more `WM_PAINT` messages to process.
+    /// - **macOS / Android / iOS:** Never emitted.
+    RedrawEventsCleared,
+
+    /// An event sent with `EventsLoopProxy::send_event`.
+    UserEvent(T),
+
+    /// Another instance of the application was launched, and handed over to this one by
+    /// `EventsLoop::new_single_instance`.
+    ///
+    /// Contains the arguments the other instance was started with, as returned by
+    /// `std::env::args`, with invalid Unicode replaced.
+    InstanceLaunched(Vec<String>),
+}
+
+impl Event {
+    // The backends never produce user events, so their events can be given any user event type.
+    pub(crate) fn with_user_event<T>(self) -> Event<T> {
+        match self {
+            Event::WindowEvent { window_id, event } => Event::WindowEvent { window_id, event },
+            Event::DeviceEvent { device_id, event } => Event::DeviceEvent { device_id, event },
+            Event::Awakened => Event::Awakened,
+            Event::Suspended(suspended) => Event::Suspended(suspended),
+            Event::FdReady(fd) => Event::FdReady(fd),
+            Event::RedrawEventsCleared => Event::RedrawEventsCleared,
+            Event::UserEvent(()) => unreachable!(),
+            Event::InstanceLaunched(args) => Event::InstanceLaunched(args),
+        }
+    }
 }
 
 /// Describes an event from a `Window`.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub enum WindowEvent {
     /// The size of the window has changed. Contains the client area's new dimensions.
+    ///
+    /// This is also sent once after the window is created, with its initial size, so that the
+    /// size doesn't have to be queried at startup. The window may be resized again shortly after,
+    /// for example by a tiling window manager.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** The initial size is known once the compositor configured the window.
+    /// - **iOS / Emscripten:** Not sent.
     Resized(LogicalSize),
 
     /// The position of the window has changed. Contains the window's new position.
@@ -34,6 +99,13 @@ pub enum WindowEvent {
     CloseRequested,
 
     /// The window has been destroyed.
+    ///
+    /// Unlike `CloseRequested`, this means the native window no longer exists. This can happen
+    /// without the `Window` being dropped, for instance if the window manager forcibly closed it.
+    /// Once this has been received, methods on the corresponding `Window` do nothing.
+    ///
+    /// On Wayland, this is also sent for every window if the connection to the compositor is
+    /// lost, after which the `EventsLoop` stops delivering events.
     Destroyed,
 
     /// A file has been dropped into the window.
@@ -51,10 +123,41 @@ pub enum WindowEvent {
     /// The window gained or lost focus.
     ///
     /// The parameter is true if the window has gained focus, and false if it has lost focus.
+    ///
+    /// If the cursor was grabbed with `CursorState::Grab`, the grab is released before
+    /// `Focused(false)` is emitted, and re-established before `Focused(true)` is emitted, unless the
+    /// cursor state was changed in the meantime. On Wayland, the compositor handles this itself.
     Focused(bool),
 
+    /// The window was minimized or restored, either by `Window::set_minimized` or by the user.
+    ///
+    /// The parameter is true if the window was minimized, and false if it was restored.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Based on the `WM_STATE` the window manager sets.
+    /// - **Wayland / Android / iOS / Emscripten:** Never emitted, as there's no way to tell.
+    Minimized(bool),
+
     /// An event from the keyboard has been received.
-    KeyboardInput { device_id: DeviceId, input: KeyboardInput },
+    KeyboardInput {
+        device_id: DeviceId,
+        input: KeyboardInput,
+        /// When the input occurred, relative to the creation of the `EventsLoop`.
+        ///
+        /// This is taken from the platform's event, so it can be compared to the time at which a
+        /// frame is presented to measure latency. The platform's clock is matched to the
+        /// `EventsLoop`'s with the first event received, so the timestamps of the first events can
+        /// be off by the time they spent in the queue, and are never earlier than zero.
+        ///
+        /// ## Platform-specific
+        ///
+        /// - **X11 / Wayland / Windows / macOS / iOS:** Millisecond resolution. The platforms' 32-bit
+        ///   counters wrap around after about 49 days, which winit accounts for, so the timestamps
+        ///   keep increasing.
+        /// - **Android / Emscripten:** The time at which winit received the event.
+        timestamp: Duration,
+    },
 
     /// The cursor has moved on the window.
     CursorMoved {
@@ -64,7 +167,15 @@ pub enum WindowEvent {
         /// limited by the display area and it may have been transformed by the OS to implement effects such as cursor
         /// acceleration, it should not be used to implement non-cursor-like interactions such as 3D camera control.
         position: LogicalPosition,
-        modifiers: ModifiersState
+        /// The same position in physical pixels, as reported by the platform.
+        ///
+        /// `position` is obtained by dividing this by the window's DPI factor, so this is the
+        /// value to use when you need to hit-test against physical pixels without losing
+        /// precision to rounding.
+        physical_position: PhysicalPosition,
+        modifiers: ModifiersState,
+        /// When the cursor moved, as for `KeyboardInput`.
+        timestamp: Duration,
     },
 
     /// The cursor has entered the window.
@@ -74,10 +185,24 @@ pub enum WindowEvent {
     CursorLeft { device_id: DeviceId },
 
     /// A mouse wheel movement or touchpad scroll occurred.
-    MouseWheel { device_id: DeviceId, delta: MouseScrollDelta, phase: TouchPhase, modifiers: ModifiersState },
+    MouseWheel {
+        device_id: DeviceId,
+        delta: MouseScrollDelta,
+        phase: TouchPhase,
+        modifiers: ModifiersState,
+        /// When the scroll occurred, as for `KeyboardInput`.
+        timestamp: Duration,
+    },
 
     /// An mouse button press has been received.
-    MouseInput { device_id: DeviceId, state: ElementState, button: MouseButton, modifiers: ModifiersState },
+    MouseInput {
+        device_id: DeviceId,
+        state: ElementState,
+        button: MouseButton,
+        modifiers: ModifiersState,
+        /// When the button was pressed or released, as for `KeyboardInput`.
+        timestamp: Duration,
+    },
 
 
     /// Touchpad pressure event.
@@ -90,8 +215,9 @@ pub enum WindowEvent {
     /// Motion on some analog axis. May report data redundant to other, more specific events.
     AxisMotion { device_id: DeviceId, axis: AxisId, value: f64 },
 
-    /// The window needs to be redrawn.
-    Refresh,
+    /// The window needs to be redrawn, either because the OS asked for it (for instance because
+    /// part of the window was uncovered) or because `Window::request_redraw` was called.
+    RedrawRequested,
 
     /// Touch event has been received
     Touch(Touch),
@@ -117,6 +243,7 @@ pub enum WindowEvent {
 ///
 /// Note that these events are delivered regardless of input focus.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DeviceEvent {
     Added,
     Removed,
@@ -124,6 +251,21 @@ pub enum DeviceEvent {
     /// Change in physical position of a pointing device.
     ///
     /// This represents raw, unfiltered physical motion. Not to be confused with `WindowEvent::CursorMoved`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// The units of `delta` differ between platforms:
+    ///
+    /// - **Windows:** The counts reported by the device, without pointer acceleration.
+    /// - **X11** and **Wayland:** The counts reported by the device, without pointer acceleration.
+    ///   `os::unix::EventsLoopExt::set_mouse_motion_units` can be used to get accelerated pixels
+    ///   instead. On Wayland, this needs the relative pointer protocol.
+    /// - **macOS** and **Emscripten:** Pixels, with pointer acceleration applied.
+    ///
+    /// The deltas aren't normalized to a common unit. Counts depend on the resolution of the
+    /// device, which winit can't find out, so the same physical motion gives different deltas with
+    /// different mice as well as on different platforms. Applications that turn them into camera
+    /// rotation should let users adjust the sensitivity.
     MouseMotion {
         /// (x, y) change in position in unspecified units.
         ///
@@ -144,10 +286,78 @@ pub enum DeviceEvent {
     Button { button: ButtonId, state: ElementState },
     Key(KeyboardInput),
     Text { codepoint: char },
+
+    /// A button of a gamepad was pressed or released.
+    ///
+    /// Gamepads are announced with `Added` and `Removed` like other devices, including those that
+    /// are already plugged in when the `EventsLoop` is created.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Only emitted on Linux with the `gamepad` feature, which reads the gamepads from their
+    /// evdev device nodes. XInput on Windows and the GameController framework on macOS aren't
+    /// supported, so the feature has no effect on other platforms.
+    GamepadButton { button: GamepadButton, state: ElementState },
+
+    /// An analog axis of a gamepad moved.
+    ///
+    /// `value` goes from -1.0 to 1.0 for the sticks, with positive values to the right and
+    /// downwards, and from 0.0 to 1.0 for the triggers.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Only emitted on Linux with the `gamepad` feature.
+    GamepadAxis { axis: GamepadAxis, value: f64 },
+}
+
+/// Identifies a button of a gamepad by its position on the gamepad, following the layout of an
+/// Xbox controller.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum GamepadButton {
+    /// The bottom button of the right cluster, labelled A on an Xbox controller.
+    South,
+    /// The right button of the right cluster, labelled B on an Xbox controller.
+    East,
+    /// The top button of the right cluster, labelled Y on an Xbox controller.
+    North,
+    /// The left button of the right cluster, labelled X on an Xbox controller.
+    West,
+    LeftBumper,
+    RightBumper,
+    /// Reported by gamepads with digital triggers, and alongside `GamepadAxis::LeftTrigger` by
+    /// some gamepads with analog ones.
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    /// The button in the middle of the gamepad, such as the Xbox or PS button.
+    Mode,
+    /// Pressing the left stick.
+    LeftStick,
+    /// Pressing the right stick.
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// Identifies an analog axis of a gamepad.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
 }
 
 /// Describes a keyboard input event.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct KeyboardInput {
     /// Identifies the physical key pressed
     ///
@@ -158,12 +368,31 @@ pub struct KeyboardInput {
 
     pub state: ElementState,
 
-    /// Identifies the semantic meaning of the key
+    /// Identifies the key by its position on the keyboard.
     ///
-    /// Use when the semantics of the key are more important than the physical location of the key, such as when
-    /// implementing appropriate behavior for "page up."
+    /// This is the key found at the same position on a US QWERTY keyboard, and doesn't change with the
+    /// keyboard layout: the key right of Tab is always `Q`, even on an AZERTY layout where it's labelled `A`.
+    /// Use this for bindings that depend on where keys are, such as movement controls in a game.
+    pub physical_key: Option<VirtualKeyCode>,
+
+    /// Identifies the key by what the current keyboard layout produces.
+    ///
+    /// On an AZERTY layout the key right of Tab gives `A`. Use this for shortcuts and anything else that should
+    /// follow the labels on the user's keyboard, such as Ctrl+Z or "page up." Shift and AltGr are taken into
+    /// account but Ctrl isn't, so Shift+; gives `Colon` on a US layout. Use `ReceivedCharacter` for text input.
+    pub logical_key: Option<LogicalKey>,
+
+    /// **Deprecated:** this is either the physical or the logical key depending on the platform. Use
+    /// `physical_key` or `logical_key` instead. This field will be removed in a future release.
     pub virtual_keycode: Option<VirtualKeyCode>,
 
+    /// Identifies which copy of the key was pressed, for keys that appear more than once on the
+    /// keyboard.
+    ///
+    /// This tells apart keys like the numpad's Enter and the main Enter key, which have the same
+    /// `virtual_keycode`.
+    pub location: KeyLocation,
+
     /// Modifier keys active at the time of this input.
     ///
     /// This is tracked internally to avoid tracking errors arising from modifier key state changes when events from
@@ -171,8 +400,102 @@ pub struct KeyboardInput {
     pub modifiers: ModifiersState
 }
 
+/// Identifies a key by what the current keyboard layout produces.
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LogicalKey {
+    /// A key with a symbolic name, such as `Escape`, or a character that has one, such as `A` or `Colon`.
+    Named(VirtualKeyCode),
+    /// The character the key produces, when it has no `VirtualKeyCode`, such as `é` on a French layout.
+    Character(String),
+}
+
+impl LogicalKey {
+    /// Returns the logical key for a character produced by a key press.
+    ///
+    /// Every backend goes through this, so that a character maps to the same key on every platform. Control
+    /// characters return `None`, since the key is better identified by its name.
+    pub(crate) fn from_char(character: char) -> Option<LogicalKey> {
+        use self::VirtualKeyCode::*;
+        if character.is_control() {
+            return None;
+        }
+        let named = match character.to_ascii_lowercase() {
+            'a' => A,
+            'b' => B,
+            'c' => C,
+            'd' => D,
+            'e' => E,
+            'f' => F,
+            'g' => G,
+            'h' => H,
+            'i' => I,
+            'j' => J,
+            'k' => K,
+            'l' => L,
+            'm' => M,
+            'n' => N,
+            'o' => O,
+            'p' => P,
+            'q' => Q,
+            'r' => R,
+            's' => S,
+            't' => T,
+            'u' => U,
+            'v' => V,
+            'w' => W,
+            'x' => X,
+            'y' => Y,
+            'z' => Z,
+            '1' => Key1,
+            '2' => Key2,
+            '3' => Key3,
+            '4' => Key4,
+            '5' => Key5,
+            '6' => Key6,
+            '7' => Key7,
+            '8' => Key8,
+            '9' => Key9,
+            '0' => Key0,
+            ' ' => Space,
+            '-' => Minus,
+            '_' => Underline,
+            '=' => Equals,
+            '[' => LBracket,
+            ']' => RBracket,
+            ';' => Semicolon,
+            ':' => Colon,
+            '\'' => Apostrophe,
+            '\\' => Backslash,
+            ',' => Comma,
+            '.' => Period,
+            '/' => Slash,
+            '`' => Grave,
+            '@' => At,
+            '^' => Caret,
+            _ => return Some(LogicalKey::Character(character.to_string())),
+        };
+        Some(LogicalKey::Named(named))
+    }
+}
+
+/// Describes where a key is located on the keyboard.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum KeyLocation {
+    /// The key is only present once on the keyboard, or its location can't be determined.
+    Standard,
+    /// The left copy of a key present twice on the keyboard, such as the left Shift key.
+    Left,
+    /// The right copy of a key present twice on the keyboard, such as the right Shift key.
+    Right,
+    /// The key is on the numeric keypad.
+    Numpad,
+}
+
 /// Describes touch-screen input state.
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TouchPhase {
     Started,
     Moved,
@@ -196,12 +519,15 @@ pub enum TouchPhase {
 ///
 /// Touch may be cancelled if for example window lost focus.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Touch {
     pub device_id: DeviceId,
     pub phase: TouchPhase,
     pub location: LogicalPosition,
     /// unique identifier of a finger.
-    pub id: u64
+    pub id: u64,
+    /// When the touch occurred, as for `WindowEvent::KeyboardInput`.
+    pub timestamp: Duration,
 }
 
 /// Hardware-dependent keyboard scan code.
@@ -215,6 +541,7 @@ pub type ButtonId = u32;
 
 /// Describes the input state of a key.
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ElementState {
     Pressed,
     Released,
@@ -222,6 +549,7 @@ pub enum ElementState {
 
 /// Describes a button of a mouse controller.
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MouseButton {
     Left,
     Right,
@@ -231,6 +559,7 @@ pub enum MouseButton {
 
 /// Describes a difference in the mouse scroll wheel state.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MouseScrollDelta {
 	/// Amount in lines or rows to scroll in the horizontal
 	/// and vertical directions.
@@ -249,6 +578,7 @@ pub enum MouseScrollDelta {
 
 /// Symbolic name for a keyboard key.
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(u32)]
 pub enum VirtualKeyCode {
     /// The '1' key over the letters.
@@ -440,6 +770,7 @@ pub enum VirtualKeyCode {
 ///
 /// Each field of this struct represents a modifier and is `true` if this modifier is active.
 #[derive(Default, Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ModifiersState {
     /// The "shift" key
     pub shift: bool,
@@ -452,3 +783,21 @@ pub struct ModifiersState {
     /// This is the "windows" key on PC and "command" key on Mac.
     pub logo: bool
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{LogicalKey, VirtualKeyCode};
+
+    #[test]
+    fn characters_map_to_logical_keys() {
+        assert_eq!(LogicalKey::from_char('a'), Some(LogicalKey::Named(VirtualKeyCode::A)));
+        assert_eq!(LogicalKey::from_char('A'), Some(LogicalKey::Named(VirtualKeyCode::A)));
+        assert_eq!(LogicalKey::from_char('7'), Some(LogicalKey::Named(VirtualKeyCode::Key7)));
+        assert_eq!(LogicalKey::from_char(';'), Some(LogicalKey::Named(VirtualKeyCode::Semicolon)));
+        assert_eq!(LogicalKey::from_char(':'), Some(LogicalKey::Named(VirtualKeyCode::Colon)));
+        assert_eq!(LogicalKey::from_char('é'), Some(LogicalKey::Character("é".to_owned())));
+        assert_eq!(LogicalKey::from_char('!'), Some(LogicalKey::Character("!".to_owned())));
+        assert_eq!(LogicalKey::from_char('\r'), None);
+        assert_eq!(LogicalKey::from_char('\u{1b}'), None);
+    }
+}
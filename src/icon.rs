@@ -68,7 +68,7 @@ impl Error for BadIcon {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 /// An icon used for the window titlebar, taskbar, etc.
 ///
 /// Enabling the `icon_loading` feature provides you with several convenience methods for creating
@@ -168,3 +168,53 @@ impl From<image::RgbaImage> for Icon {
         Icon { rgba, width, height }
     }
 }
+
+impl Icon {
+    /// Scales a cursor image given at logical size, and the hotspot within it, by `factor`.
+    ///
+    /// Pixels are sampled from the nearest source pixel, which keeps the edges of small cursor
+    /// images sharp.
+    // Unused on the platforms that scale cursors themselves.
+    #[allow(dead_code)]
+    pub(crate) fn scale_cursor(&self, hotspot: (u32, u32), factor: f64) -> (Icon, (u32, u32)) {
+        if factor == 1.0 {
+            return (self.clone(), hotspot);
+        }
+        let scale = |length: u32| ((length as f64 * factor).round() as u32).max(1);
+        let (width, height) = (scale(self.width), scale(self.height));
+        let mut rgba = Vec::with_capacity((width * height) as usize * PIXEL_SIZE);
+        for y in 0..height {
+            let source_y = ((y as f64 / factor) as u32).min(self.height - 1);
+            for x in 0..width {
+                let source_x = ((x as f64 / factor) as u32).min(self.width - 1);
+                let offset = (source_y * self.width + source_x) as usize * PIXEL_SIZE;
+                rgba.extend_from_slice(&self.rgba[offset..offset + PIXEL_SIZE]);
+            }
+        }
+        let hotspot = (
+            ((hotspot.0 as f64 * factor) as u32).min(width - 1),
+            ((hotspot.1 as f64 * factor) as u32).min(height - 1),
+        );
+        (Icon { rgba, width, height }, hotspot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Icon;
+
+    #[test]
+    fn cursor_is_scaled_with_its_hotspot() {
+        // A 2x1 image with a red and a blue pixel.
+        let icon = Icon::from_rgba(vec![255, 0, 0, 255, 0, 0, 255, 255], 2, 1).unwrap();
+        let (scaled, hotspot) = icon.scale_cursor((1, 0), 2.0);
+        assert_eq!((scaled.width, scaled.height), (4, 2));
+        assert_eq!(&scaled.rgba[..16], &[255, 0, 0, 255, 255, 0, 0, 255, 0, 0, 255, 255, 0, 0, 255, 255][..]);
+        assert_eq!(&scaled.rgba[..16], &scaled.rgba[16..]);
+        assert_eq!(hotspot, (2, 0));
+
+        let (scaled, hotspot) = icon.scale_cursor((1, 0), 1.5);
+        assert_eq!((scaled.width, scaled.height), (3, 2));
+        assert_eq!(hotspot, (1, 0));
+    }
+}
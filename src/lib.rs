@@ -87,7 +87,12 @@
 #[allow(unused_imports)]
 #[macro_use]
 extern crate lazy_static;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
 extern crate libc;
+#[cfg(feature = "raw-window-handle")]
+extern crate raw_window_handle;
 #[cfg(feature = "icon_loading")]
 extern crate image;
 
@@ -110,16 +115,35 @@ extern crate parking_lot;
 extern crate percent_encoding;
 #[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "dragonfly", target_os = "openbsd"))]
 extern crate smithay_client_toolkit as sctk;
+#[cfg(all(feature = "gamepad", target_os = "linux"))]
+#[macro_use]
+extern crate nix;
+
+use std::any::Any;
+use std::collections::VecDeque;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::time::Instant;
 
 pub(crate) use dpi::*; // TODO: Actually change the imports throughout the codebase.
 pub use events::*;
-pub use window::{AvailableMonitorsIter, MonitorId};
+use resize_debounce::ResizeDebounce;
+use single_instance::InstanceListener;
+use user_events::UserEvents;
+pub use window::{AvailableMonitorsIter, Fullscreen, MonitorId, Orientation, VideoMode, VideoModesIter};
 pub use icon::*;
 
 pub mod dpi;
 mod events;
 mod icon;
 mod platform;
+mod resize_debounce;
+mod single_instance;
+mod timestamp;
+mod user_events;
 mod window;
 
 pub mod os;
@@ -145,6 +169,13 @@ pub mod os;
 /// ```
 pub struct Window {
     window: platform::Window,
+    user_data: Option<Box<::std::any::Any + Send + Sync>>,
+    // The minimum and maximum dimensions requested by the user. The maximum given to the backend
+    // is raised to the minimum when they contradict each other.
+    dimension_limits: ::std::sync::Mutex<(Option<LogicalSize>, Option<LogicalSize>)>,
+    // The cursor requested by the user, and how many `set_wait_cursor(true)` calls haven't been
+    // matched by a `set_wait_cursor(false)` yet.
+    cursor: ::std::sync::Mutex<(window::UserCursor, usize)>,
 }
 
 /// Identifier of a window. Unique for each window.
@@ -154,16 +185,40 @@ pub struct Window {
 /// Whenever you receive an event specific to a window, this event contains a `WindowId` which you
 /// can then compare to the ids of your windows.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct WindowId(platform::WindowId);
 
+impl WindowId {
+    /// Returns a dummy `WindowId`, useful for unit testing. The only guarantee made about the return
+    /// value of this function is that it will always be equal to itself and to future values returned
+    /// by this function. No other guarantees are made. This may be equal to a real `WindowId`.
+    ///
+    /// **Passing this into a winit function will result in undefined behavior.**
+    pub unsafe fn dummy() -> Self {
+        WindowId(platform::WindowId::dummy())
+    }
+}
+
 /// Identifier of an input device.
 ///
 /// Whenever you receive an event arising from a particular input device, this event contains a `DeviceId` which
 /// identifies its origin. Note that devices may be virtual (representing an on-screen cursor and keyboard focus) or
 /// physical. Virtual devices typically aggregate inputs from multiple physical devices.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct DeviceId(platform::DeviceId);
 
+impl DeviceId {
+    /// Returns a dummy `DeviceId`, useful for unit testing. The only guarantee made about the return
+    /// value of this function is that it will always be equal to itself and to future values returned
+    /// by this function. No other guarantees are made. This may be equal to a real `DeviceId`.
+    ///
+    /// **Passing this into a winit function will result in undefined behavior.**
+    pub unsafe fn dummy() -> Self {
+        DeviceId(platform::DeviceId::dummy())
+    }
+}
+
 /// Provides a way to retreive events from the system and from the windows that were registered to
 /// the events loop.
 ///
@@ -177,8 +232,18 @@ pub struct DeviceId(platform::DeviceId);
 /// forbiding it), as such it is neither `Send` nor `Sync`. If you need cross-thread access, the
 /// `Window` created from this `EventsLoop` _can_ be sent to an other thread, and the
 /// `EventsLoopProxy` allows you to wakeup an `EventsLoop` from an other thread.
-pub struct EventsLoop {
-    events_loop: platform::EventsLoop,
+///
+/// `T` is the type of the events that can be sent to the `EventsLoop` with
+/// `EventsLoopProxy::send_event`.
+pub struct EventsLoop<T = ()> {
+    // `None` for the events loops created with `new_headless`.
+    events_loop: Option<platform::EventsLoop>,
+    resize_debounce: ResizeDebounce,
+    // Events pushed by `inject_event`, which are delivered before those from the backend.
+    injected_events: VecDeque<Event<T>>,
+    user_events: UserEvents<T>,
+    // Set when the loop was created with `new_single_instance`.
+    instance_listener: Option<InstanceListener>,
     _marker: ::std::marker::PhantomData<*mut ()> // Not Send nor Sync
 }
 
@@ -189,8 +254,33 @@ pub struct EventsLoop {
 pub enum ControlFlow {
     /// Continue looping and waiting for events.
     Continue,
+    /// Continue looping and waiting for events, but stop waiting once the given instant is reached
+    /// and deliver an `Event::Awakened`.
+    ///
+    /// Only the value returned for the latest event counts, so keep returning `WaitUntil` until
+    /// the deadline has passed. If it has already passed, this behaves like `Continue`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Emscripten:** Behaves like `Continue`.
+    WaitUntil(Instant),
     /// Break from the event loop.
     Break,
+    /// Break from the event loop, making `run_forever` return the given exit code.
+    ///
+    /// `Break` is equivalent to `ExitWithCode(0)`.
+    ExitWithCode(i32),
+}
+
+impl ControlFlow {
+    // The deadline the backends wait for. One that has already passed is ignored, since the loop
+    // would otherwise spin, delivering `Awakened` for as long as the callback keeps returning it.
+    pub(crate) fn wait_deadline(self) -> Option<Instant> {
+        match self {
+            ControlFlow::WaitUntil(instant) if instant > Instant::now() => Some(instant),
+            _ => None,
+        }
+    }
 }
 
 impl EventsLoop {
@@ -201,8 +291,92 @@ impl EventsLoop {
     /// If it is not set, winit will try to connect to a wayland connection, and if it fails will
     /// fallback on x11. If this variable is set with any other value, winit will panic.
     pub fn new() -> EventsLoop {
+        EventsLoop::from_platform(platform::EventsLoop::new())
+    }
+
+    /// Builds a new events loop, unless another instance of the application is already running.
+    ///
+    /// The instances of an application are recognized by `app_id`, which should be unique to it,
+    /// such as a reverse domain name like `org.example.App`. It's used as a file name, so it
+    /// mustn't contain slashes.
+    ///
+    /// The first instance gets `SingleInstance::Primary`, and receives an
+    /// `Event::InstanceLaunched` with the arguments of every instance started after it, which it
+    /// can use to open files or focus its window. Those instances get `SingleInstance::Secondary`
+    /// once their arguments were sent, and should usually exit without creating any window.
+    ///
+    /// Returns an `Err` if the other instances couldn't be looked for.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Linux / BSDs / macOS:** The instances communicate through a Unix domain socket in
+    ///   `XDG_RUNTIME_DIR`, or in the temporary directory if it isn't set, whether the
+    ///   application runs on X11 or Wayland.
+    /// - **Windows:** The instances communicate through a named pipe, `\\.\pipe\<app_id>`.
+    /// - **Emscripten:** Always returns an `Err`.
+    pub fn new_single_instance(app_id: &str) -> io::Result<SingleInstance> {
+        EventsLoop::new_single_instance_user_event(app_id)
+    }
+}
+
+/// Returned by `EventsLoop::new_single_instance` and `EventsLoop::new_single_instance_user_event`.
+pub enum SingleInstance<T = ()> {
+    /// No other instance of the application was running. This one receives the arguments of the
+    /// instances started after it.
+    Primary(EventsLoop<T>),
+    /// Another instance of the application is running, and was sent the arguments of this one.
+    Secondary,
+}
+
+impl<T> EventsLoop<T> {
+    /// Builds a new events loop that accepts events of type `T` from `EventsLoopProxy::send_event`.
+    ///
+    /// This initializes the backend in the same way as `EventsLoop::new`.
+    pub fn new_user_event() -> EventsLoop<T> {
+        EventsLoop::from_platform(platform::EventsLoop::new())
+    }
+
+    /// Builds a new events loop that accepts events of type `T`, unless another instance of the
+    /// application is already running.
+    ///
+    /// This behaves like `EventsLoop::new_single_instance`.
+    pub fn new_single_instance_user_event(app_id: &str) -> io::Result<SingleInstance<T>> {
+        let server = match single_instance::claim(app_id)? {
+            Some(server) => server,
+            None => return Ok(SingleInstance::Secondary),
+        };
+        let mut events_loop = EventsLoop::new_user_event();
+        let proxy = events_loop.backend().create_proxy();
+        events_loop.instance_listener = Some(InstanceListener::spawn(server, proxy));
+        Ok(SingleInstance::Primary(events_loop))
+    }
+
+    /// Builds an events loop that isn't connected to any display server, for testing.
+    ///
+    /// It only delivers the events queued with `inject_event`, along with those sent through its
+    /// `EventsLoopProxy`s, which makes it usable in tests that run without a display server, such
+    /// as on CI. `run_forever` returns `0` once there are no such events left instead of waiting
+    /// for more.
+    ///
+    /// It has no monitors, so `get_available_monitors` returns nothing and `get_primary_monitor`
+    /// panics. Building a window with it fails, and the platform-specific `EventsLoopExt` methods
+    /// panic.
+    #[cfg(feature = "testing")]
+    pub fn new_headless() -> EventsLoop<T> {
+        EventsLoop::with_backend(None)
+    }
+
+    pub(crate) fn from_platform(events_loop: platform::EventsLoop) -> EventsLoop<T> {
+        EventsLoop::with_backend(Some(events_loop))
+    }
+
+    fn with_backend(events_loop: Option<platform::EventsLoop>) -> EventsLoop<T> {
         EventsLoop {
-            events_loop: platform::EventsLoop::new(),
+            events_loop,
+            resize_debounce: Default::default(),
+            injected_events: VecDeque::new(),
+            user_events: UserEvents::new(),
+            instance_listener: None,
             _marker: ::std::marker::PhantomData,
         }
     }
@@ -212,63 +386,360 @@ impl EventsLoop {
     // Note: should be replaced with `-> impl Iterator` once stable.
     #[inline]
     pub fn get_available_monitors(&self) -> AvailableMonitorsIter {
-        let data = self.events_loop.get_available_monitors();
+        let data = match self.events_loop {
+            Some(ref events_loop) => events_loop.get_available_monitors(),
+            None => Default::default(),
+        };
         AvailableMonitorsIter{ data: data.into_iter() }
     }
 
     /// Returns the primary monitor of the system.
+    ///
+    /// Panics if the events loop was created with `new_headless`.
     #[inline]
     pub fn get_primary_monitor(&self) -> MonitorId {
-        MonitorId { inner: self.events_loop.get_primary_monitor() }
+        MonitorId { inner: self.backend().get_primary_monitor() }
+    }
+
+    /// Returns the backend of the events loop, which headless events loops don't have.
+    pub(crate) fn backend(&self) -> &platform::EventsLoop {
+        self.events_loop
+            .as_ref()
+            .expect("a headless `EventsLoop` isn't connected to a display server")
+    }
+
+    /// Sets the minimum change in size, in logical pixels, for which `WindowEvent::Resized` is
+    /// reported. Smaller resizes are held back until they add up to the threshold in either
+    /// dimension, which reduces how often renderers have to recreate their swapchains during a
+    /// drag.
+    ///
+    /// The latest size that was held back is still reported before the next event of any other
+    /// kind, at the end of `poll_events`, and before `run_forever` returns, so the last reported
+    /// size catches up with the actual size of the window. The default is `0.0`, which reports
+    /// every resize.
+    #[inline]
+    pub fn set_resize_debounce(&mut self, threshold: f64) {
+        self.resize_debounce.set_threshold(threshold);
+    }
+
+    /// Paces `WindowEvent::RedrawRequested` to the refresh of the display, so that an application
+    /// calling `Window::request_redraw` for every frame doesn't draw frames that are never shown.
+    ///
+    /// Redraws requested too early are held back rather than dropped. This is best-effort, and
+    /// how closely the redraws follow the display depends on the platform. Disabled by default.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** The next redraw of a window waits for the compositor to ask for a new frame
+    ///   after the one drawn for the previous redraw, so the application should draw in response
+    ///   to every `RedrawRequested`. Windows that aren't visible aren't redrawn.
+    /// - **X11:** Redraws are sent at most once per refresh interval of the fastest monitor, as of
+    ///   when this was called, without being synchronized with the display.
+    /// - **Windows:** Waits for the desktop to be composited again after each batch of redraws.
+    /// - **macOS / iOS / Android / Emscripten:** Has no effect.
+    #[inline]
+    pub fn set_redraw_vsync(&mut self, enabled: bool) {
+        if let Some(ref events_loop) = self.events_loop {
+            events_loop.set_redraw_vsync(enabled);
+        }
     }
 
     /// Fetches all the events that are pending, calls the callback function for each of them,
     /// and returns.
+    ///
+    /// If the callback panics, the remaining events are dropped and the panic is resumed once the
+    /// events loop has released any cursor grab, so that the desktop isn't left unusable. This
+    /// cleanup is best-effort.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** `_NET_WM_PING` requests from the window manager are answered here and in
+    ///   `run_forever`, not between calls. If the application doesn't call either for long enough,
+    ///   the window manager may report it as not responding.
     #[inline]
-    pub fn poll_events<F>(&mut self, callback: F)
-        where F: FnMut(Event)
+    pub fn poll_events<F>(&mut self, mut callback: F)
+        where F: FnMut(Event<T>)
     {
-        self.events_loop.poll_events(callback)
+        let mut panic = None;
+        {
+            let resize_debounce = &mut self.resize_debounce;
+            let mut deliver = |event: Event<T>| {
+                if panic.is_none() {
+                    // The callback can't be observed again after it panicked, so it doesn't matter if
+                    // it was left in a broken state.
+                    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| callback(event))) {
+                        panic = Some(payload);
+                    }
+                }
+            };
+            {
+                let mut dispatch = |event: Event<T>| {
+                    if resize_debounce.filter(&event) {
+                        // Sizes that were held back are reported before anything else happens.
+                        while let Some(resized) = resize_debounce.take_held() {
+                            deliver(resized);
+                        }
+                        deliver(event);
+                    }
+                };
+                for event in self.injected_events.drain(..) {
+                    dispatch(event);
+                }
+                let user_events = &self.user_events;
+                let instance_listener = &self.instance_listener;
+                match self.events_loop {
+                    Some(ref mut events_loop) => events_loop.poll_events(|event| match event {
+                        Event::Awakened => {
+                            if let Some(ref instance_listener) = *instance_listener {
+                                while let Some(args) = instance_listener.try_recv() {
+                                    dispatch(Event::InstanceLaunched(args));
+                                }
+                            }
+                            while let Some(user_event) = user_events.try_recv() {
+                                dispatch(Event::UserEvent(user_event));
+                            }
+                            // Only the wakeups asked for with `EventsLoopProxy::wakeup` are
+                            // reported, since the backend coalesces them with those that carried
+                            // user events or launches.
+                            if user_events.take_wakeup_request() {
+                                dispatch(Event::Awakened);
+                            }
+                        },
+                        event => dispatch(event.with_user_event()),
+                    }),
+                    // Without a backend, there are only the events sent through the proxies.
+                    None => {
+                        while let Some(user_event) = user_events.try_recv() {
+                            dispatch(Event::UserEvent(user_event));
+                        }
+                        if user_events.take_wakeup_request() {
+                            dispatch(Event::Awakened);
+                        }
+                    },
+                }
+            }
+            // The sizes still held back are reported at the end of the batch, since there may not
+            // be another event for a while.
+            while let Some(resized) = resize_debounce.take_held() {
+                deliver(resized);
+            }
+        }
+        self.resume_panic(panic);
     }
 
     /// Calls `callback` every time an event is received. If no event is available, sleeps the
-    /// current thread and waits for an event. If the callback returns `ControlFlow::Break` then
-    /// `run_forever` will immediately return.
+    /// current thread and waits for an event. If the callback returns `ControlFlow::Break` or
+    /// `ControlFlow::ExitWithCode` then `run_forever` will immediately return.
+    ///
+    /// Returns the code given to `ControlFlow::ExitWithCode`, or `0` if the loop was exited with
+    /// `ControlFlow::Break`. This doesn't exit the process; pass the code to
+    /// `std::process::exit` if you want it to become the process' exit status.
     ///
     /// # Danger!
     ///
     /// The callback is run after *every* event, so if its execution time is non-trivial the event queue may not empty
     /// at a sufficient rate. Rendering in the callback with vsync enabled **will** cause significant lag.
+    ///
+    /// If the callback panics, the loop is exited and the panic is resumed in the same way as for
+    /// `poll_events`.
     #[inline]
-    pub fn run_forever<F>(&mut self, callback: F)
-        where F: FnMut(Event) -> ControlFlow
+    pub fn run_forever<F>(&mut self, mut callback: F) -> i32
+        where F: FnMut(Event<T>) -> ControlFlow
     {
-        self.events_loop.run_forever(callback)
+        // The backends only need to know whether to break, so the exit code is handled here.
+        let mut exit_code = 0;
+        let mut panic = None;
+        {
+            let resize_debounce = &mut self.resize_debounce;
+            let mut deliver = |event: Event<T>| {
+                // The callback isn't called again after it panicked.
+                if panic.is_some() {
+                    return ControlFlow::Break;
+                }
+                match panic::catch_unwind(AssertUnwindSafe(|| callback(event))) {
+                    Ok(ControlFlow::ExitWithCode(code)) => {
+                        exit_code = code;
+                        ControlFlow::Break
+                    },
+                    Ok(control_flow) => control_flow,
+                    Err(payload) => {
+                        panic = Some(payload);
+                        ControlFlow::Break
+                    },
+                }
+            };
+            {
+                let mut dispatch = |event: Event<T>| {
+                    if !resize_debounce.filter(&event) {
+                        return ControlFlow::Continue;
+                    }
+                    // Sizes that were held back are reported before anything else happens.
+                    while let Some(resized) = resize_debounce.take_held() {
+                        if deliver(resized) == ControlFlow::Break {
+                            return ControlFlow::Break;
+                        }
+                    }
+                    deliver(event)
+                };
+                let mut control_flow = ControlFlow::Continue;
+                while let Some(event) = self.injected_events.pop_front() {
+                    control_flow = dispatch(event);
+                    if control_flow == ControlFlow::Break {
+                        break;
+                    }
+                }
+                if control_flow != ControlFlow::Break {
+                    let user_events = &self.user_events;
+                    let instance_listener = &self.instance_listener;
+                    // The latest value returned by the callback, which the backend keeps to.
+                    let mut last_flow = control_flow;
+                    let mut deadline = last_flow.wait_deadline();
+                    match self.events_loop {
+                        Some(ref mut events_loop) => events_loop.run_forever(|event| {
+                            let flow = match event {
+                                Event::Awakened => {
+                                    if let Some(ref instance_listener) = *instance_listener {
+                                        while let Some(args) = instance_listener.try_recv() {
+                                            last_flow = dispatch(Event::InstanceLaunched(args));
+                                            if last_flow == ControlFlow::Break {
+                                                return last_flow;
+                                            }
+                                        }
+                                    }
+                                    while let Some(user_event) = user_events.try_recv() {
+                                        last_flow = dispatch(Event::UserEvent(user_event));
+                                        if last_flow == ControlFlow::Break {
+                                            return last_flow;
+                                        }
+                                    }
+                                    // The backend coalesces the wakeups asked for with
+                                    // `EventsLoopProxy::wakeup` with those that carried user
+                                    // events or launches, and with the end of a `WaitUntil`. Only
+                                    // the first and the last are reported.
+                                    let deadline_passed = deadline
+                                        .map_or(false, |deadline| Instant::now() >= deadline);
+                                    if user_events.take_wakeup_request() || deadline_passed {
+                                        dispatch(Event::Awakened)
+                                    } else {
+                                        last_flow
+                                    }
+                                },
+                                event => dispatch(event.with_user_event()),
+                            };
+                            last_flow = flow;
+                            deadline = flow.wait_deadline();
+                            flow
+                        }),
+                        // Without a backend, there's nothing to wait for once the events sent
+                        // through the proxies are delivered.
+                        None => loop {
+                            let event = match user_events.try_recv() {
+                                Some(user_event) => Event::UserEvent(user_event),
+                                None if user_events.take_wakeup_request() => Event::Awakened,
+                                None => break,
+                            };
+                            if dispatch(event) == ControlFlow::Break {
+                                break;
+                            }
+                        },
+                    }
+                }
+            }
+            // The sizes still held back are reported before returning, since the next call to
+            // `run_forever` or `poll_events` may be a while away.
+            while let Some(resized) = resize_debounce.take_held() {
+                if deliver(resized) == ControlFlow::Break {
+                    break;
+                }
+            }
+        }
+        self.resume_panic(panic);
+        exit_code
+    }
+
+    /// Queues `event` to be delivered by the next call to `poll_events` or `run_forever`, before any
+    /// events from the system.
+    ///
+    /// This is meant for testing how your application handles events, without needing a display
+    /// server or physical input: create the events loop with `new_headless` to run without one.
+    /// Injected events are delivered to the callback exactly like real ones. Use `WindowId::dummy`
+    /// and `DeviceId::dummy` for events that don't concern a real window or device.
+    #[cfg(feature = "testing")]
+    #[inline]
+    pub fn inject_event(&mut self, event: Event<T>) {
+        self.injected_events.push_back(event);
+    }
+
+    // Unwinding through the backends isn't safe, since the callback is often called from within
+    // OS callbacks. Instead, the panic is caught and resumed here once the backend has returned.
+    fn resume_panic(&self, panic: Option<Box<Any + Send>>) {
+        if let Some(payload) = panic {
+            if let Some(ref events_loop) = self.events_loop {
+                events_loop.release_grabs();
+            }
+            panic::resume_unwind(payload);
+        }
     }
 
     /// Creates an `EventsLoopProxy` that can be used to wake up the `EventsLoop` from another
     /// thread.
-    pub fn create_proxy(&self) -> EventsLoopProxy {
+    pub fn create_proxy(&self) -> EventsLoopProxy<T> {
         EventsLoopProxy {
-            events_loop_proxy: self.events_loop.create_proxy(),
+            events_loop_proxy: self.events_loop.as_ref().map(platform::EventsLoop::create_proxy),
+            sender: self.user_events.sender(),
+            wakeup_requested: self.user_events.wakeup_requested(),
         }
     }
 }
 
-/// Used to wake up the `EventsLoop` from another thread.
-#[derive(Clone)]
-pub struct EventsLoopProxy {
-    events_loop_proxy: platform::EventsLoopProxy,
+/// Used to wake up the `EventsLoop` from another thread, and to send it events.
+pub struct EventsLoopProxy<T = ()> {
+    // `None` for the proxies of headless events loops, which don't block.
+    events_loop_proxy: Option<platform::EventsLoopProxy>,
+    sender: Sender<T>,
+    wakeup_requested: Arc<AtomicBool>,
 }
 
-impl EventsLoopProxy {
+impl<T> Clone for EventsLoopProxy<T> {
+    fn clone(&self) -> Self {
+        EventsLoopProxy {
+            events_loop_proxy: self.events_loop_proxy.clone(),
+            sender: self.sender.clone(),
+            wakeup_requested: self.wakeup_requested.clone(),
+        }
+    }
+}
+
+impl<T> EventsLoopProxy<T> {
     /// Wake up the `EventsLoop` from which this proxy was created.
     ///
-    /// This causes the `EventsLoop` to emit an `Awakened` event.
+    /// This causes the `EventsLoop` to emit an `Awakened` event. Calls made before the `EventsLoop`
+    /// has processed a previous wakeup are coalesced, so only a single `Awakened` event is emitted
+    /// for them.
     ///
     /// Returns an `Err` if the associated `EventsLoop` no longer exists.
     pub fn wakeup(&self) -> Result<(), EventsLoopClosed> {
-        self.events_loop_proxy.wakeup()
+        self.wakeup_requested.store(true, Ordering::Relaxed);
+        self.wakeup_backend()
+    }
+
+    /// Sends `event` to the `EventsLoop` from which this proxy was created, waking it up.
+    ///
+    /// The `EventsLoop` delivers it as an `Event::UserEvent`. Unlike wakeups, events are never
+    /// coalesced: each of them is delivered, in the order they were sent. This doesn't produce an
+    /// `Awakened` event.
+    ///
+    /// Returns an `Err` if the associated `EventsLoop` no longer exists.
+    pub fn send_event(&self, event: T) -> Result<(), EventsLoopClosed> {
+        self.sender.send(event).map_err(|_| EventsLoopClosed)?;
+        self.wakeup_backend()
+    }
+
+    fn wakeup_backend(&self) -> Result<(), EventsLoopClosed> {
+        match self.events_loop_proxy {
+            Some(ref events_loop_proxy) => events_loop_proxy.wakeup(),
+            None => Ok(()),
+        }
     }
 }
 
@@ -328,8 +799,70 @@ impl std::error::Error for CreationError {
     }
 }
 
+/// The error type for when the requested operation is not supported on the current platform.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NotSupportedError {
+    _marker: (),
+}
+
+impl NotSupportedError {
+    #[inline]
+    pub(crate) fn new() -> NotSupportedError {
+        NotSupportedError { _marker: () }
+    }
+}
+
+impl std::fmt::Display for NotSupportedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", std::error::Error::description(self))
+    }
+}
+
+impl std::error::Error for NotSupportedError {
+    fn description(&self) -> &str {
+        "The requested operation is not supported on this platform"
+    }
+}
+
+/// The error type returned by `Window` methods that can fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExternalError {
+    /// The platform can't perform the operation at all.
+    NotSupported(NotSupportedError),
+    /// The operation is supported, but the attempt failed.
+    Os(String),
+}
+
+impl ExternalError {
+    fn to_string(&self) -> &str {
+        match *self {
+            ExternalError::NotSupported(_) => "The requested operation is not supported on this platform",
+            ExternalError::Os(ref text) => &text,
+        }
+    }
+}
+
+impl From<NotSupportedError> for ExternalError {
+    #[inline]
+    fn from(err: NotSupportedError) -> ExternalError {
+        ExternalError::NotSupported(err)
+    }
+}
+
+impl std::fmt::Display for ExternalError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        formatter.write_str(self.to_string())
+    }
+}
+
+impl std::error::Error for ExternalError {
+    fn description(&self) -> &str {
+        self.to_string()
+    }
+}
+
 /// Describes the appearance of the mouse cursor.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum MouseCursor {
     /// The platform-dependent default cursor.
     Default,
@@ -414,6 +947,81 @@ impl Default for CursorState {
     }
 }
 
+/// An edge or corner of a window, from which it can be resized.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ResizeDirection {
+    East,
+    North,
+    NorthEast,
+    NorthWest,
+    South,
+    SouthEast,
+    SouthWest,
+    West,
+}
+
+impl From<ResizeDirection> for MouseCursor {
+    /// The cursor typically shown over the edge or corner.
+    fn from(direction: ResizeDirection) -> Self {
+        match direction {
+            ResizeDirection::East => MouseCursor::EResize,
+            ResizeDirection::North => MouseCursor::NResize,
+            ResizeDirection::NorthEast => MouseCursor::NeResize,
+            ResizeDirection::NorthWest => MouseCursor::NwResize,
+            ResizeDirection::South => MouseCursor::SResize,
+            ResizeDirection::SouthEast => MouseCursor::SeResize,
+            ResizeDirection::SouthWest => MouseCursor::SwResize,
+            ResizeDirection::West => MouseCursor::WResize,
+        }
+    }
+}
+
+/// The space taken up by the decorations on each side of a window, in logical pixels.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Insets {
+    pub top: f64,
+    pub left: f64,
+    pub right: f64,
+    pub bottom: f64,
+}
+
+/// Describes how urgently the window requests the user's attention.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UserAttentionType {
+    /// The attention request persists until the window is focused.
+    Critical,
+
+    /// The window requests attention once, and stops drawing attention on its own.
+    Informational,
+}
+
+/// Where to place a window in the stacking order, relative to another window.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StackOrder {
+    /// The window is placed directly above the other window.
+    Above,
+
+    /// The window is placed directly below the other window.
+    Below,
+}
+
+/// Describes how a window is resized when its DPI factor changes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScaleFactorPolicy {
+    /// The window keeps the same logical size, so its contents keep the same apparent size. The
+    /// number of pixels changes.
+    PreserveLogical,
+
+    /// The window keeps the same number of pixels, so its logical size changes.
+    PreservePhysical,
+}
+
+impl Default for ScaleFactorPolicy {
+    fn default() -> Self {
+        ScaleFactorPolicy::PreserveLogical
+    }
+}
+
 /// Attributes to use when creating a window.
 #[derive(Debug, Clone)]
 pub struct WindowAttributes {
@@ -441,7 +1049,7 @@ pub struct WindowAttributes {
     /// Whether the window should be set as fullscreen upon creation.
     ///
     /// The default is `None`.
-    pub fullscreen: Option<MonitorId>,
+    pub fullscreen: Option<Fullscreen>,
 
     /// The title of the window in the title bar.
     ///
@@ -482,6 +1090,17 @@ pub struct WindowAttributes {
     /// [iOS only] Enable multitouch,
     /// see [multipleTouchEnabled](https://developer.apple.com/documentation/uikit/uiview/1622519-multipletouchenabled)
     pub multitouch: bool,
+
+    /// How the window is resized when its DPI factor changes.
+    ///
+    /// The default is `ScaleFactorPolicy::PreserveLogical`.
+    pub scale_factor_policy: ScaleFactorPolicy,
+
+    /// Whether the contents of the window should be protected from being captured by other
+    /// applications, such as screenshots and screen recordings.
+    ///
+    /// The default is `false`.
+    pub content_protected: bool,
 }
 
 impl Default for WindowAttributes {
@@ -501,6 +1120,25 @@ impl Default for WindowAttributes {
             always_on_top: false,
             window_icon: None,
             multitouch: false,
+            scale_factor_policy: Default::default(),
+            content_protected: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use ControlFlow;
+
+    #[test]
+    fn passed_deadlines_behave_like_continue() {
+        let future = Instant::now() + Duration::from_secs(60);
+        assert_eq!(ControlFlow::WaitUntil(future).wait_deadline(), Some(future));
+        // The callback keeps returning the deadline of the `Awakened` it was just given.
+        let past = Instant::now() - Duration::from_millis(1);
+        assert_eq!(ControlFlow::WaitUntil(past).wait_deadline(), None);
+        assert_eq!(ControlFlow::Continue.wait_deadline(), None);
+    }
+}
@@ -11,9 +11,9 @@ pub trait EventsLoopExt {
     fn set_suspend_callback(&self, cb: Option<Box<Fn(bool) -> ()>>);
 }
 
-impl EventsLoopExt for EventsLoop {
+impl<T> EventsLoopExt for EventsLoop<T> {
     fn set_suspend_callback(&self, cb: Option<Box<Fn(bool) -> ()>>) {
-        self.events_loop.set_suspend_callback(cb);
+        self.backend().set_suspend_callback(cb);
     }
 }
 
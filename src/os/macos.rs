@@ -3,7 +3,7 @@
 use std::convert::From;
 use std::os::raw::c_void;
 use cocoa::appkit::NSApplicationActivationPolicy;
-use {LogicalSize, MonitorId, Window, WindowBuilder};
+use {LogicalPosition, LogicalSize, MonitorId, Window, WindowBuilder};
 
 /// Additional methods on `Window` that are specific to MacOS.
 pub trait WindowExt {
@@ -16,6 +16,13 @@ pub trait WindowExt {
     ///
     /// The pointer will become invalid when the `Window` is destroyed.
     fn get_nsview(&self) -> *mut c_void;
+
+    /// Moves the close, minimize and zoom buttons so that the close button is `inset` away from
+    /// the top-left corner of the window.
+    ///
+    /// This is mostly useful along with `with_fullsize_content_view`, to make room for custom
+    /// content in the titlebar. Has no effect if the window has no titlebar.
+    fn set_traffic_light_inset(&self, inset: LogicalPosition);
 }
 
 impl WindowExt for Window {
@@ -28,6 +35,11 @@ impl WindowExt for Window {
     fn get_nsview(&self) -> *mut c_void {
         self.window.get_nsview()
     }
+
+    #[inline]
+    fn set_traffic_light_inset(&self, inset: LogicalPosition) {
+        self.window.set_traffic_light_inset(inset)
+    }
 }
 
 /// Corresponds to `NSApplicationActivationPolicy`.
@@ -60,6 +72,31 @@ impl From<ActivationPolicy> for NSApplicationActivationPolicy {
     }
 }
 
+/// The material of the blurred background of a window, which corresponds to
+/// `NSVisualEffectMaterial`.
+///
+/// `AppearanceBased`, `Light`, `Dark`, `MediumLight`, `UltraDark` and `Titlebar` are available
+/// from macOS 10.10, while the remaining materials need macOS 10.11.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlurMaterial {
+    /// Follows the appearance of the window.
+    AppearanceBased = 0,
+    Light = 1,
+    Dark = 2,
+    /// The material of window titlebars.
+    Titlebar = 3,
+    /// The material used for selected items.
+    Selection = 4,
+    /// The material of menus.
+    Menu = 5,
+    /// The material of popovers.
+    Popover = 6,
+    /// The material of sidebars, such as in Finder.
+    Sidebar = 7,
+    MediumLight = 8,
+    UltraDark = 9,
+}
+
 /// Additional methods on `WindowBuilder` that are specific to MacOS.
 ///
 /// **Note:** Properties dealing with the titlebar will be overwritten by the `with_decorations` method
@@ -87,6 +124,24 @@ pub trait WindowBuilderExt {
     fn with_fullsize_content_view(self, fullsize_content_view: bool) -> WindowBuilder;
     /// Build window with `resizeIncrements` property. Values must not be 0.
     fn with_resize_increments(self, increments: LogicalSize) -> WindowBuilder;
+    /// Moves the close, minimize and zoom buttons. See `WindowExt::set_traffic_light_inset`.
+    fn with_traffic_light_inset(self, inset: LogicalPosition) -> WindowBuilder;
+    /// Blurs what's behind the window, using the given material.
+    ///
+    /// This only has an effect if the window is transparent (see
+    /// `WindowBuilder::with_transparency`), since the blur is drawn behind the window's content.
+    fn with_blur(self, material: BlurMaterial) -> WindowBuilder;
+    /// Sets whether a standard menu bar is installed when the window is created. The default is
+    /// `true`.
+    ///
+    /// The menu bar has an application menu with Hide and Quit (Cmd+Q), an Edit menu with Undo,
+    /// Cut, Copy, Paste and Select All, and a Window menu with Minimize. Without a menu bar, these
+    /// keyboard shortcuts don't work. Quitting through the menu terminates the process
+    /// immediately, without `CloseRequested` being emitted.
+    ///
+    /// The menu bar is only installed if the application doesn't already have one, so a custom
+    /// menu bar can be used by setting the `mainMenu` of `NSApp` before creating the first window.
+    fn with_default_menu(self, default_menu: bool) -> WindowBuilder;
 }
 
 impl WindowBuilderExt for WindowBuilder {
@@ -137,6 +192,24 @@ impl WindowBuilderExt for WindowBuilder {
         self.platform_specific.resize_increments = Some(increments.into());
         self
     }
+
+    #[inline]
+    fn with_traffic_light_inset(mut self, inset: LogicalPosition) -> WindowBuilder {
+        self.platform_specific.traffic_light_inset = Some(inset);
+        self
+    }
+
+    #[inline]
+    fn with_blur(mut self, material: BlurMaterial) -> WindowBuilder {
+        self.platform_specific.blur = Some(material);
+        self
+    }
+
+    #[inline]
+    fn with_default_menu(mut self, default_menu: bool) -> WindowBuilder {
+        self.platform_specific.disable_default_menu = !default_menu;
+        self
+    }
 }
 
 /// Additional methods on `MonitorId` that are specific to MacOS.
@@ -1,15 +1,18 @@
 #![cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd"))]
 
 use std::os::raw;
+use std::os::unix::io::RawFd;
 use std::ptr;
 use std::sync::Arc;
 
 use {
     EventsLoop,
+    ExternalError,
     LogicalSize,
     MonitorId,
     Window,
     WindowBuilder,
+    WindowId,
 };
 use platform::{
     EventsLoop as LinuxEventsLoop,
@@ -17,6 +20,7 @@ use platform::{
 };
 use platform::x11::XConnection;
 use platform::x11::ffi::XVisualInfo;
+use window::UserCursor;
 
 // TODO: stupid hack so that glutin can do its work
 #[doc(hidden)]
@@ -25,6 +29,35 @@ pub use platform::x11;
 pub use platform::XNotSupported;
 pub use platform::x11::util::WindowType as XWindowType;
 
+/// The units of the deltas of `DeviceEvent::MouseMotion`.
+///
+/// This chooses between the values the system reports, neither of which is normalized across
+/// devices or platforms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseMotionUnits {
+    /// The counts reported by the device, before pointer acceleration is applied. This is the
+    /// default, and is what's usually wanted for camera controls in games.
+    Raw,
+    /// Pixels, after pointer acceleration is applied. This matches the movement of the cursor,
+    /// and is what macOS and Emscripten always report.
+    Pixels,
+}
+
+impl Default for MouseMotionUnits {
+    fn default() -> Self {
+        MouseMotionUnits::Raw
+    }
+}
+
+/// The readiness of a file descriptor registered with `EventsLoopExt::wakeup_on_fd` that wakes up
+/// the `EventsLoop`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FdInterest {
+    Readable,
+    Writable,
+    ReadWrite,
+}
+
 /// Additional methods on `EventsLoop` that are specific to Linux.
 pub trait EventsLoopExt {
     /// Builds a new `EventsLoop` that is forced to use X11.
@@ -41,46 +74,118 @@ pub trait EventsLoopExt {
     /// True if the `EventsLoop` uses X11.
     fn is_x11(&self) -> bool;
 
+    /// Sets the cursor theme and size used by every window of this `EventsLoop`, overriding the
+    /// `XCURSOR_THEME` and `XCURSOR_SIZE` environment variables.
+    ///
+    /// The size is in pixels, and is scaled according to the DPI factor on Wayland. The cursors
+    /// currently shown by the windows are updated to the new theme.
+    ///
+    /// On X11, the theme otherwise follows the `Xcursor.theme` and `Xcursor.size` X resources,
+    /// which desktop environments update when the user changes their cursor theme. Calling this
+    /// stops winit from following them.
+    fn set_cursor_theme(&self, name: &str, size: u32);
+
+    /// Sets the units of the deltas of `DeviceEvent::MouseMotion`. The default is
+    /// `MouseMotionUnits::Raw`.
+    fn set_mouse_motion_units(&self, units: MouseMotionUnits);
+
+    /// Sets whether the `ConfigureNotify` events that the window manager sends while a window is
+    /// being dragged are merged, so that only the latest position is reported as `Moved` for each
+    /// batch of events. This also merges the `Resized` events of interactive resizes. The default
+    /// is `true`; set it to `false` to receive every intermediate position.
+    ///
+    /// This only affects X11, since Wayland doesn't report window positions.
+    fn set_coalesce_moves(&self, coalesce: bool);
+
+    /// Sets whether winit handles requests to close windows, which it does by default.
+    ///
+    /// When `false`, windows created afterwards don't opt into the `WM_DELETE_WINDOW` protocol,
+    /// and `WindowEvent::CloseRequested` is never sent. This is meant for applications and
+    /// toolkits embedding winit that implement the close protocol themselves: they can set
+    /// `WM_PROTOCOLS` on the window and handle the `WM_DELETE_WINDOW` client messages, which
+    /// winit then ignores. Otherwise, the window manager usually kills the connection to the X
+    /// server when the user closes a window. This should be called before creating any window.
+    ///
+    /// This only affects X11. Wayland compositors can't close windows themselves, so
+    /// `CloseRequested` is always sent there.
+    fn set_handle_close_requests(&self, handle: bool);
+
+    /// Makes the `EventsLoop` wait on `fd` along with the connection to the display server, and
+    /// emit `Event::FdReady(fd)` whenever it's ready according to `interest`. This lets
+    /// applications multiplex their own sockets with winit without a second thread.
+    ///
+    /// Readiness is level-triggered: the event keeps being emitted until the application reads
+    /// from or writes to `fd`. Registering the same `fd` again replaces its `interest`, and it must
+    /// be removed with `remove_wakeup_fd` before being closed.
+    ///
+    /// There's no equivalent on Windows or macOS.
+    fn wakeup_on_fd(&self, fd: RawFd, interest: FdInterest);
+
+    /// Stops waiting on a file descriptor registered with `wakeup_on_fd`.
+    fn remove_wakeup_fd(&self, fd: RawFd);
+
     #[doc(hidden)]
     fn get_xlib_xconnection(&self) -> Option<Arc<XConnection>>;
 }
 
-impl EventsLoopExt for EventsLoop {
+impl<T> EventsLoopExt for EventsLoop<T> {
     #[inline]
     fn new_x11() -> Result<Self, XNotSupported> {
-        LinuxEventsLoop::new_x11().map(|ev|
-            EventsLoop {
-                events_loop: ev,
-                _marker: ::std::marker::PhantomData,
-            }
-        )
+        LinuxEventsLoop::new_x11().map(EventsLoop::from_platform)
     }
 
     #[inline]
     fn new_wayland() -> Self {
-        EventsLoop {
-            events_loop: match LinuxEventsLoop::new_wayland() {
-                Ok(e) => e,
-                Err(_) => panic!()      // TODO: propagate
-            },
-            _marker: ::std::marker::PhantomData,
-        }
+        EventsLoop::from_platform(match LinuxEventsLoop::new_wayland() {
+            Ok(e) => e,
+            Err(_) => panic!()      // TODO: propagate
+        })
     }
 
     #[inline]
     fn is_wayland(&self) -> bool {
-        self.events_loop.is_wayland()
+        self.backend().is_wayland()
     }
 
     #[inline]
     fn is_x11(&self) -> bool {
-        !self.events_loop.is_wayland()
+        !self.backend().is_wayland()
+    }
+
+    #[inline]
+    fn set_cursor_theme(&self, name: &str, size: u32) {
+        self.backend().set_cursor_theme(name, size)
+    }
+
+    #[inline]
+    fn set_mouse_motion_units(&self, units: MouseMotionUnits) {
+        self.backend().set_mouse_motion_units(units)
+    }
+
+    #[inline]
+    fn set_coalesce_moves(&self, coalesce: bool) {
+        self.backend().set_coalesce_moves(coalesce)
+    }
+
+    #[inline]
+    fn set_handle_close_requests(&self, handle: bool) {
+        self.backend().set_handle_close_requests(handle)
+    }
+
+    #[inline]
+    fn wakeup_on_fd(&self, fd: RawFd, interest: FdInterest) {
+        self.backend().wakeup_on_fd(fd, interest)
+    }
+
+    #[inline]
+    fn remove_wakeup_fd(&self, fd: RawFd) {
+        self.backend().remove_wakeup_fd(fd)
     }
 
     #[inline]
     #[doc(hidden)]
     fn get_xlib_xconnection(&self) -> Option<Arc<XConnection>> {
-        self.events_loop.x_connection().cloned()
+        self.backend().x_connection().cloned()
     }
 }
 
@@ -127,6 +232,15 @@ pub trait WindowExt {
     /// The pointer will become invalid when the glutin `Window` is destroyed.
     fn get_wayland_display(&self) -> Option<*mut raw::c_void>;
 
+    /// Sets the cursor to the one named `name` in the cursor theme, for cursors that aren't
+    /// covered by `MouseCursor`.
+    ///
+    /// Returns an `Err` if the theme doesn't have a cursor with that name. On Wayland, the theme is
+    /// the one the cursors of the window are drawn from, which `EventsLoopExt::set_cursor_theme`
+    /// overrides. While the wait cursor is shown by `Window::set_wait_cursor`, the cursor is only
+    /// looked up once it's turned off, and the default cursor is restored if it doesn't exist.
+    fn set_cursor_by_name(&self, name: &str) -> Result<(), ExternalError>;
+
     /// Check if the window is ready for drawing
     ///
     /// It is a remnant of a previous implementation detail for the
@@ -202,6 +316,11 @@ impl WindowExt for Window {
         }
     }
 
+    #[inline]
+    fn set_cursor_by_name(&self, name: &str) -> Result<(), ExternalError> {
+        self.set_user_cursor(UserCursor::Named(name.to_owned()))
+    }
+
     #[inline]
     fn is_ready(&self) -> bool {
         true
@@ -210,7 +329,17 @@ impl WindowExt for Window {
 
 /// Additional methods on `WindowBuilder` that are specific to Unix.
 pub trait WindowBuilderExt {
+    /// Build window with the visual described by the given `XVisualInfo`, and a matching colormap.
+    /// Only relevant on X11.
     fn with_x11_visual<T>(self, visual_infos: *const T) -> WindowBuilder;
+    /// Build window with the visual that has the given ID, and a matching colormap. Only relevant
+    /// on X11.
+    ///
+    /// This is for the visual chosen by EGL or GLX for a context, since the context can only be
+    /// made current on a window with a compatible visual. Otherwise, making it current fails with
+    /// `BadMatch`. Window creation fails if the screen doesn't have a visual with that ID.
+    fn with_x11_visual_id(self, visual_id: raw::c_ulong) -> WindowBuilder;
+    /// Build window on the given screen; defaults to the default screen. Only relevant on X11.
     fn with_x11_screen(self, screen_id: i32) -> WindowBuilder;
 
     /// Build window with `WM_CLASS` hint; defaults to the name of the binary. Only relevant on X11.
@@ -223,6 +352,12 @@ pub trait WindowBuilderExt {
     fn with_resize_increments(self, increments: LogicalSize) -> WindowBuilder;
     /// Build window with base size hint. Only implemented on X11.
     fn with_base_size(self, base_size: LogicalSize) -> WindowBuilder;
+    /// Build window in the window group led by the given window. Only relevant on X11.
+    ///
+    /// Window managers treat the windows of a group as a single application, e.g. for taskbar
+    /// grouping. By default, the first window created by the `EventsLoop` leads the group of every
+    /// window created after it.
+    fn with_window_group_leader(self, leader: WindowId) -> WindowBuilder;
 }
 
 impl WindowBuilderExt for WindowBuilder {
@@ -234,6 +369,12 @@ impl WindowBuilderExt for WindowBuilder {
         self
     }
 
+    #[inline]
+    fn with_x11_visual_id(mut self, visual_id: raw::c_ulong) -> WindowBuilder {
+        self.platform_specific.visual_id = Some(visual_id);
+        self
+    }
+
     #[inline]
     fn with_x11_screen(mut self, screen_id: i32) -> WindowBuilder {
         self.platform_specific.screen_id = Some(screen_id);
@@ -269,6 +410,12 @@ impl WindowBuilderExt for WindowBuilder {
         self.platform_specific.base_size = Some(base_size.into());
         self
     }
+
+    #[inline]
+    fn with_window_group_leader(mut self, leader: WindowId) -> WindowBuilder {
+        self.platform_specific.window_group_leader = Some(leader.0);
+        self
+    }
 }
 
 /// Additional methods on `MonitorId` that are specific to Linux.
@@ -13,15 +13,26 @@ pub trait EventsLoopExt {
     /// By default, winit on Windows will attempt to enable process-wide DPI awareness. If that's
     /// undesirable, you can create an `EventsLoop` using this function instead.
     fn new_dpi_unaware() -> Self where Self: Sized;
+
+    /// Sets whether winit handles requests to close windows, which it does by default.
+    ///
+    /// When `false`, `WM_CLOSE` isn't sent as `WindowEvent::CloseRequested` for windows created
+    /// afterwards, and is passed to `DefWindowProcW` instead, which destroys the window. This is
+    /// meant for applications and toolkits embedding winit that implement the close semantics
+    /// themselves: they can intercept `WM_CLOSE` by subclassing the window (see
+    /// `WindowExt::get_hwnd`). `CloseRequested` is never sent for these windows.
+    fn set_handle_close_requests(&self, handle: bool);
 }
 
-impl EventsLoopExt for EventsLoop {
+impl<T> EventsLoopExt for EventsLoop<T> {
     #[inline]
     fn new_dpi_unaware() -> Self {
-        EventsLoop {
-            events_loop: WindowsEventsLoop::with_dpi_awareness(false),
-            _marker: ::std::marker::PhantomData,
-        }
+        EventsLoop::from_platform(WindowsEventsLoop::with_dpi_awareness(false))
+    }
+
+    #[inline]
+    fn set_handle_close_requests(&self, handle: bool) {
+        self.backend().set_handle_close_requests(handle)
     }
 }
 
@@ -34,6 +45,24 @@ pub trait WindowExt {
 
     /// This sets `ICON_BIG`. A good ceiling here is 256x256.
     fn set_taskbar_icon(&self, taskbar_icon: Option<Icon>);
+
+    /// Flashes the window's caption and taskbar button `count` times, every `interval_ms`
+    /// milliseconds. If `count` is 0, the window flashes until it's focused.
+    ///
+    /// An `interval_ms` of 0 uses the cursor's blink rate.
+    fn flash_frame(&self, count: u32, interval_ms: u32);
+
+    /// Sets whether the corners of the window are rounded. See `CornerPreference`.
+    fn set_corner_preference(&self, preference: CornerPreference);
+
+    /// Sets the color of the window's border. See `FrameColor`.
+    fn set_border_color(&self, color: FrameColor);
+
+    /// Sets the background color of the window's title bar. See `FrameColor`.
+    fn set_title_background_color(&self, color: FrameColor);
+
+    /// Sets the color of the window's title text. See `FrameColor`.
+    fn set_title_text_color(&self, color: FrameColor);
 }
 
 impl WindowExt for Window {
@@ -46,6 +75,31 @@ impl WindowExt for Window {
     fn set_taskbar_icon(&self, taskbar_icon: Option<Icon>) {
         self.window.set_taskbar_icon(taskbar_icon)
     }
+
+    #[inline]
+    fn flash_frame(&self, count: u32, interval_ms: u32) {
+        self.window.flash_frame(count, interval_ms)
+    }
+
+    #[inline]
+    fn set_corner_preference(&self, preference: CornerPreference) {
+        self.window.set_corner_preference(preference)
+    }
+
+    #[inline]
+    fn set_border_color(&self, color: FrameColor) {
+        self.window.set_border_color(color)
+    }
+
+    #[inline]
+    fn set_title_background_color(&self, color: FrameColor) {
+        self.window.set_title_background_color(color)
+    }
+
+    #[inline]
+    fn set_title_text_color(&self, color: FrameColor) {
+        self.window.set_title_text_color(color)
+    }
 }
 
 /// Additional methods on `WindowBuilder` that are specific to Windows.
@@ -55,6 +109,25 @@ pub trait WindowBuilderExt {
 
     /// This sets `ICON_BIG`. A good ceiling here is 256x256.
     fn with_taskbar_icon(self, taskbar_icon: Option<Icon>) -> WindowBuilder;
+
+    /// Makes windows without decorations keep their resize border, drop shadow and snapping
+    /// behavior, only hiding the title bar and borders. The resize border overlaps the edges of
+    /// the client area.
+    ///
+    /// This has no effect while the window is decorated.
+    fn with_undecorated_shadow(self, undecorated_shadow: bool) -> WindowBuilder;
+
+    /// Sets whether the corners of the window are rounded. See `CornerPreference`.
+    fn with_corner_preference(self, preference: CornerPreference) -> WindowBuilder;
+
+    /// Sets the color of the window's border. See `FrameColor`.
+    fn with_border_color(self, color: FrameColor) -> WindowBuilder;
+
+    /// Sets the background color of the window's title bar. See `FrameColor`.
+    fn with_title_background_color(self, color: FrameColor) -> WindowBuilder;
+
+    /// Sets the color of the window's title text. See `FrameColor`.
+    fn with_title_text_color(self, color: FrameColor) -> WindowBuilder;
 }
 
 impl WindowBuilderExt for WindowBuilder {
@@ -69,6 +142,75 @@ impl WindowBuilderExt for WindowBuilder {
         self.platform_specific.taskbar_icon = taskbar_icon;
         self
     }
+
+    #[inline]
+    fn with_undecorated_shadow(mut self, undecorated_shadow: bool) -> WindowBuilder {
+        self.platform_specific.undecorated_shadow = undecorated_shadow;
+        self
+    }
+
+    #[inline]
+    fn with_corner_preference(mut self, preference: CornerPreference) -> WindowBuilder {
+        self.platform_specific.corner_preference = preference;
+        self
+    }
+
+    #[inline]
+    fn with_border_color(mut self, color: FrameColor) -> WindowBuilder {
+        self.platform_specific.border_color = color;
+        self
+    }
+
+    #[inline]
+    fn with_title_background_color(mut self, color: FrameColor) -> WindowBuilder {
+        self.platform_specific.title_background_color = color;
+        self
+    }
+
+    #[inline]
+    fn with_title_text_color(mut self, color: FrameColor) -> WindowBuilder {
+        self.platform_specific.title_text_color = color;
+        self
+    }
+}
+
+/// How Windows 11 rounds the corners of a window. Earlier versions of Windows ignore this.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CornerPreference {
+    /// Lets the system decide, which rounds the corners of most windows, but not of undecorated
+    /// ones. This is the default.
+    Default = 0,
+    /// Never rounds the corners, as is usual for tooltips.
+    DoNotRound = 1,
+    /// Rounds the corners.
+    Round = 2,
+    /// Rounds the corners with a small radius, as is usual for menus.
+    RoundSmall = 3,
+}
+
+impl Default for CornerPreference {
+    fn default() -> Self {
+        CornerPreference::Default
+    }
+}
+
+/// A color of the window frame drawn by Windows 11. Earlier versions of Windows ignore this.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FrameColor {
+    /// Uses the system's color, which follows the user's theme and accent color settings. This is
+    /// the default.
+    Default,
+    /// Doesn't draw the border at all. This is only supported for the border color; the title bar
+    /// colors are left unchanged.
+    None,
+    /// A color given by its red, green and blue components.
+    Rgb(u8, u8, u8),
+}
+
+impl Default for FrameColor {
+    fn default() -> Self {
+        FrameColor::Default
+    }
 }
 
 /// Additional methods on `MonitorId` that are specific to Windows.
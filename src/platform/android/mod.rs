@@ -10,6 +10,11 @@ use std::fmt;
 use std::os::raw::c_void;
 use std::sync::mpsc::{Receiver, channel};
 
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::RawWindowHandle;
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::android::AndroidHandle;
+
 use {
     CreationError,
     CursorState,
@@ -18,18 +23,23 @@ use {
     LogicalSize,
     MouseCursor,
     PhysicalPosition,
+    Orientation,
     PhysicalSize,
+    VideoMode,
     WindowAttributes,
     WindowEvent,
     WindowId as RootWindowId,
 };
 use CreationError::OsError;
 use events::{Touch, TouchPhase};
+use timestamp::EventTimestamps;
 use window::MonitorId as RootMonitorId;
 
 pub struct EventsLoop {
     event_rx: Receiver<android_glue::Event>,
     suspend_callback: RefCell<Option<Box<Fn(bool) -> ()>>>,
+    // The motion events aren't timestamped, so they're stamped when they're polled.
+    timestamps: EventTimestamps,
 }
 
 #[derive(Clone)]
@@ -42,6 +52,7 @@ impl EventsLoop {
         EventsLoop {
             event_rx: rx,
             suspend_callback: Default::default(),
+            timestamps: EventTimestamps::new(),
         }
     }
 
@@ -80,6 +91,7 @@ impl EventsLoop {
                             location,
                             id: motion.pointer_id as u64,
                             device_id: DEVICE_ID,
+                            timestamp: self.timestamps.now(),
                         }),
                     })
                 },
@@ -117,7 +129,7 @@ impl EventsLoop {
                     // The activity needs to be redrawn.
                     Some(Event::WindowEvent {
                         window_id: RootWindowId(WindowId),
-                        event: WindowEvent::Refresh,
+                        event: WindowEvent::RedrawRequested,
                     })
                 }
                 android_glue::Event::Wake => {
@@ -142,13 +154,26 @@ impl EventsLoop {
         where F: FnMut(::Event) -> ::ControlFlow,
     {
         // Yeah that's a very bad implementation.
+        let mut deadline = None;
         loop {
             let mut control_flow = ::ControlFlow::Continue;
-            self.poll_events(|e| {
-                if let ::ControlFlow::Break = callback(e) {
-                    control_flow = ::ControlFlow::Break;
+            let wait_deadline = deadline;
+            {
+                let mut cb = |e| {
+                    let flow = callback(e);
+                    deadline = match flow {
+                        ::ControlFlow::WaitUntil(instant) => Some(instant),
+                        _ => None,
+                    };
+                    if let ::ControlFlow::Break = flow {
+                        control_flow = ::ControlFlow::Break;
+                    }
+                };
+                self.poll_events(&mut cb);
+                if wait_deadline.map_or(false, |deadline| ::std::time::Instant::now() >= deadline) {
+                    cb(::Event::Awakened);
                 }
-            });
+            }
             if let ::ControlFlow::Break = control_flow {
                 break;
             }
@@ -156,6 +181,16 @@ impl EventsLoop {
         }
     }
 
+    #[inline]
+    pub fn release_grabs(&self) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_redraw_vsync(&self, _enabled: bool) {
+        // N/A
+    }
+
     pub fn create_proxy(&self) -> EventsLoopProxy {
         EventsLoopProxy
     }
@@ -169,11 +204,25 @@ impl EventsLoopProxy {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct WindowId;
 
+impl WindowId {
+    pub unsafe fn dummy() -> Self {
+        WindowId
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct DeviceId;
 
+impl DeviceId {
+    pub unsafe fn dummy() -> Self {
+        DeviceId
+    }
+}
+
 pub struct Window {
     native_window: *const c_void,
 }
@@ -229,6 +278,36 @@ impl MonitorId {
     pub fn get_hidpi_factor(&self) -> f64 {
         1.0
     }
+
+    #[inline]
+    pub fn get_dpi(&self) -> Option<f64> {
+        // N/A
+        None
+    }
+
+    #[inline]
+    pub fn is_primary(&self) -> bool {
+        // There's only ever one monitor.
+        true
+    }
+
+    #[inline]
+    pub fn get_current_video_mode(&self) -> Option<VideoMode> {
+        // N/A
+        None
+    }
+
+    #[inline]
+    pub fn get_video_modes(&self) -> Vec<VideoMode> {
+        // N/A
+        Vec::new()
+    }
+
+    #[inline]
+    pub fn get_orientation(&self) -> Orientation {
+        // N/A
+        Orientation::Landscape
+    }
 }
 
 #[derive(Clone, Default)]
@@ -258,6 +337,15 @@ impl Window {
         self.native_window
     }
 
+    #[cfg(feature = "raw-window-handle")]
+    #[inline]
+    pub fn raw_window_handle(&self) -> RawWindowHandle {
+        RawWindowHandle::Android(AndroidHandle {
+            a_native_window: self.native_window as *mut _,
+            ..AndroidHandle::empty()
+        })
+    }
+
     #[inline]
     pub fn set_title(&self, _: &str) {
         // N/A
@@ -305,6 +393,11 @@ impl Window {
         // N/A
     }
 
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        false
+    }
+
     #[inline]
     pub fn get_inner_size(&self) -> Option<LogicalSize> {
         if self.native_window.is_null() {
@@ -316,11 +409,30 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn get_inner_size_physical(&self) -> Option<(u32, u32)> {
+        if self.native_window.is_null() {
+            None
+        } else {
+            Some(self.get_current_monitor().get_dimensions().into())
+        }
+    }
+
     #[inline]
     pub fn get_outer_size(&self) -> Option<LogicalSize> {
         self.get_inner_size()
     }
 
+    #[inline]
+    pub fn get_outer_size_physical(&self) -> Option<(u32, u32)> {
+        self.get_inner_size_physical()
+    }
+
+    #[inline]
+    pub fn get_decoration_insets(&self) -> Option<::Insets> {
+        Some(Default::default())
+    }
+
     #[inline]
     pub fn set_inner_size(&self, _size: LogicalSize) {
         // N/A
@@ -337,17 +449,56 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_cursor_state(&self, _state: CursorState) -> Result<(), String> {
+    pub fn request_redraw(&self) {
         // N/A
-        Ok(())
     }
 
     #[inline]
-    pub fn set_cursor_position(&self, _position: LogicalPosition) -> Result<(), ()> {
+    pub fn is_cursor_visible(&self) -> bool {
+        // N/A
+        true
+    }
+
+    #[inline]
+    pub fn set_cursor_state(&self, _state: CursorState) -> Result<(), ::ExternalError> {
         // N/A
         Ok(())
     }
 
+    #[inline]
+    pub fn set_keyboard_grab(&self, _grab: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_animated_cursor(
+        &self,
+        _frames: Vec<(::Icon, ::std::time::Duration)>,
+        _hotspot: (u32, u32),
+    ) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_cursor_image(&self, _image: ::Icon, _hotspot: (u32, u32)) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn confine_cursor_to_monitor(&self, _confine: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_cursor_position(&self, _position: LogicalPosition) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_minimized(&self, _minimized: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
     #[inline]
     pub fn set_maximized(&self, _maximized: bool) {
         // N/A
@@ -355,9 +506,15 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_fullscreen(&self, _monitor: Option<RootMonitorId>) {
+    pub fn get_current_fullscreen(&self) -> Option<::Fullscreen> {
         // N/A
-        // Android has single screen maximized apps so nothing to do
+        None
+    }
+
+    #[inline]
+    pub fn set_fullscreen(&self, _fullscreen: Option<::Fullscreen>) -> Result<(), ::ExternalError> {
+        // Android has single screen maximized apps
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
     }
 
     #[inline]
@@ -366,8 +523,23 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_always_on_top(&self, _always_on_top: bool) {
-        // N/A
+    pub fn is_decorated(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_always_on_top(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn set_always_on_top(&self, _always_on_top: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn restack(&self, _relative_to: WindowId, _order: ::StackOrder) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
     }
 
     #[inline]
@@ -376,7 +548,42 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_ime_spot(&self, _spot: LogicalPosition) {
+    pub fn set_content_protected(&self, _protected: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_background_color(&self, _color: Option<(u8, u8, u8, u8)>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_ime_spot(&self, _spot: LogicalPosition) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_ime_allowed(&self, _allowed: bool, _commit: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn show_window_menu(&self, _position: LogicalPosition) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn start_drag(&self) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn start_resize(&self, _direction: ::ResizeDirection) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn request_user_attention(&self, _request_type: Option<::UserAttentionType>) {
         // N/A
     }
 
@@ -10,10 +10,16 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Mutex, Arc};
 
 use dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize};
-use window::MonitorId as RootMonitorId;
+use timestamp::EventTimestamps;
+use window::{MonitorId as RootMonitorId, Orientation, VideoMode};
 
 const DOCUMENT_NAME: &'static str = "#document\0";
 
+lazy_static! {
+    // The events aren't timestamped, so they're stamped when the callbacks receive them.
+    static ref TIMESTAMPS: EventTimestamps = EventTimestamps::new();
+}
+
 fn get_hidpi_factor() -> f64 {
     unsafe { ffi::emscripten_get_device_pixel_ratio() as f64 }
 }
@@ -25,8 +31,15 @@ unsafe impl Send for PlatformSpecificWindowBuilderAttributes {}
 unsafe impl Sync for PlatformSpecificWindowBuilderAttributes {}
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct DeviceId;
 
+impl DeviceId {
+    pub unsafe fn dummy() -> Self {
+        DeviceId
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct PlatformSpecificHeadlessBuilderAttributes;
 
@@ -53,6 +66,36 @@ impl MonitorId {
     pub fn get_hidpi_factor(&self) -> f64 {
         get_hidpi_factor()
     }
+
+    #[inline]
+    pub fn get_dpi(&self) -> Option<f64> {
+        // Browsers don't expose the physical size of the screen.
+        None
+    }
+
+    #[inline]
+    pub fn is_primary(&self) -> bool {
+        // There's only ever one monitor.
+        true
+    }
+
+    #[inline]
+    pub fn get_current_video_mode(&self) -> Option<VideoMode> {
+        // N/A
+        None
+    }
+
+    #[inline]
+    pub fn get_video_modes(&self) -> Vec<VideoMode> {
+        // N/A
+        Vec::new()
+    }
+
+    #[inline]
+    pub fn get_orientation(&self) -> Orientation {
+        // N/A
+        Orientation::Landscape
+    }
 }
 
 // Used to assign a callback to emscripten main loop
@@ -90,6 +133,7 @@ pub struct EventsLoop {
 
 impl EventsLoop {
     pub fn new() -> EventsLoop {
+        ::lazy_static::initialize(&TIMESTAMPS);
         EventsLoop {
             window: Mutex::new(None),
             interrupted: AtomicBool::new(false),
@@ -101,6 +145,16 @@ impl EventsLoop {
         self.interrupted.store(true, Ordering::Relaxed);
     }
 
+    #[inline]
+    pub fn release_grabs(&self) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_redraw_vsync(&self, _enabled: bool) {
+        // N/A
+    }
+
     #[inline]
     pub fn create_proxy(&self) -> EventsLoopProxy {
         unimplemented!()
@@ -126,6 +180,14 @@ impl EventsLoop {
             while let Some(event) = window.events.lock().unwrap().pop_front() {
                 callback(event)
             }
+            // Redraws requested by these callbacks are sent on the next call.
+            if window.redraw_requested.swap(false, Ordering::Relaxed) {
+                callback(::Event::WindowEvent {
+                    window_id: ::WindowId(WindowId(0)),
+                    event: ::WindowEvent::RedrawRequested,
+                });
+                callback(::Event::RedrawEventsCleared);
+            }
         }
     }
 
@@ -147,12 +209,20 @@ impl EventsLoop {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct WindowId(usize);
 
+impl WindowId {
+    pub unsafe fn dummy() -> Self {
+        WindowId(0)
+    }
+}
+
 pub struct Window2 {
     cursor_state: Mutex<::CursorState>,
     is_fullscreen: bool,
     events: Box<Mutex<VecDeque<::Event>>>,
+    redraw_requested: AtomicBool,
 }
 
 pub struct Window {
@@ -196,16 +266,19 @@ extern "C" fn mouse_callback(
         match event_type {
             ffi::EMSCRIPTEN_EVENT_MOUSEMOVE => {
                 let dpi_factor = get_hidpi_factor();
-                let position = LogicalPosition::from_physical(
-                    ((*event).canvasX as f64, (*event).canvasY as f64),
-                    dpi_factor,
+                let physical_position = PhysicalPosition::new(
+                    (*event).canvasX as f64,
+                    (*event).canvasY as f64,
                 );
+                let position = physical_position.to_logical(dpi_factor);
                 queue.lock().unwrap().push_back(::Event::WindowEvent {
                     window_id: ::WindowId(WindowId(0)),
                     event: ::WindowEvent::CursorMoved {
                         device_id: ::DeviceId(DeviceId),
                         position,
+                        physical_position,
                         modifiers: modifiers,
+                        timestamp: TIMESTAMPS.now(),
                     }
                 });
                 queue.lock().unwrap().push_back(::Event::DeviceEvent {
@@ -235,6 +308,7 @@ extern "C" fn mouse_callback(
                         state: state,
                         button: button,
                         modifiers: modifiers,
+                        timestamp: TIMESTAMPS.now(),
                     }
                 })
             },
@@ -269,9 +343,13 @@ extern "C" fn keyboard_callback(
                         input: ::KeyboardInput {
                             scancode: key_translate((*event).key) as u32,
                             state: ::ElementState::Pressed,
+                            physical_key: code_translate_physical((*event).code),
+                            logical_key: key_translate_logical((*event).key, (*event).location),
                             virtual_keycode: key_translate_virt((*event).key, (*event).location),
+                            location: key_location((*event).location),
                             modifiers,
                         },
+                        timestamp: TIMESTAMPS.now(),
                     },
                 });
             },
@@ -283,9 +361,13 @@ extern "C" fn keyboard_callback(
                         input: ::KeyboardInput {
                             scancode: key_translate((*event).key) as u32,
                             state: ::ElementState::Released,
+                            physical_key: code_translate_physical((*event).code),
+                            logical_key: key_translate_logical((*event).key, (*event).location),
                             virtual_keycode: key_translate_virt((*event).key, (*event).location),
+                            location: key_location((*event).location),
                             modifiers,
                         },
+                        timestamp: TIMESTAMPS.now(),
                     },
                 });
             },
@@ -327,6 +409,7 @@ extern fn touch_callback(
                         phase,
                         id: touch.identifier as u64,
                         location,
+                        timestamp: TIMESTAMPS.now(),
                     }),
                 });
             }
@@ -376,6 +459,7 @@ impl Window {
         let w = Window2 {
             cursor_state: Default::default(),
             events: Default::default(),
+            redraw_requested: AtomicBool::new(false),
             is_fullscreen: attribs.fullscreen.is_some(),
         };
 
@@ -446,6 +530,12 @@ impl Window {
 
     #[inline]
     pub fn get_inner_size(&self) -> Option<LogicalSize> {
+        self.get_inner_size_physical()
+            .map(|physical| LogicalSize::from_physical(physical, self.get_hidpi_factor()))
+    }
+
+    #[inline]
+    pub fn get_inner_size_physical(&self) -> Option<(u32, u32)> {
         unsafe {
             let mut width = 0;
             let mut height = 0;
@@ -456,9 +546,7 @@ impl Window {
             {
                 None
             } else {
-                let dpi_factor = self.get_hidpi_factor();
-                let logical = LogicalSize::from_physical((width as u32, height as u32), dpi_factor);
-                Some(logical)
+                Some((width as u32, height as u32))
             }
         }
     }
@@ -468,6 +556,16 @@ impl Window {
         self.get_inner_size()
     }
 
+    #[inline]
+    pub fn get_outer_size_physical(&self) -> Option<(u32, u32)> {
+        self.get_inner_size_physical()
+    }
+
+    #[inline]
+    pub fn get_decoration_insets(&self) -> Option<::Insets> {
+        Some(Default::default())
+    }
+
     #[inline]
     pub fn set_inner_size(&self, size: LogicalSize) {
         unsafe {
@@ -497,16 +595,31 @@ impl Window {
         // N/A
     }
 
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        false
+    }
+
     #[inline]
     pub fn show(&self) {}
     #[inline]
     pub fn hide(&self) {}
 
+    #[inline]
+    pub fn request_redraw(&self) {
+        self.window.redraw_requested.store(true, Ordering::Relaxed);
+    }
+
     #[inline]
     pub fn set_cursor(&self, _cursor: ::MouseCursor) {}
 
     #[inline]
-    pub fn set_cursor_state(&self, state: ::CursorState) -> Result<(), String> {
+    pub fn is_cursor_visible(&self) -> bool {
+        *self.window.cursor_state.lock().unwrap() != ::CursorState::Hide
+    }
+
+    #[inline]
+    pub fn set_cursor_state(&self, state: ::CursorState) -> Result<(), ::ExternalError> {
         unsafe {
             use ::CursorState::*;
 
@@ -517,21 +630,21 @@ impl Window {
 
             // Set or unset grab callback
             match state {
-                Hide | Normal => em_try(ffi::emscripten_set_pointerlockchange_callback(ptr::null(), 0 as *mut c_void, ffi::EM_FALSE, None))?,
-                Grab => em_try(ffi::emscripten_set_pointerlockchange_callback(ptr::null(), 0 as *mut c_void, ffi::EM_FALSE, Some(pointerlockchange_callback)))?,
+                Hide | Normal => em_try(ffi::emscripten_set_pointerlockchange_callback(ptr::null(), 0 as *mut c_void, ffi::EM_FALSE, None)).map_err(::ExternalError::Os)?,
+                Grab => em_try(ffi::emscripten_set_pointerlockchange_callback(ptr::null(), 0 as *mut c_void, ffi::EM_FALSE, Some(pointerlockchange_callback))).map_err(::ExternalError::Os)?,
             }
 
             // Go back to normal cursor state
             match *old_state {
                 Hide => show_mouse(),
-                Grab => em_try(ffi::emscripten_exit_pointerlock())?,
+                Grab => em_try(ffi::emscripten_exit_pointerlock()).map_err(::ExternalError::Os)?,
                 Normal => (),
             }
 
             // Set cursor from normal cursor state
             match state {
                 Hide => ffi::emscripten_hide_mouse(),
-                Grab => em_try(ffi::emscripten_request_pointerlock(ptr::null(), ffi::EM_TRUE))?,
+                Grab => em_try(ffi::emscripten_request_pointerlock(ptr::null(), ffi::EM_TRUE)).map_err(::ExternalError::Os)?,
                 Normal => (),
             }
 
@@ -548,8 +661,37 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_cursor_position(&self, _position: LogicalPosition) -> Result<(), ()> {
-        Err(())
+    pub fn set_keyboard_grab(&self, _grab: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_animated_cursor(
+        &self,
+        _frames: Vec<(::Icon, ::std::time::Duration)>,
+        _hotspot: (u32, u32),
+    ) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_cursor_image(&self, _image: ::Icon, _hotspot: (u32, u32)) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn confine_cursor_to_monitor(&self, _confine: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_cursor_position(&self, _position: LogicalPosition) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_minimized(&self, _minimized: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
     }
 
     #[inline]
@@ -558,8 +700,14 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_fullscreen(&self, _monitor: Option<::MonitorId>) {
-        // iOS has single screen maximized apps so nothing to do
+    pub fn get_current_fullscreen(&self) -> Option<::Fullscreen> {
+        // N/A
+        None
+    }
+
+    #[inline]
+    pub fn set_fullscreen(&self, _fullscreen: Option<::Fullscreen>) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
     }
 
     #[inline]
@@ -568,8 +716,23 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_always_on_top(&self, _always_on_top: bool) {
-        // N/A
+    pub fn is_decorated(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_always_on_top(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn set_always_on_top(&self, _always_on_top: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn restack(&self, _relative_to: WindowId, _order: ::StackOrder) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
     }
 
     #[inline]
@@ -578,7 +741,42 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_ime_spot(&self, _logical_spot: LogicalPosition) {
+    pub fn set_content_protected(&self, _protected: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_background_color(&self, _color: Option<(u8, u8, u8, u8)>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_ime_spot(&self, _logical_spot: LogicalPosition) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_ime_allowed(&self, _allowed: bool, _commit: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn show_window_menu(&self, _position: LogicalPosition) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn start_drag(&self) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn start_resize(&self, _direction: ::ResizeDirection) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn request_user_attention(&self, _request_type: Option<::UserAttentionType>) {
         // N/A
     }
 
@@ -656,6 +854,168 @@ fn key_translate(input: [ffi::EM_UTF8; ffi::EM_HTML5_SHORT_STRING_LEN_BYTES]) ->
     }
 }
 
+// `code` identifies the physical key, named after what it produces on a US QWERTY layout.
+fn code_translate_physical(input: [ffi::EM_UTF8; ffi::EM_HTML5_SHORT_STRING_LEN_BYTES])
+    -> Option<::VirtualKeyCode>
+{
+    let slice = &input[0..input.iter().take_while(|x| **x != 0).count()];
+    let maybe_code = unsafe { str::from_utf8(mem::transmute::<_, &[u8]>(slice)) };
+    let code = match maybe_code {
+        Ok(code) => code,
+        Err(_) => { return None; },
+    };
+    use VirtualKeyCode::*;
+    match code {
+        "KeyA" => Some(A),
+        "KeyB" => Some(B),
+        "KeyC" => Some(C),
+        "KeyD" => Some(D),
+        "KeyE" => Some(E),
+        "KeyF" => Some(F),
+        "KeyG" => Some(G),
+        "KeyH" => Some(H),
+        "KeyI" => Some(I),
+        "KeyJ" => Some(J),
+        "KeyK" => Some(K),
+        "KeyL" => Some(L),
+        "KeyM" => Some(M),
+        "KeyN" => Some(N),
+        "KeyO" => Some(O),
+        "KeyP" => Some(P),
+        "KeyQ" => Some(Q),
+        "KeyR" => Some(R),
+        "KeyS" => Some(S),
+        "KeyT" => Some(T),
+        "KeyU" => Some(U),
+        "KeyV" => Some(V),
+        "KeyW" => Some(W),
+        "KeyX" => Some(X),
+        "KeyY" => Some(Y),
+        "KeyZ" => Some(Z),
+        "Digit1" => Some(Key1),
+        "Digit2" => Some(Key2),
+        "Digit3" => Some(Key3),
+        "Digit4" => Some(Key4),
+        "Digit5" => Some(Key5),
+        "Digit6" => Some(Key6),
+        "Digit7" => Some(Key7),
+        "Digit8" => Some(Key8),
+        "Digit9" => Some(Key9),
+        "Digit0" => Some(Key0),
+
+        "Minus" => Some(Minus),
+        "Equal" => Some(Equals),
+        "BracketLeft" => Some(LBracket),
+        "BracketRight" => Some(RBracket),
+        "Backslash" => Some(Backslash),
+        "Semicolon" => Some(Semicolon),
+        "Quote" => Some(Apostrophe),
+        "Backquote" => Some(Grave),
+        "Comma" => Some(Comma),
+        "Period" => Some(Period),
+        "Slash" => Some(Slash),
+        "IntlBackslash" => Some(OEM102),
+        "IntlRo" => Some(AbntC1),
+        "IntlYen" => Some(Yen),
+
+        "Escape" => Some(Escape),
+        "Tab" => Some(Tab),
+        "CapsLock" => Some(Capital),
+        "ShiftLeft" => Some(LShift),
+        "ShiftRight" => Some(RShift),
+        "ControlLeft" => Some(LControl),
+        "ControlRight" => Some(RControl),
+        "AltLeft" => Some(LAlt),
+        "AltRight" => Some(RAlt),
+        "MetaLeft" => Some(LWin),
+        "MetaRight" => Some(RWin),
+        "ContextMenu" => Some(Apps),
+        "Enter" => Some(Return),
+        "Space" => Some(Space),
+        "Backspace" => Some(Back),
+        "Convert" => Some(Convert),
+        "NonConvert" => Some(NoConvert),
+        "KanaMode" => Some(Kana),
+
+        "Insert" => Some(Insert),
+        "Delete" => Some(Delete),
+        "Home" => Some(Home),
+        "End" => Some(End),
+        "PageUp" => Some(PageUp),
+        "PageDown" => Some(PageDown),
+        "ArrowUp" => Some(Up),
+        "ArrowDown" => Some(Down),
+        "ArrowLeft" => Some(Left),
+        "ArrowRight" => Some(Right),
+
+        "NumLock" => Some(Numlock),
+        "Numpad0" => Some(Numpad0),
+        "Numpad1" => Some(Numpad1),
+        "Numpad2" => Some(Numpad2),
+        "Numpad3" => Some(Numpad3),
+        "Numpad4" => Some(Numpad4),
+        "Numpad5" => Some(Numpad5),
+        "Numpad6" => Some(Numpad6),
+        "Numpad7" => Some(Numpad7),
+        "Numpad8" => Some(Numpad8),
+        "Numpad9" => Some(Numpad9),
+        "NumpadAdd" => Some(Add),
+        "NumpadSubtract" => Some(Subtract),
+        "NumpadMultiply" => Some(Multiply),
+        "NumpadDivide" => Some(Divide),
+        "NumpadDecimal" => Some(Decimal),
+        "NumpadEnter" => Some(NumpadEnter),
+        "NumpadEqual" => Some(NumpadEquals),
+        "NumpadComma" => Some(NumpadComma),
+
+        "F1" => Some(F1),
+        "F2" => Some(F2),
+        "F3" => Some(F3),
+        "F4" => Some(F4),
+        "F5" => Some(F5),
+        "F6" => Some(F6),
+        "F7" => Some(F7),
+        "F8" => Some(F8),
+        "F9" => Some(F9),
+        "F10" => Some(F10),
+        "F11" => Some(F11),
+        "F12" => Some(F12),
+        "F13" => Some(F13),
+        "F14" => Some(F14),
+        "F15" => Some(F15),
+        "PrintScreen" => Some(Snapshot),
+        "ScrollLock" => Some(Scroll),
+        "Pause" => Some(Pause),
+        _ => None,
+    }
+}
+
+fn key_location(location: c_ulong) -> ::KeyLocation {
+    match location {
+        ffi::DOM_KEY_LOCATION_LEFT => ::KeyLocation::Left,
+        ffi::DOM_KEY_LOCATION_RIGHT => ::KeyLocation::Right,
+        ffi::DOM_KEY_LOCATION_NUMPAD => ::KeyLocation::Numpad,
+        _ => ::KeyLocation::Standard,
+    }
+}
+
+// `key` is the text the key produces, or the name of the key if it doesn't produce any. The
+// numpad is identified by name, as it is on the other platforms.
+fn key_translate_logical(input: [ffi::EM_UTF8; ffi::EM_HTML5_SHORT_STRING_LEN_BYTES],
+                         location: c_ulong) -> Option<::LogicalKey>
+{
+    let slice = &input[0..input.iter().take_while(|x| **x != 0).count()];
+    let maybe_key = unsafe { str::from_utf8(mem::transmute::<_, &[u8]>(slice)) };
+    let mut chars = match maybe_key {
+        Ok(key) => key.chars(),
+        Err(_) => { return None; },
+    };
+    match (chars.next(), chars.next()) {
+        (Some(character), None) if location != ffi::DOM_KEY_LOCATION_NUMPAD => ::LogicalKey::from_char(character),
+        _ => key_translate_virt(input, location).map(::LogicalKey::Named),
+    }
+}
+
 fn key_translate_virt(input: [ffi::EM_UTF8; ffi::EM_HTML5_SHORT_STRING_LEN_BYTES],
                       location: c_ulong) -> Option<::VirtualKeyCode>
 {
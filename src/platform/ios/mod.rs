@@ -66,6 +66,10 @@ use std::os::raw::*;
 
 use objc::declare::ClassDecl;
 use objc::runtime::{BOOL, Class, Object, Sel, YES};
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::RawWindowHandle;
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::ios::IOSHandle;
 
 use {
     CreationError,
@@ -75,12 +79,15 @@ use {
     LogicalSize,
     MouseCursor,
     PhysicalPosition,
+    Orientation,
     PhysicalSize,
+    VideoMode,
     WindowAttributes,
     WindowEvent,
     WindowId as RootEventId,
 };
 use events::{Touch, TouchPhase};
+use timestamp::EventTimestamps;
 use window::MonitorId as RootMonitorId;
 
 mod ffi;
@@ -197,6 +204,40 @@ impl MonitorId {
         let scale: CGFloat = unsafe { msg_send![self.get_uiscreen(), nativeScale] };
         scale as f64
     }
+
+    #[inline]
+    pub fn get_dpi(&self) -> Option<f64> {
+        // UIKit doesn't expose the physical size of the screen.
+        None
+    }
+
+    #[inline]
+    pub fn is_primary(&self) -> bool {
+        // There's only ever one monitor.
+        true
+    }
+
+    #[inline]
+    pub fn get_current_video_mode(&self) -> Option<VideoMode> {
+        // N/A
+        None
+    }
+
+    #[inline]
+    pub fn get_video_modes(&self) -> Vec<VideoMode> {
+        // N/A
+        Vec::new()
+    }
+
+    #[inline]
+    pub fn get_orientation(&self) -> Orientation {
+        // N/A
+        Orientation::Landscape
+    }
+}
+
+lazy_static! {
+    static ref TIMESTAMPS: EventTimestamps = EventTimestamps::new();
 }
 
 pub struct EventsLoop {
@@ -208,6 +249,7 @@ pub struct EventsLoopProxy;
 
 impl EventsLoop {
     pub fn new() -> EventsLoop {
+        ::lazy_static::initialize(&TIMESTAMPS);
         unsafe {
             if setjmp(mem::transmute(&mut JMPBUF)) != 0 {
                 let app_class = Class::get("UIApplication").expect("Failed to get class `UIApplication`");
@@ -271,13 +313,26 @@ impl EventsLoop {
         where F: FnMut(::Event) -> ::ControlFlow,
     {
         // Yeah that's a very bad implementation.
+        let mut deadline = None;
         loop {
             let mut control_flow = ::ControlFlow::Continue;
-            self.poll_events(|e| {
-                if let ::ControlFlow::Break = callback(e) {
-                    control_flow = ::ControlFlow::Break;
+            let wait_deadline = deadline;
+            {
+                let mut cb = |e| {
+                    let flow = callback(e);
+                    deadline = match flow {
+                        ::ControlFlow::WaitUntil(instant) => Some(instant),
+                        _ => None,
+                    };
+                    if let ::ControlFlow::Break = flow {
+                        control_flow = ::ControlFlow::Break;
+                    }
+                };
+                self.poll_events(&mut cb);
+                if wait_deadline.map_or(false, |deadline| ::std::time::Instant::now() >= deadline) {
+                    cb(::Event::Awakened);
                 }
-            });
+            }
             if let ::ControlFlow::Break = control_flow {
                 break;
             }
@@ -285,6 +340,16 @@ impl EventsLoop {
         }
     }
 
+    #[inline]
+    pub fn release_grabs(&self) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_redraw_vsync(&self, _enabled: bool) {
+        // N/A
+    }
+
     pub fn create_proxy(&self) -> EventsLoopProxy {
         EventsLoopProxy
     }
@@ -297,11 +362,25 @@ impl EventsLoopProxy {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct WindowId;
 
+impl WindowId {
+    pub unsafe fn dummy() -> Self {
+        WindowId
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct DeviceId;
 
+impl DeviceId {
+    pub unsafe fn dummy() -> Self {
+        DeviceId
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct PlatformSpecificWindowBuilderAttributes;
 
@@ -326,6 +405,19 @@ impl Window {
         unsafe { (*self.delegate_state).view }
     }
 
+    #[cfg(feature = "raw-window-handle")]
+    #[inline]
+    pub fn raw_window_handle(&self) -> RawWindowHandle {
+        unsafe {
+            RawWindowHandle::IOS(IOSHandle {
+                ui_window: (*self.delegate_state).window as *mut _,
+                ui_view: (*self.delegate_state).view as *mut _,
+                ui_view_controller: (*self.delegate_state).controller as *mut _,
+                ..IOSHandle::empty()
+            })
+        }
+    }
+
     #[inline]
     pub fn set_title(&self, _title: &str) {
         // N/A
@@ -363,11 +455,27 @@ impl Window {
         unsafe { Some((&*self.delegate_state).size) }
     }
 
+    #[inline]
+    pub fn get_inner_size_physical(&self) -> Option<(u32, u32)> {
+        self.get_inner_size()
+            .map(|logical| logical.to_physical(self.get_hidpi_factor()).into())
+    }
+
     #[inline]
     pub fn get_outer_size(&self) -> Option<LogicalSize> {
         self.get_inner_size()
     }
 
+    #[inline]
+    pub fn get_outer_size_physical(&self) -> Option<(u32, u32)> {
+        self.get_inner_size_physical()
+    }
+
+    #[inline]
+    pub fn get_decoration_insets(&self) -> Option<::Insets> {
+        Some(Default::default())
+    }
+
     #[inline]
     pub fn set_inner_size(&self, _size: LogicalSize) {
         // N/A
@@ -388,13 +496,29 @@ impl Window {
         // N/A
     }
 
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        false
+    }
+
     #[inline]
     pub fn set_cursor(&self, _cursor: MouseCursor) {
         // N/A
     }
 
     #[inline]
-    pub fn set_cursor_state(&self, _cursor_state: CursorState) -> Result<(), String> {
+    pub fn request_redraw(&self) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn is_cursor_visible(&self) -> bool {
+        // N/A
+        true
+    }
+
+    #[inline]
+    pub fn set_cursor_state(&self, _cursor_state: CursorState) -> Result<(), ::ExternalError> {
         // N/A
         Ok(())
     }
@@ -405,9 +529,37 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_cursor_position(&self, _position: LogicalPosition) -> Result<(), ()> {
-        // N/A
-        Ok(())
+    pub fn set_keyboard_grab(&self, _grab: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_animated_cursor(
+        &self,
+        _frames: Vec<(::Icon, ::std::time::Duration)>,
+        _hotspot: (u32, u32),
+    ) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_cursor_image(&self, _image: ::Icon, _hotspot: (u32, u32)) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn confine_cursor_to_monitor(&self, _confine: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_cursor_position(&self, _position: LogicalPosition) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_minimized(&self, _minimized: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
     }
 
     #[inline]
@@ -417,9 +569,15 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_fullscreen(&self, _monitor: Option<RootMonitorId>) {
+    pub fn get_current_fullscreen(&self) -> Option<::Fullscreen> {
         // N/A
-        // iOS has single screen maximized apps so nothing to do
+        None
+    }
+
+    #[inline]
+    pub fn set_fullscreen(&self, _fullscreen: Option<::Fullscreen>) -> Result<(), ::ExternalError> {
+        // iOS has single screen maximized apps
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
     }
 
     #[inline]
@@ -428,8 +586,23 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_always_on_top(&self, _always_on_top: bool) {
-        // N/A
+    pub fn is_decorated(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_always_on_top(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn set_always_on_top(&self, _always_on_top: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn restack(&self, _relative_to: WindowId, _order: ::StackOrder) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
     }
 
     #[inline]
@@ -438,7 +611,42 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_ime_spot(&self, _logical_spot: LogicalPosition) {
+    pub fn set_content_protected(&self, _protected: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_background_color(&self, _color: Option<(u8, u8, u8, u8)>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_ime_spot(&self, _logical_spot: LogicalPosition) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_ime_allowed(&self, _allowed: bool, _commit: bool) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn show_window_menu(&self, _position: LogicalPosition) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn start_drag(&self) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn start_resize(&self, _direction: ::ResizeDirection) -> Result<(), ::ExternalError> {
+        Err(::ExternalError::NotSupported(::NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn request_user_attention(&self, _request_type: Option<::UserAttentionType>) {
         // N/A
     }
 
@@ -570,6 +778,8 @@ fn create_delegate_class() {
                 let location: CGPoint = msg_send![touch, locationInView:nil];
                 let touch_id = touch as u64;
                 let phase: i32 = msg_send![touch, phase];
+                // In seconds since the system started
+                let time: f64 = msg_send![touch, timestamp];
 
                 state.events_queue.push_back(Event::WindowEvent {
                     window_id: RootEventId(WindowId),
@@ -584,7 +794,8 @@ fn create_delegate_class() {
                             3 => TouchPhase::Ended,
                             4 => TouchPhase::Cancelled,
                             _ => panic!("unexpected touch phase: {:?}", phase)
-                        }
+                        },
+                        timestamp: TIMESTAMPS.from_millis((time * 1000.0) as u64 as u32),
                     }),
                 });
             }
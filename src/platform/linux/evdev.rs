@@ -0,0 +1,151 @@
+//! Both X11 and Wayland identify keys by their evdev code (X11 offsets it by 8), which is tied to
+//! the key's position rather than to the keyboard layout.
+
+use VirtualKeyCode;
+
+/// Maps an evdev key code to the key at the same position on a US QWERTY keyboard.
+pub fn evdev_to_physical_key(code: u32) -> Option<VirtualKeyCode> {
+    use VirtualKeyCode::*;
+    Some(match code {
+        1 => Escape,
+        2 => Key1,
+        3 => Key2,
+        4 => Key3,
+        5 => Key4,
+        6 => Key5,
+        7 => Key6,
+        8 => Key7,
+        9 => Key8,
+        10 => Key9,
+        11 => Key0,
+        12 => Minus,
+        13 => Equals,
+        14 => Back,
+        15 => Tab,
+        16 => Q,
+        17 => W,
+        18 => E,
+        19 => R,
+        20 => T,
+        21 => Y,
+        22 => U,
+        23 => I,
+        24 => O,
+        25 => P,
+        26 => LBracket,
+        27 => RBracket,
+        28 => Return,
+        29 => LControl,
+        30 => A,
+        31 => S,
+        32 => D,
+        33 => F,
+        34 => G,
+        35 => H,
+        36 => J,
+        37 => K,
+        38 => L,
+        39 => Semicolon,
+        40 => Apostrophe,
+        41 => Grave,
+        42 => LShift,
+        43 => Backslash,
+        44 => Z,
+        45 => X,
+        46 => C,
+        47 => V,
+        48 => B,
+        49 => N,
+        50 => M,
+        51 => Comma,
+        52 => Period,
+        53 => Slash,
+        54 => RShift,
+        55 => Multiply,
+        56 => LAlt,
+        57 => Space,
+        58 => Capital,
+        59 => F1,
+        60 => F2,
+        61 => F3,
+        62 => F4,
+        63 => F5,
+        64 => F6,
+        65 => F7,
+        66 => F8,
+        67 => F9,
+        68 => F10,
+        69 => Numlock,
+        70 => Scroll,
+        71 => Numpad7,
+        72 => Numpad8,
+        73 => Numpad9,
+        74 => Subtract,
+        75 => Numpad4,
+        76 => Numpad5,
+        77 => Numpad6,
+        78 => Add,
+        79 => Numpad1,
+        80 => Numpad2,
+        81 => Numpad3,
+        82 => Numpad0,
+        83 => Decimal,
+        86 => OEM102,
+        87 => F11,
+        88 => F12,
+        89 => AbntC1,
+        92 => Convert,
+        93 => Kana,
+        94 => NoConvert,
+        96 => NumpadEnter,
+        97 => RControl,
+        98 => Divide,
+        99 => Sysrq,
+        100 => RAlt,
+        102 => Home,
+        103 => Up,
+        104 => PageUp,
+        105 => Left,
+        106 => Right,
+        107 => End,
+        108 => Down,
+        109 => PageDown,
+        110 => Insert,
+        111 => Delete,
+        113 => Mute,
+        114 => VolumeDown,
+        115 => VolumeUp,
+        116 => Power,
+        117 => NumpadEquals,
+        119 => Pause,
+        121 => NumpadComma,
+        124 => Yen,
+        125 => LWin,
+        126 => RWin,
+        127 => Compose,
+        183 => F13,
+        184 => F14,
+        185 => F15,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::evdev_to_physical_key;
+    use VirtualKeyCode;
+
+    #[test]
+    fn codes_map_to_us_qwerty_positions() {
+        // KEY_Q, KEY_A, KEY_Z and KEY_SEMICOLON, whatever the layout puts there.
+        assert_eq!(evdev_to_physical_key(16), Some(VirtualKeyCode::Q));
+        assert_eq!(evdev_to_physical_key(30), Some(VirtualKeyCode::A));
+        assert_eq!(evdev_to_physical_key(44), Some(VirtualKeyCode::Z));
+        assert_eq!(evdev_to_physical_key(39), Some(VirtualKeyCode::Semicolon));
+        // KEY_KPENTER is told apart from KEY_ENTER.
+        assert_eq!(evdev_to_physical_key(28), Some(VirtualKeyCode::Return));
+        assert_eq!(evdev_to_physical_key(96), Some(VirtualKeyCode::NumpadEnter));
+        assert_eq!(evdev_to_physical_key(0), None);
+        assert_eq!(evdev_to_physical_key(0x2ff), None);
+    }
+}
@@ -0,0 +1,395 @@
+//! Gamepads are read from their evdev device nodes in `/dev/input`, which udev makes accessible to
+//! the user logged in at the seat. Device nodes that appear later on are noticed with inotify.
+
+use std::collections::VecDeque;
+use std::ffi::CString;
+use std::fs::{self, File, OpenOptions};
+use std::{io, mem};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::path::{Path, PathBuf};
+
+use libc;
+
+use {DeviceEvent, ElementState, GamepadAxis, GamepadButton};
+
+const INPUT_DIR: &'static str = "/dev/input";
+
+const EV_KEY: u16 = 0x01;
+const EV_ABS: u16 = 0x03;
+const KEY_MAX: usize = 0x2ff;
+const BTN_GAMEPAD: usize = 0x130;
+const ABS_X: u16 = 0x00;
+const ABS_Y: u16 = 0x01;
+const ABS_Z: u16 = 0x02;
+const ABS_RX: u16 = 0x03;
+const ABS_RY: u16 = 0x04;
+const ABS_RZ: u16 = 0x05;
+const ABS_GAS: u16 = 0x09;
+const ABS_BRAKE: u16 = 0x0a;
+const ABS_HAT0X: u16 = 0x10;
+const ABS_HAT0Y: u16 = 0x11;
+const ABS_MAX: usize = 0x3f;
+
+#[repr(C)]
+struct InputEvent {
+    time: libc::timeval,
+    type_: u16,
+    code: u16,
+    value: i32,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct AbsInfo {
+    value: i32,
+    minimum: i32,
+    maximum: i32,
+    fuzz: i32,
+    flat: i32,
+    resolution: i32,
+}
+
+// `EVIOCGBIT(ev, len)`, which reads the bitmask of the codes of type `ev` the device reports.
+fn eviocgbit(ev: u16, len: usize) -> libc::c_ulong {
+    request_code_read!(b'E', 0x20 + ev, len) as libc::c_ulong
+}
+
+// `EVIOCGABS(abs)`, which reads the range of an absolute axis.
+fn eviocgabs(abs: u16) -> libc::c_ulong {
+    request_code_read!(b'E', 0x40 + abs, mem::size_of::<AbsInfo>()) as libc::c_ulong
+}
+
+// Whether the code is set in a bitmask read with `EVIOCGBIT`.
+fn has_code(bits: &[u8], code: usize) -> bool {
+    bits.get(code / 8).map_or(false, |byte| byte & (1 << (code % 8)) != 0)
+}
+
+fn button(code: u16) -> Option<GamepadButton> {
+    Some(match code {
+        0x130 => GamepadButton::South,
+        0x131 => GamepadButton::East,
+        0x133 => GamepadButton::North,
+        0x134 => GamepadButton::West,
+        0x136 => GamepadButton::LeftBumper,
+        0x137 => GamepadButton::RightBumper,
+        0x138 => GamepadButton::LeftTrigger,
+        0x139 => GamepadButton::RightTrigger,
+        0x13a => GamepadButton::Select,
+        0x13b => GamepadButton::Start,
+        0x13c => GamepadButton::Mode,
+        0x13d => GamepadButton::LeftStick,
+        0x13e => GamepadButton::RightStick,
+        0x220 => GamepadButton::DPadUp,
+        0x221 => GamepadButton::DPadDown,
+        0x222 => GamepadButton::DPadLeft,
+        0x223 => GamepadButton::DPadRight,
+        _ => return None,
+    })
+}
+
+// Assigns the absolute axes the gamepad reports to the sticks and triggers. Most gamepads report
+// the right stick as `ABS_RX`/`ABS_RY` and the triggers as `ABS_Z`/`ABS_RZ`, but generic HID
+// gamepads report the right stick as `ABS_Z`/`ABS_RZ`, and their triggers, if they have analog
+// ones, as `ABS_BRAKE`/`ABS_GAS`.
+fn map_axes(abs_bits: &[u8]) -> Vec<(u16, GamepadAxis)> {
+    let has = |code: u16| has_code(abs_bits, code as usize);
+    let mut axes = Vec::new();
+    if has(ABS_X) && has(ABS_Y) {
+        axes.push((ABS_X, GamepadAxis::LeftStickX));
+        axes.push((ABS_Y, GamepadAxis::LeftStickY));
+    }
+    let triggers = if has(ABS_RX) && has(ABS_RY) {
+        axes.push((ABS_RX, GamepadAxis::RightStickX));
+        axes.push((ABS_RY, GamepadAxis::RightStickY));
+        if has(ABS_Z) || has(ABS_RZ) {
+            (ABS_Z, ABS_RZ)
+        } else {
+            (ABS_BRAKE, ABS_GAS)
+        }
+    } else {
+        if has(ABS_Z) && has(ABS_RZ) {
+            axes.push((ABS_Z, GamepadAxis::RightStickX));
+            axes.push((ABS_RZ, GamepadAxis::RightStickY));
+        }
+        (ABS_BRAKE, ABS_GAS)
+    };
+    if has(triggers.0) {
+        axes.push((triggers.0, GamepadAxis::LeftTrigger));
+    }
+    if has(triggers.1) {
+        axes.push((triggers.1, GamepadAxis::RightTrigger));
+    }
+    axes
+}
+
+// Maps `value` from the range of the axis to -1.0..1.0 for sticks, or 0.0..1.0 for triggers.
+fn normalize(axis: GamepadAxis, value: i32, (minimum, maximum): (i32, i32)) -> f64 {
+    let value = (value - minimum) as f64 / (maximum - minimum) as f64;
+    let value = match axis {
+        GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => value,
+        _ => value * 2.0 - 1.0,
+    };
+    value.max(-1.0).min(1.0)
+}
+
+/// The gamepads that are plugged in, and the events read from them that are yet to be sent.
+#[derive(Debug)]
+pub struct Gamepads {
+    // Watches `/dev/input` for device nodes being created, or made accessible by udev.
+    inotify: Option<File>,
+    devices: Vec<Gamepad>,
+    next_id: u32,
+    events: VecDeque<(u32, DeviceEvent)>,
+}
+
+#[derive(Debug)]
+struct Gamepad {
+    // Ids aren't reused, so that events can't be attributed to a gamepad plugged in later on.
+    id: u32,
+    path: PathBuf,
+    file: File,
+    // The code, meaning and range of each axis the gamepad has.
+    axes: Vec<(u16, GamepadAxis, (i32, i32))>,
+    // The directional pad is reported as a hat by most gamepads, and as buttons by some.
+    hat: (i32, i32),
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Gamepads::new()
+    }
+}
+
+impl Gamepads {
+    pub fn new() -> Gamepads {
+        let inotify = unsafe {
+            let fd = libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC);
+            if fd < 0 {
+                None
+            } else {
+                let file = File::from_raw_fd(fd);
+                let dir = CString::new(INPUT_DIR).unwrap();
+                if libc::inotify_add_watch(fd, dir.as_ptr(), libc::IN_CREATE | libc::IN_ATTRIB) < 0 {
+                    None
+                } else {
+                    Some(file)
+                }
+            }
+        };
+        let mut gamepads = Gamepads {
+            inotify,
+            devices: Vec::new(),
+            next_id: 0,
+            events: VecDeque::new(),
+        };
+        gamepads.scan();
+        gamepads
+    }
+
+    /// The file descriptors to poll for reading.
+    pub fn fds(&self) -> Vec<RawFd> {
+        self.inotify
+            .iter()
+            .map(AsRawFd::as_raw_fd)
+            .chain(self.devices.iter().map(|device| device.file.as_raw_fd()))
+            .collect()
+    }
+
+    /// Reads from `fd`, which was polled as ready.
+    pub fn dispatch(&mut self, fd: RawFd) {
+        if self.inotify.as_ref().map(AsRawFd::as_raw_fd) == Some(fd) {
+            // Which node changed doesn't matter, since the nodes already opened are skipped.
+            let mut buffer = [0u8; 4096];
+            while unsafe { libc::read(fd, buffer.as_mut_ptr() as *mut _, buffer.len()) } > 0 {}
+            self.scan();
+        } else if let Some(index) = self.devices.iter().position(|device| device.file.as_raw_fd() == fd) {
+            if self.devices[index].read(&mut self.events).is_err() {
+                // The gamepad was unplugged.
+                let device = self.devices.remove(index);
+                self.events.push_back((device.id, DeviceEvent::Removed));
+            }
+        }
+    }
+
+    pub fn has_events(&self) -> bool {
+        !self.events.is_empty()
+    }
+
+    /// Returns the events read so far, along with the id of the gamepad they come from.
+    pub fn take_events(&mut self) -> Vec<(u32, DeviceEvent)> {
+        self.events.drain(..).collect()
+    }
+
+    // Opens the gamepads among the device nodes that aren't open yet.
+    fn scan(&mut self) {
+        let entries = match fs::read_dir(INPUT_DIR) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            let is_event_node = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| name.starts_with("event"));
+            if !is_event_node || self.devices.iter().any(|device| device.path == path) {
+                continue;
+            }
+            if let Some(device) = Gamepad::open(&path, self.next_id) {
+                self.next_id += 1;
+                self.events.push_back((device.id, DeviceEvent::Added));
+                self.devices.push(device);
+            }
+        }
+    }
+}
+
+impl Gamepad {
+    // Returns `None` if the device node can't be opened, or isn't a gamepad.
+    fn open(path: &Path, id: u32) -> Option<Gamepad> {
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+            .open(path)
+            .ok()?;
+        let fd = file.as_raw_fd();
+
+        let mut keys = [0u8; KEY_MAX / 8 + 1];
+        if unsafe { libc::ioctl(fd, eviocgbit(EV_KEY, keys.len()) as _, keys.as_mut_ptr()) } < 0 {
+            return None;
+        }
+        if !has_code(&keys, BTN_GAMEPAD) {
+            return None;
+        }
+
+        // A gamepad without absolute axes, or whose axes can't be queried, only has buttons.
+        let mut abs_bits = [0u8; ABS_MAX / 8 + 1];
+        unsafe { libc::ioctl(fd, eviocgbit(EV_ABS, abs_bits.len()) as _, abs_bits.as_mut_ptr()) };
+        let mut axes = Vec::new();
+        for (code, axis) in map_axes(&abs_bits) {
+            let mut info = AbsInfo::default();
+            if unsafe { libc::ioctl(fd, eviocgabs(code) as _, &mut info as *mut AbsInfo) } >= 0
+                && info.maximum > info.minimum
+            {
+                axes.push((code, axis, (info.minimum, info.maximum)));
+            }
+        }
+
+        Some(Gamepad {
+            id,
+            path: path.to_owned(),
+            file,
+            axes,
+            hat: (0, 0),
+        })
+    }
+
+    // Reads the pending events. Fails once the gamepad is unplugged.
+    fn read(&mut self, events: &mut VecDeque<(u32, DeviceEvent)>) -> io::Result<()> {
+        loop {
+            let mut input: InputEvent = unsafe { mem::zeroed() };
+            let size = mem::size_of::<InputEvent>();
+            let read = unsafe {
+                libc::read(self.file.as_raw_fd(), &mut input as *mut InputEvent as *mut _, size)
+            };
+            if read < 0 {
+                let err = io::Error::last_os_error();
+                return match err.kind() {
+                    io::ErrorKind::WouldBlock => Ok(()),
+                    io::ErrorKind::Interrupted => continue,
+                    _ => Err(err),
+                };
+            }
+            if read as usize != size {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated input event"));
+            }
+            // Events dropped by the kernel because we didn't read fast enough are simply lost,
+            // which only matters for buttons released in the meantime.
+            match input.type_ {
+                EV_KEY => if let Some(button) = button(input.code) {
+                    // A value of 2 is a repeat of a held button.
+                    let state = match input.value {
+                        0 => ElementState::Released,
+                        1 => ElementState::Pressed,
+                        _ => continue,
+                    };
+                    events.push_back((self.id, DeviceEvent::GamepadButton { button, state }));
+                },
+                EV_ABS if input.code == ABS_HAT0X || input.code == ABS_HAT0Y => {
+                    let (previous, negative, positive) = if input.code == ABS_HAT0X {
+                        (mem::replace(&mut self.hat.0, input.value), GamepadButton::DPadLeft, GamepadButton::DPadRight)
+                    } else {
+                        (mem::replace(&mut self.hat.1, input.value), GamepadButton::DPadUp, GamepadButton::DPadDown)
+                    };
+                    let button_for = |value: i32| if value < 0 { negative } else { positive };
+                    if previous != 0 && previous.signum() != input.value.signum() {
+                        let button = button_for(previous);
+                        events.push_back((self.id, DeviceEvent::GamepadButton { button, state: ElementState::Released }));
+                    }
+                    if input.value != 0 && previous.signum() != input.value.signum() {
+                        let button = button_for(input.value);
+                        events.push_back((self.id, DeviceEvent::GamepadButton { button, state: ElementState::Pressed }));
+                    }
+                },
+                EV_ABS => if let Some(&(_, axis, range)) = self.axes.iter().find(|&&(code, _, _)| code == input.code) {
+                    let value = normalize(axis, input.value, range);
+                    events.push_back((self.id, DeviceEvent::GamepadAxis { axis, value }));
+                },
+                _ => (),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn abs_bits(codes: &[u16]) -> [u8; ABS_MAX / 8 + 1] {
+        let mut bits = [0u8; ABS_MAX / 8 + 1];
+        for &code in codes {
+            bits[code as usize / 8] |= 1 << (code % 8);
+        }
+        bits
+    }
+
+    #[test]
+    fn xbox_layout_axes_are_mapped() {
+        let bits = abs_bits(&[ABS_X, ABS_Y, ABS_Z, ABS_RX, ABS_RY, ABS_RZ, ABS_HAT0X, ABS_HAT0Y]);
+        assert_eq!(map_axes(&bits), vec![
+            (ABS_X, GamepadAxis::LeftStickX),
+            (ABS_Y, GamepadAxis::LeftStickY),
+            (ABS_RX, GamepadAxis::RightStickX),
+            (ABS_RY, GamepadAxis::RightStickY),
+            (ABS_Z, GamepadAxis::LeftTrigger),
+            (ABS_RZ, GamepadAxis::RightTrigger),
+        ]);
+    }
+
+    #[test]
+    fn hid_layout_axes_are_mapped() {
+        let bits = abs_bits(&[ABS_X, ABS_Y, ABS_Z, ABS_RZ, ABS_GAS, ABS_BRAKE, ABS_HAT0X, ABS_HAT0Y]);
+        assert_eq!(map_axes(&bits), vec![
+            (ABS_X, GamepadAxis::LeftStickX),
+            (ABS_Y, GamepadAxis::LeftStickY),
+            (ABS_Z, GamepadAxis::RightStickX),
+            (ABS_RZ, GamepadAxis::RightStickY),
+            (ABS_BRAKE, GamepadAxis::LeftTrigger),
+            (ABS_GAS, GamepadAxis::RightTrigger),
+        ]);
+        // Only the directional pad.
+        assert_eq!(map_axes(&abs_bits(&[ABS_HAT0X, ABS_HAT0Y])), vec![]);
+    }
+
+    #[test]
+    fn axes_are_normalized() {
+        assert_eq!(normalize(GamepadAxis::LeftStickX, -32768, (-32768, 32767)), -1.0);
+        assert_eq!(normalize(GamepadAxis::LeftStickX, 32767, (-32768, 32767)), 1.0);
+        assert_eq!(normalize(GamepadAxis::RightStickY, 128, (0, 256)), 0.0);
+        assert_eq!(normalize(GamepadAxis::LeftTrigger, 0, (0, 255)), 0.0);
+        assert_eq!(normalize(GamepadAxis::RightTrigger, 255, (0, 255)), 1.0);
+        // Values outside of the advertised range are clamped.
+        assert_eq!(normalize(GamepadAxis::RightTrigger, 300, (0, 255)), 1.0);
+    }
+}
@@ -0,0 +1,57 @@
+//! Both X11 and Wayland tell what the keyboard layout produces with keysyms, which Xlib and
+//! xkbcommon number the same way.
+
+use std::char;
+
+use {LogicalKey, VirtualKeyCode};
+
+/// Returns the character a keysym stands for.
+///
+/// The Latin-1 keysyms are the character's code point, and every other character has a keysym
+/// that is its code point plus `0x0100_0000`. The legacy keysyms of other scripts aren't handled,
+/// and neither are the keysyms of keys that don't produce text, such as the arrows.
+pub fn keysym_to_char(keysym: u32) -> Option<char> {
+    match keysym {
+        0x20...0x7e | 0xa0...0xff => char::from_u32(keysym),
+        0x0100_0100...0x0110_ffff => char::from_u32(keysym - 0x0100_0000),
+        _ => None,
+    }
+}
+
+/// Returns the logical key of a keysym, falling back to the key's symbolic name for keysyms that
+/// don't stand for a character.
+pub fn keysym_to_logical_key<F>(keysym: u32, named: F) -> Option<LogicalKey>
+    where F: FnOnce(u32) -> Option<VirtualKeyCode>
+{
+    keysym_to_char(keysym)
+        .and_then(LogicalKey::from_char)
+        .or_else(|| named(keysym).map(LogicalKey::Named))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{keysym_to_char, keysym_to_logical_key};
+    use {LogicalKey, VirtualKeyCode};
+
+    #[test]
+    fn keysyms_map_to_characters() {
+        // XK_a, XK_colon, XK_eacute
+        assert_eq!(keysym_to_char(0x61), Some('a'));
+        assert_eq!(keysym_to_char(0x3a), Some(':'));
+        assert_eq!(keysym_to_char(0xe9), Some('é'));
+        // XK_EuroSign is a legacy keysym, but U+20AC has its Unicode keysym.
+        assert_eq!(keysym_to_char(0x0100_20ac), Some('€'));
+        // XK_Return, XK_Left
+        assert_eq!(keysym_to_char(0xff0d), None);
+        assert_eq!(keysym_to_char(0xff51), None);
+    }
+
+    #[test]
+    fn keysyms_map_to_logical_keys() {
+        let named = |keysym| if keysym == 0xff0d { Some(VirtualKeyCode::Return) } else { None };
+        assert_eq!(keysym_to_logical_key(0x3a, named), Some(LogicalKey::Named(VirtualKeyCode::Colon)));
+        assert_eq!(keysym_to_logical_key(0xe9, named), Some(LogicalKey::Character("é".to_owned())));
+        assert_eq!(keysym_to_logical_key(0xff0d, named), Some(LogicalKey::Named(VirtualKeyCode::Return)));
+        assert_eq!(keysym_to_logical_key(0xff51, named), None);
+    }
+}
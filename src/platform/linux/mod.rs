@@ -4,29 +4,49 @@ use std::collections::VecDeque;
 use std::{env, mem};
 use std::ffi::CStr;
 use std::os::raw::*;
+use std::os::unix::io::RawFd;
 use std::sync::Arc;
+use std::time::Duration;
 
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::RawWindowHandle;
 use sctk::reexports::client::ConnectError;
 
 use {
     CreationError,
     CursorState,
     EventsLoopClosed,
+    ExternalError,
+    Fullscreen,
     Icon,
+    Insets,
     LogicalPosition,
     LogicalSize,
     MouseCursor,
+    NotSupportedError,
     PhysicalPosition,
     PhysicalSize,
+    ResizeDirection,
     ControlFlow,
+    StackOrder,
+    UserAttentionType,
+    Orientation,
+    VideoMode,
     WindowAttributes,
 };
+use os::unix::{FdInterest, MouseMotionUnits};
 use window::MonitorId as RootMonitorId;
 use self::x11::{XConnection, XError};
 use self::x11::ffi::XVisualInfo;
 pub use self::x11::XNotSupported;
 
 mod dlopen;
+mod evdev;
+#[cfg(all(feature = "gamepad", target_os = "linux"))]
+mod gamepad;
+mod keysym;
+mod wakeup_fds;
+mod xcursor;
 pub mod wayland;
 pub mod x11;
 
@@ -42,12 +62,14 @@ const BACKEND_PREFERENCE_ENV_VAR: &str = "WINIT_UNIX_BACKEND";
 #[derive(Clone, Default)]
 pub struct PlatformSpecificWindowBuilderAttributes {
     pub visual_infos: Option<XVisualInfo>,
+    pub visual_id: Option<c_ulong>,
     pub screen_id: Option<i32>,
     pub resize_increments: Option<(u32, u32)>,
     pub base_size: Option<(u32, u32)>,
     pub class: Option<(String, String)>,
     pub override_redirect: bool,
     pub x11_window_type: x11::util::WindowType,
+    pub window_group_leader: Option<WindowId>,
 }
 
 thread_local!(
@@ -62,15 +84,31 @@ pub enum Window {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub enum WindowId {
     X(x11::WindowId),
     Wayland(wayland::WindowId),
 }
 
+impl WindowId {
+    pub unsafe fn dummy() -> Self {
+        WindowId::X(x11::WindowId::dummy())
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub enum DeviceId {
     X(x11::DeviceId),
     Wayland(wayland::DeviceId),
+    #[cfg(all(feature = "gamepad", target_os = "linux"))]
+    Gamepad(u32),
+}
+
+impl DeviceId {
+    pub unsafe fn dummy() -> Self {
+        DeviceId::X(x11::DeviceId::dummy())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -119,6 +157,46 @@ impl MonitorId {
             &MonitorId::Wayland(ref m) => m.get_hidpi_factor() as f64,
         }
     }
+
+    #[inline]
+    pub fn get_dpi(&self) -> Option<f64> {
+        match self {
+            &MonitorId::X(ref m) => m.get_dpi(),
+            &MonitorId::Wayland(ref m) => m.get_dpi(),
+        }
+    }
+
+    #[inline]
+    pub fn is_primary(&self) -> bool {
+        match self {
+            &MonitorId::X(ref m) => m.is_primary(),
+            &MonitorId::Wayland(ref m) => m.is_primary(),
+        }
+    }
+
+    #[inline]
+    pub fn get_current_video_mode(&self) -> Option<VideoMode> {
+        match self {
+            &MonitorId::X(ref m) => m.get_current_video_mode(),
+            &MonitorId::Wayland(ref m) => m.get_current_video_mode(),
+        }
+    }
+
+    #[inline]
+    pub fn get_video_modes(&self) -> Vec<VideoMode> {
+        match self {
+            &MonitorId::X(ref m) => m.get_video_modes(),
+            &MonitorId::Wayland(ref m) => m.get_video_modes(),
+        }
+    }
+
+    #[inline]
+    pub fn get_orientation(&self) -> Orientation {
+        match self {
+            &MonitorId::X(ref m) => m.get_orientation(),
+            &MonitorId::Wayland(ref m) => m.get_orientation(),
+        }
+    }
 }
 
 impl Window {
@@ -170,6 +248,14 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn request_redraw(&self) {
+        match self {
+            &Window::X(ref w) => w.request_redraw(),
+            &Window::Wayland(ref w) => w.request_redraw(),
+        }
+    }
+
     #[inline]
     pub fn get_position(&self) -> Option<LogicalPosition> {
         match self {
@@ -202,6 +288,14 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn get_inner_size_physical(&self) -> Option<(u32, u32)> {
+        match self {
+            &Window::X(ref w) => w.get_inner_size_physical(),
+            &Window::Wayland(ref w) => w.get_inner_size_physical(),
+        }
+    }
+
     #[inline]
     pub fn get_outer_size(&self) -> Option<LogicalSize> {
         match self {
@@ -210,6 +304,22 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn get_outer_size_physical(&self) -> Option<(u32, u32)> {
+        match self {
+            &Window::X(ref w) => w.get_outer_size_physical(),
+            &Window::Wayland(ref w) => w.get_outer_size_physical(),
+        }
+    }
+
+    #[inline]
+    pub fn get_decoration_insets(&self) -> Option<Insets> {
+        match self {
+            &Window::X(ref w) => w.get_decoration_insets(),
+            &Window::Wayland(ref w) => w.get_decoration_insets(),
+        }
+    }
+
     #[inline]
     pub fn set_inner_size(&self, size: LogicalSize) {
         match self {
@@ -242,6 +352,14 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        match self {
+            &Window::X(ref w) => w.is_resizable(),
+            &Window::Wayland(ref w) => w.is_resizable(),
+        }
+    }
+
     #[inline]
     pub fn set_cursor(&self, cursor: MouseCursor) {
         match self {
@@ -251,13 +369,65 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_cursor_state(&self, state: CursorState) -> Result<(), String> {
+    pub fn set_animated_cursor(
+        &self,
+        frames: Vec<(Icon, Duration)>,
+        hotspot: (u32, u32),
+    ) -> Result<(), ExternalError> {
+        match self {
+            &Window::X(ref w) => w.set_animated_cursor(frames, hotspot),
+            &Window::Wayland(ref w) => w.set_animated_cursor(frames, hotspot),
+        }
+    }
+
+    #[inline]
+    pub fn set_cursor_image(&self, image: Icon, hotspot: (u32, u32)) -> Result<(), ExternalError> {
+        match self {
+            &Window::X(ref w) => w.set_cursor_image(image, hotspot),
+            &Window::Wayland(ref w) => w.set_cursor_image(image, hotspot),
+        }
+    }
+
+    #[inline]
+    pub fn set_cursor_by_name(&self, name: &str) -> Result<(), ExternalError> {
+        match self {
+            &Window::X(ref w) => w.set_cursor_by_name(name),
+            &Window::Wayland(ref w) => w.set_cursor_by_name(name),
+        }
+    }
+
+    #[inline]
+    pub fn is_cursor_visible(&self) -> bool {
+        match self {
+            &Window::X(ref w) => w.is_cursor_visible(),
+            &Window::Wayland(ref w) => w.is_cursor_visible(),
+        }
+    }
+
+    #[inline]
+    pub fn set_cursor_state(&self, state: CursorState) -> Result<(), ExternalError> {
         match self {
             &Window::X(ref w) => w.set_cursor_state(state),
             &Window::Wayland(ref w) => w.set_cursor_state(state)
         }
     }
 
+    #[inline]
+    pub fn set_keyboard_grab(&self, grab: bool) -> Result<(), ExternalError> {
+        match self {
+            &Window::X(ref w) => w.set_keyboard_grab(grab),
+            &Window::Wayland(ref w) => w.set_keyboard_grab(grab),
+        }
+    }
+
+    #[inline]
+    pub fn confine_cursor_to_monitor(&self, confine: bool) -> Result<(), ExternalError> {
+        match self {
+            &Window::X(ref w) => w.confine_cursor_to_monitor(confine),
+            &Window::Wayland(_) => Err(ExternalError::NotSupported(NotSupportedError::new())),
+        }
+    }
+
     #[inline]
     pub fn get_hidpi_factor(&self) -> f64 {
        match self {
@@ -267,13 +437,30 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_cursor_position(&self, position: LogicalPosition) -> Result<(), ()> {
+    pub fn set_cursor_position(&self, position: LogicalPosition) -> Result<(), ExternalError> {
         match self {
             &Window::X(ref w) => w.set_cursor_position(position),
             &Window::Wayland(ref w) => w.set_cursor_position(position),
         }
     }
 
+    #[cfg(feature = "raw-window-handle")]
+    #[inline]
+    pub fn raw_window_handle(&self) -> RawWindowHandle {
+        match self {
+            &Window::X(ref w) => w.raw_window_handle(),
+            &Window::Wayland(ref w) => w.raw_window_handle(),
+        }
+    }
+
+    #[inline]
+    pub fn set_minimized(&self, minimized: bool) -> Result<(), ExternalError> {
+        match self {
+            &Window::X(ref w) => w.set_minimized(minimized),
+            &Window::Wayland(ref w) => w.set_minimized(minimized),
+        }
+    }
+
     #[inline]
     pub fn set_maximized(&self, maximized: bool) {
         match self {
@@ -283,10 +470,18 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_fullscreen(&self, monitor: Option<RootMonitorId>) {
+    pub fn get_current_fullscreen(&self) -> Option<Fullscreen> {
+        match self {
+            &Window::X(ref w) => w.get_current_fullscreen(),
+            &Window::Wayland(ref w) => w.get_current_fullscreen(),
+        }
+    }
+
+    #[inline]
+    pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) -> Result<(), ExternalError> {
         match self {
-            &Window::X(ref w) => w.set_fullscreen(monitor),
-            &Window::Wayland(ref w) => w.set_fullscreen(monitor)
+            &Window::X(ref w) => w.set_fullscreen(fullscreen),
+            &Window::Wayland(ref w) => w.set_fullscreen(fullscreen)
         }
     }
 
@@ -299,10 +494,52 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_always_on_top(&self, always_on_top: bool) {
+    pub fn is_decorated(&self) -> bool {
+        match self {
+            &Window::X(ref w) => w.is_decorated(),
+            &Window::Wayland(ref w) => w.is_decorated(),
+        }
+    }
+
+    #[inline]
+    pub fn set_always_on_top(&self, always_on_top: bool) -> Result<(), ExternalError> {
         match self {
             &Window::X(ref w) => w.set_always_on_top(always_on_top),
-            &Window::Wayland(_) => (),
+            &Window::Wayland(_) => Err(ExternalError::NotSupported(NotSupportedError::new())),
+        }
+    }
+
+    #[inline]
+    pub fn is_always_on_top(&self) -> bool {
+        match self {
+            &Window::X(ref w) => w.is_always_on_top(),
+            &Window::Wayland(_) => false,
+        }
+    }
+
+    #[inline]
+    pub fn restack(&self, relative_to: WindowId, order: StackOrder) -> Result<(), ExternalError> {
+        match (self, relative_to) {
+            (&Window::X(ref w), WindowId::X(relative_to)) => w.restack(relative_to, order),
+            // Wayland doesn't let clients control the stacking order.
+            (&Window::Wayland(_), _) => Err(ExternalError::NotSupported(NotSupportedError::new())),
+            (&Window::X(_), WindowId::Wayland(_)) => {
+                Err(ExternalError::Os("The windows belong to different backends".to_owned()))
+            },
+        }
+    }
+
+    #[inline]
+    pub fn set_content_protected(&self, _protected: bool) -> Result<(), ExternalError> {
+        // Neither X11 nor Wayland have a way to prevent other clients from capturing a window.
+        Err(ExternalError::NotSupported(NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_background_color(&self, color: Option<(u8, u8, u8, u8)>) {
+        match self {
+            &Window::X(ref w) => w.set_background_color(color),
+            &Window::Wayland(ref w) => w.set_background_color(color),
         }
     }
 
@@ -315,9 +552,50 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_ime_spot(&self, position: LogicalPosition) {
+    pub fn set_ime_spot(&self, position: LogicalPosition) -> Result<(), ExternalError> {
         match self {
             &Window::X(ref w) => w.set_ime_spot(position),
+            // There's no IME support until the text-input protocol is implemented
+            &Window::Wayland(_) => Err(ExternalError::NotSupported(NotSupportedError::new())),
+        }
+    }
+
+    #[inline]
+    pub fn set_ime_allowed(&self, allowed: bool, commit: bool) -> Result<(), ExternalError> {
+        match self {
+            &Window::X(ref w) => w.set_ime_allowed(allowed, commit),
+            &Window::Wayland(_) => Err(ExternalError::NotSupported(NotSupportedError::new())),
+        }
+    }
+
+    #[inline]
+    pub fn show_window_menu(&self, position: LogicalPosition) -> Result<(), ExternalError> {
+        match self {
+            &Window::X(ref w) => w.show_window_menu(position),
+            &Window::Wayland(ref w) => w.show_window_menu(position),
+        }
+    }
+
+    #[inline]
+    pub fn start_drag(&self) -> Result<(), ExternalError> {
+        match self {
+            &Window::X(ref w) => w.start_drag(),
+            &Window::Wayland(ref w) => w.start_drag(),
+        }
+    }
+
+    #[inline]
+    pub fn start_resize(&self, direction: ResizeDirection) -> Result<(), ExternalError> {
+        match self {
+            &Window::X(ref w) => w.start_resize(direction),
+            &Window::Wayland(ref w) => w.start_resize(direction),
+        }
+    }
+
+    #[inline]
+    pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
+        match self {
+            &Window::X(ref w) => w.request_user_attention(request_type),
             &Window::Wayland(_) => (),
         }
     }
@@ -476,6 +754,14 @@ r#"Failed to initialize any backend!
         }
     }
 
+    #[inline]
+    pub fn release_grabs(&self) {
+        match *self {
+            EventsLoop::Wayland(ref evlp) => evlp.release_grabs(),
+            EventsLoop::X(ref evlp) => evlp.release_grabs(),
+        }
+    }
+
     pub fn create_proxy(&self) -> EventsLoopProxy {
         match *self {
             EventsLoop::Wayland(ref evlp) => EventsLoopProxy::Wayland(evlp.create_proxy()),
@@ -509,6 +795,62 @@ r#"Failed to initialize any backend!
         }
     }
 
+    #[inline]
+    pub fn set_cursor_theme(&self, name: &str, size: u32) {
+        match *self {
+            EventsLoop::Wayland(ref evlp) => evlp.set_cursor_theme(name, size),
+            EventsLoop::X(ref evlp) => evlp.set_cursor_theme(name, size),
+        }
+    }
+
+    #[inline]
+    pub fn set_mouse_motion_units(&self, units: MouseMotionUnits) {
+        match *self {
+            EventsLoop::Wayland(ref evlp) => evlp.set_mouse_motion_units(units),
+            EventsLoop::X(ref evlp) => evlp.set_mouse_motion_units(units),
+        }
+    }
+
+    pub fn set_redraw_vsync(&self, enabled: bool) {
+        match *self {
+            EventsLoop::Wayland(ref evlp) => evlp.set_redraw_vsync(enabled),
+            EventsLoop::X(ref evlp) => evlp.set_redraw_vsync(enabled),
+        }
+    }
+
+    #[inline]
+    pub fn set_coalesce_moves(&self, coalesce: bool) {
+        match *self {
+            // Wayland doesn't tell clients where their windows are, so there's nothing to coalesce
+            EventsLoop::Wayland(_) => (),
+            EventsLoop::X(ref evlp) => evlp.set_coalesce_moves(coalesce),
+        }
+    }
+
+    #[inline]
+    pub fn set_handle_close_requests(&self, handle: bool) {
+        match *self {
+            EventsLoop::Wayland(_) => (),
+            EventsLoop::X(ref evlp) => evlp.set_handle_close_requests(handle),
+        }
+    }
+
+    #[inline]
+    pub fn wakeup_on_fd(&self, fd: RawFd, interest: FdInterest) {
+        match *self {
+            EventsLoop::Wayland(ref evlp) => evlp.wakeup_on_fd(fd, interest),
+            EventsLoop::X(ref evlp) => evlp.wakeup_on_fd(fd, interest),
+        }
+    }
+
+    #[inline]
+    pub fn remove_wakeup_fd(&self, fd: RawFd) {
+        match *self {
+            EventsLoop::Wayland(ref evlp) => evlp.remove_wakeup_fd(fd),
+            EventsLoop::X(ref evlp) => evlp.remove_wakeup_fd(fd),
+        }
+    }
+
     #[inline]
     pub fn x_connection(&self) -> Option<&Arc<XConnection>> {
         match *self {
@@ -0,0 +1,161 @@
+use std::cell::RefCell;
+use std::cmp;
+use std::io;
+use std::os::raw::c_short;
+use std::os::unix::io::RawFd;
+use std::time::Instant;
+
+use libc;
+
+use os::unix::FdInterest;
+#[cfg(all(feature = "gamepad", target_os = "linux"))]
+use super::gamepad::Gamepads;
+
+/// The file descriptors registered with `EventsLoopExt::wakeup_on_fd`, which are polled alongside
+/// the connection to the display server when the `EventsLoop` blocks.
+///
+/// With the `gamepad` feature, the device nodes of gamepads are polled as well. They're read here,
+/// and their events are handed out by `take_device_events` instead of being reported as ready.
+#[derive(Debug, Default)]
+pub struct WakeupFds {
+    fds: RefCell<Vec<(RawFd, FdInterest)>>,
+    #[cfg(all(feature = "gamepad", target_os = "linux"))]
+    gamepads: RefCell<Gamepads>,
+}
+
+impl WakeupFds {
+    pub fn insert(&self, fd: RawFd, interest: FdInterest) {
+        let mut fds = self.fds.borrow_mut();
+        fds.retain(|&(registered, _)| registered != fd);
+        fds.push((fd, interest));
+    }
+
+    pub fn remove(&self, fd: RawFd) {
+        self.fds.borrow_mut().retain(|&(registered, _)| registered != fd);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fds.borrow().is_empty() && self.gamepad_fds().is_empty()
+    }
+
+    /// Returns the events read from gamepads since the last call.
+    #[cfg(all(feature = "gamepad", target_os = "linux"))]
+    pub fn take_device_events<T>(&self) -> Vec<::Event<T>> {
+        self.gamepads
+            .borrow_mut()
+            .take_events()
+            .into_iter()
+            .map(|(id, event)| ::Event::DeviceEvent {
+                device_id: ::DeviceId(super::DeviceId::Gamepad(id)),
+                event,
+            })
+            .collect()
+    }
+
+    #[cfg(not(all(feature = "gamepad", target_os = "linux")))]
+    pub fn take_device_events<T>(&self) -> Vec<::Event<T>> {
+        Vec::new()
+    }
+
+    #[cfg(all(feature = "gamepad", target_os = "linux"))]
+    fn has_device_events(&self) -> bool {
+        self.gamepads.borrow().has_events()
+    }
+
+    #[cfg(not(all(feature = "gamepad", target_os = "linux")))]
+    fn has_device_events(&self) -> bool {
+        false
+    }
+
+    #[cfg(all(feature = "gamepad", target_os = "linux"))]
+    fn gamepad_fds(&self) -> Vec<RawFd> {
+        self.gamepads.borrow().fds()
+    }
+
+    #[cfg(not(all(feature = "gamepad", target_os = "linux")))]
+    fn gamepad_fds(&self) -> Vec<RawFd> {
+        Vec::new()
+    }
+
+    #[cfg(all(feature = "gamepad", target_os = "linux"))]
+    fn dispatch_gamepad(&self, fd: RawFd) {
+        self.gamepads.borrow_mut().dispatch(fd)
+    }
+
+    #[cfg(not(all(feature = "gamepad", target_os = "linux")))]
+    fn dispatch_gamepad(&self, _fd: RawFd) {}
+
+    /// Waits for at most `timeout` milliseconds (forever if negative) until `connection_fd` is
+    /// readable or one of the registered file descriptors is ready.
+    ///
+    /// Returns whether `connection_fd` is readable, along with the registered file descriptors
+    /// that are ready. Gamepads that are ready are read before returning.
+    pub fn poll(&self, connection_fd: RawFd, timeout: i32) -> (bool, Vec<RawFd>) {
+        let mut pollfds = vec![libc::pollfd {
+            fd: connection_fd,
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+        pollfds.extend(self.fds.borrow().iter().map(|&(fd, interest)| libc::pollfd {
+            fd,
+            events: poll_events(interest),
+            revents: 0,
+        }));
+        let user_count = pollfds.len();
+        pollfds.extend(self.gamepad_fds().into_iter().map(|fd| libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        }));
+        // Gamepads that were plugged in when the `EventsLoop` was created are announced without
+        // waiting for anything else to happen.
+        let timeout = if self.has_device_events() { 0 } else { timeout };
+
+        loop {
+            let count = unsafe {
+                libc::poll(pollfds.as_mut_ptr(), pollfds.len() as libc::nfds_t, timeout)
+            };
+            if count >= 0 {
+                break;
+            }
+            if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+                // Let the caller read from the connection, which will report the error if there's
+                // something wrong with it.
+                return (true, Vec::new());
+            }
+        }
+
+        let connection_ready = pollfds[0].revents != 0;
+        let ready = pollfds[1..user_count]
+            .iter()
+            .filter(|pollfd| pollfd.revents != 0)
+            .map(|pollfd| pollfd.fd)
+            .collect();
+        for pollfd in &pollfds[user_count..] {
+            if pollfd.revents != 0 {
+                self.dispatch_gamepad(pollfd.fd);
+            }
+        }
+        (connection_ready, ready)
+    }
+}
+
+/// Converts `deadline` into a timeout for `WakeupFds::poll`, rounded up so that we don't wake up
+/// before the deadline.
+pub fn timeout_until(deadline: Instant) -> i32 {
+    let now = Instant::now();
+    if deadline <= now {
+        return 0;
+    }
+    let duration = deadline - now;
+    let millis = duration.as_secs() * 1000 + (duration.subsec_nanos() as u64 + 999_999) / 1_000_000;
+    cmp::min(millis, i32::max_value() as u64) as i32
+}
+
+fn poll_events(interest: FdInterest) -> c_short {
+    match interest {
+        FdInterest::Readable => libc::POLLIN,
+        FdInterest::Writable => libc::POLLOUT,
+        FdInterest::ReadWrite => libc::POLLIN | libc::POLLOUT,
+    }
+}
@@ -0,0 +1,89 @@
+use std::os::unix::io::AsRawFd;
+
+use sctk::reexports::client::Proxy;
+use sctk::reexports::client::protocol::{wl_buffer, wl_shm, wl_surface};
+use sctk::reexports::client::protocol::wl_buffer::RequestsTrait as BufferRequests;
+use sctk::reexports::client::protocol::wl_shm::RequestsTrait as ShmRequests;
+use sctk::reexports::client::protocol::wl_shm_pool::RequestsTrait as ShmPoolRequests;
+use sctk::reexports::client::protocol::wl_surface::RequestsTrait as SurfaceRequests;
+
+use super::create_shm_file;
+
+/// The color set by `Window::set_background_color`.
+///
+/// A Wayland window has no contents besides the buffers attached to its surface, so the color is
+/// drawn into a buffer of its own, which is committed when the window gets its initial size. Once
+/// the window was asked to draw, the contents of the surface belong to the application.
+pub struct Background {
+    shm: Proxy<wl_shm::WlShm>,
+    color: Option<(u8, u8, u8, u8)>,
+    // The alpha channel is ignored unless the window is transparent.
+    transparent: bool,
+    // Set once the first `RedrawRequested` was sent for the window.
+    done: bool,
+}
+
+impl Background {
+    pub fn new(shm: Proxy<wl_shm::WlShm>, transparent: bool) -> Background {
+        Background {
+            shm,
+            color: None,
+            transparent,
+            done: false,
+        }
+    }
+
+    pub fn set_color(&mut self, color: Option<(u8, u8, u8, u8)>) {
+        self.color = color;
+    }
+
+    /// Fills `surface` with the color, unless the window was already asked to draw.
+    ///
+    /// `size` is the logical size of the window, and `scale` the buffer scale of its surface.
+    pub fn draw(&self, surface: &Proxy<wl_surface::WlSurface>, size: (u32, u32), scale: i32) {
+        let (red, green, blue, alpha) = match self.color {
+            Some(color) if !self.done => color,
+            _ => return,
+        };
+        let (format, alpha) = if self.transparent {
+            (wl_shm::Format::Argb8888, alpha)
+        } else {
+            (wl_shm::Format::Xrgb8888, 255)
+        };
+        let premultiply = |channel: u8| ((channel as u32 * alpha as u32 + 127) / 255) as u8;
+        // `wl_shm` formats are little-endian
+        let pixel = [premultiply(blue), premultiply(green), premultiply(red), alpha];
+
+        let (width, height) = (size.0 as i32 * scale, size.1 as i32 * scale);
+        let mut data = Vec::with_capacity((width * height * 4) as usize);
+        for _ in 0..width * height {
+            data.extend_from_slice(&pixel);
+        }
+        let file = match create_shm_file(&data) {
+            Ok(file) => file,
+            Err(_) => return,
+        };
+        let pool = match self.shm.create_pool(file.as_raw_fd(), data.len() as i32) {
+            Ok(pool) => pool.implement(|_, _| ()),
+            Err(_) => return,
+        };
+        let buffer = pool.create_buffer(0, width, height, width * 4, format);
+        pool.destroy();
+        if let Ok(buffer) = buffer {
+            // The surface needs the buffer until the application attaches its own.
+            let buffer = buffer.implement(|event, buffer: Proxy<wl_buffer::WlBuffer>| {
+                if let wl_buffer::Event::Release = event {
+                    buffer.destroy();
+                }
+            });
+            surface.attach(Some(&buffer), 0, 0);
+            surface.damage(0, 0, size.0 as i32, size.1 as i32);
+            surface.commit();
+        }
+    }
+
+    /// Called as the window is first asked to draw, after which the color is no longer drawn.
+    pub fn finish(&mut self) {
+        self.done = true;
+    }
+}
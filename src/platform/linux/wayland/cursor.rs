@@ -0,0 +1,400 @@
+use std::{cmp, env, str};
+use std::os::unix::io::AsRawFd;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use sctk::reexports::client::Proxy;
+use sctk::reexports::client::cursor::{load_theme, CursorTheme};
+use sctk::reexports::client::protocol::{wl_buffer, wl_compositor, wl_pointer, wl_shm, wl_surface};
+use sctk::reexports::client::protocol::wl_buffer::RequestsTrait as BufferRequests;
+use sctk::reexports::client::protocol::wl_compositor::RequestsTrait as CompositorRequests;
+use sctk::reexports::client::protocol::wl_pointer::RequestsTrait as PointerRequests;
+use sctk::reexports::client::protocol::wl_shm::RequestsTrait as ShmRequests;
+use sctk::reexports::client::protocol::wl_shm_pool::RequestsTrait as ShmPoolRequests;
+use sctk::reexports::client::protocol::wl_surface::RequestsTrait as SurfaceRequests;
+
+use {ExternalError, Icon, MouseCursor};
+use platform::platform::xcursor::cursor_names;
+
+use super::create_shm_file;
+
+// Cursor size used when `XCURSOR_SIZE` isn't set, matching the default of most desktops.
+const DEFAULT_CURSOR_SIZE: u32 = 24;
+
+// The shortest time a frame of an animated cursor is shown, so that frames without a duration
+// don't keep the events loop busy.
+const MIN_FRAME_DURATION_MS: u64 = 10;
+
+/// The cursor a window shows.
+#[derive(Clone)]
+pub enum WindowCursor {
+    Standard(MouseCursor),
+    /// Set by `Window::set_cursor_image`. It's shared with the pointers currently showing it.
+    Image(Arc<CursorImage>),
+    /// Set by `WindowExt::set_cursor_by_name`, which checked that the theme has it.
+    Named(String),
+    /// Set by `Window::set_animated_cursor`.
+    Animated(Arc<AnimatedCursor>),
+}
+
+impl Default for WindowCursor {
+    fn default() -> Self {
+        WindowCursor::Standard(Default::default())
+    }
+}
+
+/// A cursor drawn from an image provided by the application, at logical size.
+pub struct CursorImage {
+    image: Icon,
+    hotspot: (u32, u32),
+    // The image is drawn at the buffer scale of the surface the pointer entered, so that it stays
+    // crisp on hidpi outputs. We keep one buffer per buffer scale we've encountered.
+    buffers: Mutex<Vec<(i32, ImageBuffer)>>,
+}
+
+/// A cursor cycling through images. The events loop moves on to the next frame when it's due,
+/// and shows it on the pointers that are over the window.
+pub struct AnimatedCursor {
+    frames: Vec<CursorImage>,
+    timer: Mutex<FrameTimer>,
+}
+
+impl AnimatedCursor {
+    /// When the next frame is due.
+    pub fn deadline(&self) -> Instant {
+        self.timer.lock().unwrap().next
+    }
+
+    /// Moves on to the frame that's due at `now`. Returns whether the frame changed.
+    pub fn advance(&self, now: Instant) -> bool {
+        self.timer.lock().unwrap().advance(now)
+    }
+
+    fn current_frame(&self) -> &CursorImage {
+        let current = self.timer.lock().unwrap().current;
+        &self.frames[current]
+    }
+}
+
+// Keeps track of the frame an animation is at.
+struct FrameTimer {
+    durations: Vec<Duration>,
+    current: usize,
+    // When the frame after `current` is due.
+    next: Instant,
+}
+
+impl FrameTimer {
+    fn new(durations: Vec<Duration>, now: Instant) -> FrameTimer {
+        let min = Duration::from_millis(MIN_FRAME_DURATION_MS);
+        let durations: Vec<_> = durations.into_iter().map(|duration| cmp::max(duration, min)).collect();
+        let next = now + durations[0];
+        FrameTimer {
+            durations,
+            current: 0,
+            next,
+        }
+    }
+
+    fn advance(&mut self, now: Instant) -> bool {
+        if now < self.next {
+            return false;
+        }
+        // Frames missed because the events loop wasn't running are skipped, rather than played
+        // back quickly. Whole cycles are skipped at once.
+        let cycle = self.durations.iter().fold(Duration::from_secs(0), |sum, &duration| sum + duration);
+        if now - self.next >= cycle {
+            self.next = now;
+        }
+        while self.next <= now {
+            self.current = (self.current + 1) % self.durations.len();
+            self.next += self.durations[self.current];
+        }
+        true
+    }
+}
+
+struct ImageBuffer {
+    buffer: Proxy<wl_buffer::WlBuffer>,
+}
+
+impl Drop for ImageBuffer {
+    fn drop(&mut self) {
+        self.buffer.destroy();
+    }
+}
+
+/// Loads cursor themes and applies cursor images to pointers.
+///
+/// The theme and size are taken from the `XCURSOR_THEME` and `XCURSOR_SIZE` environment
+/// variables, which is how the user's desktop communicates its cursor settings to clients. If
+/// unset, the default theme is used, at a size of 24 pixels.
+pub struct CursorManager {
+    compositor: Option<Proxy<wl_compositor::WlCompositor>>,
+    shm: Option<Proxy<wl_shm::WlShm>>,
+    theme_name: Option<String>,
+    size: u32,
+    // Themes are loaded at `size * scale`, so we keep one per buffer scale we've encountered.
+    themes: Vec<(i32, CursorTheme)>,
+}
+
+impl CursorManager {
+    pub fn new() -> CursorManager {
+        let theme_name = env::var("XCURSOR_THEME").ok();
+        let size = env::var("XCURSOR_SIZE")
+            .ok()
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(DEFAULT_CURSOR_SIZE);
+        CursorManager {
+            compositor: None,
+            shm: None,
+            theme_name,
+            size,
+            themes: Vec::new(),
+        }
+    }
+
+    // The globals aren't available yet when the seats are first advertised, so they're provided
+    // once the environment has been initialized.
+    pub fn set_globals(
+        &mut self,
+        compositor: Proxy<wl_compositor::WlCompositor>,
+        shm: Proxy<wl_shm::WlShm>,
+    ) {
+        self.compositor = Some(compositor);
+        self.shm = Some(shm);
+    }
+
+    pub fn set_theme(&mut self, name: &str, size: u32) {
+        self.theme_name = Some(name.to_owned());
+        self.size = size;
+        // The themes will be reloaded the next time they're needed
+        self.themes.clear();
+    }
+
+    pub fn create_surface(&self) -> Option<Proxy<wl_surface::WlSurface>> {
+        self.compositor
+            .as_ref()
+            .and_then(|compositor| compositor.create_surface().ok())
+            .map(|surface| surface.implement(|_, _| ()))
+    }
+
+    fn get_theme(&mut self, scale: i32) -> Option<&CursorTheme> {
+        if !self.themes.iter().any(|&(s, _)| s == scale) {
+            let theme = {
+                let shm = self.shm.as_ref()?;
+                load_theme(self.theme_name.as_ref().map(String::as_str), self.size * scale as u32, shm)
+            };
+            self.themes.push((scale, theme));
+        }
+        self.themes.iter().find(|&&(s, _)| s == scale).map(|&(_, ref theme)| theme)
+    }
+
+    /// Whether the theme has a cursor named `name`.
+    pub fn has_cursor(&mut self, name: &str, scale: i32) -> bool {
+        self.get_theme(scale).map_or(false, |theme| theme.get_cursor(name).is_some())
+    }
+
+    /// Sets the cursor image of `pointer` to the cursor named `name`, drawing it on `surface`.
+    ///
+    /// `scale` is the buffer scale of the surface the pointer entered, so the cursor stays crisp on
+    /// hidpi outputs.
+    pub fn set_cursor(
+        &mut self,
+        pointer: &Proxy<wl_pointer::WlPointer>,
+        surface: &Proxy<wl_surface::WlSurface>,
+        serial: u32,
+        name: &str,
+        scale: i32,
+    ) -> Result<(), ()> {
+        // Without version 3 the surface can't have a buffer scale, so we draw at scale 1.
+        let scale = if surface.version() >= 3 { scale } else { 1 };
+        let theme = self.get_theme(scale).ok_or(())?;
+        let cursor = theme.get_cursor(name).ok_or(())?;
+        let buffer = cursor.frame_buffer(0).ok_or(())?;
+        let (width, height, hotspot_x, hotspot_y, _) = cursor.frame_info(0).ok_or(())?;
+
+        if surface.version() >= 3 {
+            surface.set_buffer_scale(scale);
+        }
+        surface.attach(Some(&buffer), 0, 0);
+        surface.damage(0, 0, width as i32 / scale, height as i32 / scale);
+        surface.commit();
+        pointer.set_cursor(
+            serial,
+            Some(surface),
+            hotspot_x as i32 / scale,
+            hotspot_y as i32 / scale,
+        );
+        Ok(())
+    }
+
+    /// Creates a cursor from `image`, which is drawn right away at buffer scale `scale` so that
+    /// errors are reported to the caller.
+    pub fn create_image(&self, image: Icon, hotspot: (u32, u32), scale: i32) -> Result<CursorImage, ExternalError> {
+        let buffer = self.draw_image(&image, hotspot, scale)?;
+        Ok(CursorImage {
+            image,
+            hotspot,
+            buffers: Mutex::new(vec![(scale, buffer)]),
+        })
+    }
+
+    /// Creates an animated cursor, whose frames are drawn right away like those of `create_image`.
+    ///
+    /// `frames` must not be empty.
+    pub fn create_animated_cursor(
+        &self,
+        frames: Vec<(Icon, Duration)>,
+        hotspot: (u32, u32),
+        scale: i32,
+    ) -> Result<AnimatedCursor, ExternalError> {
+        let mut images = Vec::with_capacity(frames.len());
+        let mut durations = Vec::with_capacity(frames.len());
+        for (image, duration) in frames {
+            images.push(self.create_image(image, hotspot, scale)?);
+            durations.push(duration);
+        }
+        Ok(AnimatedCursor {
+            frames: images,
+            timer: Mutex::new(FrameTimer::new(durations, Instant::now())),
+        })
+    }
+
+    // Uploads `image`, scaled by `scale`, to a buffer the compositor can draw, in the
+    // premultiplied ARGB format every compositor supports.
+    fn draw_image(&self, image: &Icon, hotspot: (u32, u32), scale: i32) -> Result<ImageBuffer, ExternalError> {
+        let shm = self.shm
+            .as_ref()
+            .ok_or_else(|| ExternalError::Os("`wl_shm` isn't available yet".to_owned()))?;
+        let (image, _) = image.scale_cursor(hotspot, scale as f64);
+        let mut data = Vec::with_capacity(image.rgba.len());
+        for pixel in image.to_premultiplied_argb() {
+            // `wl_shm` formats are little-endian
+            data.extend_from_slice(&[pixel as u8, (pixel >> 8) as u8, (pixel >> 16) as u8, (pixel >> 24) as u8]);
+        }
+        let file = create_shm_file(&data)
+            .map_err(|err| ExternalError::Os(format!("Failed to create the cursor's buffer: {}", err)))?;
+
+        let (width, height) = (image.width as i32, image.height as i32);
+        let pool = shm.create_pool(file.as_raw_fd(), data.len() as i32)
+            .map_err(|_| ExternalError::Os("`wl_shm` is dead".to_owned()))?
+            .implement(|_, _| ());
+        // The buffer keeps the memory mapped after the pool and the file are gone.
+        let buffer = pool.create_buffer(0, width, height, width * 4, wl_shm::Format::Argb8888);
+        pool.destroy();
+        let buffer = buffer
+            .map_err(|_| ExternalError::Os("`wl_shm_pool` is dead".to_owned()))?
+            .implement(|_, _| ());
+        Ok(ImageBuffer { buffer })
+    }
+
+    /// Sets the cursor image of `pointer` to `image`.
+    ///
+    /// `scale` is the buffer scale of the surface the pointer entered. The image keeps its logical
+    /// size, and is drawn again the first time it's shown at a new scale.
+    pub fn set_image_cursor(
+        &self,
+        pointer: &Proxy<wl_pointer::WlPointer>,
+        surface: &Proxy<wl_surface::WlSurface>,
+        serial: u32,
+        image: &CursorImage,
+        scale: i32,
+    ) -> Result<(), ()> {
+        // Without version 3 the surface can't have a buffer scale, so we draw at scale 1.
+        let scale = if surface.version() >= 3 { scale } else { 1 };
+        let mut buffers = image.buffers.lock().unwrap();
+        if !buffers.iter().any(|&(s, _)| s == scale) {
+            let buffer = self.draw_image(&image.image, image.hotspot, scale).map_err(|_| ())?;
+            buffers.push((scale, buffer));
+        }
+        let buffer = buffers.iter().find(|&&(s, _)| s == scale).map(|&(_, ref buffer)| buffer).unwrap();
+        if surface.version() >= 3 {
+            surface.set_buffer_scale(scale);
+        }
+        surface.attach(Some(&buffer.buffer), 0, 0);
+        surface.damage(0, 0, image.image.width as i32, image.image.height as i32);
+        surface.commit();
+        // The hotspot is in surface coordinates, which are logical.
+        pointer.set_cursor(serial, Some(surface), image.hotspot.0 as i32, image.hotspot.1 as i32);
+        Ok(())
+    }
+
+    /// Sets the cursor image of `pointer` to the cursor of a window.
+    pub fn set_window_cursor(
+        &mut self,
+        pointer: &Proxy<wl_pointer::WlPointer>,
+        surface: &Proxy<wl_surface::WlSurface>,
+        serial: u32,
+        cursor: &WindowCursor,
+        scale: i32,
+    ) -> Result<(), ()> {
+        match *cursor {
+            WindowCursor::Standard(cursor) => self.set_mouse_cursor(pointer, surface, serial, cursor, scale),
+            WindowCursor::Image(ref image) => self.set_image_cursor(pointer, surface, serial, image, scale),
+            WindowCursor::Named(ref name) => self.set_cursor(pointer, surface, serial, name, scale),
+            WindowCursor::Animated(ref animation) => {
+                self.set_image_cursor(pointer, surface, serial, animation.current_frame(), scale)
+            },
+        }
+    }
+
+    /// Sets the cursor image of `pointer` to the first of the names of `cursor` that the theme
+    /// has, or to the default cursor if it has none of them. `MouseCursor::NoneCursor` hides the
+    /// cursor.
+    pub fn set_mouse_cursor(
+        &mut self,
+        pointer: &Proxy<wl_pointer::WlPointer>,
+        surface: &Proxy<wl_surface::WlSurface>,
+        serial: u32,
+        cursor: MouseCursor,
+        scale: i32,
+    ) -> Result<(), ()> {
+        if cursor == MouseCursor::NoneCursor {
+            pointer.set_cursor(serial, None, 0, 0);
+            return Ok(());
+        }
+        for name in cursor_names(cursor) {
+            // Strip the nul terminator
+            let name = str::from_utf8(&name[..name.len() - 1]).unwrap();
+            if self.set_cursor(pointer, surface, serial, name, scale).is_ok() {
+                return Ok(());
+            }
+        }
+        self.set_cursor(pointer, surface, serial, "left_ptr", scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::FrameTimer;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn frames_advance_when_they_are_due() {
+        let start = Instant::now();
+        let mut timer = FrameTimer::new(vec![ms(100), ms(50), ms(200)], start);
+        assert!(!timer.advance(start + ms(99)));
+        assert_eq!(timer.current, 0);
+        assert!(timer.advance(start + ms(100)));
+        assert_eq!((timer.current, timer.next), (1, start + ms(150)));
+        // The animation loops, and skips the frames it missed.
+        assert!(timer.advance(start + ms(360)));
+        assert_eq!((timer.current, timer.next), (0, start + ms(450)));
+    }
+
+    #[test]
+    fn frames_without_a_duration_are_shown_briefly() {
+        let start = Instant::now();
+        let mut timer = FrameTimer::new(vec![ms(0), ms(0)], start);
+        assert_eq!(timer.next, start + ms(10));
+        // Whole cycles are skipped at once.
+        assert!(timer.advance(start + ms(10_000)));
+        assert_eq!((timer.current, timer.next), (1, start + ms(10_010)));
+    }
+}
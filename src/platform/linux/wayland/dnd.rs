@@ -0,0 +1,283 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use libc;
+use percent_encoding::percent_decode;
+
+use WindowEvent;
+use platform::platform::wakeup_fds::timeout_until;
+
+use super::WindowId;
+use super::event_loop::EventsLoopSink;
+use super::window::WindowStore;
+
+use sctk::reexports::client::{Display, NewProxy, Proxy};
+use sctk::reexports::client::protocol::wl_data_device::{self, Event as DeviceEvent, WlDataDevice};
+use sctk::reexports::client::protocol::wl_data_device_manager::DndAction;
+use sctk::reexports::client::protocol::wl_data_offer::{Event as OfferEvent, WlDataOffer};
+use sctk::reexports::client::protocol::wl_data_offer::RequestsTrait as OfferRequests;
+
+const URI_LIST: &str = "text/uri-list";
+
+// How long a drop waits for the source to finish sending the list of files.
+const DROP_TIMEOUT_MS: u64 = 500;
+
+// A drag over one of our windows.
+struct Drag {
+    offer: Proxy<WlDataOffer>,
+    window_id: WindowId,
+    // The serial of the `enter` event, which accepting the offer requires.
+    serial: u32,
+    // The list of files being received, until the source is done sending it.
+    transfer: Option<Transfer>,
+    // The files, which were announced with `HoveredFile` once the list was received. Empty if the
+    // offer had no files for us, in which case it was rejected.
+    paths: Vec<PathBuf>,
+}
+
+impl Drag {
+    // Reads what the source has sent so far, and announces the files once the list is complete.
+    fn receive_files(&mut self, sink: &Mutex<EventsLoopSink>) {
+        let done = match self.transfer {
+            Some(ref mut transfer) => transfer.read_available(),
+            None => return,
+        };
+        if !done {
+            return;
+        }
+        let data = self.transfer.take().unwrap().data;
+        self.paths = parse_uri_list(&data);
+        if self.paths.is_empty() {
+            self.offer.accept(self.serial, None);
+        } else {
+            let mut sink = sink.lock().unwrap();
+            for path in &self.paths {
+                sink.send_event(WindowEvent::HoveredFile(path.clone()), self.window_id);
+            }
+        }
+    }
+}
+
+// Receives the offer's `text/uri-list` through a non-blocking pipe, so that a slow source doesn't
+// hold up the events loop.
+struct Transfer {
+    reader: File,
+    data: Vec<u8>,
+}
+
+impl Transfer {
+    // The source writes to the pipe once the compositor has forwarded our request, so the
+    // connection is flushed right away.
+    fn start(offer: &Proxy<WlDataOffer>, display: &Weak<Display>) -> Option<Transfer> {
+        let display = display.upgrade()?;
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+            return None;
+        }
+        let reader = unsafe { File::from_raw_fd(fds[0]) };
+        // Only our end is non-blocking, since the source may not expect its end to be.
+        if unsafe { libc::fcntl(fds[0], libc::F_SETFL, libc::O_NONBLOCK) } < 0 {
+            unsafe { libc::close(fds[1]) };
+            return None;
+        }
+        offer.receive(URI_LIST.to_owned(), fds[1]);
+        // The request holds a duplicate of the write end, which the source gets.
+        unsafe { libc::close(fds[1]) };
+        display.flush().ok()?;
+        Some(Transfer { reader, data: Vec::new() })
+    }
+
+    // Reads what's available without blocking. Returns whether the source is done, which is also
+    // the case if reading fails, so that the files received so far are used.
+    fn read_available(&mut self) -> bool {
+        let mut buffer = [0u8; 4096];
+        loop {
+            match self.reader.read(&mut buffer) {
+                Ok(0) => return true,
+                Ok(count) => self.data.extend_from_slice(&buffer[..count]),
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return false,
+                Err(_) => return true,
+            }
+        }
+    }
+
+    // Waits for the rest of the list for at most `timeout`, and returns what was received.
+    fn finish(mut self, timeout: Duration) -> Vec<u8> {
+        let deadline = Instant::now() + timeout;
+        while !self.read_available() {
+            let mut pollfd = libc::pollfd {
+                fd: self.reader.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let timeout = timeout_until(deadline);
+            if timeout == 0 || unsafe { libc::poll(&mut pollfd, 1, timeout) } == 0 {
+                break;
+            }
+        }
+        self.data
+    }
+}
+
+pub fn implement_data_device(
+    device: NewProxy<WlDataDevice>,
+    sink: Arc<Mutex<EventsLoopSink>>,
+    store: Arc<Mutex<WindowStore>>,
+    display: Weak<Display>,
+) -> Proxy<WlDataDevice> {
+    // The offers introduced by `data_offer` events, along with the MIME types they advertise
+    let mut offers: Vec<(Proxy<WlDataOffer>, Arc<Mutex<Vec<String>>>)> = Vec::new();
+    let mut drag: Option<Drag> = None;
+
+    device.implement(move |evt, _: Proxy<_>| {
+        match evt {
+            DeviceEvent::DataOffer { id } => {
+                let mime_types = Arc::new(Mutex::new(Vec::new()));
+                let offer = {
+                    let mime_types = mime_types.clone();
+                    id.implement(move |evt, _: Proxy<_>| {
+                        if let OfferEvent::Offer { mime_type } = evt {
+                            mime_types.lock().unwrap().push(mime_type);
+                        }
+                    })
+                };
+                offers.push((offer, mime_types));
+            }
+            DeviceEvent::Enter { serial, surface, id, .. } => {
+                let offer = match id {
+                    Some(offer) => offer,
+                    None => return,
+                };
+                let mime_types = match offers.iter().position(|&(ref o, _)| o.equals(&offer)) {
+                    Some(idx) => offers.swap_remove(idx).1,
+                    None => return,
+                };
+                let window_id = match store.lock().unwrap().find_wid(&surface) {
+                    Some(window_id) => window_id,
+                    None => {
+                        destroy_offer(&offer);
+                        return;
+                    }
+                };
+
+                let has_uri_list = mime_types.lock().unwrap().iter().any(|m| m == URI_LIST);
+                let transfer = if has_uri_list {
+                    Transfer::start(&offer, &display)
+                } else {
+                    None
+                };
+
+                // The offer is accepted until the list turns out not to have any local files.
+                if transfer.is_some() {
+                    offer.accept(serial, Some(URI_LIST.to_owned()));
+                    // Without an action, compositors cancel the drag instead of dropping.
+                    if offer.version() >= 3 {
+                        offer.set_actions(DndAction::Copy, DndAction::Copy);
+                    }
+                } else {
+                    offer.accept(serial, None);
+                }
+
+                if let Some(drag) = drag.take() {
+                    destroy_offer(&drag.offer);
+                }
+                let mut new_drag = Drag { offer, window_id, serial, transfer, paths: Vec::new() };
+                new_drag.receive_files(&sink);
+                drag = Some(new_drag);
+            }
+            DeviceEvent::Leave => {
+                if let Some(drag) = drag.take() {
+                    if !drag.paths.is_empty() {
+                        sink.lock().unwrap().send_event(WindowEvent::HoveredFileCancelled, drag.window_id);
+                    }
+                    destroy_offer(&drag.offer);
+                }
+            }
+            DeviceEvent::Drop => {
+                if let Some(mut drag) = drag.take() {
+                    // The files are dropped without having been hovered if the list is still
+                    // being received, which only waits so long for a source that doesn't finish.
+                    if let Some(transfer) = drag.transfer.take() {
+                        drag.paths = parse_uri_list(&transfer.finish(Duration::from_millis(DROP_TIMEOUT_MS)));
+                    }
+                    if !drag.paths.is_empty() {
+                        let mut sink = sink.lock().unwrap();
+                        for path in drag.paths {
+                            sink.send_event(WindowEvent::DroppedFile(path), drag.window_id);
+                        }
+                        // Lets the source know it can clean up, e.g. after a move.
+                        if drag.offer.version() >= 3 {
+                            drag.offer.finish();
+                        }
+                    }
+                    destroy_offer(&drag.offer);
+                }
+            }
+            DeviceEvent::Selection { id } => {
+                // We don't support the clipboard, so the selection's offer is of no use to us.
+                if let Some(offer) = id {
+                    offers.retain(|&(ref o, _)| !o.equals(&offer));
+                    destroy_offer(&offer);
+                }
+            }
+            // The source may only be done sending the list after the drag entered the window.
+            DeviceEvent::Motion { .. } => if let Some(ref mut drag) = drag {
+                drag.receive_files(&sink);
+            },
+        }
+    })
+}
+
+pub fn release_data_device(device: &Proxy<WlDataDevice>) {
+    if device.version() >= 2 {
+        use self::wl_data_device::RequestsTrait;
+        device.release();
+    }
+}
+
+fn destroy_offer(offer: &Proxy<WlDataOffer>) {
+    offer.destroy();
+}
+
+// Paths that aren't local files are skipped.
+fn parse_uri_list(data: &[u8]) -> Vec<PathBuf> {
+    let data = String::from_utf8_lossy(data);
+    data.split("\r\n")
+        // Lines starting with `#` are comments
+        .filter(|uri| !uri.is_empty() && !uri.starts_with('#'))
+        .filter_map(|uri| {
+            // The format is file://host/path, where the host is typically empty.
+            let path = if uri.starts_with("file:///") {
+                &uri["file://".len()..]
+            } else if uri.starts_with("file://localhost/") {
+                &uri["file://localhost".len()..]
+            } else {
+                return None;
+            };
+            percent_decode(path.as_bytes())
+                .decode_utf8()
+                .ok()
+                .map(|path| PathBuf::from(path.into_owned()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::parse_uri_list;
+
+    #[test]
+    fn parses_uri_list() {
+        let data = b"# comment\r\nfile:///home/user/a%20b.txt\r\nfile://localhost/tmp/c\r\nhttp://example.com/d\r\n";
+        assert_eq!(
+            parse_uri_list(data),
+            vec![PathBuf::from("/home/user/a b.txt"), PathBuf::from("/tmp/c")],
+        );
+    }
+}
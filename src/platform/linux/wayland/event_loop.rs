@@ -1,31 +1,47 @@
 use std::cell::RefCell;
+use std::cmp;
 use std::collections::VecDeque;
 use std::fmt;
+use std::os::unix::io::RawFd;
 use std::sync::{Arc, Mutex, Weak};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
-use {ControlFlow, EventsLoopClosed, PhysicalPosition, PhysicalSize};
+use {ControlFlow, EventsLoopClosed, Orientation, PhysicalPosition, PhysicalSize, VideoMode};
+use dpi::calc_dpi;
+use os::unix::{FdInterest, MouseMotionUnits};
+use platform::platform::wakeup_fds::{self, WakeupFds};
+use timestamp::EventTimestamps;
 
 use super::WindowId;
+use super::cursor::{CursorManager, WindowCursor};
+use super::keyboard::KeyRepeat;
+use super::pointer::PointerState;
 use super::window::WindowStore;
 
 use sctk::Environment;
 use sctk::output::OutputMgr;
 use sctk::reexports::client::{Display, EventQueue, GlobalEvent, Proxy, ConnectError};
 use sctk::reexports::client::commons::Implementation;
-use sctk::reexports::client::protocol::{wl_keyboard, wl_output, wl_pointer, wl_registry, wl_seat,
-                                        wl_touch};
+use sctk::reexports::client::protocol::{wl_data_device, wl_data_device_manager, wl_keyboard,
+                                        wl_output, wl_pointer, wl_registry, wl_seat, wl_touch};
 
 use sctk::reexports::client::protocol::wl_display::RequestsTrait as DisplayRequests;
+use sctk::reexports::protocols::unstable::relative_pointer::v1::client::zwp_relative_pointer_manager_v1::{self, ZwpRelativePointerManagerV1};
+use sctk::reexports::protocols::unstable::relative_pointer::v1::client::zwp_relative_pointer_v1::{self, ZwpRelativePointerV1};
+use sctk::reexports::protocols::unstable::pointer_constraints::v1::client::zwp_pointer_constraints_v1::ZwpPointerConstraintsV1;
 
 pub struct EventsLoopSink {
     buffer: VecDeque<::Event>,
+    // Converts the timestamps of the input events, which the handlers have in common
+    pub timestamps: EventTimestamps,
 }
 
 impl EventsLoopSink {
     pub fn new() -> EventsLoopSink {
         EventsLoopSink {
             buffer: VecDeque::new(),
+            timestamps: EventTimestamps::new(),
         }
     }
 
@@ -68,6 +84,16 @@ pub struct EventsLoop {
     pub display: Arc<Display>,
     // The list of seats
     pub seats: Arc<Mutex<Vec<(u32, Proxy<wl_seat::WlSeat>)>>>,
+    // The cursor theme, shared by all pointers
+    pub cursor_manager: Arc<Mutex<CursorManager>>,
+    // The pointers and the globals used for pointer locking and relative motion
+    pub pointer_state: Arc<Mutex<PointerState>>,
+    // The key being repeated, which the loop wakes up for
+    key_repeat: Arc<Mutex<KeyRepeat>>,
+    // Set once the connection to the compositor has failed, after which no more events are read
+    disconnected: bool,
+    // The file descriptors to poll alongside the connection to the compositor
+    wakeup_fds: WakeupFds,
 }
 
 // A handle that can be sent across threads and used to wake up the `EventsLoop`.
@@ -103,23 +129,42 @@ impl EventsLoopProxy {
 impl EventsLoop {
     pub fn new() -> Result<EventsLoop, ConnectError> {
         let (display, mut event_queue) = Display::connect_to_env()?;
+        let display = Arc::new(display);
 
         let sink = Arc::new(Mutex::new(EventsLoopSink::new()));
         let store = Arc::new(Mutex::new(WindowStore::new()));
         let seats = Arc::new(Mutex::new(Vec::new()));
+        let cursor_manager = Arc::new(Mutex::new(CursorManager::new()));
+        let pointer_state = Arc::new(Mutex::new(PointerState::new()));
+        let key_repeat = Arc::new(Mutex::new(KeyRepeat::new()));
 
+        let registry = display
+            .get_registry()
+            .map_err(|_| ConnectError::NoCompositorListening)?;
         let env = Environment::from_registry_with_cb(
-            display.get_registry().unwrap(),
+            registry,
             &mut event_queue,
             SeatManager {
                 sink: sink.clone(),
                 store: store.clone(),
                 seats: seats.clone(),
+                cursor_manager: cursor_manager.clone(),
+                pointer_state: pointer_state.clone(),
+                key_repeat: key_repeat.clone(),
+                display: Arc::downgrade(&display),
+                data_device_manager: None,
+                data_devices: Vec::new(),
+                outputs: Vec::new(),
             },
-        ).unwrap();
+        ).map_err(|_| ConnectError::NoCompositorListening)?;
+
+        cursor_manager
+            .lock()
+            .unwrap()
+            .set_globals(env.compositor.clone(), env.shm.clone());
 
         Ok(EventsLoop {
-            display: Arc::new(display),
+            display: display,
             evq: RefCell::new(event_queue),
             sink: sink,
             pending_wakeup: Arc::new(AtomicBool::new(false)),
@@ -127,9 +172,19 @@ impl EventsLoop {
             env: env,
             cleanup_needed: Arc::new(Mutex::new(false)),
             seats: seats,
+            cursor_manager: cursor_manager,
+            pointer_state: pointer_state,
+            key_repeat,
+            disconnected: false,
+            wakeup_fds: Default::default(),
         })
     }
 
+    pub fn release_grabs(&self) {
+        // Locked pointers are released by the compositor once the surface or the connection is
+        // gone, so there's nothing to restore.
+    }
+
     pub fn create_proxy(&self) -> EventsLoopProxy {
         EventsLoopProxy {
             display: Arc::downgrade(&self.display),
@@ -141,21 +196,45 @@ impl EventsLoop {
     where
         F: FnMut(::Event),
     {
-        // send pending events to the server
-        self.display.flush().expect("Wayland connection lost.");
-
         // dispatch any pre-buffered events
         self.sink.lock().unwrap().empty_with(&mut callback);
 
+        if self.disconnected {
+            return;
+        }
+
+        // send pending events to the server
+        if self.display.flush().is_err() {
+            self.connection_lost();
+            self.sink.lock().unwrap().empty_with(&mut callback);
+            return;
+        }
+
         // try to read pending events
         if let Some(h) = self.evq.get_mut().prepare_read() {
-            h.read_events().expect("Wayland connection lost.");
+            if h.read_events().is_err() {
+                self.connection_lost();
+                self.sink.lock().unwrap().empty_with(&mut callback);
+                return;
+            }
         }
         // dispatch wayland events
-        self.evq
-            .get_mut()
-            .dispatch_pending()
-            .expect("Wayland connection lost.");
+        if self.evq.get_mut().dispatch_pending().is_err() {
+            self.connection_lost();
+            self.sink.lock().unwrap().empty_with(&mut callback);
+            return;
+        }
+
+        if !self.wakeup_fds.is_empty() {
+            let (_, ready_fds) = self.wakeup_fds.poll(self.display.get_connection_fd(), 0);
+            let mut sink = self.sink.lock().unwrap();
+            for fd in ready_fds {
+                sink.send_raw_event(::Event::FdReady(fd));
+            }
+            for event in self.wakeup_fds.take_device_events() {
+                sink.send_raw_event(event);
+            }
+        }
         self.post_dispatch_triggers();
 
         // dispatch buffered events to client
@@ -166,27 +245,56 @@ impl EventsLoop {
     where
         F: FnMut(::Event) -> ControlFlow,
     {
-        // send pending events to the server
-        self.display.flush().expect("Wayland connection lost.");
+        if self.disconnected {
+            return;
+        }
 
         // Check for control flow by wrapping the callback.
         let control_flow = ::std::cell::Cell::new(ControlFlow::Continue);
+        // The deadline returned with `WaitUntil` for the latest event.
+        let deadline = ::std::cell::Cell::new(None);
         let mut callback = |event| {
-            if let ControlFlow::Break = callback(event) {
+            let flow = callback(event);
+            deadline.set(flow.wait_deadline());
+            if let ControlFlow::Break = flow {
                 control_flow.set(ControlFlow::Break);
             }
         };
 
+        // send pending events to the server
+        if self.display.flush().is_err() {
+            self.connection_lost();
+            self.sink.lock().unwrap().empty_with(&mut callback);
+            return;
+        }
+
         // dispatch any pre-buffered events
         self.post_dispatch_triggers();
         self.sink.lock().unwrap().empty_with(&mut callback);
 
         loop {
             // dispatch events blocking if needed
-            self.evq
-                .get_mut()
-                .dispatch()
-                .expect("Wayland connection lost.");
+            let wait_deadline = deadline.get();
+            // The loop also wakes up when the held key is due to repeat, and when an animated
+            // cursor is due to show its next frame.
+            let repeat_deadline = self.key_repeat.lock().unwrap().deadline();
+            let timeout_deadline = earliest(
+                earliest(wait_deadline, repeat_deadline),
+                self.animate_cursors(),
+            );
+            let dispatched = if self.wakeup_fds.is_empty() && timeout_deadline.is_none() {
+                self.evq.get_mut().dispatch().map(|_| ()).map_err(|_| ())
+            } else {
+                self.dispatch_with_timeout(timeout_deadline.map_or(-1, wakeup_fds::timeout_until))
+            };
+            if dispatched.is_err() {
+                self.connection_lost();
+                self.sink.lock().unwrap().empty_with(&mut callback);
+                break;
+            }
+            if wait_deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+                self.sink.lock().unwrap().send_raw_event(::Event::Awakened);
+            }
             self.post_dispatch_triggers();
 
             // empty buffer of events
@@ -202,6 +310,44 @@ impl EventsLoop {
         get_primary_monitor(&self.env.outputs)
     }
 
+    pub fn set_cursor_theme(&self, name: &str, size: u32) {
+        // Same locking order as the pointer's `enter` handler
+        let store = self.store.lock().unwrap();
+        let mut cursor_manager = self.cursor_manager.lock().unwrap();
+        cursor_manager.set_theme(name, size);
+        // Pointers that are over a window would otherwise keep the previous theme's cursor until
+        // they leave it.
+        let pointer_state = self.pointer_state.lock().unwrap();
+        for focus in &pointer_state.focuses {
+            if let Some(cursor) = store.get_cursor(&focus.surface) {
+                let scale = store.get_dpi(&focus.surface).unwrap_or(1);
+                let _ = cursor_manager.set_window_cursor(
+                    &focus.pointer,
+                    &focus.cursor_surface,
+                    focus.serial,
+                    &cursor,
+                    scale,
+                );
+            }
+        }
+    }
+
+    pub fn set_mouse_motion_units(&self, units: MouseMotionUnits) {
+        self.pointer_state.lock().unwrap().mouse_motion_units = units;
+    }
+
+    pub fn set_redraw_vsync(&self, enabled: bool) {
+        self.store.lock().unwrap().set_redraw_vsync(enabled);
+    }
+
+    pub fn wakeup_on_fd(&self, fd: RawFd, interest: FdInterest) {
+        self.wakeup_fds.insert(fd, interest);
+    }
+
+    pub fn remove_wakeup_fd(&self, fd: RawFd) {
+        self.wakeup_fds.remove(fd);
+    }
+
     pub fn get_available_monitors(&self) -> VecDeque<MonitorId> {
         get_available_monitors(&self.env.outputs)
     }
@@ -212,13 +358,100 @@ impl EventsLoop {
  */
 
 impl EventsLoop {
+    // Called when the compositor disconnected us or sent a protocol error. There's no recovering
+    // the connection, so every window is reported as destroyed and the loop stops reading events.
+    fn connection_lost(&mut self) {
+        self.disconnected = true;
+        let mut sink = self.sink.lock().unwrap();
+        for wid in self.store.lock().unwrap().window_ids() {
+            sink.send_event(::WindowEvent::Destroyed, wid);
+        }
+    }
+
+    // Same as `EventQueue::dispatch`, except that the registered file descriptors are polled
+    // together with the connection, and an `FdReady` event is buffered for each one that's ready,
+    // as are the events read from gamepads.
+    // Blocks for at most `timeout` milliseconds, or forever if it's negative.
+    fn dispatch_with_timeout(&mut self, timeout: i32) -> Result<(), ()> {
+        let evq = self.evq.get_mut();
+        if evq.dispatch_pending().map_err(|_| ())? > 0 {
+            return Ok(());
+        }
+        self.display.flush().map_err(|_| ())?;
+        let ready_fds = {
+            let guard = match evq.prepare_read() {
+                Some(guard) => guard,
+                // Events were queued in the meantime, so they'll be dispatched on the next call.
+                None => return Ok(()),
+            };
+            let (connection_ready, ready_fds) = self.wakeup_fds
+                .poll(self.display.get_connection_fd(), timeout);
+            if connection_ready {
+                guard.read_events().map_err(|_| ())?;
+            } else {
+                guard.cancel();
+            }
+            ready_fds
+        };
+        evq.dispatch_pending().map_err(|_| ())?;
+        let mut sink = self.sink.lock().unwrap();
+        for fd in ready_fds {
+            sink.send_raw_event(::Event::FdReady(fd));
+        }
+        for event in self.wakeup_fds.take_device_events() {
+            sink.send_raw_event(event);
+        }
+        Ok(())
+    }
+
+    // Shows the frames of the animated cursors that are due on the pointers over their window, and
+    // returns when the next frame is due.
+    fn animate_cursors(&self) -> Option<Instant> {
+        let now = Instant::now();
+        // Same locking order as the pointer's `enter` handler
+        let store = self.store.lock().unwrap();
+        let mut cursor_manager = self.cursor_manager.lock().unwrap();
+        let pointer_state = self.pointer_state.lock().unwrap();
+        let animations: Vec<_> = pointer_state.focuses
+            .iter()
+            .filter_map(|focus| match store.get_cursor(&focus.surface) {
+                Some(WindowCursor::Animated(animation)) => Some((focus, animation)),
+                _ => None,
+            })
+            .collect();
+        // Several pointers may be over the same window, so every animation is advanced before
+        // any frame is shown.
+        let changed: Vec<_> = animations
+            .iter()
+            .filter(|&&(_, ref animation)| animation.advance(now))
+            .map(|&(_, ref animation)| animation.clone())
+            .collect();
+        let mut deadline = None;
+        for &(focus, ref animation) in &animations {
+            if changed.iter().any(|other| Arc::ptr_eq(animation, other)) {
+                let scale = store.get_dpi(&focus.surface).unwrap_or(1);
+                let _ = cursor_manager.set_window_cursor(
+                    &focus.pointer,
+                    &focus.cursor_surface,
+                    focus.serial,
+                    &WindowCursor::Animated(animation.clone()),
+                    scale,
+                );
+            }
+            deadline = earliest(deadline, Some(animation.deadline()));
+        }
+        deadline
+    }
+
     fn post_dispatch_triggers(&mut self) {
+        self.animate_cursors();
         let mut sink = self.sink.lock().unwrap();
         // process a possible pending wakeup call
         if self.pending_wakeup.load(Ordering::Relaxed) {
             sink.send_raw_event(::Event::Awakened);
             self.pending_wakeup.store(false, Ordering::Relaxed);
         }
+        self.key_repeat.lock().unwrap().send_due_repeats(&mut sink);
         // prune possible dead windows
         {
             let mut cleanup_needed = self.cleanup_needed.lock().unwrap();
@@ -231,6 +464,8 @@ impl EventsLoop {
             }
         }
         // process pending resize/refresh
+        // Redraws are sent last, so that they see the effect of every other event.
+        let mut redraws = Vec::new();
         self.store.lock().unwrap().for_each(
             |newsize, size, new_dpi, refresh, frame_refresh, closed, wid, frame| {
                 if let Some(frame) = frame {
@@ -248,13 +483,19 @@ impl EventsLoop {
                     sink.send_event(::WindowEvent::HiDpiFactorChanged(dpi as f64), wid);
                 }
                 if refresh {
-                    sink.send_event(::WindowEvent::Refresh, wid);
+                    redraws.push(wid);
                 }
                 if closed {
                     sink.send_event(::WindowEvent::CloseRequested, wid);
                 }
             },
-        )
+        );
+        for &wid in &redraws {
+            sink.send_event(::WindowEvent::RedrawRequested, wid);
+        }
+        if !redraws.is_empty() {
+            sink.send_raw_event(::Event::RedrawEventsCleared);
+        }
     }
 }
 
@@ -266,6 +507,35 @@ struct SeatManager {
     sink: Arc<Mutex<EventsLoopSink>>,
     store: Arc<Mutex<WindowStore>>,
     seats: Arc<Mutex<Vec<(u32, Proxy<wl_seat::WlSeat>)>>>,
+    cursor_manager: Arc<Mutex<CursorManager>>,
+    pointer_state: Arc<Mutex<PointerState>>,
+    key_repeat: Arc<Mutex<KeyRepeat>>,
+    display: Weak<Display>,
+    data_device_manager: Option<Proxy<wl_data_device_manager::WlDataDeviceManager>>,
+    // The data device of each seat, which receives drag-and-drop offers
+    data_devices: Vec<(u32, Proxy<wl_data_device::WlDataDevice>)>,
+    // Our own binding of each output, which tells us when its scale changes
+    outputs: Vec<(u32, Proxy<wl_output::WlOutput>)>,
+}
+
+impl SeatManager {
+    fn new_data_device(&mut self, seat_id: u32, seat: &Proxy<wl_seat::WlSeat>) {
+        use self::wl_data_device_manager::RequestsTrait;
+        let device = match self.data_device_manager {
+            Some(ref manager) => manager.get_data_device(seat),
+            None => return,
+        };
+        // this only fails if the seat is already dead
+        if let Ok(device) = device {
+            let device = super::dnd::implement_data_device(
+                device,
+                self.sink.clone(),
+                self.store.clone(),
+                self.display.clone(),
+            );
+            self.data_devices.push((seat_id, device));
+        }
+    }
 }
 
 impl Implementation<Proxy<wl_registry::WlRegistry>, GlobalEvent> for SeatManager {
@@ -280,20 +550,90 @@ impl Implementation<Proxy<wl_registry::WlRegistry>, GlobalEvent> for SeatManager
             } if interface == "wl_seat" =>
             {
                 use std::cmp::min;
-                let seat = registry
-                    .bind::<wl_seat::WlSeat>(min(version, 5), id)
-                    .unwrap()
-                    .implement(SeatData {
-                        sink: self.sink.clone(),
-                        store: self.store.clone(),
-                        pointer: None,
-                        keyboard: None,
-                        touch: None,
-                    });
+                let seat = match registry.bind::<wl_seat::WlSeat>(min(version, 5), id) {
+                    Ok(seat) => seat,
+                    // the registry is dead, so is the connection
+                    Err(_) => return,
+                };
+                let seat = seat.implement(SeatData {
+                    sink: self.sink.clone(),
+                    store: self.store.clone(),
+                    cursor_manager: self.cursor_manager.clone(),
+                    pointer_state: self.pointer_state.clone(),
+                    key_repeat: self.key_repeat.clone(),
+                    pointer: None,
+                    relative_pointer: None,
+                    keyboard: None,
+                    touch: None,
+                });
                 self.store.lock().unwrap().new_seat(&seat);
+                self.new_data_device(id, &seat);
                 self.seats.lock().unwrap().push((id, seat));
             }
+            GlobalEvent::New {
+                id,
+                ref interface,
+                version,
+            } if interface == "wl_data_device_manager" =>
+            {
+                use std::cmp::min;
+                // Version 3 is needed to negotiate the drag-and-drop action
+                if let Ok(manager) = registry.bind::<wl_data_device_manager::WlDataDeviceManager>(min(version, 3), id) {
+                    self.data_device_manager = Some(manager.implement(|_, _| ()));
+                    // The seats advertised before the manager don't have a data device yet
+                    let seats = self.seats.lock().unwrap().clone();
+                    for (seat_id, seat) in seats {
+                        self.new_data_device(seat_id, &seat);
+                    }
+                }
+            }
+            GlobalEvent::New {
+                id,
+                ref interface,
+                version,
+            } if interface == "wl_output" =>
+            {
+                use std::cmp::min;
+                // The output manager keeps track of the outputs, but doesn't tell us when they
+                // change.
+                if let Ok(output) = registry.bind::<wl_output::WlOutput>(min(version, 3), id) {
+                    let output = output.implement(OutputData {
+                        id,
+                        store: self.store.clone(),
+                        pending_scale: None,
+                    });
+                    self.outputs.push((id, output));
+                }
+            }
+            // Both of these globals are advertised in the initial burst of the registry, so they
+            // are known by the time the seats advertise their pointers.
+            GlobalEvent::New {
+                id,
+                ref interface,
+                ..
+            } if interface == "zwp_relative_pointer_manager_v1" =>
+            {
+                if let Ok(manager) = registry.bind::<ZwpRelativePointerManagerV1>(1, id) {
+                    self.pointer_state.lock().unwrap().relative_pointer_manager =
+                        Some(manager.implement(|_, _| ()));
+                }
+            }
+            GlobalEvent::New {
+                id,
+                ref interface,
+                ..
+            } if interface == "zwp_pointer_constraints_v1" =>
+            {
+                if let Ok(constraints) = registry.bind::<ZwpPointerConstraintsV1>(1, id) {
+                    self.pointer_state.lock().unwrap().pointer_constraints =
+                        Some(constraints.implement(|_, _| ()));
+                }
+            }
             GlobalEvent::Removed { id, ref interface } if interface == "wl_seat" => {
+                if let Some(idx) = self.data_devices.iter().position(|&(i, _)| i == id) {
+                    let (_, device) = self.data_devices.swap_remove(idx);
+                    super::dnd::release_data_device(&device);
+                }
                 let mut seats = self.seats.lock().unwrap();
                 if let Some(idx) = seats.iter().position(|&(i, _)| i == id) {
                     let (_, seat) = seats.swap_remove(idx);
@@ -302,6 +642,15 @@ impl Implementation<Proxy<wl_registry::WlRegistry>, GlobalEvent> for SeatManager
                     }
                 }
             }
+            GlobalEvent::Removed { id, ref interface } if interface == "wl_output" => {
+                use self::wl_output::RequestsTrait as OutputRequests;
+                if let Some(idx) = self.outputs.iter().position(|&(i, _)| i == id) {
+                    let (_, output) = self.outputs.swap_remove(idx);
+                    if output.version() >= 3 {
+                        output.release();
+                    }
+                }
+            }
             _ => (),
         }
     }
@@ -310,7 +659,11 @@ impl Implementation<Proxy<wl_registry::WlRegistry>, GlobalEvent> for SeatManager
 struct SeatData {
     sink: Arc<Mutex<EventsLoopSink>>,
     store: Arc<Mutex<WindowStore>>,
+    cursor_manager: Arc<Mutex<CursorManager>>,
+    pointer_state: Arc<Mutex<PointerState>>,
+    key_repeat: Arc<Mutex<KeyRepeat>>,
     pointer: Option<Proxy<wl_pointer::WlPointer>>,
+    relative_pointer: Option<Proxy<ZwpRelativePointerV1>>,
     keyboard: Option<Proxy<wl_keyboard::WlKeyboard>>,
     touch: Option<Proxy<wl_touch::WlTouch>>,
 }
@@ -323,15 +676,36 @@ impl Implementation<Proxy<wl_seat::WlSeat>, wl_seat::Event> for SeatData {
             wl_seat::Event::Capabilities { capabilities } => {
                 // create pointer if applicable
                 if capabilities.contains(wl_seat::Capability::Pointer) && self.pointer.is_none() {
-                    self.pointer = Some(super::pointer::implement_pointer(
-                        seat.get_pointer().unwrap(),
-                        self.sink.clone(),
-                        self.store.clone(),
-                    ))
+                    // this only fails if the seat is already dead
+                    if let Ok(pointer) = seat.get_pointer() {
+                        let pointer = super::pointer::implement_pointer(
+                            pointer,
+                            seat.clone(),
+                            self.sink.clone(),
+                            self.store.clone(),
+                            self.cursor_manager.clone(),
+                            self.pointer_state.clone(),
+                        );
+                        let mut pointer_state = self.pointer_state.lock().unwrap();
+                        if let Some(ref manager) = pointer_state.relative_pointer_manager {
+                            use self::zwp_relative_pointer_manager_v1::RequestsTrait;
+                            if let Ok(relative_pointer) = manager.get_relative_pointer(&pointer) {
+                                self.relative_pointer = Some(super::pointer::implement_relative_pointer(
+                                    relative_pointer,
+                                    self.sink.clone(),
+                                    self.pointer_state.clone(),
+                                ));
+                            }
+                        }
+                        pointer_state.pointers.push(pointer.clone());
+                        self.pointer = Some(pointer);
+                    }
                 }
                 // destroy pointer if applicable
                 if !capabilities.contains(wl_seat::Capability::Pointer) {
+                    self.destroy_relative_pointer();
                     if let Some(pointer) = self.pointer.take() {
+                        self.pointer_state.lock().unwrap().remove_pointer(&pointer);
                         if pointer.version() >= 3 {
                             use self::wl_pointer::RequestsTrait;
                             pointer.release();
@@ -340,10 +714,13 @@ impl Implementation<Proxy<wl_seat::WlSeat>, wl_seat::Event> for SeatData {
                 }
                 // create keyboard if applicable
                 if capabilities.contains(wl_seat::Capability::Keyboard) && self.keyboard.is_none() {
-                    self.keyboard = Some(super::keyboard::init_keyboard(
-                        seat.get_keyboard().unwrap(),
-                        self.sink.clone(),
-                    ))
+                    if let Ok(keyboard) = seat.get_keyboard() {
+                        self.keyboard = Some(super::keyboard::init_keyboard(
+                            keyboard,
+                            self.sink.clone(),
+                            self.key_repeat.clone(),
+                        ))
+                    }
                 }
                 // destroy keyboard if applicable
                 if !capabilities.contains(wl_seat::Capability::Keyboard) {
@@ -356,11 +733,13 @@ impl Implementation<Proxy<wl_seat::WlSeat>, wl_seat::Event> for SeatData {
                 }
                 // create touch if applicable
                 if capabilities.contains(wl_seat::Capability::Touch) && self.touch.is_none() {
-                    self.touch = Some(super::touch::implement_touch(
-                        seat.get_touch().unwrap(),
-                        self.sink.clone(),
-                        self.store.clone(),
-                    ))
+                    if let Ok(touch) = seat.get_touch() {
+                        self.touch = Some(super::touch::implement_touch(
+                            touch,
+                            self.sink.clone(),
+                            self.store.clone(),
+                        ))
+                    }
                 }
                 // destroy touch if applicable
                 if !capabilities.contains(wl_seat::Capability::Touch) {
@@ -376,9 +755,20 @@ impl Implementation<Proxy<wl_seat::WlSeat>, wl_seat::Event> for SeatData {
     }
 }
 
+impl SeatData {
+    fn destroy_relative_pointer(&mut self) {
+        if let Some(relative_pointer) = self.relative_pointer.take() {
+            use self::zwp_relative_pointer_v1::RequestsTrait;
+            relative_pointer.destroy();
+        }
+    }
+}
+
 impl Drop for SeatData {
     fn drop(&mut self) {
+        self.destroy_relative_pointer();
         if let Some(pointer) = self.pointer.take() {
+            self.pointer_state.lock().unwrap().remove_pointer(&pointer);
             if pointer.version() >= 3 {
                 use self::wl_pointer::RequestsTrait;
                 pointer.release();
@@ -403,6 +793,33 @@ impl Drop for SeatData {
  * Monitor stuff
  */
 
+struct OutputData {
+    // The global id of the output
+    id: u32,
+    store: Arc<Mutex<WindowStore>>,
+    pending_scale: Option<i32>,
+}
+
+impl Implementation<Proxy<wl_output::WlOutput>, wl_output::Event> for OutputData {
+    fn receive(&mut self, evt: wl_output::Event, output: Proxy<wl_output::WlOutput>) {
+        // Changes are applied once the output is done sending them, which outputs older than
+        // version 2 don't announce.
+        let done = match evt {
+            wl_output::Event::Scale { factor } => {
+                self.pending_scale = Some(factor);
+                output.version() < 2
+            },
+            wl_output::Event::Done => true,
+            _ => false,
+        };
+        if done {
+            if let Some(scale) = self.pending_scale.take() {
+                self.store.lock().unwrap().output_scale_changed(self.id, scale);
+            }
+        }
+    }
+}
+
 pub struct MonitorId {
     pub(crate) proxy: Proxy<wl_output::WlOutput>,
     pub(crate) mgr: OutputMgr,
@@ -477,6 +894,62 @@ impl MonitorId {
             .with_info(&self.proxy, |_, info| info.scale_factor)
             .unwrap_or(1)
     }
+
+    pub fn get_dpi(&self) -> Option<f64> {
+        let (width_mm, height_mm) = self.mgr.with_info(&self.proxy, |_, info| info.physical_size)?;
+        let dimensions = self.get_dimensions().into();
+        calc_dpi(dimensions, (width_mm as f64, height_mm as f64))
+    }
+
+    pub fn get_current_video_mode(&self) -> Option<VideoMode> {
+        self.mgr.with_info(&self.proxy, |_, info| {
+            info.modes
+                .iter()
+                .find(|m| m.is_current)
+                .map(|m| VideoMode {
+                    dimensions: (m.dimensions.0 as u32, m.dimensions.1 as u32),
+                    // The protocol doesn't expose the bit depth.
+                    bit_depth: 32,
+                    // `wl_output` advertises refresh rates in mHz.
+                    refresh_rate: (m.refresh_rate as f64 / 1000.0).round() as u16,
+                })
+        }).and_then(|mode| mode)
+    }
+
+    pub fn get_video_modes(&self) -> Vec<VideoMode> {
+        self.mgr.with_info(&self.proxy, |_, info| {
+            info.modes
+                .iter()
+                .map(|m| VideoMode {
+                    dimensions: (m.dimensions.0 as u32, m.dimensions.1 as u32),
+                    bit_depth: 32,
+                    refresh_rate: (m.refresh_rate as f64 / 1000.0).round() as u16,
+                })
+                .collect()
+        }).unwrap_or_default()
+    }
+
+    pub fn get_orientation(&self) -> Orientation {
+        use self::wl_output::Transform;
+        // Transforms are counter-clockwise.
+        self.mgr.with_info(&self.proxy, |_, info| match info.transform {
+            Transform::_90 | Transform::Flipped90 => Orientation::PortraitFlipped,
+            Transform::_180 | Transform::Flipped180 => Orientation::LandscapeFlipped,
+            Transform::_270 | Transform::Flipped270 => Orientation::Portrait,
+            _ => Orientation::Landscape,
+        }).unwrap_or(Orientation::Landscape)
+    }
+
+    // Wayland has no notion of a primary output, so we consider the first one to be primary, which
+    // is consistent with `get_primary_monitor`.
+    #[inline]
+    pub fn is_primary(&self) -> bool {
+        self.mgr.with_all(|list| {
+            list.first()
+                .map(|&(_, ref proxy, _)| proxy.equals(&self.proxy))
+                .unwrap_or(false)
+        })
+    }
 }
 
 pub fn get_primary_monitor(outputs: &OutputMgr) -> MonitorId {
@@ -502,3 +975,11 @@ pub fn get_available_monitors(outputs: &OutputMgr) -> VecDeque<MonitorId> {
             .collect()
     })
 }
+
+// The earlier of two deadlines, where `None` means there's no deadline.
+fn earliest(a: Option<Instant>, b: Option<Instant>) -> Option<Instant> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(cmp::min(a, b)),
+        (a, b) => a.or(b),
+    }
+}
@@ -0,0 +1,254 @@
+use std::sync::{Arc, Mutex};
+
+use Insets;
+
+use sctk::window::{BasicFrame, Frame, FrameRequest};
+use sctk::reexports::client::Proxy;
+use sctk::reexports::client::protocol::{wl_compositor, wl_seat, wl_shm, wl_subcompositor, wl_surface};
+
+type Implementation = Box<FnMut(FrameRequest, u32) + Send>;
+
+lazy_static! {
+    // The handles of the frames `WinitFrame::init` created, along with the surface they decorate,
+    // until `take_handle` hands them to the window. `SWindow` keeps its frame to itself, so it
+    // can't give us the handle, but the surface identifies the frame across event loops.
+    static ref NEW_HANDLES: Mutex<Vec<(Proxy<wl_surface::WlSurface>, Arc<FrameHandle>)>> = Mutex::default();
+}
+
+/// Returns the handle of the frame that decorates `surface`, which is created along with the
+/// `SWindow` of the surface.
+pub fn take_handle(surface: &Proxy<wl_surface::WlSurface>) -> Option<Arc<FrameHandle>> {
+    let mut handles = NEW_HANDLES.lock().unwrap();
+    let index = handles.iter().position(|&(ref s, _)| s.equals(surface))?;
+    Some(handles.remove(index).1)
+}
+
+/// The decorations of our windows, which are drawn by `BasicFrame`.
+///
+/// The frame is shared with a `FrameHandle`, so that the window can find out how large the
+/// decorations are and make the same requests to the shell surface as the decorations do.
+pub struct WinitFrame {
+    handle: Arc<FrameHandle>,
+}
+
+pub struct FrameHandle {
+    state: Mutex<FrameState<BasicFrame>>,
+    // Forwards requests to the shell surface of the `SWindow`.
+    implementation: Arc<Mutex<Implementation>>,
+}
+
+impl FrameHandle {
+    /// The size of the decorations on each side of the surface, in logical pixels.
+    pub fn insets(&self) -> Insets {
+        self.state.lock().unwrap().insets()
+    }
+
+    /// Adds the size of the decorations to the given inner size.
+    pub fn add_borders(&self, size: (u32, u32)) -> (u32, u32) {
+        self.state.lock().unwrap().outer_size(size)
+    }
+
+    /// Whether the decorations are drawn, which `SWindow` decides from `set_decorate`.
+    pub fn is_decorated(&self) -> bool {
+        !self.state.lock().unwrap().hidden
+    }
+
+    /// Makes a request to the shell surface, such as starting an interactive move.
+    ///
+    /// The serial is the one of the input event that triggered the request.
+    pub fn request(&self, request: FrameRequest, serial: u32) {
+        let mut implementation = self.implementation.lock().unwrap();
+        (&mut **implementation)(request, serial)
+    }
+}
+
+impl Frame for WinitFrame {
+    type Error = <BasicFrame as Frame>::Error;
+
+    fn init(
+        base_surface: &Proxy<wl_surface::WlSurface>,
+        compositor: &Proxy<wl_compositor::WlCompositor>,
+        subcompositor: &Proxy<wl_subcompositor::WlSubcompositor>,
+        shm: &Proxy<wl_shm::WlShm>,
+        implementation: Implementation,
+    ) -> Result<WinitFrame, Self::Error> {
+        let implementation = Arc::new(Mutex::new(implementation));
+        let frame = {
+            let implementation = implementation.clone();
+            BasicFrame::init(
+                base_surface,
+                compositor,
+                subcompositor,
+                shm,
+                Box::new(move |request, serial| {
+                    let mut implementation = implementation.lock().unwrap();
+                    (&mut **implementation)(request, serial)
+                }),
+            )?
+        };
+        let handle = Arc::new(FrameHandle {
+            state: Mutex::new(FrameState::new(frame)),
+            implementation,
+        });
+        NEW_HANDLES.lock().unwrap().push((base_surface.clone(), handle.clone()));
+        Ok(WinitFrame { handle })
+    }
+
+    fn set_active(&mut self, active: bool) -> bool {
+        self.handle.state.lock().unwrap().frame.set_active(active)
+    }
+
+    fn set_maximized(&mut self, maximized: bool) -> bool {
+        self.handle.state.lock().unwrap().frame.set_maximized(maximized)
+    }
+
+    fn set_hidden(&mut self, hidden: bool) {
+        self.handle.state.lock().unwrap().set_hidden(hidden)
+    }
+
+    fn set_resizable(&mut self, resizable: bool) {
+        self.handle.state.lock().unwrap().frame.set_resizable(resizable)
+    }
+
+    fn new_seat(&mut self, seat: &Proxy<wl_seat::WlSeat>) {
+        self.handle.state.lock().unwrap().frame.new_seat(seat)
+    }
+
+    fn resize(&mut self, newsize: (u32, u32)) {
+        self.handle.state.lock().unwrap().frame.resize(newsize)
+    }
+
+    fn redraw(&mut self) {
+        self.handle.state.lock().unwrap().frame.redraw()
+    }
+
+    fn subtract_borders(&self, width: i32, height: i32) -> (i32, i32) {
+        self.handle.state.lock().unwrap().frame.subtract_borders(width, height)
+    }
+
+    fn add_borders(&self, width: i32, height: i32) -> (i32, i32) {
+        self.handle.state.lock().unwrap().frame.add_borders(width, height)
+    }
+
+    fn location(&self) -> (i32, i32) {
+        self.handle.state.lock().unwrap().frame.location()
+    }
+}
+
+// The decorations are hidden when the window asks for it, which `SWindow` does for undecorated
+// windows.
+struct FrameState<F> {
+    frame: F,
+    hidden: bool,
+}
+
+impl<F: Frame> FrameState<F> {
+    fn new(frame: F) -> FrameState<F> {
+        FrameState {
+            frame,
+            hidden: false,
+        }
+    }
+
+    fn set_hidden(&mut self, hidden: bool) {
+        self.hidden = hidden;
+        self.frame.set_hidden(hidden);
+    }
+
+    fn outer_size(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        let (width, height) = self.frame.add_borders(width as i32, height as i32);
+        (width as u32, height as u32)
+    }
+
+    fn insets(&self) -> Insets {
+        // The location is the offset of the top left corner of the decorations, so it's negative.
+        let (x, y) = self.frame.location();
+        let (width, height) = self.frame.add_borders(0, 0);
+        Insets {
+            top: -y as f64,
+            left: -x as f64,
+            right: (width + x) as f64,
+            bottom: (height + y) as f64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FrameState;
+
+    use sctk::window::{Frame, FrameRequest};
+    use sctk::reexports::client::Proxy;
+    use sctk::reexports::client::protocol::{wl_compositor, wl_seat, wl_shm, wl_subcompositor, wl_surface};
+
+    // Decorations with a 24px header and 4px borders on the other sides.
+    struct TestFrame {
+        hidden: bool,
+    }
+
+    impl Frame for TestFrame {
+        type Error = ();
+
+        fn init(
+            _: &Proxy<wl_surface::WlSurface>,
+            _: &Proxy<wl_compositor::WlCompositor>,
+            _: &Proxy<wl_subcompositor::WlSubcompositor>,
+            _: &Proxy<wl_shm::WlShm>,
+            _: Box<FnMut(FrameRequest, u32) + Send>,
+        ) -> Result<TestFrame, ()> {
+            Ok(TestFrame { hidden: false })
+        }
+
+        fn set_active(&mut self, _: bool) -> bool { false }
+        fn set_maximized(&mut self, _: bool) -> bool { false }
+        fn set_hidden(&mut self, hidden: bool) { self.hidden = hidden }
+        fn set_resizable(&mut self, _: bool) {}
+        fn new_seat(&mut self, _: &Proxy<wl_seat::WlSeat>) {}
+        fn resize(&mut self, _: (u32, u32)) {}
+        fn redraw(&mut self) {}
+
+        fn subtract_borders(&self, width: i32, height: i32) -> (i32, i32) {
+            let (w, h) = self.add_borders(0, 0);
+            (width - w, height - h)
+        }
+
+        fn add_borders(&self, width: i32, height: i32) -> (i32, i32) {
+            if self.hidden { (width, height) } else { (width + 8, height + 32) }
+        }
+
+        fn location(&self) -> (i32, i32) {
+            if self.hidden { (0, 0) } else { (-4, -28) }
+        }
+    }
+
+    #[test]
+    fn decorated_window_has_insets() {
+        let state = FrameState::new(TestFrame { hidden: false });
+        let insets = state.insets();
+        assert_eq!((insets.top, insets.left, insets.right, insets.bottom), (28.0, 4.0, 4.0, 4.0));
+    }
+
+    #[test]
+    fn undecorated_window_has_no_insets() {
+        let mut state = FrameState::new(TestFrame { hidden: false });
+        state.set_hidden(true);
+        assert!(state.frame.hidden);
+        assert_eq!(state.insets(), Default::default());
+        assert_eq!(state.outer_size((800, 600)), (800, 600));
+    }
+
+    #[test]
+    fn outer_size_includes_the_decorations() {
+        let mut state = FrameState::new(TestFrame { hidden: false });
+        let (width, height) = state.outer_size((800, 600));
+        assert_eq!((width, height), (808, 632));
+        // The outer size is the inner size plus the insets.
+        let insets = state.insets();
+        assert_eq!(width as f64, 800.0 + insets.left + insets.right);
+        assert_eq!(height as f64, 600.0 + insets.top + insets.bottom);
+        // Decorating the window again brings them back.
+        state.set_hidden(true);
+        state.set_hidden(false);
+        assert_eq!(state.outer_size((800, 600)), (808, 632));
+    }
+}
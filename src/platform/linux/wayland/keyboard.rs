@@ -1,19 +1,156 @@
+use std::cmp;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use {ElementState, KeyboardInput, ModifiersState, VirtualKeyCode, WindowEvent};
+use {ElementState, KeyLocation, KeyboardInput, LogicalKey, ModifiersState, VirtualKeyCode, WindowEvent};
+use platform::platform::evdev::evdev_to_physical_key;
+use platform::platform::keysym::keysym_to_logical_key;
 
-use super::{make_wid, DeviceId, EventsLoopSink};
+use super::{make_wid, DeviceId, EventsLoopSink, WindowId};
 use sctk::keyboard::{self, map_keyboard_auto, Event as KbEvent};
 use sctk::reexports::client::{NewProxy, Proxy};
 use sctk::reexports::client::protocol::wl_keyboard;
 
+// The repeat rate (in keys per second) and delay (in milliseconds) used until the compositor sends
+// `repeat_info`, which are the defaults of the X server.
+const DEFAULT_REPEAT_RATE: i32 = 25;
+const DEFAULT_REPEAT_DELAY: i32 = 600;
+
+/// Wayland leaves key repeat to clients. While a key is held, the events loop wakes up when the
+/// next repeat is due, and `send_due_repeats` sends a copy of the key's press event.
+///
+/// The keyboards of every seat share it, so only the key pressed last repeats.
+pub struct KeyRepeat {
+    rate: i32,
+    delay: i32,
+    // The modifiers given with the latest key event.
+    modifiers: ModifiersState,
+    // The key being repeated, if any.
+    key: Option<RepeatedKey>,
+}
+
+struct RepeatedKey {
+    wid: WindowId,
+    input: KeyboardInput,
+    utf8: Option<String>,
+    // When the next repeat is due, and the timestamp it's sent with.
+    next: Instant,
+    timestamp: Duration,
+    interval: Duration,
+}
+
+impl KeyRepeat {
+    pub fn new() -> KeyRepeat {
+        KeyRepeat {
+            rate: DEFAULT_REPEAT_RATE,
+            delay: DEFAULT_REPEAT_DELAY,
+            modifiers: ModifiersState::default(),
+            key: None,
+        }
+    }
+
+    /// When the events loop has to wake up to send the next repeat.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.key.as_ref().map(|key| key.next)
+    }
+
+    /// Sends a repeat of the held key if one is due.
+    pub fn send_due_repeats(&mut self, sink: &mut EventsLoopSink) {
+        let (wid, input, utf8, timestamp) = match self.take_due(Instant::now()) {
+            Some(repeat) => repeat,
+            None => return,
+        };
+        sink.send_event(
+            WindowEvent::KeyboardInput {
+                device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
+                input,
+                timestamp,
+            },
+            wid,
+        );
+        if let Some(txt) = utf8 {
+            for chr in txt.chars() {
+                sink.send_event(WindowEvent::ReceivedCharacter(chr), wid);
+            }
+        }
+    }
+
+    // Returns the repeat that's due at `now`, and schedules the next one. Repeats that were missed
+    // because the events loop wasn't running aren't made up for, so they don't arrive in a burst.
+    fn take_due(&mut self, now: Instant) -> Option<(WindowId, KeyboardInput, Option<String>, Duration)> {
+        if !self.key.as_ref().map_or(false, |key| key.next <= now) {
+            return None;
+        }
+        let modifiers = self.modifiers;
+        let key = self.key.as_mut().unwrap();
+        let mut input = key.input.clone();
+        input.modifiers = modifiers;
+        let repeat = (key.wid, input, key.utf8.clone(), key.timestamp);
+        while key.next <= now {
+            key.next += key.interval;
+            key.timestamp += key.interval;
+        }
+        Some(repeat)
+    }
+
+    fn start(&mut self, wid: WindowId, input: KeyboardInput, utf8: Option<String>, timestamp: Duration) {
+        self.stop();
+        // A rate of zero disables repeat
+        if self.rate <= 0 {
+            return;
+        }
+        let delay = Duration::from_millis(self.delay.max(0) as u64);
+        let interval = Duration::from_millis(cmp::max(1000 / self.rate as u64, 1));
+        self.key = Some(RepeatedKey {
+            wid,
+            input,
+            utf8,
+            next: Instant::now() + delay,
+            // The repeats are timestamped with when they're due, rather than when they're sent
+            timestamp: timestamp + delay,
+            interval,
+        });
+    }
+
+    fn stop(&mut self) {
+        self.key = None;
+    }
+
+    // Called with the modifiers of every key event. The text of the held key was translated with
+    // the modifiers it was pressed with, and xkbcommon can't be asked to translate it again with
+    // the new ones, so a change of modifiers stops the repeat rather than sending stale text.
+    fn set_modifiers(&mut self, modifiers: ModifiersState) {
+        if modifiers != self.modifiers {
+            self.stop();
+        }
+        self.modifiers = modifiers;
+    }
+
+    fn is_repeating(&self, rawkey: u32) -> bool {
+        self.key.as_ref().map_or(false, |key| key.input.scancode == rawkey)
+    }
+}
+
+// Modifiers and lock keys don't repeat.
+fn keysym_repeats(keysym: u32) -> bool {
+    use sctk::keyboard::keysyms;
+    match keysym {
+        keysyms::XKB_KEY_Shift_L ... keysyms::XKB_KEY_Hyper_R => false,
+        keysyms::XKB_KEY_ISO_Lock ... keysyms::XKB_KEY_ISO_Level5_Lock => false,
+        keysyms::XKB_KEY_Num_Lock | keysyms::XKB_KEY_Scroll_Lock => false,
+        _ => true,
+    }
+}
+
 pub fn init_keyboard(
     keyboard: NewProxy<wl_keyboard::WlKeyboard>,
     sink: Arc<Mutex<EventsLoopSink>>,
+    key_repeat: Arc<Mutex<KeyRepeat>>,
 ) -> Proxy<wl_keyboard::WlKeyboard> {
     // { variables to be captured by the closure
     let mut target = None;
     let my_sink = sink.clone();
+    let repeat = key_repeat;
     // }
     let ret = map_keyboard_auto(keyboard, move |evt: KbEvent, _| match evt {
         KbEvent::Enter { surface, .. } => {
@@ -25,6 +162,7 @@ pub fn init_keyboard(
             target = Some(wid);
         }
         KbEvent::Leave { surface, .. } => {
+            repeat.lock().unwrap().stop();
             let wid = make_wid(&surface);
             my_sink
                 .lock()
@@ -33,6 +171,7 @@ pub fn init_keyboard(
             target = None;
         }
         KbEvent::Key {
+            time,
             modifiers,
             rawkey,
             keysym,
@@ -46,31 +185,59 @@ pub fn init_keyboard(
                     wl_keyboard::KeyState::Released => ElementState::Released,
                 };
                 let vkcode = key_to_vkey(rawkey, keysym);
-                let mut guard = my_sink.lock().unwrap();
-                guard.send_event(
-                    WindowEvent::KeyboardInput {
-                        device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
-                        input: KeyboardInput {
-                            state: state,
-                            scancode: rawkey,
-                            virtual_keycode: vkcode,
-                            modifiers: modifiers.into(),
-                        },
-                    },
-                    wid,
-                );
-                // send char event only on key press, not release
-                if let ElementState::Released = state {
-                    return;
+                // The legacy keysyms, such as the Cyrillic ones, don't map to characters, but
+                // xkbcommon gives us the text of the key.
+                let logical_key = keysym_to_logical_key(keysym, keysym_to_vkey).or_else(|| {
+                    utf8.as_ref().and_then(|txt| txt.chars().next()).and_then(LogicalKey::from_char)
+                });
+                let input = KeyboardInput {
+                    state: state,
+                    scancode: rawkey,
+                    physical_key: evdev_to_physical_key(rawkey),
+                    logical_key,
+                    virtual_keycode: vkcode,
+                    location: rawkey_to_location(rawkey),
+                    modifiers: modifiers.into(),
+                };
+                {
+                    let mut repeat = repeat.lock().unwrap();
+                    repeat.set_modifiers(input.modifiers);
+                    if state == ElementState::Released && repeat.is_repeating(rawkey) {
+                        repeat.stop();
+                    }
                 }
-                if let Some(txt) = utf8 {
-                    for chr in txt.chars() {
-                        guard.send_event(WindowEvent::ReceivedCharacter(chr), wid);
+                let timestamp;
+                {
+                    let mut guard = my_sink.lock().unwrap();
+                    timestamp = guard.timestamps.from_millis(time);
+                    guard.send_event(
+                        WindowEvent::KeyboardInput {
+                            device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
+                            input: input.clone(),
+                            timestamp,
+                        },
+                        wid,
+                    );
+                    // send char event only on key press, not release
+                    if let ElementState::Released = state {
+                        return;
                     }
+                    if let Some(ref txt) = utf8 {
+                        for chr in txt.chars() {
+                            guard.send_event(WindowEvent::ReceivedCharacter(chr), wid);
+                        }
+                    }
+                }
+                if keysym_repeats(keysym) {
+                    repeat.lock().unwrap().start(wid, input, utf8, timestamp);
                 }
             }
         }
-        KbEvent::RepeatInfo { .. } => { /* TODO: handle repeat info */ }
+        KbEvent::RepeatInfo { rate, delay } => {
+            let mut repeat = repeat.lock().unwrap();
+            repeat.rate = rate;
+            repeat.delay = delay;
+        }
     });
 
     match ret {
@@ -104,21 +271,28 @@ pub fn init_keyboard(
                         .send_event(WindowEvent::Focused(false), wid);
                     target = None;
                 }
-                wl_keyboard::Event::Key { key, state, .. } => {
+                wl_keyboard::Event::Key { time, key, state, .. } => {
                     if let Some(wid) = target {
                         let state = match state {
                             wl_keyboard::KeyState::Pressed => ElementState::Pressed,
                             wl_keyboard::KeyState::Released => ElementState::Released,
                         };
-                        my_sink.lock().unwrap().send_event(
+                        let mut sink = my_sink.lock().unwrap();
+                        let timestamp = sink.timestamps.from_millis(time);
+                        sink.send_event(
                             WindowEvent::KeyboardInput {
                                 device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
                                 input: KeyboardInput {
                                     state: state,
                                     scancode: key,
+                                    physical_key: evdev_to_physical_key(key),
+                                    // Without a keymap we don't know what the layout produces
+                                    logical_key: None,
                                     virtual_keycode: None,
+                                    location: rawkey_to_location(key),
                                     modifiers: ModifiersState::default(),
                                 },
+                                timestamp,
                             },
                             wid,
                         );
@@ -130,6 +304,21 @@ pub fn init_keyboard(
     }
 }
 
+// The raw keys are evdev scancodes, which identify the physical key regardless of the layout.
+fn rawkey_to_location(rawkey: u32) -> KeyLocation {
+    match rawkey {
+        // KEY_KP7 to KEY_KPDOT
+        key if key >= 71 && key <= 83 => KeyLocation::Numpad,
+        // KEY_KPASTERISK, KEY_KPENTER, KEY_KPSLASH, KEY_KPEQUAL, KEY_KPPLUSMINUS and KEY_KPCOMMA
+        55 | 96 | 98 | 117 | 118 | 121 => KeyLocation::Numpad,
+        // KEY_LEFTCTRL, KEY_LEFTSHIFT, KEY_LEFTALT and KEY_LEFTMETA
+        29 | 42 | 56 | 125 => KeyLocation::Left,
+        // KEY_RIGHTSHIFT, KEY_RIGHTCTRL, KEY_RIGHTALT and KEY_RIGHTMETA
+        54 | 97 | 100 | 126 => KeyLocation::Right,
+        _ => KeyLocation::Standard,
+    }
+}
+
 fn key_to_vkey(rawkey: u32, keysym: u32) -> Option<VirtualKeyCode> {
     match rawkey {
         1 => Some(VirtualKeyCode::Escape),
@@ -177,6 +366,17 @@ fn keysym_to_vkey(keysym: u32) -> Option<VirtualKeyCode> {
         keysyms::XKB_KEY_X | keysyms::XKB_KEY_x => Some(VirtualKeyCode::X),
         keysyms::XKB_KEY_Y | keysyms::XKB_KEY_y => Some(VirtualKeyCode::Y),
         keysyms::XKB_KEY_Z | keysyms::XKB_KEY_z => Some(VirtualKeyCode::Z),
+        // numbers
+        keysyms::XKB_KEY_1 => Some(VirtualKeyCode::Key1),
+        keysyms::XKB_KEY_2 => Some(VirtualKeyCode::Key2),
+        keysyms::XKB_KEY_3 => Some(VirtualKeyCode::Key3),
+        keysyms::XKB_KEY_4 => Some(VirtualKeyCode::Key4),
+        keysyms::XKB_KEY_5 => Some(VirtualKeyCode::Key5),
+        keysyms::XKB_KEY_6 => Some(VirtualKeyCode::Key6),
+        keysyms::XKB_KEY_7 => Some(VirtualKeyCode::Key7),
+        keysyms::XKB_KEY_8 => Some(VirtualKeyCode::Key8),
+        keysyms::XKB_KEY_9 => Some(VirtualKeyCode::Key9),
+        keysyms::XKB_KEY_0 => Some(VirtualKeyCode::Key0),
         // F--
         keysyms::XKB_KEY_F1 => Some(VirtualKeyCode::F1),
         keysyms::XKB_KEY_F2 => Some(VirtualKeyCode::F2),
@@ -310,3 +510,56 @@ impl From<keyboard::ModifiersState> for ModifiersState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use {ElementState, KeyLocation, KeyboardInput, ModifiersState};
+    use platform::platform::evdev::evdev_to_physical_key;
+    use super::KeyRepeat;
+    use super::super::WindowId;
+
+    fn key_a() -> KeyboardInput {
+        KeyboardInput {
+            state: ElementState::Pressed,
+            scancode: 30,
+            physical_key: evdev_to_physical_key(30),
+            logical_key: None,
+            virtual_keycode: None,
+            location: KeyLocation::Standard,
+            modifiers: ModifiersState::default(),
+        }
+    }
+
+    #[test]
+    fn repeats_are_due_after_the_delay_and_then_at_the_rate() {
+        let mut repeat = KeyRepeat::new();
+        let wid = unsafe { WindowId::dummy() };
+        repeat.start(wid, key_a(), Some("a".to_owned()), Duration::from_millis(1000));
+        let start = repeat.deadline().unwrap() - Duration::from_millis(600);
+        assert!(repeat.take_due(start + Duration::from_millis(599)).is_none());
+        let (_, input, utf8, timestamp) = repeat.take_due(start + Duration::from_millis(600)).unwrap();
+        assert_eq!(input.scancode, 30);
+        assert_eq!(utf8, Some("a".to_owned()));
+        assert_eq!(timestamp, Duration::from_millis(1600));
+        assert_eq!(repeat.deadline(), Some(start + Duration::from_millis(640)));
+        // The repeats missed in the meantime are skipped.
+        let (_, _, _, timestamp) = repeat.take_due(start + Duration::from_millis(700)).unwrap();
+        assert_eq!(timestamp, Duration::from_millis(1640));
+        assert_eq!(repeat.deadline(), Some(start + Duration::from_millis(720)));
+    }
+
+    #[test]
+    fn changing_the_modifiers_stops_the_repeat() {
+        let mut repeat = KeyRepeat::new();
+        repeat.start(unsafe { WindowId::dummy() }, key_a(), Some("a".to_owned()), Duration::from_millis(0));
+        assert!(repeat.is_repeating(30));
+        repeat.set_modifiers(ModifiersState::default());
+        assert!(repeat.is_repeating(30));
+        repeat.set_modifiers(ModifiersState { shift: true, ..Default::default() });
+        assert!(!repeat.is_repeating(30));
+        assert!(repeat.deadline().is_none());
+        assert!(repeat.take_due(Instant::now() + Duration::from_secs(1)).is_none());
+    }
+}
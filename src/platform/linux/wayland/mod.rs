@@ -4,22 +4,68 @@
 pub use self::window::Window;
 pub use self::event_loop::{EventsLoop, EventsLoopProxy, EventsLoopSink, MonitorId};
 
+use std::{env, io};
+use std::ffi::CString;
+use std::fs::File;
+use std::io::Write;
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::io::FromRawFd;
+use std::path::PathBuf;
+
+use libc;
+
 use sctk::reexports::client::protocol::wl_surface;
 use sctk::reexports::client::Proxy;
 
+mod background;
+mod cursor;
+mod dnd;
 mod event_loop;
+mod frame;
 mod pointer;
 mod touch;
 mod keyboard;
 mod window;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct DeviceId;
 
+impl DeviceId {
+    pub unsafe fn dummy() -> Self {
+        DeviceId
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct WindowId(usize);
 
+impl WindowId {
+    pub unsafe fn dummy() -> Self {
+        WindowId(0)
+    }
+}
+
 #[inline]
 fn make_wid(s: &Proxy<wl_surface::WlSurface>) -> WindowId {
     WindowId(s.c_ptr() as usize)
 }
+
+// Creates an unlinked file holding `data`, to share it with the compositor. The runtime directory
+// is preferred, since it's usually backed by memory.
+fn create_shm_file(data: &[u8]) -> io::Result<File> {
+    let dir = env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir);
+    let template = CString::new(dir.join("winit-shm-XXXXXX").into_os_string().into_vec())?;
+    let mut template = template.into_bytes_with_nul();
+    let fd = unsafe { libc::mkstemp(template.as_mut_ptr() as *mut libc::c_char) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    unsafe { libc::unlink(template.as_ptr() as *const libc::c_char) };
+    file.write_all(data)?;
+    Ok(file)
+}
@@ -1,57 +1,144 @@
 use std::sync::{Arc, Mutex};
 
-use {ElementState, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent};
+use {DeviceEvent, ElementState, LogicalPosition, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent};
 use events::ModifiersState;
+use os::unix::MouseMotionUnits;
 
 use super::DeviceId;
+use super::cursor::CursorManager;
 use super::event_loop::EventsLoopSink;
 use super::window::WindowStore;
 
 use sctk::reexports::client::{NewProxy, Proxy};
 use sctk::reexports::client::protocol::wl_pointer::{self, Event as PtrEvent, WlPointer};
+use sctk::reexports::client::protocol::wl_seat::WlSeat;
+use sctk::reexports::client::protocol::wl_surface::WlSurface;
+use sctk::reexports::protocols::unstable::relative_pointer::v1::client::zwp_relative_pointer_manager_v1::ZwpRelativePointerManagerV1;
+use sctk::reexports::protocols::unstable::relative_pointer::v1::client::zwp_relative_pointer_v1::{self, ZwpRelativePointerV1};
+use sctk::reexports::protocols::unstable::pointer_constraints::v1::client::zwp_pointer_constraints_v1::ZwpPointerConstraintsV1;
+
+/// The pointers of all seats, along with the optional globals used to lock them and to receive
+/// relative motion. These are shared between the seats and the windows.
+pub struct PointerState {
+    pub pointers: Vec<Proxy<WlPointer>>,
+    /// The pointers that are over one of our windows, whose cursor the window can change.
+    pub focuses: Vec<PointerFocus>,
+    pub relative_pointer_manager: Option<Proxy<ZwpRelativePointerManagerV1>>,
+    pub pointer_constraints: Option<Proxy<ZwpPointerConstraintsV1>>,
+    pub mouse_motion_units: MouseMotionUnits,
+}
+
+pub struct PointerFocus {
+    pub pointer: Proxy<WlPointer>,
+    /// The seat of the pointer.
+    pub seat: Proxy<WlSeat>,
+    /// The surface of the window the pointer entered.
+    pub surface: Proxy<WlSurface>,
+    /// The serial of the `enter` event, which setting the cursor requires.
+    pub serial: u32,
+    /// The surface the pointer's cursor is drawn on.
+    pub cursor_surface: Proxy<WlSurface>,
+    /// The serial of the button press that's still held, which interactive moves and resizes
+    /// require.
+    pub press_serial: Option<u32>,
+}
+
+impl PointerState {
+    pub fn new() -> PointerState {
+        PointerState {
+            pointers: Vec::new(),
+            focuses: Vec::new(),
+            relative_pointer_manager: None,
+            pointer_constraints: None,
+            mouse_motion_units: Default::default(),
+        }
+    }
+
+    pub fn remove_pointer(&mut self, pointer: &Proxy<WlPointer>) {
+        self.pointers.retain(|p| !p.equals(pointer));
+        self.focuses.retain(|focus| !focus.pointer.equals(pointer));
+    }
+}
 
 pub fn implement_pointer(
     pointer: NewProxy<WlPointer>,
+    seat: Proxy<WlSeat>,
     sink: Arc<Mutex<EventsLoopSink>>,
     store: Arc<Mutex<WindowStore>>,
+    cursor_manager: Arc<Mutex<CursorManager>>,
+    pointer_state: Arc<Mutex<PointerState>>,
 ) -> Proxy<WlPointer> {
     let mut mouse_focus = None;
+    // The surface under the pointer, used to look up its DPI factor
+    let mut focus_surface = None;
+    let mut cursor_surface = None;
     let mut axis_buffer = None;
     let mut axis_discrete_buffer = None;
     let mut axis_state = TouchPhase::Ended;
+    // The time of the last axis event, for the scroll sent with the next frame
+    let mut axis_time = 0;
 
     pointer.implement(move |evt, pointer: Proxy<_>| {
         let mut sink = sink.lock().unwrap();
         let store = store.lock().unwrap();
         match evt {
             PtrEvent::Enter {
+                serial,
                 surface,
                 surface_x,
                 surface_y,
-                ..
             } => {
                 let wid = store.find_wid(&surface);
                 if let Some(wid) = wid {
                     mouse_focus = Some(wid);
+                    let mut cursor_manager = cursor_manager.lock().unwrap();
+                    if cursor_surface.is_none() {
+                        cursor_surface = cursor_manager.create_surface();
+                    }
+                    if let Some(ref cursor_surface) = cursor_surface {
+                        let scale = store.get_dpi(&surface).unwrap_or(1);
+                        let cursor = store.get_cursor(&surface).unwrap_or_default();
+                        let _ = cursor_manager.set_window_cursor(&pointer, cursor_surface, serial, &cursor, scale);
+                        // Remember the serial, so that the window can change the cursor later on
+                        let mut pointer_state = pointer_state.lock().unwrap();
+                        pointer_state.focuses.retain(|focus| !focus.pointer.equals(&pointer));
+                        pointer_state.focuses.push(PointerFocus {
+                            pointer: pointer.clone(),
+                            seat: seat.clone(),
+                            surface: surface.clone(),
+                            serial,
+                            cursor_surface: cursor_surface.clone(),
+                            press_serial: None,
+                        });
+                    }
                     sink.send_event(
                         WindowEvent::CursorEntered {
                             device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
                         },
                         wid,
                     );
+                    let position = LogicalPosition::new(surface_x, surface_y);
+                    let dpi = store.get_dpi(&surface).unwrap_or(1);
+                    // `enter` isn't timestamped
+                    let timestamp = sink.timestamps.now();
                     sink.send_event(
                         WindowEvent::CursorMoved {
                             device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
-                            position: (surface_x, surface_y).into(),
+                            position,
+                            physical_position: position.to_physical(dpi as f64),
                             // TODO: replace dummy value with actual modifier state
                             modifiers: ModifiersState::default(),
+                            timestamp,
                         },
                         wid,
                     );
+                    focus_surface = Some(surface);
                 }
             }
             PtrEvent::Leave { surface, .. } => {
                 mouse_focus = None;
+                focus_surface = None;
+                pointer_state.lock().unwrap().focuses.retain(|focus| !focus.pointer.equals(&pointer));
                 let wid = store.find_wid(&surface);
                 if let Some(wid) = wid {
                     sink.send_event(
@@ -63,23 +150,40 @@ pub fn implement_pointer(
                 }
             }
             PtrEvent::Motion {
+                time,
                 surface_x,
                 surface_y,
-                ..
             } => {
                 if let Some(wid) = mouse_focus {
+                    let position = LogicalPosition::new(surface_x, surface_y);
+                    let dpi = focus_surface
+                        .as_ref()
+                        .and_then(|surface| store.get_dpi(surface))
+                        .unwrap_or(1);
+                    let timestamp = sink.timestamps.from_millis(time);
                     sink.send_event(
                         WindowEvent::CursorMoved {
                             device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
-                            position: (surface_x, surface_y).into(),
+                            position,
+                            physical_position: position.to_physical(dpi as f64),
                             // TODO: replace dummy value with actual modifier state
                             modifiers: ModifiersState::default(),
+                            timestamp,
                         },
                         wid,
                     );
                 }
             }
-            PtrEvent::Button { button, state, .. } => {
+            PtrEvent::Button { serial, time, button, state } => {
+                {
+                    let mut pointer_state = pointer_state.lock().unwrap();
+                    if let Some(focus) = pointer_state.focuses.iter_mut().find(|focus| focus.pointer.equals(&pointer)) {
+                        focus.press_serial = match state {
+                            wl_pointer::ButtonState::Pressed => Some(serial),
+                            wl_pointer::ButtonState::Released => None,
+                        };
+                    }
+                }
                 if let Some(wid) = mouse_focus {
                     let state = match state {
                         wl_pointer::ButtonState::Pressed => ElementState::Pressed,
@@ -92,6 +196,7 @@ pub fn implement_pointer(
                         // TODO figure out the translation ?
                         _ => return,
                     };
+                    let timestamp = sink.timestamps.from_millis(time);
                     sink.send_event(
                         WindowEvent::MouseInput {
                             device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
@@ -99,13 +204,15 @@ pub fn implement_pointer(
                             button: button,
                             // TODO: replace dummy value with actual modifier state
                             modifiers: ModifiersState::default(),
+                            timestamp,
                         },
                         wid,
                     );
                 }
             }
-            PtrEvent::Axis { axis, value, .. } => {
+            PtrEvent::Axis { time, axis, value } => {
                 if let Some(wid) = mouse_focus {
+                    let timestamp = sink.timestamps.from_millis(time);
                     if pointer.version() < 5 {
                         let (mut x, mut y) = (0.0, 0.0);
                         // old seat compatibility
@@ -121,6 +228,7 @@ pub fn implement_pointer(
                                 phase: TouchPhase::Moved,
                                 // TODO: replace dummy value with actual modifier state
                                 modifiers: ModifiersState::default(),
+                                timestamp,
                             },
                             wid,
                         );
@@ -132,6 +240,7 @@ pub fn implement_pointer(
                             wl_pointer::Axis::HorizontalScroll => x += value as f32,
                         }
                         axis_buffer = Some((x, y));
+                        axis_time = time;
                         axis_state = match axis_state {
                             TouchPhase::Started | TouchPhase::Moved => TouchPhase::Moved,
                             _ => TouchPhase::Started,
@@ -143,6 +252,7 @@ pub fn implement_pointer(
                 let axis_buffer = axis_buffer.take();
                 let axis_discrete_buffer = axis_discrete_buffer.take();
                 if let Some(wid) = mouse_focus {
+                    let timestamp = sink.timestamps.from_millis(axis_time);
                     if let Some((x, y)) = axis_discrete_buffer {
                         sink.send_event(
                             WindowEvent::MouseWheel {
@@ -151,6 +261,7 @@ pub fn implement_pointer(
                                 phase: axis_state,
                                 // TODO: replace dummy value with actual modifier state
                                 modifiers: ModifiersState::default(),
+                                timestamp,
                             },
                             wid,
                         );
@@ -162,6 +273,7 @@ pub fn implement_pointer(
                                 phase: axis_state,
                                 // TODO: replace dummy value with actual modifier state
                                 modifiers: ModifiersState::default(),
+                                timestamp,
                             },
                             wid,
                         );
@@ -188,3 +300,30 @@ pub fn implement_pointer(
         }
     })
 }
+
+// Relative motion isn't affected by pointer locks or the edges of the screen, which makes it the
+// source of `DeviceEvent::MouseMotion`.
+pub fn implement_relative_pointer(
+    relative_pointer: NewProxy<ZwpRelativePointerV1>,
+    sink: Arc<Mutex<EventsLoopSink>>,
+    pointer_state: Arc<Mutex<PointerState>>,
+) -> Proxy<ZwpRelativePointerV1> {
+    relative_pointer.implement(move |evt, _| match evt {
+        zwp_relative_pointer_v1::Event::RelativeMotion {
+            dx,
+            dy,
+            dx_unaccel,
+            dy_unaccel,
+            ..
+        } => {
+            let delta = match pointer_state.lock().unwrap().mouse_motion_units {
+                MouseMotionUnits::Raw => (dx_unaccel, dy_unaccel),
+                MouseMotionUnits::Pixels => (dx, dy),
+            };
+            sink.lock().unwrap().send_raw_event(::Event::DeviceEvent {
+                device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
+                event: DeviceEvent::MouseMotion { delta },
+            });
+        }
+    })
+}
@@ -26,16 +26,18 @@ pub(crate) fn implement_touch(
         let store = store.lock().unwrap();
         match evt {
             TouchEvent::Down {
-                surface, id, x, y, ..
+                time, surface, id, x, y, ..
             } => {
                 let wid = store.find_wid(&surface);
                 if let Some(wid) = wid {
+                    let timestamp = sink.timestamps.from_millis(time);
                     sink.send_event(
                         WindowEvent::Touch(::Touch {
                             device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
                             phase: TouchPhase::Started,
                             location: (x, y).into(),
                             id: id as u64,
+                            timestamp,
                         }),
                         wid,
                     );
@@ -46,31 +48,35 @@ pub(crate) fn implement_touch(
                     });
                 }
             }
-            TouchEvent::Up { id, .. } => {
+            TouchEvent::Up { time, id, .. } => {
                 let idx = pending_ids.iter().position(|p| p.id == id);
                 if let Some(idx) = idx {
                     let pt = pending_ids.remove(idx);
+                    let timestamp = sink.timestamps.from_millis(time);
                     sink.send_event(
                         WindowEvent::Touch(::Touch {
                             device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
                             phase: TouchPhase::Ended,
                             location: pt.location.into(),
                             id: id as u64,
+                            timestamp,
                         }),
                         pt.wid,
                     );
                 }
             }
-            TouchEvent::Motion { id, x, y, .. } => {
+            TouchEvent::Motion { time, id, x, y } => {
                 let pt = pending_ids.iter_mut().find(|p| p.id == id);
                 if let Some(pt) = pt {
                     pt.location = (x, y);
+                    let timestamp = sink.timestamps.from_millis(time);
                     sink.send_event(
                         WindowEvent::Touch(::Touch {
                             device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
                             phase: TouchPhase::Moved,
                             location: (x, y).into(),
                             id: id as u64,
+                            timestamp,
                         }),
                         pt.wid,
                     );
@@ -78,12 +84,15 @@ pub(crate) fn implement_touch(
             }
             TouchEvent::Frame => (),
             TouchEvent::Cancel => for pt in pending_ids.drain(..) {
+                // `cancel` isn't timestamped
+                let timestamp = sink.timestamps.now();
                 sink.send_event(
                     WindowEvent::Touch(::Touch {
                         device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
                         phase: TouchPhase::Cancelled,
                         location: pt.location.into(),
                         id: pt.id as u64,
+                        timestamp,
                     }),
                     pt.wid,
                 );
@@ -1,43 +1,83 @@
+use std::cmp;
 use std::collections::VecDeque;
 use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
 
-use {CreationError, CursorState, MouseCursor, WindowAttributes, LogicalPosition, LogicalSize};
+use {CreationError, CursorState, ExternalError, Fullscreen, Icon, Insets, MouseCursor, NotSupportedError, ResizeDirection, ScaleFactorPolicy, WindowAttributes,
+     LogicalPosition, LogicalSize};
 use platform::MonitorId as PlatformMonitorId;
 use window::MonitorId as RootMonitorId;
 
-use sctk::window::{BasicFrame, Event as WEvent, Window as SWindow};
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::RawWindowHandle;
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::unix::WaylandHandle;
+
+use sctk::window::{Event as WEvent, FrameRequest, Window as SWindow};
 use sctk::reexports::client::{Display, Proxy};
-use sctk::reexports::client::protocol::{wl_seat, wl_surface, wl_output};
+use sctk::reexports::client::protocol::{wl_callback, wl_seat, wl_surface, wl_output};
 use sctk::reexports::client::protocol::wl_compositor::RequestsTrait as CompositorRequests;
 use sctk::reexports::client::protocol::wl_surface::RequestsTrait as SurfaceRequests;
 use sctk::output::OutputMgr;
+use sctk::reexports::protocols::unstable::pointer_constraints::v1::client::zwp_pointer_constraints_v1::{self, RequestsTrait as ConstraintsRequests};
+use sctk::reexports::protocols::unstable::pointer_constraints::v1::client::zwp_locked_pointer_v1::{self, ZwpLockedPointerV1};
+use sctk::reexports::protocols::xdg_shell::client::xdg_toplevel::ResizeEdge;
 
 use super::{make_wid, EventsLoop, MonitorId, WindowId};
+use super::background::Background;
+use super::cursor::{CursorManager, WindowCursor};
+use super::frame::{self, FrameHandle, WinitFrame};
+use super::pointer::PointerState;
 use platform::platform::wayland::event_loop::{get_available_monitors, get_primary_monitor};
 
 pub struct Window {
     surface: Proxy<wl_surface::WlSurface>,
-    frame: Arc<Mutex<SWindow<BasicFrame>>>,
+    frame: Arc<Mutex<SWindow<WinitFrame>>>,
+    // Shares the decorations with `frame`, to get their size and make requests through them
+    frame_handle: Arc<FrameHandle>,
     monitors: Arc<Mutex<MonitorList>>, // Monitors this window is currently on
     outputs: OutputMgr, // Access to info for all monitors
     size: Arc<Mutex<(u32, u32)>>,
+    size_limits: Arc<Mutex<SizeLimits>>,
     kill_switch: (Arc<Mutex<bool>>, Arc<Mutex<bool>>),
     display: Arc<Display>,
+    need_refresh: Arc<Mutex<bool>>,
     need_frame_refresh: Arc<Mutex<bool>>,
+    resizable: Mutex<bool>,
+    pointer_state: Arc<Mutex<PointerState>>,
+    locked_pointers: Mutex<Vec<Proxy<ZwpLockedPointerV1>>>,
+    cursor: Arc<Mutex<WindowCursor>>,
+    cursor_hidden: Arc<Mutex<bool>>,
+    cursor_manager: Arc<Mutex<CursorManager>>,
+    // The fullscreen state last requested, since the compositor doesn't tell us about it.
+    fullscreen: Mutex<Option<Fullscreen>>,
+    background: Arc<Mutex<Background>>,
 }
 
 impl Window {
     pub fn new(evlp: &EventsLoop, attributes: WindowAttributes) -> Result<Window, CreationError> {
+        // Clients can't change the video mode.
+        if let Some(Fullscreen::Exclusive(..)) = attributes.fullscreen {
+            return Err(CreationError::NotSupported);
+        }
+
         // TODO: Update for new DPI API
         //let (width, height) = attributes.dimensions.unwrap_or((800, 600));
         let (width, height) = (64, 64);
         // Create the window
         let size = Arc::new(Mutex::new((width, height)));
+        let size_limits = Arc::new(Mutex::new(SizeLimits {
+            min: attributes.min_dimensions.map(Into::into),
+            max: attributes.max_dimensions.map(Into::into),
+        }));
 
         // monitor tracking
         let monitor_list = Arc::new(Mutex::new(MonitorList::new()));
 
-        let surface = evlp.env.compositor.create_surface().unwrap().implement({
+        let new_surface = evlp.env.compositor
+            .create_surface()
+            .map_err(|_| CreationError::OsError("Failed to create a Wayland surface.".to_owned()))?;
+        let surface = new_surface.implement({
             let list = monitor_list.clone();
             let omgr = evlp.env.outputs.clone();
             let window_store = evlp.store.clone();
@@ -70,7 +110,8 @@ impl Window {
 
         let window_store = evlp.store.clone();
         let my_surface = surface.clone();
-        let mut frame = SWindow::<BasicFrame>::init(
+        let my_size_limits = size_limits.clone();
+        let mut frame = SWindow::<WinitFrame>::init(
             surface.clone(),
             (width, height),
             &evlp.env.compositor,
@@ -82,8 +123,16 @@ impl Window {
                     let mut store = window_store.lock().unwrap();
                     for window in &mut store.windows {
                         if window.surface.equals(&my_surface) {
-                            window.newsize = new_size;
-                            window.need_refresh = true;
+                            // Some compositors ignore the size limits during interactive resizes.
+                            let size_limits = *my_size_limits.lock().unwrap();
+                            window.newsize = new_size.map(|size| size_limits.clamp(size));
+                            // When the compositor lets us pick the size, the initial `Resized`
+                            // reports the one we picked.
+                            if !window.configured && window.newsize.is_none() {
+                                window.newsize = Some(*window.size.lock().unwrap());
+                            }
+                            window.configured = true;
+                            *(window.need_refresh.lock().unwrap()) = true;
                             *(window.need_frame_refresh.lock().unwrap()) = true;
                             return;
                         }
@@ -108,20 +157,28 @@ impl Window {
                     }
                 }
             },
-        ).unwrap();
+        ).map_err(|_| {
+            // The frame may have been created before the shell surface failed.
+            frame::take_handle(&surface);
+            CreationError::OsError("Failed to create the Wayland shell surface.".to_owned())
+        })?;
+        let frame_handle = frame::take_handle(&surface)
+            .expect("`SWindow` didn't initialize its frame");
 
         for &(_, ref seat) in evlp.seats.lock().unwrap().iter() {
             frame.new_seat(seat);
         }
 
         // Check for fullscreen requirements
-        if let Some(RootMonitorId {
-            inner: PlatformMonitorId::Wayland(ref monitor_id),
-        }) = attributes.fullscreen
-        {
-            frame.set_fullscreen(Some(&monitor_id.proxy));
-        } else if attributes.maximized {
-            frame.set_maximized();
+        match attributes.fullscreen {
+            Some(Fullscreen::Borderless(Some(RootMonitorId {
+                inner: PlatformMonitorId::Wayland(ref monitor_id),
+            }))) => frame.set_fullscreen(Some(&monitor_id.proxy)),
+            // Let the compositor pick the output
+            Some(Fullscreen::Borderless(None)) => frame.set_fullscreen(None),
+            _ => if attributes.maximized {
+                frame.set_maximized();
+            },
         }
 
         frame.set_resizable(attributes.resizable);
@@ -130,37 +187,66 @@ impl Window {
         frame.set_decorate(attributes.decorations);
 
         // min-max dimensions
-        // TODO: Update for new DPI API
-        //frame.set_min_size(attributes.min_dimensions);
-        //frame.set_max_size(attributes.max_dimensions);
+        frame.set_min_size(attributes.min_dimensions.map(Into::into));
+        frame.set_max_size(attributes.max_dimensions.map(Into::into));
 
         let kill_switch = Arc::new(Mutex::new(false));
+        let need_refresh = Arc::new(Mutex::new(false));
         let need_frame_refresh = Arc::new(Mutex::new(true));
+        let cursor = Arc::new(Mutex::new(WindowCursor::default()));
+        let cursor_hidden = Arc::new(Mutex::new(false));
+        let background = Arc::new(Mutex::new(Background::new(evlp.env.shm.clone(), attributes.transparent)));
         let frame = Arc::new(Mutex::new(frame));
 
         evlp.store.lock().unwrap().windows.push(InternalWindow {
             closed: false,
             newsize: None,
+            configured: false,
             size: size.clone(),
-            need_refresh: false,
+            need_refresh: need_refresh.clone(),
             need_frame_refresh: need_frame_refresh.clone(),
             surface: surface.clone(),
             kill_switch: kill_switch.clone(),
             frame: Arc::downgrade(&frame),
+            frame_handle: frame_handle.clone(),
             current_dpi: 1,
             new_dpi: None,
+            scale_factor_policy: attributes.scale_factor_policy,
+            monitors: monitor_list.clone(),
+            cursor: cursor.clone(),
+            cursor_hidden: cursor_hidden.clone(),
+            background: background.clone(),
+            frame_pending: Arc::new(Mutex::new(false)),
         });
-        evlp.evq.borrow_mut().sync_roundtrip().unwrap();
+        if let Err(e) = evlp.evq.borrow_mut().sync_roundtrip() {
+            // There won't be a `Window` to clean the entry up once it's dropped.
+            evlp.store.lock().unwrap().windows.retain(|window| !window.surface.equals(&surface));
+            // The shell surface has to go before the surface it's the role of.
+            drop(frame);
+            surface.destroy();
+            return Err(CreationError::OsError(format!("Wayland roundtrip failed: {}", e)));
+        }
 
         Ok(Window {
             display: evlp.display.clone(),
             surface: surface,
             frame: frame,
+            frame_handle,
             monitors: monitor_list,
             outputs: evlp.env.outputs.clone(),
             size: size,
+            size_limits,
             kill_switch: (kill_switch, evlp.cleanup_needed.clone()),
+            need_refresh,
             need_frame_refresh: need_frame_refresh,
+            resizable: Mutex::new(attributes.resizable),
+            pointer_state: evlp.pointer_state.clone(),
+            locked_pointers: Mutex::new(Vec::new()),
+            cursor,
+            cursor_hidden,
+            cursor_manager: evlp.cursor_manager.clone(),
+            fullscreen: Mutex::new(attributes.fullscreen.clone()),
+            background,
         })
     }
 
@@ -183,6 +269,14 @@ impl Window {
         // TODO
     }
 
+    pub fn request_redraw(&self) {
+        *(self.need_refresh.lock().unwrap()) = true;
+        // Cause the `EventsLoop` to break from `dispatch` if it is currently blocked, so that it
+        // sends the event.
+        let _ = self.display.sync();
+        let _ = self.display.flush();
+    }
+
     #[inline]
     pub fn get_position(&self) -> Option<LogicalPosition> {
         // Not possible with wayland
@@ -204,11 +298,31 @@ impl Window {
         Some(self.size.lock().unwrap().clone().into())
     }
 
+    // The surface is drawn at the buffer scale, so the physical size is always a whole multiple.
     #[inline]
-    pub fn get_outer_size(&self) -> Option<LogicalSize> {
+    pub fn get_inner_size_physical(&self) -> Option<(u32, u32)> {
         let (w, h) = self.size.lock().unwrap().clone();
-        // let (w, h) = super::wayland_window::add_borders(w as i32, h as i32);
-        Some((w, h).into())
+        let dpi = self.hidpi_factor() as u32;
+        Some((w * dpi, h * dpi))
+    }
+
+    #[inline]
+    pub fn get_outer_size(&self) -> Option<LogicalSize> {
+        let size = self.size.lock().unwrap().clone();
+        Some(self.frame_handle.add_borders(size).into())
+    }
+
+    #[inline]
+    pub fn get_outer_size_physical(&self) -> Option<(u32, u32)> {
+        let size = self.size.lock().unwrap().clone();
+        let (w, h) = self.frame_handle.add_borders(size);
+        let dpi = self.hidpi_factor() as u32;
+        Some((w * dpi, h * dpi))
+    }
+
+    #[inline]
+    pub fn get_decoration_insets(&self) -> Option<Insets> {
+        Some(self.frame_handle.insets())
     }
 
     #[inline]
@@ -221,35 +335,173 @@ impl Window {
 
     #[inline]
     pub fn set_min_dimensions(&self, dimensions: Option<LogicalSize>) {
-        self.frame.lock().unwrap().set_min_size(dimensions.map(Into::into));
+        let dimensions = dimensions.map(Into::into);
+        self.size_limits.lock().unwrap().min = dimensions;
+        self.frame.lock().unwrap().set_min_size(dimensions);
     }
 
     #[inline]
     pub fn set_max_dimensions(&self, dimensions: Option<LogicalSize>) {
-        self.frame.lock().unwrap().set_max_size(dimensions.map(Into::into));
+        let dimensions = dimensions.map(Into::into);
+        self.size_limits.lock().unwrap().max = dimensions;
+        self.frame.lock().unwrap().set_max_size(dimensions);
     }
 
     #[inline]
     pub fn set_resizable(&self, resizable: bool) {
         self.frame.lock().unwrap().set_resizable(resizable);
+        *(self.resizable.lock().unwrap()) = resizable;
     }
 
     #[inline]
-    pub fn set_cursor(&self, _cursor: MouseCursor) {
-        // TODO
+    pub fn is_resizable(&self) -> bool {
+        *self.resizable.lock().unwrap()
+    }
+
+    #[inline]
+    pub fn set_cursor(&self, cursor: MouseCursor) {
+        // Pointers entering the window later on pick the cursor up from the store
+        *self.cursor.lock().unwrap() = WindowCursor::Standard(cursor);
+        self.update_cursor();
+    }
+
+    pub fn set_cursor_image(&self, image: Icon, hotspot: (u32, u32)) -> Result<(), ExternalError> {
+        let scale = self.hidpi_factor();
+        let image = self.cursor_manager.lock().unwrap().create_image(image, hotspot, scale)?;
+        *self.cursor.lock().unwrap() = WindowCursor::Image(Arc::new(image));
+        self.update_cursor();
+        Ok(())
+    }
+
+    pub fn set_animated_cursor(
+        &self,
+        frames: Vec<(Icon, Duration)>,
+        hotspot: (u32, u32),
+    ) -> Result<(), ExternalError> {
+        let scale = self.hidpi_factor();
+        let animation = self.cursor_manager.lock().unwrap().create_animated_cursor(frames, hotspot, scale)?;
+        *self.cursor.lock().unwrap() = WindowCursor::Animated(Arc::new(animation));
+        self.update_cursor();
+        Ok(())
+    }
+
+    pub fn set_cursor_by_name(&self, name: &str) -> Result<(), ExternalError> {
+        let scale = self.hidpi_factor();
+        if !self.cursor_manager.lock().unwrap().has_cursor(name, scale) {
+            return Err(ExternalError::Os(
+                format!("the cursor theme doesn't have a cursor named `{}`", name),
+            ));
+        }
+        *self.cursor.lock().unwrap() = WindowCursor::Named(name.to_owned());
+        self.update_cursor();
+        Ok(())
+    }
+
+    // Applies the cursor to the pointers that are currently over the window.
+    fn update_cursor(&self) {
+        let cursor = if *self.cursor_hidden.lock().unwrap() {
+            WindowCursor::Standard(MouseCursor::NoneCursor)
+        } else {
+            self.cursor.lock().unwrap().clone()
+        };
+        let scale = self.hidpi_factor();
+        // Same locking order as the pointer's `enter` handler
+        let mut cursor_manager = self.cursor_manager.lock().unwrap();
+        let pointer_state = self.pointer_state.lock().unwrap();
+        for focus in &pointer_state.focuses {
+            if focus.surface.equals(&self.surface) {
+                let _ = cursor_manager.set_window_cursor(
+                    &focus.pointer,
+                    &focus.cursor_surface,
+                    focus.serial,
+                    &cursor,
+                    scale,
+                );
+            }
+        }
+    }
+
+    #[inline]
+    pub fn is_cursor_visible(&self) -> bool {
+        !*self.cursor_hidden.lock().unwrap()
     }
 
     #[inline]
-    pub fn set_cursor_state(&self, state: CursorState) -> Result<(), String> {
+    pub fn set_cursor_state(&self, state: CursorState) -> Result<(), ExternalError> {
         use CursorState::{Grab, Hide, Normal};
-        // TODO : not yet possible on wayland to grab cursor
         match state {
-            Grab => Err("Cursor cannot be grabbed on wayland yet.".to_string()),
-            Hide => Err("Cursor cannot be hidden on wayland yet.".to_string()),
-            Normal => Ok(()),
+            Grab => {
+                self.set_cursor_hidden(false);
+                self.lock_pointers()
+            },
+            Hide => {
+                self.unlock_pointers();
+                self.set_cursor_hidden(true);
+                Ok(())
+            },
+            Normal => {
+                self.unlock_pointers();
+                self.set_cursor_hidden(false);
+                Ok(())
+            }
+        }
+    }
+
+    fn set_cursor_hidden(&self, hidden: bool) {
+        let changed = {
+            let mut cursor_hidden = self.cursor_hidden.lock().unwrap();
+            let changed = *cursor_hidden != hidden;
+            *cursor_hidden = hidden;
+            changed
+        };
+        if changed {
+            self.update_cursor();
+        }
+    }
+
+    fn lock_pointers(&self) -> Result<(), ExternalError> {
+        let pointer_state = self.pointer_state.lock().unwrap();
+        // The compositor doesn't support the pointer constraints protocol.
+        let constraints = pointer_state.pointer_constraints
+            .as_ref()
+            .ok_or(ExternalError::NotSupported(NotSupportedError::new()))?;
+        // Without a pointer, there's nothing to grab.
+        if pointer_state.pointers.is_empty() {
+            return Err(ExternalError::NotSupported(NotSupportedError::new()));
+        }
+        let mut locked_pointers = self.locked_pointers.lock().unwrap();
+        if !locked_pointers.is_empty() {
+            return Ok(());
+        }
+        for pointer in &pointer_state.pointers {
+            // The lock becomes active whenever the pointer enters the surface, and stays around
+            // until we destroy it.
+            let locked_pointer = constraints
+                .lock_pointer(
+                    &self.surface,
+                    pointer,
+                    None,
+                    zwp_pointer_constraints_v1::Lifetime::Persistent,
+                )
+                .map_err(|_| ExternalError::Os("Failed to lock the pointer.".to_string()))?;
+            locked_pointers.push(locked_pointer.implement(|_, _| ()));
+        }
+        Ok(())
+    }
+
+    fn unlock_pointers(&self) {
+        use self::zwp_locked_pointer_v1::RequestsTrait;
+        for locked_pointer in self.locked_pointers.lock().unwrap().drain(..) {
+            locked_pointer.destroy();
         }
     }
 
+    #[inline]
+    pub fn set_keyboard_grab(&self, _grab: bool) -> Result<(), ExternalError> {
+        // TODO: use the keyboard-shortcuts-inhibit protocol once it's available to us
+        Err(ExternalError::NotSupported(NotSupportedError::new()))
+    }
+
     #[inline]
     pub fn hidpi_factor(&self) -> i32 {
         self.monitors.lock().unwrap().compute_hidpi_factor()
@@ -260,6 +512,26 @@ impl Window {
         *(self.need_frame_refresh.lock().unwrap()) = true;
     }
 
+    #[inline]
+    pub fn is_decorated(&self) -> bool {
+        self.frame_handle.is_decorated()
+    }
+
+    #[inline]
+    pub fn set_background_color(&self, color: Option<(u8, u8, u8, u8)>) {
+        // It's drawn once the initial size is known.
+        self.background.lock().unwrap().set_color(color);
+    }
+
+    pub fn set_minimized(&self, minimized: bool) -> Result<(), ExternalError> {
+        // Only the compositor can restore a minimized window.
+        if !minimized {
+            return Err(ExternalError::NotSupported(NotSupportedError::new()));
+        }
+        self.frame.lock().unwrap().set_minimized();
+        Ok(())
+    }
+
     pub fn set_maximized(&self, maximized: bool) {
         if maximized {
             self.frame.lock().unwrap().set_maximized();
@@ -268,24 +540,85 @@ impl Window {
         }
     }
 
-    pub fn set_fullscreen(&self, monitor: Option<RootMonitorId>) {
-        if let Some(RootMonitorId {
-            inner: PlatformMonitorId::Wayland(ref monitor_id),
-        }) = monitor
-        {
-            self.frame
-                .lock()
-                .unwrap()
-                .set_fullscreen(Some(&monitor_id.proxy));
-        } else {
-            self.frame.lock().unwrap().unset_fullscreen();
+    pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) -> Result<(), ExternalError> {
+        // Clients can't change the video mode.
+        if let Some(Fullscreen::Exclusive(..)) = fullscreen {
+            return Err(ExternalError::NotSupported(NotSupportedError::new()));
+        }
+        match fullscreen {
+            Some(Fullscreen::Borderless(Some(RootMonitorId {
+                inner: PlatformMonitorId::Wayland(ref monitor_id),
+            }))) => self.frame.lock().unwrap().set_fullscreen(Some(&monitor_id.proxy)),
+            Some(Fullscreen::Borderless(None)) => self.frame.lock().unwrap().set_fullscreen(None),
+            _ => self.frame.lock().unwrap().unset_fullscreen(),
         }
+        *self.fullscreen.lock().unwrap() = fullscreen;
+        Ok(())
+    }
+
+    pub fn get_current_fullscreen(&self) -> Option<Fullscreen> {
+        self.fullscreen.lock().unwrap().clone()
     }
 
     #[inline]
-    pub fn set_cursor_position(&self, _pos: LogicalPosition) -> Result<(), ()> {
-        // TODO: not yet possible on wayland
-        Err(())
+    pub fn show_window_menu(&self, _position: LogicalPosition) -> Result<(), ExternalError> {
+        // TODO: requires `xdg_toplevel.show_window_menu`. We have the seat and serial of the
+        // button press, but neither `SWindow` nor its frame give access to the toplevel.
+        Err(ExternalError::NotSupported(NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn start_drag(&self) -> Result<(), ExternalError> {
+        if let Some((seat, serial)) = self.get_pressed_pointer() {
+            self.frame_handle.request(FrameRequest::Move(seat), serial);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn start_resize(&self, direction: ResizeDirection) -> Result<(), ExternalError> {
+        let edge = match direction {
+            ResizeDirection::East => ResizeEdge::Right,
+            ResizeDirection::North => ResizeEdge::Top,
+            ResizeDirection::NorthEast => ResizeEdge::TopRight,
+            ResizeDirection::NorthWest => ResizeEdge::TopLeft,
+            ResizeDirection::South => ResizeEdge::Bottom,
+            ResizeDirection::SouthEast => ResizeEdge::BottomRight,
+            ResizeDirection::SouthWest => ResizeEdge::BottomLeft,
+            ResizeDirection::West => ResizeEdge::Left,
+        };
+        if let Some((seat, serial)) = self.get_pressed_pointer() {
+            self.frame_handle.request(FrameRequest::Resize(seat, edge), serial);
+        }
+        Ok(())
+    }
+
+    // Returns the seat of a pointer over the window that has a button held, and the serial of the
+    // press, which the compositor checks before starting an interactive move or resize.
+    fn get_pressed_pointer(&self) -> Option<(Proxy<wl_seat::WlSeat>, u32)> {
+        let pointer_state = self.pointer_state.lock().unwrap();
+        pointer_state.focuses
+            .iter()
+            .filter(|focus| focus.surface.equals(&self.surface))
+            .filter_map(|focus| focus.press_serial.map(|serial| (focus.seat.clone(), serial)))
+            .next()
+    }
+
+    #[inline]
+    pub fn set_cursor_position(&self, pos: LogicalPosition) -> Result<(), ExternalError> {
+        use self::zwp_locked_pointer_v1::RequestsTrait;
+        // Wayland doesn't let us warp the cursor. While it's locked we can at least hint where
+        // it should reappear once it's unlocked.
+        let locked_pointers = self.locked_pointers.lock().unwrap();
+        if locked_pointers.is_empty() {
+            return Err(ExternalError::NotSupported(NotSupportedError::new()));
+        }
+        for locked_pointer in locked_pointers.iter() {
+            locked_pointer.set_cursor_position_hint(pos.x, pos.y);
+        }
+        // The hint is double-buffered state of the surface.
+        self.surface.commit();
+        Ok(())
     }
 
     pub fn get_display(&self) -> &Display {
@@ -296,11 +629,26 @@ impl Window {
         &self.surface
     }
 
+    #[cfg(feature = "raw-window-handle")]
+    pub fn raw_window_handle(&self) -> RawWindowHandle {
+        RawWindowHandle::Wayland(WaylandHandle {
+            surface: self.surface.c_ptr() as *mut _,
+            display: self.display.c_ptr() as *mut _,
+            ..WaylandHandle::empty()
+        })
+    }
+
     pub fn get_current_monitor(&self) -> MonitorId {
         // we don't know how much each monitor sees us so...
         // just return the most recent one ?
         let guard = self.monitors.lock().unwrap();
-        guard.monitors.last().unwrap().clone()
+        match guard.monitors.last() {
+            Some(monitor) => monitor.clone(),
+            // The surface only enters an output once it's been drawn on it, which hasn't happened
+            // yet right after the window was built. The compositor usually shows new windows on
+            // the primary monitor.
+            None => self.get_primary_monitor(),
+        }
     }
 
     pub fn get_available_monitors(&self) -> VecDeque<MonitorId> {
@@ -312,8 +660,30 @@ impl Window {
     }
 }
 
+// The minimum and maximum inner sizes of a window.
+#[derive(Debug, Clone, Copy, Default)]
+struct SizeLimits {
+    min: Option<(u32, u32)>,
+    max: Option<(u32, u32)>,
+}
+
+impl SizeLimits {
+    fn clamp(&self, (mut width, mut height): (u32, u32)) -> (u32, u32) {
+        if let Some((max_width, max_height)) = self.max {
+            width = cmp::min(width, max_width);
+            height = cmp::min(height, max_height);
+        }
+        if let Some((min_width, min_height)) = self.min {
+            width = cmp::max(width, min_width);
+            height = cmp::max(height, min_height);
+        }
+        (width, height)
+    }
+}
+
 impl Drop for Window {
     fn drop(&mut self) {
+        self.unlock_pointers();
         *(self.kill_switch.0.lock().unwrap()) = true;
         *(self.kill_switch.1.lock().unwrap()) = true;
     }
@@ -326,24 +696,60 @@ impl Drop for Window {
 struct InternalWindow {
     surface: Proxy<wl_surface::WlSurface>,
     newsize: Option<(u32, u32)>,
+    // Whether the first configure, which decides the initial size, was received
+    configured: bool,
     size: Arc<Mutex<(u32, u32)>>,
-    need_refresh: bool,
+    need_refresh: Arc<Mutex<bool>>,
     need_frame_refresh: Arc<Mutex<bool>>,
     closed: bool,
     kill_switch: Arc<Mutex<bool>>,
-    frame: Weak<Mutex<SWindow<BasicFrame>>>,
+    frame: Weak<Mutex<SWindow<WinitFrame>>>,
+    frame_handle: Arc<FrameHandle>,
     current_dpi: i32,
-    new_dpi: Option<i32>
+    new_dpi: Option<i32>,
+    scale_factor_policy: ScaleFactorPolicy,
+    monitors: Arc<Mutex<MonitorList>>,
+    cursor: Arc<Mutex<WindowCursor>>,
+    cursor_hidden: Arc<Mutex<bool>>,
+    background: Arc<Mutex<Background>>,
+    // Set while the compositor hasn't shown the frame drawn for the last `RedrawRequested`, when
+    // redraws are paced with `set_redraw_vsync`.
+    frame_pending: Arc<Mutex<bool>>,
+}
+
+impl InternalWindow {
+    fn set_dpi(&mut self, new: i32) {
+        let old = self.new_dpi.unwrap_or(self.current_dpi);
+        // The size is logical, so it has to be scaled to keep the same number of pixels.
+        if self.scale_factor_policy == ScaleFactorPolicy::PreservePhysical && old != new {
+            let (w, h) = self.newsize.unwrap_or(*self.size.lock().unwrap());
+            self.newsize = Some((w * old as u32 / new as u32, h * old as u32 / new as u32));
+        }
+        self.new_dpi = Some(new);
+    }
 }
 
 pub struct WindowStore {
     windows: Vec<InternalWindow>,
+    redraw_vsync: bool,
 }
 
 impl WindowStore {
     pub fn new() -> WindowStore {
         WindowStore {
             windows: Vec::new(),
+            redraw_vsync: false,
+        }
+    }
+
+    /// Holds back the redraws of each window until the compositor sends the frame callback for
+    /// the frame it drew last.
+    pub fn set_redraw_vsync(&mut self, enabled: bool) {
+        self.redraw_vsync = enabled;
+        if !enabled {
+            for window in &self.windows {
+                *window.frame_pending.lock().unwrap() = false;
+            }
         }
     }
 
@@ -379,26 +785,104 @@ impl WindowStore {
         }
     }
 
+    pub fn window_ids(&self) -> Vec<WindowId> {
+        self.windows.iter().map(|window| make_wid(&window.surface)).collect()
+    }
+
+    // Returns the buffer scale of the given window's surface.
+    pub fn get_dpi(&self, surface: &Proxy<wl_surface::WlSurface>) -> Option<i32> {
+        self.windows
+            .iter()
+            .find(|window| surface.equals(&window.surface))
+            .map(|window| window.new_dpi.unwrap_or(window.current_dpi))
+    }
+
+    /// Returns the cursor to show over the window, which is `MouseCursor::NoneCursor` while it's
+    /// hidden.
+    pub fn get_cursor(&self, surface: &Proxy<wl_surface::WlSurface>) -> Option<WindowCursor> {
+        self.windows
+            .iter()
+            .find(|window| surface.equals(&window.surface))
+            .map(|window| if *window.cursor_hidden.lock().unwrap() {
+                WindowCursor::Standard(MouseCursor::NoneCursor)
+            } else {
+                window.cursor.lock().unwrap().clone()
+            })
+    }
+
     fn dpi_change(&mut self, surface: &Proxy<wl_surface::WlSurface>, new: i32) {
         for window in &mut self.windows {
             if surface.equals(&window.surface) {
-                window.new_dpi = Some(new);
+                window.set_dpi(new);
+            }
+        }
+    }
+
+    // Surfaces are only told when they enter or leave an output, so this is called when the scale
+    // of the output with the global id `id` changes while windows may be on it, for instance
+    // because the user changed the display settings.
+    pub fn output_scale_changed(&mut self, id: u32, scale: i32) {
+        for window in &mut self.windows {
+            // without version 3 we can't be dpi aware
+            if window.surface.version() < 3 {
+                continue;
+            }
+            let scales = match window.monitors.lock().unwrap().get_scales_with(id, scale) {
+                Some(scales) => scales,
+                None => continue,
+            };
+            let current = window.new_dpi.unwrap_or(window.current_dpi);
+            if let Some(dpi) = updated_hidpi_factor(current, scales) {
+                window.set_dpi(dpi);
+                window.surface.set_buffer_scale(dpi);
             }
         }
     }
 
     pub fn for_each<F>(&mut self, mut f: F)
     where
-        F: FnMut(Option<(u32, u32)>, &mut (u32, u32), Option<i32>, bool, bool, bool, WindowId, Option<&mut SWindow<BasicFrame>>),
+        F: FnMut(Option<(u32, u32)>, &mut (u32, u32), Option<i32>, bool, bool, bool, WindowId, Option<&mut SWindow<WinitFrame>>),
     {
+        let redraw_vsync = self.redraw_vsync;
         for window in &mut self.windows {
             let opt_arc = window.frame.upgrade();
             let mut opt_mutex_lock = opt_arc.as_ref().map(|m| m.lock().unwrap());
+            let newsize = window.newsize.take();
+            // Redraws requested after this are sent on the next iteration.
+            let refresh = if redraw_vsync && *window.frame_pending.lock().unwrap() {
+                false
+            } else {
+                ::std::mem::replace(&mut *window.need_refresh.lock().unwrap(), false)
+            };
+            if refresh && redraw_vsync {
+                // The callback is committed along with the frame the application draws, and is
+                // sent by the compositor once it's a good time to draw the next one.
+                if let Ok(callback) = window.surface.frame() {
+                    let frame_pending = window.frame_pending.clone();
+                    *frame_pending.lock().unwrap() = true;
+                    callback.implement(move |event, _: Proxy<wl_callback::WlCallback>| {
+                        if let wl_callback::Event::Done { .. } = event {
+                            *frame_pending.lock().unwrap() = false;
+                        }
+                    });
+                }
+            }
+            {
+                // The initial size is applied before the first redraw, so the background color
+                // fills the window until the application draws into it.
+                let mut background = window.background.lock().unwrap();
+                if let Some(size) = newsize {
+                    background.draw(&window.surface, size, window.new_dpi.unwrap_or(window.current_dpi));
+                }
+                if refresh {
+                    background.finish();
+                }
+            }
             f(
-                window.newsize.take(),
+                newsize,
                 &mut *(window.size.lock().unwrap()),
                 window.new_dpi,
-                window.need_refresh,
+                refresh,
                 ::std::mem::replace(&mut *window.need_frame_refresh.lock().unwrap(), false),
                 window.closed,
                 make_wid(&window.surface),
@@ -407,7 +891,6 @@ impl WindowStore {
             if let Some(dpi) = window.new_dpi.take() {
                 window.current_dpi = dpi;
             }
-            window.need_refresh = false;
             // avoid re-spamming the event
             window.closed = false;
         }
@@ -429,13 +912,22 @@ impl MonitorList {
         }
     }
 
+    fn get_scales(&self) -> Vec<i32> {
+        self.monitors.iter().map(MonitorId::get_hidpi_factor).collect()
+    }
+
+    // The scales of the outputs, with the scale of output `id` replaced by `scale`, since the
+    // output manager may not have been told about it yet. Returns `None` if the surface isn't on
+    // that output.
+    fn get_scales_with(&self, id: u32, scale: i32) -> Option<Vec<i32>> {
+        let scales = self.monitors
+            .iter()
+            .map(|monitor| (monitor.get_native_identifier(), monitor.get_hidpi_factor()));
+        replace_output_scale(scales, id, scale)
+    }
+
     fn compute_hidpi_factor(&self) -> i32 {
-        let mut factor = 1;
-        for monitor_id in &self.monitors {
-            let monitor_dpi = monitor_id.get_hidpi_factor();
-            if monitor_dpi > factor { factor = monitor_dpi; }
-        }
-        factor
+        hidpi_factor_for_scales(self.get_scales())
     }
 
     fn add_output(&mut self, monitor: MonitorId) -> Option<i32> {
@@ -460,3 +952,80 @@ impl MonitorList {
         }
     }
 }
+
+// Surfaces use the highest scale factor of the outputs they're on.
+fn hidpi_factor_for_scales<I: IntoIterator<Item = i32>>(scales: I) -> i32 {
+    scales.into_iter().fold(1, cmp::max)
+}
+
+// Takes the global ids and scale factors of the outputs a surface is on, and replaces the scale of
+// output `id`. Returns `None` if the surface isn't on that output.
+fn replace_output_scale<I>(outputs: I, id: u32, scale: i32) -> Option<Vec<i32>>
+where
+    I: IntoIterator<Item = (u32, i32)>,
+{
+    let mut found = false;
+    let scales = outputs
+        .into_iter()
+        .map(|(output_id, output_scale)| if output_id == id {
+            found = true;
+            scale
+        } else {
+            output_scale
+        })
+        .collect();
+    if found {
+        Some(scales)
+    } else {
+        None
+    }
+}
+
+// Returns the DPI factor a surface on outputs with the given scale factors should switch to, if it
+// differs from `current`.
+fn updated_hidpi_factor<I: IntoIterator<Item = i32>>(current: i32, scales: I) -> Option<i32> {
+    let new = hidpi_factor_for_scales(scales);
+    if new != current {
+        Some(new)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{replace_output_scale, updated_hidpi_factor, SizeLimits};
+
+    #[test]
+    fn configured_size_is_clamped_to_limits() {
+        let limits = SizeLimits {
+            min: Some((200, 150)),
+            max: Some((1000, 800)),
+        };
+        assert_eq!(limits.clamp((100, 100)), (200, 150));
+        assert_eq!(limits.clamp((150, 400)), (200, 400));
+        assert_eq!(limits.clamp((1200, 900)), (1000, 800));
+        assert_eq!(limits.clamp((640, 480)), (640, 480));
+        assert_eq!(SizeLimits::default().clamp((1, 1)), (1, 1));
+    }
+
+    #[test]
+    fn output_scale_change_updates_hidpi_factor() {
+        // A window spanning two outputs with a scale of 1.
+        assert_eq!(updated_hidpi_factor(1, vec![1, 1]), None);
+        // The user sets the scale of the second output to 2.
+        assert_eq!(updated_hidpi_factor(1, vec![1, 2]), Some(2));
+        assert_eq!(updated_hidpi_factor(2, vec![1, 2]), None);
+        // And back to 1.
+        assert_eq!(updated_hidpi_factor(2, vec![1, 1]), Some(1));
+        // Windows that aren't on any output yet keep the default.
+        assert_eq!(updated_hidpi_factor(1, vec![]), None);
+    }
+
+    #[test]
+    fn output_scale_change_only_concerns_surfaces_on_the_output() {
+        // A window on the outputs 4 and 7, both with a scale of 1, when output 7 is set to 2.
+        assert_eq!(replace_output_scale(vec![(4, 1), (7, 1)], 7, 2), Some(vec![1, 2]));
+        assert_eq!(replace_output_scale(vec![(4, 1)], 7, 2), None);
+    }
+}
@@ -1,6 +1,26 @@
 use {events, libc};
 use super::ffi;
-use VirtualKeyCode;
+use {KeyLocation, VirtualKeyCode};
+
+pub fn keysym_to_location(keysym: libc::c_uint) -> KeyLocation {
+    match keysym {
+        // All the keypad keysyms lie in this range.
+        keysym if keysym >= ffi::XK_KP_Space && keysym <= ffi::XK_KP_Equal => KeyLocation::Numpad,
+        ffi::XK_Shift_L
+        | ffi::XK_Control_L
+        | ffi::XK_Meta_L
+        | ffi::XK_Alt_L
+        | ffi::XK_Super_L
+        | ffi::XK_Hyper_L => KeyLocation::Left,
+        ffi::XK_Shift_R
+        | ffi::XK_Control_R
+        | ffi::XK_Meta_R
+        | ffi::XK_Alt_R
+        | ffi::XK_Super_R
+        | ffi::XK_Hyper_R => KeyLocation::Right,
+        _ => KeyLocation::Standard,
+    }
+}
 
 pub fn keysym_to_element(keysym: libc::c_uint) -> Option<VirtualKeyCode> {
     Some(match keysym {
@@ -1,5 +1,6 @@
 pub use x11_dl::keysym::*;
 pub use x11_dl::xcursor::*;
+pub use x11_dl::xfixes::*;
 pub use x11_dl::xlib::*;
 pub use x11_dl::xinput::*;
 pub use x11_dl::xinput2::*;
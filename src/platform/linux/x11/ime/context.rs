@@ -1,4 +1,5 @@
 use std::ptr;
+use std::ffi::CStr;
 use std::sync::Arc;
 use std::os::raw::{c_short, c_void};
 
@@ -115,6 +116,22 @@ impl ImeContext {
         xconn.check_errors()
     }
 
+    // Resets the composition, returning the text that was being composed, if any.
+    pub fn reset(&self, xconn: &Arc<XConnection>) -> Result<Option<String>, XError> {
+        let preedit = unsafe {
+            let preedit = (xconn.xlib.Xutf8ResetIC)(self.ic);
+            if preedit.is_null() {
+                None
+            } else {
+                let text = CStr::from_ptr(preedit).to_string_lossy().into_owned();
+                (xconn.xlib.XFree)(preedit as _);
+                Some(text)
+            }
+        };
+        xconn.check_errors()?;
+        Ok(preedit.and_then(|text| if text.is_empty() { None } else { Some(text) }))
+    }
+
     pub fn set_spot(&mut self, xconn: &Arc<XConnection>, x: c_short, y: c_short) {
         if self.ic_spot.x == x && self.ic_spot.y == y {
             return;
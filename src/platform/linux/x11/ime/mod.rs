@@ -5,6 +5,7 @@ mod input_method;
 mod context;
 mod callbacks;
 
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::sync::mpsc::{Receiver, Sender};
 
@@ -15,8 +16,15 @@ use self::input_method::PotentialInputMethods;
 use self::context::{ImeContextCreationError, ImeContext};
 use self::callbacks::*;
 
-pub type ImeReceiver = Receiver<(ffi::Window, i16, i16)>;
-pub type ImeSender = Sender<(ffi::Window, i16, i16)>;
+pub type ImeReceiver = Receiver<(ffi::Window, ImeRequest)>;
+pub type ImeSender = Sender<(ffi::Window, ImeRequest)>;
+
+// Windows send these to the event loop, since all XIM calls need to happen from its thread.
+#[derive(Debug, Clone, Copy)]
+pub enum ImeRequest {
+    Spot(i16, i16),
+    Allowed { allowed: bool, commit: bool },
+}
 
 #[derive(Debug)]
 pub enum ImeCreationError {
@@ -29,6 +37,9 @@ pub struct Ime {
     // The actual meat of this struct is boxed away, since it needs to have a fixed location in
     // memory so we can pass a pointer to it around.
     inner: Box<ImeInner>,
+    // Windows for which the IME was disallowed through `Window::set_ime_allowed`.
+    disallowed: HashSet<ffi::Window>,
+    focused_window: Option<ffi::Window>,
 }
 
 impl Ime {
@@ -69,7 +80,12 @@ impl Ime {
                 }
                 result?;
             }
-            Ok(Ime { xconn, inner })
+            Ok(Ime {
+                xconn,
+                inner,
+                disallowed: HashSet::new(),
+                focused_window: None,
+            })
         } else {
             Err(ImeCreationError::OpenFailure(inner.potential_input_methods))
         }
@@ -113,6 +129,7 @@ impl Ime {
     }
 
     pub fn remove_context(&mut self, window: ffi::Window) -> Result<bool, XError> {
+        self.disallowed.remove(&window);
         if let Some(Some(context)) = self.inner.contexts.remove(&window) {
             unsafe {
                 self.inner.destroy_ic_if_necessary(context.ic)?;
@@ -124,7 +141,8 @@ impl Ime {
     }
 
     pub fn focus(&mut self, window: ffi::Window) -> Result<bool, XError> {
-        if self.is_destroyed() {
+        self.focused_window = Some(window);
+        if self.is_destroyed() || self.disallowed.contains(&window) {
             return Ok(false);
         }
         if let Some(&mut Some(ref mut context)) = self.inner.contexts.get_mut(&window) {
@@ -135,6 +153,9 @@ impl Ime {
     }
 
     pub fn unfocus(&mut self, window: ffi::Window) -> Result<bool, XError> {
+        if self.focused_window == Some(window) {
+            self.focused_window = None;
+        }
         if self.is_destroyed() {
             return Ok(false);
         }
@@ -145,6 +166,38 @@ impl Ime {
         }
     }
 
+    // When disallowing, the composition in progress is reset. If `commit` is true, the text that
+    // was being composed is returned so that it can be delivered to the application.
+    pub fn set_allowed(
+        &mut self,
+        window: ffi::Window,
+        allowed: bool,
+        commit: bool,
+    ) -> Result<Option<String>, XError> {
+        let changed = if allowed {
+            self.disallowed.remove(&window)
+        } else {
+            self.disallowed.insert(window)
+        };
+        if !changed || self.is_destroyed() {
+            return Ok(None);
+        }
+        if let Some(&Some(ref context)) = self.inner.contexts.get(&window) {
+            if allowed {
+                if self.focused_window == Some(window) {
+                    context.focus(&self.xconn)?;
+                }
+                Ok(None)
+            } else {
+                let preedit = context.reset(&self.xconn)?;
+                context.unfocus(&self.xconn)?;
+                Ok(if commit { preedit } else { None })
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn send_xim_spot(&mut self, window: ffi::Window, x: i16, y: i16) {
         if self.is_destroyed() {
             return;
@@ -7,22 +7,26 @@ mod window;
 mod xdisplay;
 mod dnd;
 mod ime;
+mod motion;
 pub mod util;
 
 pub use self::monitor::MonitorId;
 pub use self::window::UnownedWindow;
-pub use self::xdisplay::{XConnection, XNotSupported, XError};
+pub use self::xdisplay::{CursorId, XConnection, XNotSupported, XError};
 
-use std::{mem, ptr, slice};
-use std::cell::RefCell;
-use std::collections::HashMap;
-use std::ffi::CStr;
+use std::{cmp, mem, ptr, slice};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::ffi::{CStr, CString};
 use std::ops::Deref;
 use std::os::raw::*;
+use std::os::unix::io::RawFd;
 use std::sync::{Arc, mpsc, Weak};
 use std::sync::atomic::{self, AtomicBool};
+use std::time::{Duration, Instant};
 
 use libc::{self, setlocale, LC_CTYPE};
+use parking_lot::Mutex;
 
 use {
     ControlFlow,
@@ -33,17 +37,27 @@ use {
     KeyboardInput,
     LogicalPosition,
     LogicalSize,
+    PhysicalPosition,
+    ScaleFactorPolicy,
     WindowAttributes,
     WindowEvent,
 };
 use events::ModifiersState;
+use os::unix::{FdInterest, MouseMotionUnits};
 use platform::PlatformSpecificWindowBuilderAttributes;
+use platform::platform::evdev::evdev_to_physical_key;
+use platform::platform::keysym::keysym_to_logical_key;
+use platform::platform::wakeup_fds::{self, WakeupFds};
+use timestamp::EventTimestamps;
 use self::dnd::{Dnd, DndState};
-use self::ime::{ImeReceiver, ImeSender, ImeCreationError, Ime};
+use self::motion::{AxisValue, MotionCoalescer};
+use self::ime::{ImeReceiver, ImeRequest, ImeSender, ImeCreationError, Ime};
 
 pub struct EventsLoop {
     xconn: Arc<XConnection>,
+    wm_protocols: ffi::Atom,
     wm_delete_window: ffi::Atom,
+    net_wm_ping: ffi::Atom,
     dnd: Dnd,
     ime_receiver: ImeReceiver,
     ime_sender: ImeSender,
@@ -57,6 +71,35 @@ pub struct EventsLoop {
     // A dummy, `InputOnly` window that we can use to receive wakeup events and interrupt blocking
     // `XNextEvent` calls.
     wakeup_dummy_window: ffi::Window,
+    mouse_motion_units: Cell<MouseMotionUnits>,
+    wakeup_fds: WakeupFds,
+    // Whether consecutive `ConfigureNotify` events sent by the WM are merged, see
+    // `coalesce_configure_notify`.
+    coalesce_moves: Cell<bool>,
+    // When `false`, windows don't opt into `WM_DELETE_WINDOW`, and the application handles the
+    // messages itself.
+    handle_close_requests: Cell<bool>,
+    // The window that leads the `WM_HINTS` window group of windows created without an explicit
+    // leader. This is the first such window, until it's destroyed.
+    window_group_leader: Cell<Option<ffi::Window>>,
+    timestamps: EventTimestamps,
+    // The windows that were exposed or called `request_redraw` since `RedrawRequested` was last
+    // sent. This is shared with the windows.
+    pending_redraws: Arc<Mutex<HashSet<WindowId>>>,
+    // The windows created since the queued events were last processed, whose initial size may
+    // have to be reported.
+    pending_initial_sizes: RefCell<HashSet<WindowId>>,
+    // Set once the application chose a cursor theme, which then takes precedence over the X
+    // resources.
+    cursor_theme_overridden: Cell<bool>,
+    // The motion events of the batch being processed, see `is_motion_event`.
+    pending_motion: MotionCoalescer,
+    // The refresh interval of the fastest monitor while redraws are paced with
+    // `set_redraw_vsync`. There's no way to find out when a frame was shown, so batches of
+    // redraws are simply sent at most once per interval.
+    redraw_interval: Cell<Option<Duration>>,
+    // When `RedrawRequested` was last sent.
+    last_redraw: Cell<Option<Instant>>,
 }
 
 #[derive(Clone)]
@@ -70,7 +113,9 @@ impl EventsLoop {
     pub fn new(xconn: Arc<XConnection>) -> EventsLoop {
         let root = unsafe { (xconn.xlib.XDefaultRootWindow)(xconn.display) };
 
+        let wm_protocols = unsafe { xconn.get_atom_unchecked(b"WM_PROTOCOLS\0") };
         let wm_delete_window = unsafe { xconn.get_atom_unchecked(b"WM_DELETE_WINDOW\0") };
+        let net_wm_ping = unsafe { xconn.get_atom_unchecked(b"_NET_WM_PING\0") };
 
         let dnd = Dnd::new(Arc::clone(&xconn))
             .expect("Failed to call XInternAtoms when initializing drag and drop");
@@ -144,7 +189,9 @@ impl EventsLoop {
 
         let result = EventsLoop {
             xconn,
+            wm_protocols,
             wm_delete_window,
+            net_wm_ping,
             dnd,
             ime_receiver,
             ime_sender,
@@ -156,8 +203,26 @@ impl EventsLoop {
             pending_wakeup: Default::default(),
             root,
             wakeup_dummy_window,
+            mouse_motion_units: Default::default(),
+            wakeup_fds: Default::default(),
+            coalesce_moves: Cell::new(true),
+            handle_close_requests: Cell::new(true),
+            window_group_leader: Default::default(),
+            timestamps: EventTimestamps::new(),
+            pending_redraws: Default::default(),
+            pending_initial_sizes: Default::default(),
+            cursor_theme_overridden: Cell::new(false),
+            pending_motion: Default::default(),
+            redraw_interval: Cell::new(None),
+            last_redraw: Cell::new(None),
         };
 
+        // Desktop environments update the X resources when the user changes the cursor theme, so
+        // we listen for changes to `RESOURCE_MANAGER`.
+        unsafe {
+            (result.xconn.xlib.XSelectInput)(result.xconn.display, root, ffi::PropertyChangeMask);
+        }
+
         // Register for device hotplug events
         // (The request buffer is flushed during `init_device`)
         result.xconn.select_xinput_events(
@@ -177,6 +242,118 @@ impl EventsLoop {
         &self.xconn
     }
 
+    pub fn set_cursor_theme(&self, name: &str, size: u32) {
+        let name = match CString::new(name) {
+            Ok(name) => name,
+            Err(_) => return,
+        };
+        self.cursor_theme_overridden.set(true);
+        unsafe {
+            (self.xconn.xcursor.XcursorSetTheme)(self.xconn.display, name.as_ptr());
+            (self.xconn.xcursor.XcursorSetDefaultSize)(self.xconn.display, size as c_int);
+        }
+        self.reload_cursors();
+    }
+
+    // Applies the cursor theme from the X resources if it changed.
+    fn update_cursor_theme(&self) {
+        if self.cursor_theme_overridden.get() {
+            return;
+        }
+        let (theme, size) = self.xconn.get_cursor_theme_resources(self.root);
+        let mut changed = false;
+        unsafe {
+            if let Some(theme) = theme.and_then(|theme| CString::new(theme).ok()) {
+                let current = (self.xconn.xcursor.XcursorGetTheme)(self.xconn.display);
+                if current.is_null() || CStr::from_ptr(current) != theme.as_c_str() {
+                    (self.xconn.xcursor.XcursorSetTheme)(self.xconn.display, theme.as_ptr());
+                    changed = true;
+                }
+            }
+            if let Some(size) = size {
+                if (self.xconn.xcursor.XcursorGetDefaultSize)(self.xconn.display) != size as c_int {
+                    (self.xconn.xcursor.XcursorSetDefaultSize)(self.xconn.display, size as c_int);
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            self.reload_cursors();
+        }
+    }
+
+    // Loads the cursors again from the current theme, for every window.
+    fn reload_cursors(&self) {
+        unsafe {
+            for (_, cursor) in self.xconn.cursor_cache.lock().drain() {
+                (self.xconn.xlib.XFreeCursor)(self.xconn.display, cursor);
+            }
+        }
+        for window in self.windows.borrow().values() {
+            if let Some(window) = window.upgrade() {
+                window.reapply_cursor();
+            }
+        }
+    }
+
+    pub fn set_mouse_motion_units(&self, units: MouseMotionUnits) {
+        self.mouse_motion_units.set(units);
+    }
+
+    pub fn set_redraw_vsync(&self, enabled: bool) {
+        let interval = if enabled {
+            let refresh_rate = self.xconn
+                .get_available_monitors()
+                .iter()
+                .filter_map(|monitor| monitor.get_current_video_mode())
+                .map(|mode| mode.refresh_rate)
+                .max()
+                .unwrap_or(0);
+            // The refresh rate is unknown for some outputs, such as virtual ones.
+            let refresh_rate = if refresh_rate > 0 { refresh_rate } else { 60 };
+            Some(Duration::new(0, 1_000_000_000 / refresh_rate as u32))
+        } else {
+            None
+        };
+        self.redraw_interval.set(interval);
+    }
+
+    pub fn set_coalesce_moves(&self, coalesce: bool) {
+        self.coalesce_moves.set(coalesce);
+    }
+
+    pub fn set_handle_close_requests(&self, handle: bool) {
+        self.handle_close_requests.set(handle);
+    }
+
+    pub fn wakeup_on_fd(&self, fd: RawFd, interest: FdInterest) {
+        self.wakeup_fds.insert(fd, interest);
+    }
+
+    pub fn remove_wakeup_fd(&self, fd: RawFd) {
+        self.wakeup_fds.remove(fd);
+    }
+
+    // Grabs outlive the windows that made them if the process doesn't exit, so they're released
+    // for all windows at once.
+    pub fn release_grabs(&self) {
+        let mut pointer_grabbed = false;
+        for window in self.windows.borrow().values() {
+            if let Some(window) = window.upgrade() {
+                window.destroy_pointer_barriers();
+                window.destroy_monitor_barriers();
+                pointer_grabbed |= window.holds_pointer_grab();
+            }
+        }
+        unsafe {
+            if pointer_grabbed {
+                (self.xconn.xlib.XUngrabPointer)(self.xconn.display, ffi::CurrentTime);
+            }
+            (self.xconn.xlib.XUngrabKeyboard)(self.xconn.display, ffi::CurrentTime);
+        }
+        let _ = self.xconn.flush_requests();
+    }
+
     pub fn create_proxy(&self) -> EventsLoopProxy {
         EventsLoopProxy {
             pending_wakeup: Arc::downgrade(&self.pending_wakeup),
@@ -202,27 +379,91 @@ impl EventsLoop {
             }
             self.process_event(&mut xev, &mut callback);
         }
+        self.pending_motion.flush(&mut callback);
+        self.send_initial_sizes(&mut callback);
+
+        if !self.wakeup_fds.is_empty() {
+            let connection_fd = unsafe { (self.xconn.xlib.XConnectionNumber)(self.xconn.display) };
+            let (_, ready_fds) = self.wakeup_fds.poll(connection_fd, 0);
+            for fd in ready_fds {
+                callback(Event::FdReady(fd));
+            }
+            for event in self.wakeup_fds.take_device_events() {
+                callback(event);
+            }
+        }
+
+        self.send_pending_redraws(&mut callback);
     }
 
     pub fn run_forever<F>(&mut self, mut callback: F)
         where F: FnMut(Event) -> ControlFlow
     {
         let mut xev = unsafe { mem::uninitialized() };
+        // The deadline returned with `WaitUntil` for the latest event.
+        let mut deadline = None;
 
         loop {
-            unsafe { (self.xconn.xlib.XNextEvent)(self.xconn.display, &mut xev) }; // Blocks as necessary
-
             let mut control_flow = ControlFlow::Continue;
+            let wait_deadline = deadline;
 
             // Track whether or not `Break` was returned when processing the event.
             {
                 let mut cb = |event| {
-                    if let ControlFlow::Break = callback(event) {
+                    let flow = callback(event);
+                    deadline = flow.wait_deadline();
+                    if let ControlFlow::Break = flow {
                         control_flow = ControlFlow::Break;
                     }
                 };
 
-                self.process_event(&mut xev, &mut cb);
+                if wait_deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+                    cb(Event::Awakened);
+                } else if !self.pending_motion.is_empty()
+                    && unsafe { (self.xconn.xlib.XPending)(self.xconn.display) } == 0
+                {
+                    // The batch of motion events is over, and we mustn't block before sending it.
+                    self.pending_motion.flush(&mut cb);
+                } else if !self.pending_initial_sizes.borrow().is_empty()
+                    && unsafe { (self.xconn.xlib.XPending)(self.xconn.display) } == 0
+                {
+                    self.send_initial_sizes(&mut cb);
+                } else if !self.pending_redraws.lock().is_empty()
+                    && self.redraw_deadline().is_none()
+                    && unsafe { (self.xconn.xlib.XPending)(self.xconn.display) } == 0
+                {
+                    // Redraws are sent once there are no more events to process, and we mustn't
+                    // block while some are pending.
+                    self.send_pending_redraws(&mut cb);
+                } else if self.wakeup_fds.is_empty()
+                    && wait_deadline.is_none()
+                    && self.redraw_deadline().is_none()
+                {
+                    unsafe { (self.xconn.xlib.XNextEvent)(self.xconn.display, &mut xev) }; // Blocks as necessary
+                    self.process_event(&mut xev, &mut cb);
+                } else if unsafe { (self.xconn.xlib.XPending)(self.xconn.display) } > 0 {
+                    unsafe { (self.xconn.xlib.XNextEvent)(self.xconn.display, &mut xev) };
+                    self.process_event(&mut xev, &mut cb);
+                } else {
+                    // `XPending` flushed the request buffer, so we can block on the connection
+                    // together with the registered file descriptors.
+                    let connection_fd = unsafe {
+                        (self.xconn.xlib.XConnectionNumber)(self.xconn.display)
+                    };
+                    // Held back redraws are sent once their deadline passes, without `Awakened`.
+                    let poll_deadline = match (wait_deadline, self.redraw_deadline()) {
+                        (Some(a), Some(b)) => Some(cmp::min(a, b)),
+                        (a, b) => a.or(b),
+                    };
+                    let timeout = poll_deadline.map_or(-1, wakeup_fds::timeout_until);
+                    let (_, ready_fds) = self.wakeup_fds.poll(connection_fd, timeout);
+                    for fd in ready_fds {
+                        cb(Event::FdReady(fd));
+                    }
+                    for event in self.wakeup_fds.take_device_events() {
+                        cb(event);
+                    }
+                }
             }
 
             if let ControlFlow::Break = control_flow {
@@ -231,9 +472,114 @@ impl EventsLoop {
         }
     }
 
+    // Sends `Resized` to the new windows that didn't receive a `ConfigureNotify` yet, which doesn't
+    // happen at all without a window manager. This waits for the queued events to be processed, so
+    // that the first `ConfigureNotify` usually reports the size instead.
+    fn send_initial_sizes<F>(&self, callback: &mut F)
+        where F: FnMut(Event)
+    {
+        let pending_initial_sizes = mem::replace(&mut *self.pending_initial_sizes.borrow_mut(), HashSet::new());
+        for window_id in pending_initial_sizes {
+            let resized = self.with_window(window_id.0, |window| {
+                let size = window.get_inner_size_physical()?;
+                let dpi_factor = window.get_hidpi_factor(); // This must be done *before* locking!
+                let mut shared_state_lock = window.shared_state.lock();
+                if shared_state_lock.size.is_some() {
+                    return None;
+                }
+                shared_state_lock.size = Some(size);
+                Some(LogicalSize::from_physical(size, dpi_factor))
+            });
+            if let Some(Some(logical_size)) = resized {
+                callback(Event::WindowEvent {
+                    window_id: mkwid(window_id.0),
+                    event: WindowEvent::Resized(logical_size),
+                });
+            }
+        }
+    }
+
+    // Sends a single `RedrawRequested` to each window that needs it, followed by
+    // `RedrawEventsCleared`. Redraws requested while these events are handled are sent the next
+    // time.
+    fn send_pending_redraws<F>(&self, callback: &mut F)
+        where F: FnMut(Event)
+    {
+        if self.redraw_deadline().is_some() {
+            return;
+        }
+        let pending_redraws = mem::replace(&mut *self.pending_redraws.lock(), HashSet::new());
+        let mut redrawn = false;
+        for window_id in pending_redraws {
+            if self.window_exists(window_id.0) {
+                callback(Event::WindowEvent {
+                    window_id: mkwid(window_id.0),
+                    event: WindowEvent::RedrawRequested,
+                });
+                redrawn = true;
+            }
+        }
+        if redrawn {
+            self.last_redraw.set(Some(Instant::now()));
+            callback(Event::RedrawEventsCleared);
+        }
+    }
+
+    // Returns when the pending redraws may be sent, if they're held back by `set_redraw_vsync`.
+    fn redraw_deadline(&self) -> Option<Instant> {
+        let deadline = self.last_redraw.get()? + self.redraw_interval.get()?;
+        if deadline > Instant::now() && !self.pending_redraws.lock().is_empty() {
+            Some(deadline)
+        } else {
+            None
+        }
+    }
+
+    // While the WM moves a window, it sends a synthetic `ConfigureNotify` for every step of the
+    // drag. Only the last of those that directly follow each other in the queue matters, so the
+    // others are dropped instead of flooding the user with `Moved` events. Events are never taken
+    // from further down the queue, which would reorder them relative to the events in between.
+    // Real `ConfigureNotify` events carry frame extent changes (see the `ConfigureNotify` handling
+    // below), so they're never skipped.
+    fn coalesce_configure_notify(&self, mut xev: ffi::XConfigureEvent) -> ffi::XConfigureEvent {
+        if !self.coalesce_moves.get() || xev.send_event != ffi::True {
+            return xev;
+        }
+        let mut next: ffi::XEvent = unsafe { mem::uninitialized() };
+        while unsafe { (self.xconn.xlib.XEventsQueued)(self.xconn.display, ffi::QueuedAlready) } > 0 {
+            unsafe { (self.xconn.xlib.XPeekEvent)(self.xconn.display, &mut next) };
+            if next.get_type() != ffi::ConfigureNotify {
+                break;
+            }
+            let next_configure: ffi::XConfigureEvent = *next.as_ref();
+            if next_configure.window != xev.window || next_configure.send_event != ffi::True {
+                break;
+            }
+            // Drop the event we peeked at.
+            unsafe { (self.xconn.xlib.XNextEvent)(self.xconn.display, &mut next) };
+            xev = next_configure;
+        }
+        xev
+    }
+
+    // Motion events are coalesced until the queue is drained or another event arrives, so that a
+    // high polling rate mouse doesn't make us dispatch thousands of events every second.
+    fn is_motion_event(&self, xev: &ffi::XEvent) -> bool {
+        if xev.get_type() != ffi::GenericEvent {
+            return false;
+        }
+        let cookie: &ffi::XGenericEventCookie = xev.as_ref();
+        cookie.extension == self.xi2ext.opcode
+            && (cookie.evtype == ffi::XI_Motion || cookie.evtype == ffi::XI_RawMotion)
+    }
+
     fn process_event<F>(&mut self, xev: &mut ffi::XEvent, mut callback: F)
         where F: FnMut(Event)
     {
+        if !self.is_motion_event(xev) {
+            self.pending_motion.flush(&mut callback);
+        }
+
         // XFilterEvent tells us when an event has been discarded by the input method.
         // Specifically, this involves all of the KeyPress events in compose/pre-edit sequences,
         // along with an extra copy of the KeyRelease events. This also prevents backspace and
@@ -259,7 +605,22 @@ impl EventsLoop {
                 let window_id = mkwid(window);
 
                 if client_msg.data.get_long(0) as ffi::Atom == self.wm_delete_window {
-                    callback(Event::WindowEvent { window_id, event: WindowEvent::CloseRequested });
+                    if self.handle_close_requests.get() {
+                        callback(Event::WindowEvent { window_id, event: WindowEvent::CloseRequested });
+                    }
+                } else if client_msg.message_type == self.wm_protocols
+                    && client_msg.data.get_long(0) as ffi::Atom == self.net_wm_ping
+                {
+                    // The window manager checks that we're still processing events by sending us
+                    // this, and expects it back on the root window. Otherwise, it eventually
+                    // offers the user to kill us.
+                    let mut pong = *client_msg;
+                    pong.window = self.root;
+                    self.xconn.send_event(
+                        self.root,
+                        Some(ffi::SubstructureNotifyMask | ffi::SubstructureRedirectMask),
+                        pong,
+                    ).queue();
                 } else if client_msg.message_type == self.dnd.atoms.enter {
                     let source_window = client_msg.data.get_long(0) as c_ulong;
                     let flags = client_msg.data.get_long(1);
@@ -396,7 +757,7 @@ impl EventsLoop {
                     dpi_changed: Option<WindowEvent>,
                 }
 
-                let xev: &ffi::XConfigureEvent = xev.as_ref();
+                let xev = self.coalesce_configure_notify(*xev.as_ref());
                 let xwindow = xev.window;
                 let events = self.with_window(xwindow, |window| {
                     // So apparently...
@@ -473,7 +834,8 @@ impl EventsLoop {
                             });
                         let outer = frame_extents.inner_pos_to_outer(new_inner_position.0, new_inner_position.1);
                         shared_state_lock.position = Some(outer);
-                        if moved {
+                        // The move requested by `set_position` isn't reported.
+                        if moved && shared_state_lock.pending_move.take() != Some(outer) {
                             let logical_position = LogicalPosition::from_physical(outer, monitor.hidpi_factor);
                             events.moved = Some(WindowEvent::Moved(logical_position));
                         }
@@ -511,13 +873,23 @@ impl EventsLoop {
                             height,
                         );
                         flusher.queue();
-                        shared_state_lock.dpi_adjusted = Some((new_width, new_height));
+                        // The window isn't resized when it keeps its physical size, so there's
+                        // nothing for the Xfwm workaround above to enforce.
+                        if window.scale_factor_policy == ScaleFactorPolicy::PreserveLogical {
+                            shared_state_lock.dpi_adjusted = Some((new_width, new_height));
+                        }
                     }
 
                     events
                 });
 
                 if let Some(events) = events {
+                    if events.resized.is_some() || events.moved.is_some() {
+                        self.with_window(xwindow, |window| window.update_pointer_barriers());
+                    }
+                    if events.dpi_changed.is_some() {
+                        self.with_window(xwindow, |window| window.rescale_cursor_image());
+                    }
                     let window_id = mkwid(xwindow);
                     if let Some(event) = events.resized {
                         callback(Event::WindowEvent { window_id, event });
@@ -531,6 +903,35 @@ impl EventsLoop {
                 }
             }
 
+            ffi::PropertyNotify => {
+                let xev: &ffi::XPropertyEvent = xev.as_ref();
+                let resource_manager = unsafe { self.xconn.get_atom_unchecked(b"RESOURCE_MANAGER\0") };
+                let wm_state = unsafe { self.xconn.get_atom_unchecked(b"WM_STATE\0") };
+                let net_wm_state = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_STATE\0") };
+                if xev.window == self.root && xev.atom == resource_manager {
+                    self.update_cursor_theme();
+                } else if xev.atom == net_wm_state {
+                    self.with_window(xev.window, |window| window.update_maximized());
+                } else if xev.atom == wm_state {
+                    let minimized = self.with_window(xev.window, |window| {
+                        let minimized = window.is_iconic();
+                        let mut shared_state_lock = window.shared_state.lock();
+                        if shared_state_lock.minimized != minimized {
+                            shared_state_lock.minimized = minimized;
+                            Some(minimized)
+                        } else {
+                            None
+                        }
+                    });
+                    if let Some(Some(minimized)) = minimized {
+                        callback(Event::WindowEvent {
+                            window_id: mkwid(xev.window),
+                            event: WindowEvent::Minimized(minimized),
+                        });
+                    }
+                }
+            }
+
             ffi::ReparentNotify => {
                 let xev: &ffi::XReparentEvent = xev.as_ref();
 
@@ -553,8 +954,16 @@ impl EventsLoop {
                 let window_id = mkwid(window);
 
                 // In the event that the window's been destroyed without being dropped first, we
-                // cleanup again here.
-                self.windows.borrow_mut().remove(&WindowId(window));
+                // cleanup again here, and make sure further calls on the `Window` do nothing.
+                let removed = self.windows.borrow_mut().remove(&WindowId(window));
+                if let Some(window) = removed.and_then(|window| window.upgrade()) {
+                    window.mark_destroyed();
+                }
+
+                // The next window created will lead a new group.
+                if self.window_group_leader.get() == Some(window) {
+                    self.window_group_leader.set(None);
+                }
 
                 // Since all XIM stuff needs to happen from the same thread, we destroy the input
                 // context here instead of when dropping the window.
@@ -569,10 +978,9 @@ impl EventsLoop {
             ffi::Expose => {
                 let xev: &ffi::XExposeEvent = xev.as_ref();
 
-                let window = xev.window;
-                let window_id = mkwid(window);
-
-                callback(Event::WindowEvent { window_id, event: WindowEvent::Refresh });
+                // An `Expose` event is sent for every rectangle that has to be redrawn, which are
+                // coalesced into a single `RedrawRequested`.
+                self.pending_redraws.lock().insert(WindowId(xev.window));
             }
 
             ffi::KeyPress | ffi::KeyRelease => {
@@ -618,6 +1026,7 @@ impl EventsLoop {
                         keysym
                     };
                     let virtual_keycode = events::keysym_to_element(keysym as c_uint);
+                    let location = events::keysym_to_location(keysym as c_uint);
 
                     callback(Event::WindowEvent {
                         window_id,
@@ -626,9 +1035,13 @@ impl EventsLoop {
                             input: KeyboardInput {
                                 state,
                                 scancode: xkev.keycode - 8,
+                                physical_key: evdev_to_physical_key(xkev.keycode - 8),
+                                logical_key: keysym_to_logical_key(keysym as u32, events::keysym_to_element),
                                 virtual_keycode,
+                                location,
                                 modifiers,
                             },
+                            timestamp: self.timestamps.from_millis(xkev.time as u32),
                         }
                     });
                 }
@@ -677,12 +1090,16 @@ impl EventsLoop {
                         }
 
                         let modifiers = ModifiersState::from(xev.mods);
+                        let timestamp = self.timestamps.from_millis(xev.time as u32);
 
                         let state = if xev.evtype == ffi::XI_ButtonPress {
                             Pressed
                         } else {
                             Released
                         };
+                        self.with_window(xev.event, |window| {
+                            window.update_pressed_buttons(xev.detail as u32, state == Pressed)
+                        });
                         match xev.detail as u32 {
                             ffi::Button1 => callback(Event::WindowEvent {
                                 window_id,
@@ -691,6 +1108,7 @@ impl EventsLoop {
                                     state,
                                     button: Left,
                                     modifiers,
+                                    timestamp,
                                 },
                             }),
                             ffi::Button2 => callback(Event::WindowEvent {
@@ -700,6 +1118,7 @@ impl EventsLoop {
                                     state,
                                     button: Middle,
                                     modifiers,
+                                    timestamp,
                                 },
                             }),
                             ffi::Button3 => callback(Event::WindowEvent {
@@ -709,6 +1128,7 @@ impl EventsLoop {
                                     state,
                                     button: Right,
                                     modifiers,
+                                    timestamp,
                                 },
                             }),
 
@@ -729,6 +1149,7 @@ impl EventsLoop {
                                         },
                                         phase: TouchPhase::Moved,
                                         modifiers,
+                                        timestamp,
                                     },
                                 });
                             },
@@ -740,6 +1161,7 @@ impl EventsLoop {
                                     state,
                                     button: Other(x as u8),
                                     modifiers,
+                                    timestamp,
                                 },
                             }),
                         }
@@ -751,6 +1173,14 @@ impl EventsLoop {
                         let new_cursor_pos = (xev.event_x, xev.event_y);
 
                         let modifiers = ModifiersState::from(xev.mods);
+                        let timestamp = self.timestamps.from_millis(xev.time as u32);
+
+                        let warped = self.with_window(xev.event, |window| {
+                            window.is_warp_motion(xev.serial, new_cursor_pos)
+                        });
+                        if warped == Some(true) {
+                            return;
+                        }
 
                         let cursor_moved = self.with_window(xev.event, |window| {
                             let mut shared_state_lock = window.shared_state.lock();
@@ -761,18 +1191,22 @@ impl EventsLoop {
                                 window.get_hidpi_factor()
                             });
                             if let Some(dpi_factor) = dpi_factor {
-                                let position = LogicalPosition::from_physical(
-                                    (xev.event_x as f64, xev.event_y as f64),
-                                    dpi_factor,
+                                let physical_position = PhysicalPosition::new(
+                                    xev.event_x as f64,
+                                    xev.event_y as f64,
                                 );
-                                callback(Event::WindowEvent {
+                                let position = physical_position.to_logical(dpi_factor);
+                                let event = Event::WindowEvent {
                                     window_id,
                                     event: CursorMoved {
                                         device_id,
                                         position,
+                                        physical_position,
                                         modifiers,
+                                        timestamp,
                                     },
-                                });
+                                };
+                                self.pending_motion.add_cursor_moved(window_id, device_id, event, &mut callback);
                             } else {
                                 return;
                             }
@@ -808,6 +1242,7 @@ impl EventsLoop {
                                                 },
                                                 phase: TouchPhase::Moved,
                                                 modifiers,
+                                                timestamp,
                                             },
                                         });
                                     } else {
@@ -824,6 +1259,10 @@ impl EventsLoop {
                                 }
                             }
                         }
+                        // Scrolling isn't coalesced, so it has to come after the earlier motion.
+                        if !events.is_empty() {
+                            self.pending_motion.flush(&mut callback);
+                        }
                         for event in events {
                             callback(event);
                         }
@@ -866,16 +1305,19 @@ impl EventsLoop {
                             window.get_hidpi_factor()
                         });
                         if let Some(dpi_factor) = dpi_factor {
-                            let position = LogicalPosition::from_physical(
-                                (xev.event_x as f64, xev.event_y as f64),
-                                dpi_factor,
+                            let physical_position = PhysicalPosition::new(
+                                xev.event_x as f64,
+                                xev.event_y as f64,
                             );
+                            let position = physical_position.to_logical(dpi_factor);
                             callback(Event::WindowEvent {
                                 window_id,
                                 event: CursorMoved {
                                     device_id,
                                     position,
+                                    physical_position,
                                     modifiers,
+                                    timestamp: self.timestamps.from_millis(xev.time as u32),
                                 },
                             });
                         }
@@ -909,6 +1351,7 @@ impl EventsLoop {
                             .focus(xev.event)
                             .expect("Failed to focus input context");
 
+                        self.with_window(xev.event, |window| window.resume_cursor_grab());
                         callback(Event::WindowEvent { window_id, event: Focused(true) });
 
                         // The deviceid for this event is for a keyboard instead of a pointer,
@@ -919,16 +1362,19 @@ impl EventsLoop {
                             .map(|device| device.attachment)
                             .unwrap_or(2);
 
-                        let position = LogicalPosition::from_physical(
-                            (xev.event_x as f64, xev.event_y as f64),
-                            dpi_factor,
+                        let physical_position = PhysicalPosition::new(
+                            xev.event_x as f64,
+                            xev.event_y as f64,
                         );
+                        let position = physical_position.to_logical(dpi_factor);
                         callback(Event::WindowEvent {
                             window_id,
                             event: CursorMoved {
                                 device_id: mkdid(pointer_id),
                                 position,
+                                physical_position,
                                 modifiers: ModifiersState::from(xev.mods),
+                                timestamp: self.timestamps.from_millis(xev.time as u32),
                             }
                         });
                     }
@@ -939,6 +1385,9 @@ impl EventsLoop {
                             .borrow_mut()
                             .unfocus(xev.event)
                             .expect("Failed to unfocus input context");
+                        // This has to happen before the application is told, so that it doesn't
+                        // end up fighting us over the grab.
+                        self.with_window(xev.event, |window| window.suspend_cursor_grab());
                         callback(Event::WindowEvent {
                             window_id: mkwid(xev.event),
                             event: Focused(false),
@@ -969,6 +1418,7 @@ impl EventsLoop {
                                     phase,
                                     location,
                                     id: xev.detail as u64,
+                                    timestamp: self.timestamps.from_millis(xev.time as u32),
                                 }),
                             })
                         }
@@ -993,38 +1443,43 @@ impl EventsLoop {
                         let did = mkdid(xev.deviceid);
 
                         let mask = unsafe { slice::from_raw_parts(xev.valuators.mask, xev.valuators.mask_len as usize) };
+                        // `valuators.values` has the values after pointer acceleration was applied,
+                        // while `raw_values` has the values as reported by the device.
+                        let accelerated = self.mouse_motion_units.get() == MouseMotionUnits::Pixels;
                         let mut value = xev.raw_values;
+                        let mut accelerated_value = xev.valuators.values;
+                        let mut axes = Vec::new();
                         let mut mouse_delta = (0.0, 0.0);
                         let mut scroll_delta = (0.0, 0.0);
+                        let absolute_axes = self.devices
+                            .borrow()
+                            .get(&DeviceId(xev.deviceid))
+                            .map_or_else(Vec::new, |device| device.absolute_axes.clone());
                         for i in 0..xev.valuators.mask_len*8 {
                             if ffi::XIMaskIsSet(mask, i) {
                                 let x = unsafe { *value };
+                                let motion = if accelerated { unsafe { *accelerated_value } } else { x };
                                 // We assume that every XInput2 device with analog axes is a pointing device emitting
                                 // relative coordinates.
                                 match i {
-                                    0 => mouse_delta.0 = x,
-                                    1 => mouse_delta.1 = x,
-                                    2 => scroll_delta.0 = x as f32,
-                                    3 => scroll_delta.1 = x as f32,
+                                    0 => mouse_delta.0 = motion,
+                                    1 => mouse_delta.1 = motion,
+                                    2 => scroll_delta.0 = x,
+                                    3 => scroll_delta.1 = x,
                                     _ => {},
                                 }
-                                callback(Event::DeviceEvent { device_id: did, event: DeviceEvent::Motion {
-                                    axis: i as u32,
-                                    value: x,
-                                }});
+                                let axis_value = if absolute_axes.contains(&i) {
+                                    AxisValue::Absolute(x)
+                                } else {
+                                    AxisValue::Relative(x)
+                                };
+                                axes.push((i as u32, axis_value));
                                 value = unsafe { value.offset(1) };
+                                accelerated_value = unsafe { accelerated_value.offset(1) };
                             }
                         }
-                        if mouse_delta != (0.0, 0.0) {
-                            callback(Event::DeviceEvent { device_id: did, event: DeviceEvent::MouseMotion {
-                                delta: mouse_delta,
-                            }});
-                        }
-                        if scroll_delta != (0.0, 0.0) {
-                            callback(Event::DeviceEvent { device_id: did, event: DeviceEvent::MouseWheel {
-                                delta: LineDelta(scroll_delta.0, scroll_delta.1),
-                            }});
-                        }
+                        // The deltas are summed until the end of the batch.
+                        self.pending_motion.add_raw_motion(did, &axes, mouse_delta, scroll_delta, &mut callback);
                     }
 
                     ffi::XI_RawKeyPress | ffi::XI_RawKeyRelease => {
@@ -1051,12 +1506,17 @@ impl EventsLoop {
                         self.xconn.check_errors().expect("Failed to lookup raw keysym");
 
                         let virtual_keycode = events::keysym_to_element(keysym as c_uint);
+                        let location = events::keysym_to_location(keysym as c_uint);
 
                         callback(Event::DeviceEvent {
                             device_id: mkdid(device_id),
                             event: DeviceEvent::Key(KeyboardInput {
                                 scancode,
+                                physical_key: evdev_to_physical_key(scancode),
+                                // Without modifiers this is the unshifted keysym of the key.
+                                logical_key: keysym_to_logical_key(keysym as u32, events::keysym_to_element),
                                 virtual_keycode,
+                                location,
                                 state,
                                 // So, in an ideal world we can use libxkbcommon to get modifiers.
                                 // However, libxkbcommon-x11 isn't as commonly installed as one
@@ -1086,11 +1546,20 @@ impl EventsLoop {
                 }
             },
             _ => {
-                if event_type == self.randr_event_offset {
+                // Switching the video mode of a monitor only sends an `RRNotify` for its CRTC,
+                // unless the size of the screen changes as well.
+                if event_type == self.randr_event_offset || event_type == self.randr_event_offset + ffi::RRNotify {
                     // In the future, it would be quite easy to emit monitor hotplug events.
                     let prev_list = monitor::invalidate_cached_monitor_list();
+                    let new_list = self.xconn.get_available_monitors();
+                    for new_monitor in &new_list {
+                        for window in self.windows.borrow().values() {
+                            if let Some(window) = window.upgrade() {
+                                window.update_video_mode(new_monitor);
+                            }
+                        }
+                    }
                     if let Some(prev_list) = prev_list {
-                        let new_list = self.xconn.get_available_monitors();
                         for new_monitor in new_list {
                             prev_list
                                 .iter()
@@ -1130,11 +1599,30 @@ impl EventsLoop {
             },
         }
 
-        match self.ime_receiver.try_recv() {
-            Ok((window_id, x, y)) => {
-                self.ime.borrow_mut().send_xim_spot(window_id, x, y);
-            },
-            Err(_) => (),
+        while let Ok((window, request)) = self.ime_receiver.try_recv() {
+            match request {
+                ImeRequest::Spot(x, y) => self.ime.borrow_mut().send_xim_spot(window, x, y),
+                ImeRequest::Allowed { allowed, commit } => {
+                    // Like a failure to move the spot, this isn't worth taking the application
+                    // down for.
+                    let result = self.ime.borrow_mut().set_allowed(window, allowed, commit);
+                    let committed = match result {
+                        Ok(committed) => committed,
+                        Err(err) => {
+                            eprintln!("[winit X11 error] Failed to update input context: {:?}", err);
+                            None
+                        },
+                    };
+                    if let Some(committed) = committed {
+                        for chr in committed.chars() {
+                            callback(Event::WindowEvent {
+                                window_id: mkwid(window),
+                                event: WindowEvent::ReceivedCharacter(chr),
+                            });
+                        }
+                    }
+                },
+            }
         }
     }
 
@@ -1176,13 +1664,16 @@ impl EventsLoop {
 impl EventsLoopProxy {
     pub fn wakeup(&self) -> Result<(), EventsLoopClosed> {
         // Update the `EventsLoop`'s `pending_wakeup` flag.
-        let display = match (self.pending_wakeup.upgrade(), self.xconn.upgrade()) {
-            (Some(wakeup), Some(display)) => {
-                wakeup.store(true, atomic::Ordering::Relaxed);
-                display
-            },
-            _ => return Err(EventsLoopClosed),
-        };
+        match self.pending_wakeup.upgrade() {
+            Some(wakeup) => wakeup.store(true, atomic::Ordering::Relaxed),
+            None => return Err(EventsLoopClosed),
+        }
+        self.interrupt()
+    }
+
+    // Makes `run_forever` process events if it's blocked, without sending `Awakened`.
+    fn interrupt(&self) -> Result<(), EventsLoopClosed> {
+        let display = self.xconn.upgrade().ok_or(EventsLoopClosed)?;
 
         // Push an event on the X event queue so that methods run_forever will advance.
         //
@@ -1244,11 +1735,25 @@ impl<'a> Deref for DeviceInfo<'a> {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct WindowId(ffi::Window);
 
+impl WindowId {
+    pub unsafe fn dummy() -> Self {
+        WindowId(0)
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct DeviceId(c_int);
 
+impl DeviceId {
+    pub unsafe fn dummy() -> Self {
+        DeviceId(0)
+    }
+}
+
 pub struct Window(Arc<UnownedWindow>);
 
 impl Deref for Window {
@@ -1277,11 +1782,17 @@ impl Drop for Window {
     fn drop(&mut self) {
         let window = self.deref();
         let xconn = &window.xconn;
+        // Barriers belong to the connection rather than the window, so they'd outlive it.
+        window.destroy_pointer_barriers();
+        window.destroy_monitor_barriers();
+        // Like the barriers, a switched video mode would otherwise outlive the window.
+        window.restore_video_mode();
         unsafe {
             (xconn.xlib.XDestroyWindow)(xconn.display, window.id().0);
             // If the window was somehow already destroyed, we'll get a `BadWindow` error, which we don't care about.
             let _ = xconn.check_errors();
         }
+        window.free_custom_cursor();
     }
 }
 
@@ -1327,6 +1838,8 @@ fn mkdid(w: c_int) -> ::DeviceId { ::DeviceId(::platform::DeviceId::X(DeviceId(w
 struct Device {
     name: String,
     scroll_axes: Vec<(i32, ScrollAxis)>,
+    // The valuators that report a position rather than a change, such as those of a tablet.
+    absolute_axes: Vec<i32>,
     // For master devices, this is the paired device (pointer <-> keyboard).
     // For slave devices, this is the master.
     attachment: c_int,
@@ -1349,6 +1862,7 @@ impl Device {
     fn new(el: &EventsLoop, info: &ffi::XIDeviceInfo) -> Self {
         let name = unsafe { CStr::from_ptr(info.name).to_string_lossy() };
         let mut scroll_axes = Vec::new();
+        let mut absolute_axes = Vec::new();
 
         if Device::physical_device(info) {
             // Register for global raw events
@@ -1376,6 +1890,12 @@ impl Device {
                             position: 0.0,
                         }));
                     }
+                    ffi::XIValuatorClass => {
+                        let info = unsafe { mem::transmute::<&ffi::XIAnyClassInfo, &ffi::XIValuatorClassInfo>(class) };
+                        if info.mode == ffi::XIModeAbsolute {
+                            absolute_axes.push(info.number);
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -1384,6 +1904,7 @@ impl Device {
         let mut device = Device {
             name: name.into_owned(),
             scroll_axes: scroll_axes,
+            absolute_axes,
             attachment: info.attachment,
         };
         device.reset_scroll_position(info);
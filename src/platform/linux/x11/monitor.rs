@@ -2,10 +2,12 @@ use std::os::raw::*;
 
 use parking_lot::Mutex;
 
-use {PhysicalPosition, PhysicalSize};
+use {Orientation, PhysicalPosition, PhysicalSize, VideoMode};
+use dpi::calc_dpi;
 use super::{util, XConnection, XError};
 use super::ffi::{
     RRCrtcChangeNotifyMask,
+    RROutput,
     RROutputPropertyNotifyMask,
     RRScreenChangeNotifyMask,
     True,
@@ -54,6 +56,16 @@ pub struct MonitorId {
     primary: bool,
     /// The DPI scale factor
     pub(crate) hidpi_factor: f64,
+    /// The physical size of the monitor in millimeters
+    physical_size: (u64, u64),
+    /// The video mode in use when the monitor list was queried
+    video_mode: Option<VideoMode>,
+    /// The video modes supported when the monitor list was queried
+    video_modes: Vec<VideoMode>,
+    /// The output whose CRTC is switched for exclusive fullscreen
+    pub(crate) output: RROutput,
+    /// The rotation of the monitor when the monitor list was queried
+    orientation: Orientation,
     /// Used to determine which windows are on this monitor
     pub(crate) rect: util::Rect,
 }
@@ -66,13 +78,22 @@ impl MonitorId {
         repr: util::MonitorRepr,
         primary: bool,
     ) -> Self {
-        let (name, hidpi_factor) = unsafe { xconn.get_output_info(resources, &repr) };
+        let (name, hidpi_factor, physical_size) = unsafe { xconn.get_output_info(resources, &repr) };
         let (dimensions, position) = unsafe { (repr.get_dimensions(), repr.get_position()) };
+        let video_mode = unsafe { xconn.get_current_video_mode(resources, &repr) };
+        let video_modes = unsafe { xconn.get_video_modes(resources, &repr) };
+        let output = unsafe { repr.get_output() };
+        let orientation = unsafe { xconn.get_orientation(resources, &repr) };
         let rect = util::Rect::new(position, dimensions);
         MonitorId {
             id,
             name,
             hidpi_factor,
+            physical_size,
+            video_mode,
+            video_modes,
+            output,
+            orientation,
             dimensions,
             position,
             primary,
@@ -101,6 +122,31 @@ impl MonitorId {
     pub fn get_hidpi_factor(&self) -> f64 {
         self.hidpi_factor
     }
+
+    #[inline]
+    pub fn get_dpi(&self) -> Option<f64> {
+        calc_dpi(self.dimensions, (self.physical_size.0 as f64, self.physical_size.1 as f64))
+    }
+
+    #[inline]
+    pub fn is_primary(&self) -> bool {
+        self.primary
+    }
+
+    #[inline]
+    pub fn get_current_video_mode(&self) -> Option<VideoMode> {
+        self.video_mode
+    }
+
+    #[inline]
+    pub fn get_video_modes(&self) -> Vec<VideoMode> {
+        self.video_modes.clone()
+    }
+
+    #[inline]
+    pub fn get_orientation(&self) -> Orientation {
+        self.orientation
+    }
 }
 
 impl XConnection {
@@ -191,7 +237,6 @@ impl XConnection {
             // If no monitors were detected as being primary, we just pick one ourselves!
             if !has_primary {
                 if let Some(ref mut fallback) = available.first_mut() {
-                    // Setting this here will come in handy if we ever add an `is_primary` method.
                     fallback.primary = true;
                 }
             }
@@ -0,0 +1,163 @@
+use {DeviceEvent, DeviceId, Event, WindowId};
+use events::MouseScrollDelta::LineDelta;
+
+/// Coalesces the motion events of a batch, since a high polling rate mouse sends thousands of them
+/// every second.
+///
+/// Raw motion is summed, so that the total delta is preserved exactly, except for the axes that
+/// report a position, of which only the last value is kept, as with `CursorMoved`. The events are held back until `flush` is called, which has to happen
+/// before any other event is delivered so that the order of events is preserved.
+#[derive(Default)]
+pub struct MotionCoalescer {
+    raw_motion: Option<RawMotion>,
+    cursor_moved: Option<(WindowId, DeviceId, Event)>,
+}
+
+/// The value of a valuator in a `XI_RawMotion` event.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AxisValue {
+    /// A change since the previous event, which is summed.
+    Relative(f64),
+    /// A position, of which only the latest counts.
+    Absolute(f64),
+}
+
+struct RawMotion {
+    device_id: DeviceId,
+    // The value of each axis that moved, in the order they were first reported
+    axes: Vec<(u32, f64)>,
+    mouse_delta: (f64, f64),
+    scroll_delta: (f64, f64),
+}
+
+impl MotionCoalescer {
+    pub fn is_empty(&self) -> bool {
+        self.raw_motion.is_none() && self.cursor_moved.is_none()
+    }
+
+    pub fn add_raw_motion<F>(
+        &mut self,
+        device_id: DeviceId,
+        axes: &[(u32, AxisValue)],
+        mouse_delta: (f64, f64),
+        scroll_delta: (f64, f64),
+        callback: &mut F,
+    )
+        where F: FnMut(Event)
+    {
+        // The motion of different devices isn't summed
+        if self.raw_motion.as_ref().map_or(false, |motion| motion.device_id != device_id) {
+            self.flush(callback);
+        }
+        let motion = self.raw_motion.get_or_insert_with(|| RawMotion {
+            device_id,
+            axes: Vec::new(),
+            mouse_delta: (0.0, 0.0),
+            scroll_delta: (0.0, 0.0),
+        });
+        for &(axis, value) in axes {
+            match (motion.axes.iter_mut().find(|&&mut (a, _)| a == axis), value) {
+                (Some(&mut (_, ref mut sum)), AxisValue::Relative(delta)) => *sum += delta,
+                (Some(&mut (_, ref mut position)), AxisValue::Absolute(latest)) => *position = latest,
+                (None, AxisValue::Relative(value)) | (None, AxisValue::Absolute(value)) => {
+                    motion.axes.push((axis, value))
+                },
+            }
+        }
+        motion.mouse_delta.0 += mouse_delta.0;
+        motion.mouse_delta.1 += mouse_delta.1;
+        motion.scroll_delta.0 += scroll_delta.0;
+        motion.scroll_delta.1 += scroll_delta.1;
+    }
+
+    pub fn add_cursor_moved<F>(
+        &mut self,
+        window_id: WindowId,
+        device_id: DeviceId,
+        event: Event,
+        callback: &mut F,
+    )
+        where F: FnMut(Event)
+    {
+        // Only the last position within a window matters
+        let is_same_pointer = |&(w, d, _): &(WindowId, DeviceId, Event)| w == window_id && d == device_id;
+        if self.cursor_moved.as_ref().map_or(false, |moved| !is_same_pointer(moved)) {
+            self.flush(callback);
+        }
+        self.cursor_moved = Some((window_id, device_id, event));
+    }
+
+    pub fn flush<F>(&mut self, callback: &mut F)
+        where F: FnMut(Event)
+    {
+        if let Some(motion) = self.raw_motion.take() {
+            let device_id = motion.device_id;
+            for (axis, value) in motion.axes {
+                callback(Event::DeviceEvent { device_id, event: DeviceEvent::Motion { axis, value } });
+            }
+            if motion.mouse_delta != (0.0, 0.0) {
+                callback(Event::DeviceEvent { device_id, event: DeviceEvent::MouseMotion {
+                    delta: motion.mouse_delta,
+                }});
+            }
+            if motion.scroll_delta != (0.0, 0.0) {
+                callback(Event::DeviceEvent { device_id, event: DeviceEvent::MouseWheel {
+                    delta: LineDelta(motion.scroll_delta.0 as f32, motion.scroll_delta.1 as f32),
+                }});
+            }
+        }
+        if let Some((_, _, event)) = self.cursor_moved.take() {
+            callback(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::AxisValue::*;
+    use platform::DeviceId as PlatformDeviceId;
+    use super::super::DeviceId as X11DeviceId;
+
+    #[test]
+    fn raw_motion_is_summed() {
+        let device_id = DeviceId(PlatformDeviceId::X(X11DeviceId(2)));
+        let mut coalescer = MotionCoalescer::default();
+        let mut events = Vec::new();
+        {
+            let mut callback = |event| events.push(event);
+            coalescer.add_raw_motion(device_id, &[(0, Relative(0.5)), (1, Relative(1.0))], (0.5, 1.0), (0.0, 0.0), &mut callback);
+            coalescer.add_raw_motion(device_id, &[(0, Relative(0.25))], (0.25, 0.0), (0.0, 0.0), &mut callback);
+            coalescer.flush(&mut callback);
+        }
+        assert!(coalescer.is_empty());
+        match events.last() {
+            Some(&Event::DeviceEvent { event: DeviceEvent::MouseMotion { delta }, .. }) => {
+                assert_eq!(delta, (0.75, 1.0));
+            },
+            _ => panic!("expected a single `MouseMotion`, got {:?}", events),
+        }
+        assert_eq!(events.len(), 3);
+    }
+
+    #[test]
+    fn absolute_axes_keep_their_last_value() {
+        let device_id = DeviceId(PlatformDeviceId::X(X11DeviceId(12)));
+        let mut coalescer = MotionCoalescer::default();
+        let mut events = Vec::new();
+        {
+            let mut callback = |event| events.push(event);
+            // A tablet's pen position, along with its relative pressure change.
+            coalescer.add_raw_motion(device_id, &[(0, Absolute(100.0)), (2, Relative(0.5))], (0.0, 0.0), (0.0, 0.0), &mut callback);
+            coalescer.add_raw_motion(device_id, &[(0, Absolute(120.0)), (2, Relative(0.25))], (0.0, 0.0), (0.0, 0.0), &mut callback);
+            coalescer.flush(&mut callback);
+        }
+        let motion: Vec<_> = events.iter()
+            .filter_map(|event| match *event {
+                Event::DeviceEvent { event: DeviceEvent::Motion { axis, value }, .. } => Some((axis, value)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(motion, vec![(0, 120.0), (2, 0.75)]);
+    }
+}
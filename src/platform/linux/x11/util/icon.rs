@@ -1,4 +1,5 @@
-use {Icon, Pixel, PIXEL_SIZE};
+use {Icon, PIXEL_SIZE};
+use icon::Pixel;
 use super::*;
 
 impl Pixel {
@@ -14,6 +15,15 @@ impl Pixel {
         }
         cardinal
     }
+
+    // Xcursor wants the color channels to be premultiplied by the alpha, unlike `_NET_WM_ICON`.
+    pub fn to_premultiplied_argb(&self) -> u32 {
+        let premultiply = |channel: u8| (channel as u32 * self.a as u32 + 127) / 255;
+        (self.a as u32) << 24
+            | premultiply(self.r) << 16
+            | premultiply(self.g) << 8
+            | premultiply(self.b)
+    }
 }
 
 impl Icon {
@@ -31,4 +41,14 @@ impl Icon {
         }
         data
     }
+
+    pub(crate) fn to_premultiplied_argb(&self) -> Vec<u32> {
+        assert_eq!(self.rgba.len() % PIXEL_SIZE, 0);
+        let pixel_count = self.rgba.len() / PIXEL_SIZE;
+        assert_eq!(pixel_count, (self.width * self.height) as usize);
+        let pixels = self.rgba.as_ptr() as *const Pixel;
+        (0..pixel_count)
+            .map(|pixel_index| unsafe { &*pixels.offset(pixel_index as isize) }.to_premultiplied_argb())
+            .collect()
+    }
 }
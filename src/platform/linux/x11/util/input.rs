@@ -1,4 +1,4 @@
-use std::str;
+use std::{slice, str};
 
 use super::*;
 use events::ModifiersState;
@@ -38,6 +38,25 @@ impl<'a> PointerState<'a> {
     pub fn get_modifier_state(&self) -> ModifiersState {
         self.modifiers.into()
     }
+
+    pub fn get_window_position(&self) -> (f64, f64) {
+        (self.win_x, self.win_y)
+    }
+
+    pub fn get_root_position(&self) -> (f64, f64) {
+        (self.root_x, self.root_y)
+    }
+
+    // The lowest of the primary, middle and secondary buttons that's currently held.
+    pub fn get_pressed_button(&self) -> Option<c_int> {
+        if self.buttons.mask.is_null() {
+            return None;
+        }
+        let mask = unsafe {
+            slice::from_raw_parts(self.buttons.mask, self.buttons.mask_len as usize)
+        };
+        (1..4).find(|&button| ffi::XIMaskIsSet(mask, button))
+    }
 }
 
 impl<'a> Drop for PointerState<'a> {
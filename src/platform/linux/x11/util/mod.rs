@@ -10,6 +10,8 @@ mod icon;
 mod input;
 mod memory;
 mod randr;
+mod resources;
+mod visual;
 mod window_property;
 mod wm;
 
@@ -22,6 +24,8 @@ pub use self::icon::*;
 pub use self::input::*;
 pub use self::memory::*;
 pub use self::randr::*;
+pub use self::resources::*;
+pub use self::visual::*;
 pub use self::window_property::*;
 pub use self::wm::*;
 
@@ -102,3 +106,18 @@ impl XConnection {
         self.check_errors()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::maybe_change;
+
+    #[test]
+    fn configure_notify_with_same_size_isnt_a_resize() {
+        let mut size = None;
+        assert!(maybe_change(&mut size, (800, 600)));
+        // Making the window unresizable sets its min and max size to its current size, which
+        // makes some window managers send a `ConfigureNotify` with that same size.
+        assert!(!maybe_change(&mut size, (800, 600)));
+        assert!(maybe_change(&mut size, (801, 600)));
+    }
+}
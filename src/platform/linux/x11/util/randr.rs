@@ -1,7 +1,8 @@
 use std::{env, slice};
+use std::os::raw::{c_int, c_ulong};
 use std::str::FromStr;
 
-use validate_hidpi_factor;
+use {validate_hidpi_factor, Orientation, VideoMode};
 use super::*;
 
 pub fn calc_dpi_factor(
@@ -36,6 +37,22 @@ pub fn calc_dpi_factor(
     dpi_factor
 }
 
+// Computes the refresh rate of a mode in Hz, or 0 if it's unknown.
+fn get_refresh_rate(mode: &ffi::XRRModeInfo) -> u16 {
+    let mut v_total = mode.vTotal as f64;
+    if mode.modeFlags & ffi::RR_DoubleScan as c_ulong != 0 {
+        v_total *= 2.0;
+    }
+    if mode.modeFlags & ffi::RR_Interlace as c_ulong != 0 {
+        v_total /= 2.0;
+    }
+    if mode.hTotal != 0 && v_total != 0.0 {
+        (mode.dotClock as f64 / (mode.hTotal as f64 * v_total)).round() as u16
+    } else {
+        0
+    }
+}
+
 pub enum MonitorRepr {
     Monitor(*mut ffi::XRRMonitorInfo),
     Crtc(*mut ffi::XRRCrtcInfo),
@@ -78,7 +95,11 @@ impl From<*mut ffi::XRRCrtcInfo> for MonitorRepr {
 }
 
 impl XConnection {
-    pub unsafe fn get_output_info(&self, resources: *mut ffi::XRRScreenResources, repr: &MonitorRepr) -> (String, f64) {
+    pub unsafe fn get_output_info(
+        &self,
+        resources: *mut ffi::XRRScreenResources,
+        repr: &MonitorRepr,
+    ) -> (String, f64, (u64, u64)) {
         let output_info = (self.xrandr.XRRGetOutputInfo)(
             self.display,
             resources,
@@ -89,11 +110,261 @@ impl XConnection {
             (*output_info).nameLen as usize,
         );
         let name = String::from_utf8_lossy(name_slice).into();
-        let hidpi_factor = calc_dpi_factor(
-            repr.get_dimensions(),
-            ((*output_info).mm_width as u64, (*output_info).mm_height as u64),
+        let physical_size = ((*output_info).mm_width as u64, (*output_info).mm_height as u64);
+        let hidpi_factor = calc_dpi_factor(repr.get_dimensions(), physical_size);
+        (self.xrandr.XRRFreeOutputInfo)(output_info);
+        (name, hidpi_factor, physical_size)
+    }
+
+    // Reads the mode and rotation of the CRTC that the monitor's first output is connected to.
+    unsafe fn get_crtc_state(
+        &self,
+        resources: *mut ffi::XRRScreenResources,
+        repr: &MonitorRepr,
+    ) -> Option<(ffi::RRMode, ffi::Rotation)> {
+        let output_info = (self.xrandr.XRRGetOutputInfo)(
+            self.display,
+            resources,
+            repr.get_output(),
+        );
+        if output_info.is_null() {
+            return None;
+        }
+        let crtc_id = (*output_info).crtc;
+        (self.xrandr.XRRFreeOutputInfo)(output_info);
+        if crtc_id == 0 {
+            // The output is disabled
+            return None;
+        }
+
+        let crtc = (self.xrandr.XRRGetCrtcInfo)(self.display, resources, crtc_id);
+        if crtc.is_null() {
+            return None;
+        }
+        let state = ((*crtc).mode, (*crtc).rotation);
+        (self.xrandr.XRRFreeCrtcInfo)(crtc);
+        Some(state)
+    }
+
+    unsafe fn get_bit_depth(&self) -> u16 {
+        (self.xlib.XDefaultDepth)(
+            self.display,
+            (self.xlib.XDefaultScreen)(self.display),
+        ) as u16
+    }
+
+    pub unsafe fn get_current_video_mode(
+        &self,
+        resources: *mut ffi::XRRScreenResources,
+        repr: &MonitorRepr,
+    ) -> Option<VideoMode> {
+        let (mode_id, _) = self.get_crtc_state(resources, repr)?;
+
+        let modes = slice::from_raw_parts((*resources).modes, (*resources).nmode as usize);
+        let mode = modes.iter().find(|mode| mode.id == mode_id)?;
+
+        // The monitor's dimensions already account for rotation, unlike the mode's.
+        let dimensions = repr.get_dimensions();
+        let bit_depth = self.get_bit_depth();
+        let refresh_rate = get_refresh_rate(mode);
+
+        Some(VideoMode { dimensions, bit_depth, refresh_rate })
+    }
+
+    // Lists the distinct modes supported by the monitor's first output, with the monitor's current
+    // rotation applied to their dimensions.
+    pub unsafe fn get_video_modes(
+        &self,
+        resources: *mut ffi::XRRScreenResources,
+        repr: &MonitorRepr,
+    ) -> Vec<VideoMode> {
+        let output_info = (self.xrandr.XRRGetOutputInfo)(
+            self.display,
+            resources,
+            repr.get_output(),
         );
+        if output_info.is_null() {
+            return Vec::new();
+        }
+        let output_modes = slice::from_raw_parts(
+            (*output_info).modes,
+            (*output_info).nmode as usize,
+        ).to_owned();
         (self.xrandr.XRRFreeOutputInfo)(output_info);
-        (name, hidpi_factor)
+
+        let rotated = match self.get_crtc_state(resources, repr) {
+            Some((_, rotation)) => rotation as c_int & (ffi::RR_Rotate_90 | ffi::RR_Rotate_270) != 0,
+            None => false,
+        };
+        let bit_depth = self.get_bit_depth();
+        let modes = slice::from_raw_parts((*resources).modes, (*resources).nmode as usize);
+
+        let mut video_modes = Vec::with_capacity(output_modes.len());
+        for mode_id in output_modes {
+            let mode = match modes.iter().find(|mode| mode.id == mode_id) {
+                Some(mode) => mode,
+                None => continue,
+            };
+            let dimensions = if rotated {
+                (mode.height, mode.width)
+            } else {
+                (mode.width, mode.height)
+            };
+            let video_mode = VideoMode {
+                dimensions,
+                bit_depth,
+                refresh_rate: get_refresh_rate(mode),
+            };
+            // Modes that only differ in their timings are indistinguishable to the user.
+            if !video_modes.contains(&video_mode) {
+                video_modes.push(video_mode);
+            }
+        }
+        video_modes
+    }
+
+    // Finds the mode of `output` matching `video_mode`. Rotated modes are matched too, since
+    // `get_video_modes` reports them as such.
+    unsafe fn find_mode(
+        &self,
+        resources: *mut ffi::XRRScreenResources,
+        output_info: *mut ffi::XRROutputInfo,
+        video_mode: VideoMode,
+    ) -> Option<ffi::RRMode> {
+        let output_modes = slice::from_raw_parts((*output_info).modes, (*output_info).nmode as usize);
+        let modes = slice::from_raw_parts((*resources).modes, (*resources).nmode as usize);
+        let (width, height) = video_mode.dimensions;
+        output_modes
+            .iter()
+            .filter_map(|&mode_id| modes.iter().find(|mode| mode.id == mode_id))
+            .find(|mode| {
+                let matches_dimensions = (mode.width, mode.height) == (width, height)
+                    || (mode.height, mode.width) == (width, height);
+                matches_dimensions && get_refresh_rate(mode) == video_mode.refresh_rate
+            })
+            .map(|mode| mode.id)
+    }
+
+    /// Switches the CRTC driving `output` to the mode matching `video_mode`.
+    ///
+    /// Returns the mode the CRTC was using beforehand, so that it can be restored with
+    /// `set_output_mode`.
+    pub fn set_output_video_mode(
+        &self,
+        output: ffi::RROutput,
+        video_mode: VideoMode,
+    ) -> Result<ffi::RRMode, ()> {
+        self.with_output_crtc(output, |resources, output_info, crtc_id, crtc| unsafe {
+            let original_mode = (*crtc).mode;
+            let mode = self.find_mode(resources, output_info, video_mode).ok_or(())?;
+            if mode != original_mode {
+                self.set_crtc_config(resources, crtc_id, crtc, mode)?;
+            }
+            Ok(original_mode)
+        })
+    }
+
+    /// Reads back the mode the CRTC driving `output` is using, which can differ from the one
+    /// requested with `set_output_video_mode` if the server substituted another.
+    pub fn get_output_video_mode(&self, output: ffi::RROutput) -> Result<VideoMode, ()> {
+        self.with_output_crtc(output, |resources, _, _, crtc| unsafe {
+            self.get_current_video_mode(resources, &MonitorRepr::Crtc(crtc)).ok_or(())
+        })
+    }
+
+    /// Switches the CRTC driving `output` to the mode with the ID `mode`.
+    pub fn set_output_mode(&self, output: ffi::RROutput, mode: ffi::RRMode) -> Result<(), ()> {
+        self.with_output_crtc(output, |resources, _, crtc_id, crtc| unsafe {
+            if (*crtc).mode != mode {
+                self.set_crtc_config(resources, crtc_id, crtc, mode)?;
+            }
+            Ok(())
+        })
+    }
+
+    unsafe fn set_crtc_config(
+        &self,
+        resources: *mut ffi::XRRScreenResources,
+        crtc_id: ffi::RRCrtc,
+        crtc: *mut ffi::XRRCrtcInfo,
+        mode: ffi::RRMode,
+    ) -> Result<(), ()> {
+        // The CRTC keeps its position, rotation and outputs; only the mode changes.
+        let status = (self.xrandr.XRRSetCrtcConfig)(
+            self.display,
+            resources,
+            crtc_id,
+            ffi::CurrentTime,
+            (*crtc).x,
+            (*crtc).y,
+            mode,
+            (*crtc).rotation,
+            (*crtc).outputs,
+            (*crtc).noutput,
+        );
+        if status == ffi::RRSetConfigSuccess {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    // Looks up the CRTC driving `output` and passes it to `f`, freeing everything afterwards.
+    fn with_output_crtc<T, F>(&self, output: ffi::RROutput, f: F) -> Result<T, ()>
+    where
+        F: FnOnce(
+            *mut ffi::XRRScreenResources,
+            *mut ffi::XRROutputInfo,
+            ffi::RRCrtc,
+            *mut ffi::XRRCrtcInfo,
+        ) -> Result<T, ()>,
+    {
+        unsafe {
+            let root = (self.xlib.XDefaultRootWindow)(self.display);
+            let resources = (self.xrandr.XRRGetScreenResources)(self.display, root);
+            if resources.is_null() {
+                return Err(());
+            }
+            let output_info = (self.xrandr.XRRGetOutputInfo)(self.display, resources, output);
+            let result = if output_info.is_null() || (*output_info).crtc == 0 {
+                Err(())
+            } else {
+                let crtc_id = (*output_info).crtc;
+                let crtc = (self.xrandr.XRRGetCrtcInfo)(self.display, resources, crtc_id);
+                if crtc.is_null() {
+                    Err(())
+                } else {
+                    let result = f(resources, output_info, crtc_id, crtc);
+                    (self.xrandr.XRRFreeCrtcInfo)(crtc);
+                    result
+                }
+            };
+            if !output_info.is_null() {
+                (self.xrandr.XRRFreeOutputInfo)(output_info);
+            }
+            (self.xrandr.XRRFreeScreenResources)(resources);
+            result
+        }
+    }
+
+    pub unsafe fn get_orientation(
+        &self,
+        resources: *mut ffi::XRRScreenResources,
+        repr: &MonitorRepr,
+    ) -> Orientation {
+        let rotation = match self.get_crtc_state(resources, repr) {
+            Some((_, rotation)) => rotation as c_int,
+            None => return Orientation::Landscape,
+        };
+        // XRandR rotates counter-clockwise. The reflection bits are ignored.
+        if rotation & ffi::RR_Rotate_90 != 0 {
+            Orientation::PortraitFlipped
+        } else if rotation & ffi::RR_Rotate_180 != 0 {
+            Orientation::LandscapeFlipped
+        } else if rotation & ffi::RR_Rotate_270 != 0 {
+            Orientation::Portrait
+        } else {
+            Orientation::Landscape
+        }
     }
 }
@@ -0,0 +1,51 @@
+use std::str;
+
+use super::*;
+
+impl XConnection {
+    /// Returns the `Xcursor.theme` and `Xcursor.size` resources. These are read from the
+    /// `RESOURCE_MANAGER` property of the root window, rather than from the copy Xlib made when
+    /// the display was opened, so that changes are picked up.
+    pub fn get_cursor_theme_resources(&self, root: ffi::Window) -> (Option<String>, Option<u32>) {
+        let resource_manager = unsafe { self.get_atom_unchecked(b"RESOURCE_MANAGER\0") };
+        let resources: Vec<c_uchar> = match self.get_property(root, resource_manager, ffi::XA_STRING) {
+            Ok(resources) => resources,
+            Err(_) => return (None, None),
+        };
+        let resources = match str::from_utf8(&resources) {
+            Ok(resources) => resources,
+            Err(_) => return (None, None),
+        };
+        let theme = find_resource(resources, "Xcursor.theme").map(str::to_owned);
+        let size = find_resource(resources, "Xcursor.size").and_then(|size| size.parse().ok());
+        (theme, size)
+    }
+}
+
+// Looks up `name` in resources formatted like the output of `xrdb -query`. The last definition
+// wins, as it does for `xrdb`.
+fn find_resource<'a>(resources: &'a str, name: &str) -> Option<&'a str> {
+    resources
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, ':');
+            match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) if key.trim() == name => Some(value.trim()),
+                _ => None,
+            }
+        })
+        .last()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_resource;
+
+    #[test]
+    fn cursor_resources_are_found() {
+        let resources = "Xft.dpi:\t96\nXcursor.theme:\tAdwaita\nXcursor.size:\t24\nXcursor.theme:\tbreeze\n";
+        assert_eq!(find_resource(resources, "Xcursor.theme"), Some("breeze"));
+        assert_eq!(find_resource(resources, "Xcursor.size"), Some("24"));
+        assert_eq!(find_resource(resources, "Xcursor"), None);
+    }
+}
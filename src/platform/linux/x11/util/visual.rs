@@ -0,0 +1,24 @@
+use super::*;
+
+impl XConnection {
+    // Returns the description of the visual with the given ID, if the screen has one.
+    pub fn get_visual_info(
+        &self,
+        screen_id: c_int,
+        visual_id: ffi::VisualID,
+    ) -> Option<ffi::XVisualInfo> {
+        let mut template: ffi::XVisualInfo = unsafe { mem::zeroed() };
+        template.visualid = visual_id;
+        template.screen = screen_id;
+        let mut count = 0;
+        let visual_infos = unsafe {
+            (self.xlib.XGetVisualInfo)(
+                self.display,
+                ffi::VisualIDMask | ffi::VisualScreenMask,
+                &mut template,
+                &mut count,
+            )
+        };
+        XSmartPointer::new(self, visual_infos).map(|visual_info| *visual_info)
+    }
+}
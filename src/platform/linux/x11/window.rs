@@ -1,20 +1,46 @@
-use std::{cmp, env, mem};
-use std::ffi::CString;
+use std::{cmp, env, mem, ptr};
+use std::collections::HashSet;
+use std::ffi::{CStr, CString};
 use std::os::raw::*;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use libc;
 use parking_lot::Mutex;
-
-use {CursorState, Icon, LogicalPosition, LogicalSize, MouseCursor, WindowAttributes};
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::RawWindowHandle;
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::unix::XlibHandle;
+
+use {
+    CursorState,
+    ExternalError,
+    Fullscreen,
+    Icon,
+    Insets,
+    LogicalPosition,
+    LogicalSize,
+    MouseCursor,
+    NotSupportedError,
+    ResizeDirection,
+    ScaleFactorPolicy,
+    StackOrder,
+    UserAttentionType,
+    VideoMode,
+    WindowAttributes,
+};
 use CreationError::{self, OsError};
 use platform::MonitorId as PlatformMonitorId;
 use platform::PlatformSpecificWindowBuilderAttributes;
+use platform::platform::xcursor::cursor_names;
 use platform::x11::MonitorId as X11MonitorId;
 use window::MonitorId as RootMonitorId;
 
-use super::{ffi, util, ImeSender, XConnection, XError, WindowId, EventsLoop};
+use super::{ffi, util, CursorId, ImeRequest, ImeSender, XConnection, XError, WindowId, EventsLoop, EventsLoopProxy};
+
+// The `_NET_WM_MOVERESIZE` direction for moving the window with the pointer.
+const NET_WM_MOVERESIZE_MOVE: c_long = 8;
 
 unsafe extern "C" fn visibility_predicate(
     _display: *mut ffi::Display,
@@ -39,17 +65,75 @@ pub struct SharedState {
     pub dpi_adjusted: Option<(f64, f64)>,
     // Used to restore position after exiting fullscreen.
     pub restore_position: Option<(i32, i32)>,
+    // The fullscreen state applied by `set_fullscreen`.
+    pub fullscreen: Option<Fullscreen>,
+    // The output switched to another mode for exclusive fullscreen, and the mode to restore.
+    pub desktop_video_mode: Option<(ffi::RROutput, ffi::RRMode)>,
+    // Outer position and inner size to restore after unmaximizing, since some WMs forget them.
+    pub restore_geometry: Option<((i32, i32), (u32, u32))>,
+    // Set by `set_maximized(false)`, so that `restore_geometry` is applied once the WM actually
+    // unmaximized the window rather than racing with it.
+    pub restore_requested: bool,
     pub frame_extents: Option<util::FrameExtentsHeuristic>,
     pub min_dimensions: Option<LogicalSize>,
     pub max_dimensions: Option<LogicalSize>,
+    pub decorations: bool,
+    pub resizable: bool,
+    pub always_on_top: bool,
+    // The minimization last reported with `Minimized`
+    pub minimized: bool,
+    // Set when the window was destroyed by someone other than us, after which the `Window` is inert.
+    pub destroyed: bool,
+    // Set when the cursor grab was released because the window lost focus.
+    pub cursor_grab_suspended: bool,
+    // Set when the cursor stopped being confined to the monitor because the window lost focus.
+    pub monitor_confinement_suspended: bool,
+    // The keyboard grab requested with `set_keyboard_grab`.
+    pub keyboard_grabbed: bool,
+    // Set when the keyboard grab was released because the window lost focus.
+    pub keyboard_grab_suspended: bool,
+    // The buttons pressed over the window, one bit per button. The X server grabs the pointer for
+    // the window while any of them is held.
+    pub pressed_buttons: u32,
+    // The serial of our last `XWarpPointer` request and the position it moved the cursor to, until
+    // the resulting motion event is received.
+    pub pending_warp: Option<(c_ulong, (f64, f64))>,
+    // The outer position requested by `set_position`, until the window is next moved.
+    pub pending_move: Option<(i32, i32)>,
 }
 
 impl SharedState {
-    fn new() -> Mutex<Self> {
+    fn new(window_attrs: &WindowAttributes) -> Mutex<Self> {
         let mut shared_state = SharedState::default();
         shared_state.is_new_window = true;
+        shared_state.decorations = window_attrs.decorations;
+        shared_state.resizable = window_attrs.resizable;
+        shared_state.always_on_top = window_attrs.always_on_top;
         Mutex::new(shared_state)
     }
+
+    // Maximizing an already maximized window mustn't overwrite the geometry from before the first
+    // maximization.
+    fn save_restore_geometry(&mut self, position: (i32, i32), size: (u32, u32)) {
+        if self.restore_geometry.is_none() {
+            self.restore_geometry = Some((position, size));
+        }
+    }
+
+    // Called when `_NET_WM_STATE` changed. Once the window isn't maximized anymore, the saved
+    // geometry is dropped, and returned if `set_maximized(false)` asked for it to be restored. A
+    // window the user unmaximized through the WM is left where the WM put it.
+    pub fn maximized_changed(&mut self, maximized: bool) -> Option<((i32, i32), (u32, u32))> {
+        if maximized {
+            return None;
+        }
+        let restore_geometry = self.restore_geometry.take();
+        if mem::replace(&mut self.restore_requested, false) {
+            restore_geometry
+        } else {
+            None
+        }
+    }
 }
 
 unsafe impl Send for UnownedWindow {}
@@ -60,11 +144,21 @@ pub struct UnownedWindow {
     xwindow: ffi::Window, // never changes
     root: ffi::Window, // never changes
     screen_id: i32, // never changes
-    cursor: Mutex<MouseCursor>,
+    cursor: Mutex<CursorId>,
     cursor_state: Mutex<CursorState>,
+    // The cursor last defined on the window with `XDefineCursor`.
+    defined_cursor: Mutex<ffi::Cursor>,
+    // Non-empty while the cursor is confined using pointer barriers rather than a grab
+    pointer_barriers: Mutex<Vec<ffi::PointerBarrier>>,
+    // Non-empty while the cursor is confined to the window's monitor
+    monitor_barriers: Mutex<Vec<ffi::PointerBarrier>>,
     ime_sender: Mutex<ImeSender>,
     pub multitouch: bool, // never changes
+    pub scale_factor_policy: ScaleFactorPolicy, // never changes
     pub shared_state: Mutex<SharedState>,
+    // Shared with the events loop, which sends `RedrawRequested` to the windows in it.
+    pending_redraws: Arc<Mutex<HashSet<WindowId>>>,
+    events_loop_proxy: EventsLoopProxy,
 }
 
 impl UnownedWindow {
@@ -74,7 +168,6 @@ impl UnownedWindow {
         pl_attribs: PlatformSpecificWindowBuilderAttributes,
     ) -> Result<UnownedWindow, CreationError> {
         let xconn = &event_loop.xconn;
-        let root = event_loop.root;
 
         let max_dimensions: Option<(u32, u32)> = window_attrs.max_dimensions.map(Into::into);
         let min_dimensions: Option<(u32, u32)> = window_attrs.min_dimensions.map(Into::into);
@@ -100,11 +193,28 @@ impl UnownedWindow {
             Some(id) => id,
             None => unsafe { (xconn.xlib.XDefaultScreen)(xconn.display) },
         };
+        // The window, and the colormap of its visual, have to be created on its screen's root.
+        let root = match pl_attribs.screen_id {
+            Some(id) => unsafe { (xconn.xlib.XRootWindow)(xconn.display, id) },
+            None => event_loop.root,
+        };
+
+        let visual_infos = match (pl_attribs.visual_infos, pl_attribs.visual_id) {
+            (Some(visual_infos), _) => Some(visual_infos),
+            (None, Some(visual_id)) => Some(
+                xconn.get_visual_info(screen_id, visual_id).ok_or_else(|| OsError(format!(
+                    "Screen {} doesn't have a visual with the ID {:#x}",
+                    screen_id,
+                    visual_id,
+                )))?
+            ),
+            (None, None) => None,
+        };
 
         // creating
         let mut set_win_attr = {
             let mut swa: ffi::XSetWindowAttributes = unsafe { mem::zeroed() };
-            swa.colormap = if let Some(vi) = pl_attribs.visual_infos {
+            swa.colormap = if let Some(vi) = visual_infos {
                 unsafe {
                     let visual = vi.visual;
                     (xconn.xlib.XCreateColormap)(xconn.display, root, visual, ffi::AllocNone)
@@ -118,7 +228,8 @@ impl UnownedWindow {
                 | ffi::KeymapStateMask
                 | ffi::ButtonPressMask
                 | ffi::ButtonReleaseMask
-                | ffi::PointerMotionMask;
+                | ffi::PointerMotionMask
+                | ffi::PropertyChangeMask;
             swa.border_pixel = 0;
             swa.override_redirect = pl_attribs.override_redirect as c_int;
             swa
@@ -130,6 +241,13 @@ impl UnownedWindow {
             window_attributes |= ffi::CWOverrideRedirect;
         }
 
+        // Without a background, the server doesn't paint over the window's contents, so a
+        // transparent window stays transparent until the application draws its first frame.
+        if window_attrs.transparent {
+            set_win_attr.background_pixmap = 0;
+            window_attributes |= ffi::CWBackPixmap;
+        }
+
         // finally creating the window
         let xwindow = unsafe {
             (xconn.xlib.XCreateWindow)(
@@ -140,12 +258,12 @@ impl UnownedWindow {
                 dimensions.0 as c_uint,
                 dimensions.1 as c_uint,
                 0,
-                match pl_attribs.visual_infos {
+                match visual_infos {
                     Some(vi) => vi.depth,
                     None => ffi::CopyFromParent,
                 },
                 ffi::InputOutput as c_uint,
-                match pl_attribs.visual_infos {
+                match visual_infos {
                     Some(vi) => vi.visual,
                     None => ffi::CopyFromParent as *mut ffi::Visual,
                 },
@@ -161,9 +279,15 @@ impl UnownedWindow {
             screen_id,
             cursor: Default::default(),
             cursor_state: Default::default(),
+            defined_cursor: Default::default(),
+            pointer_barriers: Default::default(),
+            monitor_barriers: Default::default(),
             ime_sender: Mutex::new(event_loop.ime_sender.clone()),
             multitouch: window_attrs.multitouch,
-            shared_state: SharedState::new(),
+            scale_factor_policy: window_attrs.scale_factor_policy,
+            shared_state: SharedState::new(&window_attrs),
+            pending_redraws: Arc::clone(&event_loop.pending_redraws),
+            events_loop_proxy: event_loop.create_proxy(),
         };
 
         // Title must be set before mapping. Some tiling window managers (i.e. i3) use the window
@@ -171,6 +295,8 @@ impl UnownedWindow {
         // act on the wrong title state.
         window.set_title_inner(&window_attrs.title).queue();
         window.set_decorations_inner(window_attrs.decorations).queue();
+        // Otherwise the window shows its parent's cursor until `set_cursor` is called.
+        window.reapply_cursor();
 
         {
             // Enable drag and drop (TODO: extend API to make this toggleable)
@@ -255,20 +381,46 @@ impl UnownedWindow {
                 xconn.set_normal_hints(window.xwindow, normal_hints).queue();
             }
 
+            // Set the window group, which also has to happen before mapping
+            {
+                let leader = match pl_attribs.window_group_leader {
+                    Some(::platform::WindowId::X(WindowId(leader))) => leader,
+                    _ => match event_loop.window_group_leader.get() {
+                        Some(leader) => leader,
+                        None => {
+                            event_loop.window_group_leader.set(Some(window.xwindow));
+                            window.xwindow
+                        },
+                    },
+                };
+                let mut wm_hints = xconn.alloc_wm_hints();
+                (*wm_hints).flags = ffi::WindowGroupHint;
+                (*wm_hints).window_group = leader;
+                xconn.set_wm_hints(window.xwindow, wm_hints).queue();
+            }
+
             // Set window icons
             if let Some(icon) = window_attrs.window_icon {
                 window.set_icon_inner(icon).queue();
             }
 
-            // Opt into handling window close
-            unsafe {
-                (xconn.xlib.XSetWMProtocols)(
-                    xconn.display,
-                    window.xwindow,
-                    &event_loop.wm_delete_window as *const ffi::Atom as *mut ffi::Atom,
-                    1,
-                );
-            }//.queue();
+            // Opt into answering pings, and into handling window close
+            {
+                let mut protocols = [event_loop.net_wm_ping, event_loop.wm_delete_window];
+                let protocols = if event_loop.handle_close_requests.get() {
+                    &mut protocols[..]
+                } else {
+                    &mut protocols[..1]
+                };
+                unsafe {
+                    (xconn.xlib.XSetWMProtocols)(
+                        xconn.display,
+                        window.xwindow,
+                        protocols.as_mut_ptr(),
+                        protocols.len() as c_int,
+                    );
+                }//.queue();
+            }
 
             // Set visibility (map window)
             if window_attrs.visible {
@@ -351,6 +503,8 @@ impl UnownedWindow {
             }
         }
 
+        event_loop.pending_initial_sizes.borrow_mut().insert(window.id());
+
         // We never want to give the user a broken window, since by then, it's too late to handle.
         xconn.sync_with_server()
             .map(|_| window)
@@ -416,6 +570,10 @@ impl UnownedWindow {
 
     #[inline]
     pub fn set_urgent(&self, is_urgent: bool) {
+        if self.is_destroyed() {
+            return;
+        }
+
         let mut wm_hints = self.xconn.get_wm_hints(self.xwindow).expect("`XGetWMHints` failed");
         if is_urgent {
             (*wm_hints).flags |= ffi::XUrgencyHint;
@@ -451,32 +609,143 @@ impl UnownedWindow {
         self.set_netwm(fullscreen.into(), (fullscreen_atom as c_long, 0, 0, 0))
     }
 
-    fn set_fullscreen_inner(&self, monitor: Option<RootMonitorId>) -> util::Flusher {
-        match monitor {
+    // Switches the monitor to `video_mode`, remembering which mode to restore afterwards.
+    //
+    // Returns the mode the monitor actually switched to, or `None` if it wasn't switched.
+    fn set_video_mode(&self, monitor: &X11MonitorId, video_mode: VideoMode) -> Option<VideoMode> {
+        let mut shared_state = self.shared_state.lock();
+        // Only one output is switched at a time, so moving to another monitor restores the first.
+        if let Some((output, mode)) = shared_state.desktop_video_mode {
+            if output != monitor.output {
+                let _ = self.xconn.set_output_mode(output, mode);
+                shared_state.desktop_video_mode = None;
+            }
+        }
+        match self.xconn.set_output_video_mode(monitor.output, video_mode) {
+            Ok(original_mode) => {
+                if shared_state.desktop_video_mode.is_none() {
+                    shared_state.desktop_video_mode = Some((monitor.output, original_mode));
+                }
+                // The mode is read back from the CRTC rather than trusting that it's the requested one.
+                Some(self.xconn.get_output_video_mode(monitor.output).unwrap_or(video_mode))
+            },
+            Err(()) => None,
+        }
+    }
+
+    // Called when XRandR reports that the monitors changed. If the window is in exclusive
+    // fullscreen on `monitor`, this stores the mode the monitor is using now, and fits the window
+    // to the monitor's new size, which is reported with `Resized` once the window is configured.
+    pub(crate) fn update_video_mode(&self, monitor: &X11MonitorId) {
+        {
+            let mut shared_state_lock = self.shared_state.lock();
+            let on_monitor = match shared_state_lock.fullscreen {
+                Some(Fullscreen::Exclusive(RootMonitorId { inner: PlatformMonitorId::X(ref fullscreen_monitor) }, _)) => {
+                    fullscreen_monitor.output == monitor.output
+                },
+                _ => false,
+            };
+            if !on_monitor {
+                return;
+            }
+            let video_mode = match self.xconn.get_output_video_mode(monitor.output) {
+                Ok(video_mode) => video_mode,
+                Err(()) => return,
+            };
+            shared_state_lock.fullscreen = Some(Fullscreen::Exclusive(
+                RootMonitorId { inner: PlatformMonitorId::X(monitor.clone()) },
+                video_mode,
+            ));
+        }
+        let (x, y): (i32, i32) = monitor.get_position().into();
+        let (width, height): (u32, u32) = monitor.get_dimensions().into();
+        unsafe {
+            (self.xconn.xlib.XMoveResizeWindow)(
+                self.xconn.display,
+                self.xwindow,
+                x as c_int,
+                y as c_int,
+                width as c_uint,
+                height as c_uint,
+            );
+        }
+        let _ = self.xconn.flush_requests();
+    }
+
+    // Restores the mode the monitor was using before exclusive fullscreen, if it was switched.
+    pub fn restore_video_mode(&self) {
+        let desktop_video_mode = self.shared_state.lock().desktop_video_mode.take();
+        if let Some((output, mode)) = desktop_video_mode {
+            let _ = self.xconn.set_output_mode(output, mode);
+        }
+    }
+
+    fn set_fullscreen_inner(&self, fullscreen: Option<Fullscreen>) -> util::Flusher {
+        match fullscreen {
             None => {
+                self.restore_video_mode();
                 let flusher = self.set_fullscreen_hint(false);
-                if let Some(position) = self.shared_state.lock().restore_position.take() {
+                let restore_position = {
+                    let mut shared_state_lock = self.shared_state.lock();
+                    shared_state_lock.fullscreen = None;
+                    shared_state_lock.restore_position.take()
+                };
+                if let Some(position) = restore_position {
                     self.set_position_inner(position.0, position.1).queue();
                 }
                 flusher
             },
-            Some(RootMonitorId { inner: PlatformMonitorId::X(monitor) }) => {
+            Some(fullscreen) => {
+                let (monitor, video_mode) = match fullscreen {
+                    Fullscreen::Borderless(Some(RootMonitorId { inner: PlatformMonitorId::X(monitor) })) => {
+                        (monitor, None)
+                    },
+                    Fullscreen::Borderless(None) => (self.get_current_monitor(), None),
+                    Fullscreen::Exclusive(RootMonitorId { inner: PlatformMonitorId::X(monitor) }, video_mode) => {
+                        (monitor, Some(video_mode))
+                    },
+                    _ => unreachable!(),
+                };
+                let applied_video_mode = match video_mode {
+                    Some(video_mode) => self.set_video_mode(&monitor, video_mode),
+                    None => {
+                        self.restore_video_mode();
+                        None
+                    },
+                };
                 let window_position = self.get_position_physical();
-                self.shared_state.lock().restore_position = window_position;
+                {
+                    let mut shared_state_lock = self.shared_state.lock();
+                    shared_state_lock.restore_position = window_position;
+                    let root_monitor = RootMonitorId { inner: PlatformMonitorId::X(monitor.clone()) };
+                    shared_state_lock.fullscreen = Some(match applied_video_mode {
+                        Some(video_mode) => Fullscreen::Exclusive(root_monitor, video_mode),
+                        None => Fullscreen::Borderless(Some(root_monitor)),
+                    });
+                }
                 let monitor_origin: (i32, i32) = monitor.get_position().into();
                 self.set_position_inner(monitor_origin.0, monitor_origin.1).queue();
                 self.set_fullscreen_hint(true)
             }
-            _ => unreachable!(),
         }
     }
 
     #[inline]
-    pub fn set_fullscreen(&self, monitor: Option<RootMonitorId>) {
-        self.set_fullscreen_inner(monitor)
+    pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) -> Result<(), ExternalError> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+
+        let result = self.set_fullscreen_inner(fullscreen)
             .flush()
-            .expect("Failed to change window fullscreen state");
+            .map_err(|err| ExternalError::Os(format!("Failed to change window fullscreen state: {:?}", err)));
         self.invalidate_cached_frame_extents();
+        result
+    }
+
+    #[inline]
+    pub fn get_current_fullscreen(&self) -> Option<Fullscreen> {
+        self.shared_state.lock().fullscreen.clone()
     }
 
     fn get_rect(&self) -> Option<util::Rect> {
@@ -512,13 +781,55 @@ impl UnownedWindow {
     }
 
     fn set_maximized_inner(&self, maximized: bool) -> util::Flusher {
+        if maximized {
+            let position = self.get_position_physical();
+            let size = self.get_inner_size_physical();
+            if let (Some(position), Some(size)) = (position, size) {
+                self.shared_state.lock().save_restore_geometry(position, size);
+            }
+        }
         let horz_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_STATE_MAXIMIZED_HORZ\0") };
         let vert_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_STATE_MAXIMIZED_VERT\0") };
+        {
+            let mut shared_state_lock = self.shared_state.lock();
+            shared_state_lock.restore_requested = !maximized && shared_state_lock.restore_geometry.is_some();
+        }
         self.set_netwm(maximized.into(), (horz_atom as c_long, vert_atom as c_long, 0, 0))
     }
 
+    pub(crate) fn is_maximized(&self) -> bool {
+        let state_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_STATE\0") };
+        let horz_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_STATE_MAXIMIZED_HORZ\0") };
+        let vert_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_STATE_MAXIMIZED_VERT\0") };
+        self.xconn.get_property::<ffi::Atom>(self.xwindow, state_atom, ffi::XA_ATOM)
+            .map_or(false, |atoms| atoms.contains(&horz_atom) && atoms.contains(&vert_atom))
+    }
+
+    // Applies the geometry saved by `set_maximized(true)`, once the WM reported the window as
+    // unmaximized. This is redundant on WMs that restore the geometry themselves, but harmless.
+    pub(crate) fn update_maximized(&self) {
+        let maximized = self.is_maximized();
+        let restore_geometry = self.shared_state.lock().maximized_changed(maximized);
+        if let Some(((x, y), (width, height))) = restore_geometry {
+            self.set_position_inner(x, y).queue();
+            unsafe {
+                (self.xconn.xlib.XResizeWindow)(
+                    self.xconn.display,
+                    self.xwindow,
+                    width as c_uint,
+                    height as c_uint,
+                );
+            }
+            let _ = self.xconn.flush_requests();
+        }
+    }
+
     #[inline]
     pub fn set_maximized(&self, maximized: bool) {
+        if self.is_destroyed() {
+            return;
+        }
+
         self.set_maximized_inner(maximized)
             .flush()
             .expect("Failed to change window maximization");
@@ -547,6 +858,10 @@ impl UnownedWindow {
 
     #[inline]
     pub fn set_title(&self, title: &str) {
+        if self.is_destroyed() {
+            return;
+        }
+
         self.set_title_inner(title)
             .flush()
             .expect("Failed to set window title");
@@ -571,10 +886,22 @@ impl UnownedWindow {
 
     #[inline]
     pub fn set_decorations(&self, decorations: bool) {
+        if self.is_destroyed() {
+            return;
+        }
+
         self.set_decorations_inner(decorations)
             .flush()
             .expect("Failed to set decoration state");
+        self.shared_state.lock().decorations = decorations;
         self.invalidate_cached_frame_extents();
+        // Some window managers remap the window to change its decorations.
+        self.reapply_cursor();
+    }
+
+    #[inline]
+    pub fn is_decorated(&self) -> bool {
+        self.shared_state.lock().decorations
     }
 
     fn set_always_on_top_inner(&self, always_on_top: bool) -> util::Flusher {
@@ -583,10 +910,100 @@ impl UnownedWindow {
     }
 
     #[inline]
-    pub fn set_always_on_top(&self, always_on_top: bool) {
+    pub fn set_always_on_top(&self, always_on_top: bool) -> Result<(), ExternalError> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+
         self.set_always_on_top_inner(always_on_top)
             .flush()
-            .expect("Failed to set always-on-top state");
+            .map_err(|err| ExternalError::Os(format!("Failed to set always-on-top state: {:?}", err)))?;
+        self.shared_state.lock().always_on_top = always_on_top;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn is_always_on_top(&self) -> bool {
+        self.shared_state.lock().always_on_top
+    }
+
+    pub fn restack(&self, relative_to: WindowId, order: StackOrder) -> Result<(), ExternalError> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+
+        // Our window is reparented by the window manager, so it has no siblings to be restacked
+        // against using `XConfigureWindow`. Instead, we ask the window manager to do it.
+        let restack_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_RESTACK_WINDOW\0") };
+        let detail = match order {
+            StackOrder::Above => ffi::Above,
+            StackOrder::Below => ffi::Below,
+        };
+        self.xconn.send_client_msg(
+            self.xwindow,
+            self.root,
+            restack_atom,
+            Some(ffi::SubstructureRedirectMask | ffi::SubstructureNotifyMask),
+            [
+                // Window managers tend to ignore restacking requests from normal applications, so
+                // we identify as a pager.
+                2,
+                relative_to.0 as c_long,
+                detail as c_long,
+                0,
+                0,
+            ],
+        )
+            .flush()
+            .map_err(|err| ExternalError::Os(format!("Failed to restack window: {:?}", err)))
+    }
+
+    #[inline]
+    pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
+        if self.is_destroyed() {
+            return;
+        }
+
+        // `_NET_WM_STATE_DEMANDS_ATTENTION` is a plain flag, so both request types are treated the
+        // same. The window manager clears it once the window is focused.
+        let attention_atom = unsafe {
+            self.xconn.get_atom_unchecked(b"_NET_WM_STATE_DEMANDS_ATTENTION\0")
+        };
+        self.set_netwm(request_type.is_some().into(), (attention_atom as c_long, 0, 0, 0))
+            .flush()
+            .expect("Failed to request user attention");
+    }
+
+    pub fn set_background_color(&self, color: Option<(u8, u8, u8, u8)>) {
+        if self.is_destroyed() {
+            return;
+        }
+
+        unsafe {
+            if let Some((red, green, blue, alpha)) = color {
+                // 32-bit visuals expect premultiplied alpha, while other visuals have no alpha
+                // channel at all. Either way, this assumes a TrueColor visual, which is what
+                // practically every X server uses.
+                let is_argb = self.xconn.get_geometry(self.xwindow)
+                    .map(|geometry| geometry.depth == 32)
+                    .unwrap_or(false);
+                let premultiply = |channel: u8| if is_argb {
+                    (channel as c_ulong * alpha as c_ulong) / 255
+                } else {
+                    channel as c_ulong
+                };
+                let pixel = (alpha as c_ulong) << 24
+                    | premultiply(red) << 16
+                    | premultiply(green) << 8
+                    | premultiply(blue);
+                (self.xconn.xlib.XSetWindowBackground)(self.xconn.display, self.xwindow, pixel);
+            } else {
+                // A background of `None` leaves the window's contents alone.
+                (self.xconn.xlib.XSetWindowBackgroundPixmap)(self.xconn.display, self.xwindow, 0);
+            }
+            (self.xconn.xlib.XClearWindow)(self.xconn.display, self.xwindow);
+        }
+        self.xconn.flush_requests().expect("Failed to set window background");
     }
 
     fn set_icon_inner(&self, icon: Icon) -> util::Flusher {
@@ -615,6 +1032,10 @@ impl UnownedWindow {
 
     #[inline]
     pub fn set_window_icon(&self, icon: Option<Icon>) {
+        if self.is_destroyed() {
+            return;
+        }
+
         match icon {
             Some(icon) => self.set_icon_inner(icon),
             None => self.unset_icon_inner(),
@@ -623,15 +1044,51 @@ impl UnownedWindow {
 
     #[inline]
     pub fn show(&self) {
+        if self.is_destroyed() {
+            return;
+        }
+
         unsafe {
             (self.xconn.xlib.XMapRaised)(self.xconn.display, self.xwindow);
             self.xconn.flush_requests()
                 .expect("Failed to call XMapRaised");
         }
+        self.reapply_cursor();
+    }
+
+    pub fn set_minimized(&self, minimized: bool) -> Result<(), ExternalError> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+
+        unsafe {
+            if minimized {
+                (self.xconn.xlib.XIconifyWindow)(self.xconn.display, self.xwindow, self.screen_id);
+            } else if self.is_iconic() {
+                // Mapping the window again asks the window manager to restore it, in the state it
+                // was in before being minimized.
+                (self.xconn.xlib.XMapRaised)(self.xconn.display, self.xwindow);
+            }
+        }
+        self.xconn.flush_requests()
+            .map_err(|err| ExternalError::Os(format!("Failed to change window minimization: {:?}", err)))
+    }
+
+    // The window manager sets `WM_STATE` to `IconicState` while the window is minimized.
+    pub(crate) fn is_iconic(&self) -> bool {
+        let wm_state_atom = unsafe { self.xconn.get_atom_unchecked(b"WM_STATE\0") };
+        self.xconn.get_property::<c_ulong>(self.xwindow, wm_state_atom, wm_state_atom)
+            .ok()
+            .and_then(|state| state.first().cloned())
+            .map_or(false, |state| state == ffi::IconicState as c_ulong)
     }
 
     #[inline]
     pub fn hide(&self) {
+        if self.is_destroyed() {
+            return;
+        }
+
         unsafe {
             (self.xconn.xlib.XUnmapWindow)(self.xconn.display, self.xwindow);
             self.xconn.flush_requests()
@@ -639,6 +1096,24 @@ impl UnownedWindow {
         }
     }
 
+    pub fn request_redraw(&self) {
+        if self.is_destroyed() {
+            return;
+        }
+
+        let was_empty = {
+            let mut pending_redraws = self.pending_redraws.lock();
+            let was_empty = pending_redraws.is_empty();
+            pending_redraws.insert(self.id());
+            was_empty
+        };
+        // `run_forever` doesn't block while redraws are pending, so it only has to be woken up for
+        // the first one.
+        if was_empty {
+            let _ = self.events_loop_proxy.interrupt();
+        }
+    }
+
     fn update_cached_frame_extents(&self) {
         let extents = self.xconn.get_frame_extents_heuristic(self.xwindow, self.root);
         (*self.shared_state.lock()).frame_extents = Some(extents);
@@ -715,7 +1190,12 @@ impl UnownedWindow {
 
     #[inline]
     pub fn set_position(&self, logical_position: LogicalPosition) {
+        if self.is_destroyed() {
+            return;
+        }
+
         let (x, y) = logical_position.to_physical(self.get_hidpi_factor()).into();
+        self.shared_state.lock().pending_move = Some((x, y));
         self.set_position_physical(x, y);
     }
 
@@ -754,6 +1234,27 @@ impl UnownedWindow {
         }
     }
 
+    #[inline]
+    pub fn get_decoration_insets(&self) -> Option<Insets> {
+        // Some window managers leave a border around undecorated windows, which we don't count.
+        if !self.shared_state.lock().decorations {
+            return Some(Default::default());
+        }
+        let extents = self.shared_state.lock().frame_extents.clone();
+        if let Some(extents) = extents {
+            let extents = extents.frame_extents.as_logical(self.get_hidpi_factor());
+            Some(Insets {
+                top: extents.top,
+                left: extents.left,
+                right: extents.right,
+                bottom: extents.bottom,
+            })
+        } else {
+            self.update_cached_frame_extents();
+            self.get_decoration_insets()
+        }
+    }
+
     pub(crate) fn set_inner_size_physical(&self, width: u32, height: u32) {
         unsafe {
             (self.xconn.xlib.XResizeWindow)(
@@ -768,6 +1269,10 @@ impl UnownedWindow {
 
     #[inline]
     pub fn set_inner_size(&self, logical_size: LogicalSize) {
+        if self.is_destroyed() {
+            return;
+        }
+
         let dpi_factor = self.get_hidpi_factor();
         let (width, height) = logical_size.to_physical(dpi_factor).into();
         self.set_inner_size_physical(width, height);
@@ -788,6 +1293,10 @@ impl UnownedWindow {
 
     #[inline]
     pub fn set_min_dimensions(&self, logical_dimensions: Option<LogicalSize>) {
+        if self.is_destroyed() {
+            return;
+        }
+
         self.shared_state.lock().min_dimensions = logical_dimensions;
         let physical_dimensions = logical_dimensions.map(|logical_dimensions| {
             logical_dimensions.to_physical(self.get_hidpi_factor()).into()
@@ -802,6 +1311,10 @@ impl UnownedWindow {
 
     #[inline]
     pub fn set_max_dimensions(&self, logical_dimensions: Option<LogicalSize>) {
+        if self.is_destroyed() {
+            return;
+        }
+
         self.shared_state.lock().max_dimensions = logical_dimensions;
         let physical_dimensions = logical_dimensions.map(|logical_dimensions| {
             logical_dimensions.to_physical(self.get_hidpi_factor()).into()
@@ -817,8 +1330,11 @@ impl UnownedWindow {
         height: f64,
     ) -> (f64, f64, util::Flusher) {
         let scale_factor = new_dpi_factor / old_dpi_factor;
-        let new_width = width * scale_factor;
-        let new_height = height * scale_factor;
+        // The size hints are logical values, so they're always adjusted.
+        let (new_width, new_height) = match self.scale_factor_policy {
+            ScaleFactorPolicy::PreserveLogical => (width * scale_factor, height * scale_factor),
+            ScaleFactorPolicy::PreservePhysical => (width, height),
+        };
         self.update_normal_hints(|normal_hints| {
             let dpi_adjuster = |(width, height): (u32, u32)| -> (u32, u32) {
                 let new_width = width as f64 * scale_factor;
@@ -834,18 +1350,26 @@ impl UnownedWindow {
             normal_hints.set_resize_increments(resize_increments);
             normal_hints.set_base_size(base_size);
         }).expect("Failed to update normal hints");
-        unsafe {
-            (self.xconn.xlib.XResizeWindow)(
-                self.xconn.display,
-                self.xwindow,
-                new_width.round() as c_uint,
-                new_height.round() as c_uint,
-            );
+        if self.scale_factor_policy == ScaleFactorPolicy::PreserveLogical {
+            unsafe {
+                (self.xconn.xlib.XResizeWindow)(
+                    self.xconn.display,
+                    self.xwindow,
+                    new_width.round() as c_uint,
+                    new_height.round() as c_uint,
+                );
+            }
         }
         (new_width, new_height, util::Flusher::new(&self.xconn))
     }
 
     pub fn set_resizable(&self, resizable: bool) {
+        if self.is_destroyed() {
+            return;
+        }
+
+        self.shared_state.lock().resizable = resizable;
+
         if util::wm_name_is_one_of(&["Xfwm4"]) {
             // Making the window unresizable on Xfwm prevents further changes to `WM_NORMAL_HINTS` from being detected.
             // This makes it impossible for resizing to be re-enabled, and also breaks DPI scaling. As such, we choose
@@ -853,27 +1377,34 @@ impl UnownedWindow {
             return;
         }
 
-        let (logical_min, logical_max) = if resizable {
-            let shared_state_lock = self.shared_state.lock();
-            (shared_state_lock.min_dimensions, shared_state_lock.max_dimensions)
+        let (min_dimensions, max_dimensions) = if resizable {
+            let (logical_min, logical_max) = {
+                let shared_state_lock = self.shared_state.lock();
+                (shared_state_lock.min_dimensions, shared_state_lock.max_dimensions)
+            };
+            let dpi_factor = self.get_hidpi_factor();
+            let to_physical = |logical_size: LogicalSize| -> (u32, u32) {
+                logical_size.to_physical(dpi_factor).into()
+            };
+            (logical_min.map(&to_physical), logical_max.map(&to_physical))
         } else {
-            let window_size = self.get_inner_size();
-            (window_size.clone(), window_size)
+            // Converting the size to logical and back can round it to a neighboring size, which
+            // the window manager would then resize the window to, producing a spurious `Resized`.
+            let window_size = self.get_inner_size_physical();
+            (window_size, window_size)
         };
 
-        let dpi_factor = self.get_hidpi_factor();
-        let min_dimensions = logical_min
-            .map(|logical_size| logical_size.to_physical(dpi_factor))
-            .map(Into::into);
-        let max_dimensions = logical_max
-            .map(|logical_size| logical_size.to_physical(dpi_factor))
-            .map(Into::into);
         self.update_normal_hints(|normal_hints| {
             normal_hints.set_min_size(min_dimensions);
             normal_hints.set_max_size(max_dimensions);
         }).expect("Failed to call `XSetWMNormalHints`");
     }
 
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        self.shared_state.lock().resizable
+    }
+
     #[inline]
     pub fn get_xlib_display(&self) -> *mut c_void {
         self.xconn.display as _
@@ -894,6 +1425,16 @@ impl UnownedWindow {
         self.xwindow
     }
 
+    #[cfg(feature = "raw-window-handle")]
+    #[inline]
+    pub fn raw_window_handle(&self) -> RawWindowHandle {
+        RawWindowHandle::Xlib(XlibHandle {
+            window: self.xwindow,
+            display: self.xconn.display as _,
+            ..XlibHandle::empty()
+        })
+    }
+
     #[inline]
     pub fn get_xcb_connection(&self) -> *mut c_void {
         unsafe {
@@ -921,88 +1462,225 @@ impl UnownedWindow {
     }
 
     fn get_cursor(&self, cursor: MouseCursor) -> ffi::Cursor {
-        let load = |name: &[u8]| {
-            self.load_cursor(name)
-        };
+        let cursor_id = CursorId::Standard(cursor);
+        if let Some(&xcursor) = self.xconn.cursor_cache.lock().get(&cursor_id) {
+            return xcursor;
+        }
 
-        let loadn = |names: &[&[u8]]| {
-            self.load_first_existing_cursor(names)
+        let xcursor = match cursor {
+            MouseCursor::NoneCursor => self.create_empty_cursor()
+                .expect("Failed to create empty cursor"),
+            // If the theme doesn't have any of the names, fall back to the default cursor rather
+            // than to the root window's cursor.
+            _ => match self.load_first_existing_cursor(cursor_names(cursor)) {
+                0 => self.load_cursor(b"left_ptr\0"),
+                xcursor => xcursor,
+            },
         };
+        if xcursor != 0 {
+            self.xconn.cursor_cache.lock().insert(cursor_id, xcursor);
+        }
+        xcursor
+    }
 
-        // Try multiple names in some cases where the name
-        // differs on the desktop environments or themes.
-        //
-        // Try the better looking (or more suiting) names first.
-        match cursor {
-            MouseCursor::Alias => load(b"link\0"),
-            MouseCursor::Arrow => load(b"arrow\0"),
-            MouseCursor::Cell => load(b"plus\0"),
-            MouseCursor::Copy => load(b"copy\0"),
-            MouseCursor::Crosshair => load(b"crosshair\0"),
-            MouseCursor::Default => load(b"left_ptr\0"),
-            MouseCursor::Hand => loadn(&[b"hand2\0", b"hand1\0"]),
-            MouseCursor::Help => load(b"question_arrow\0"),
-            MouseCursor::Move => load(b"move\0"),
-            MouseCursor::Grab => loadn(&[b"openhand\0", b"grab\0"]),
-            MouseCursor::Grabbing => loadn(&[b"closedhand\0", b"grabbing\0"]),
-            MouseCursor::Progress => load(b"left_ptr_watch\0"),
-            MouseCursor::AllScroll => load(b"all-scroll\0"),
-            MouseCursor::ContextMenu => load(b"context-menu\0"),
-
-            MouseCursor::NoDrop => loadn(&[b"no-drop\0", b"circle\0"]),
-            MouseCursor::NotAllowed => load(b"crossed_circle\0"),
-
-
-            // Resize cursors
-            MouseCursor::EResize => load(b"right_side\0"),
-            MouseCursor::NResize => load(b"top_side\0"),
-            MouseCursor::NeResize => load(b"top_right_corner\0"),
-            MouseCursor::NwResize => load(b"top_left_corner\0"),
-            MouseCursor::SResize => load(b"bottom_side\0"),
-            MouseCursor::SeResize => load(b"bottom_right_corner\0"),
-            MouseCursor::SwResize => load(b"bottom_left_corner\0"),
-            MouseCursor::WResize => load(b"left_side\0"),
-            MouseCursor::EwResize => load(b"h_double_arrow\0"),
-            MouseCursor::NsResize => load(b"v_double_arrow\0"),
-            MouseCursor::NwseResize => loadn(&[b"bd_double_arrow\0", b"size_bdiag\0"]),
-            MouseCursor::NeswResize => loadn(&[b"fd_double_arrow\0", b"size_fdiag\0"]),
-            MouseCursor::ColResize => loadn(&[b"split_h\0", b"h_double_arrow\0"]),
-            MouseCursor::RowResize => loadn(&[b"split_v\0", b"v_double_arrow\0"]),
-
-            MouseCursor::Text => loadn(&[b"text\0", b"xterm\0"]),
-            MouseCursor::VerticalText => load(b"vertical-text\0"),
-
-            MouseCursor::Wait => load(b"watch\0"),
-
-            MouseCursor::ZoomIn => load(b"zoom-in\0"),
-            MouseCursor::ZoomOut => load(b"zoom-out\0"),
+    // Returns 0 if the theme doesn't have a cursor with that name.
+    fn get_named_cursor(&self, name: &CStr) -> ffi::Cursor {
+        let cursor_id = CursorId::Named(name.to_string_lossy().into_owned());
+        if let Some(&xcursor) = self.xconn.cursor_cache.lock().get(&cursor_id) {
+            return xcursor;
+        }
 
-            MouseCursor::NoneCursor => self.create_empty_cursor()
-                .expect("Failed to create empty cursor"),
+        let xcursor = self.load_cursor(name.to_bytes_with_nul());
+        if xcursor != 0 {
+            self.xconn.cursor_cache.lock().insert(cursor_id, xcursor);
         }
+        xcursor
     }
 
-    fn update_cursor(&self, cursor: ffi::Cursor) {
+    fn get_current_cursor(&self) -> ffi::Cursor {
+        match *self.cursor.lock() {
+            CursorId::Standard(cursor) => self.get_cursor(cursor),
+            CursorId::Named(ref name) => {
+                // The name was validated by `set_cursor_by_name`, so this can't fail.
+                let name = CString::new(name.as_bytes()).unwrap();
+                self.get_named_cursor(&name)
+            },
+            CursorId::Image(_, _, xcursor) | CursorId::Custom(xcursor) => xcursor,
+        }
+    }
+
+    // Stores the cursor to show from now on, freeing the previous one if it was created by
+    // `set_animated_cursor` or `set_cursor_image`.
+    fn replace_cursor(&self, cursor: CursorId) {
+        let previous = mem::replace(&mut *self.cursor.lock(), cursor);
+        match previous {
+            CursorId::Image(_, _, xcursor) | CursorId::Custom(xcursor) => unsafe {
+                (self.xconn.xlib.XFreeCursor)(self.xconn.display, xcursor);
+            },
+            _ => (),
+        }
+    }
+
+    // Frees the cursor created by `set_animated_cursor` or `set_cursor_image`, if any. Only called
+    // as the window is destroyed.
+    pub fn free_custom_cursor(&self) {
+        self.replace_cursor(CursorId::default());
+    }
+
+    fn create_animated_cursor(
+        &self,
+        frames: &[(Icon, Duration)],
+        hotspot: (u32, u32),
+    ) -> Result<ffi::Cursor, ExternalError> {
+        let xcursor = &self.xconn.xcursor;
         unsafe {
-            (self.xconn.xlib.XDefineCursor)(self.xconn.display, self.xwindow, cursor);
+            let images = (xcursor.XcursorImagesCreate)(frames.len() as c_int);
+            if images.is_null() {
+                return Err(ExternalError::Os("`XcursorImagesCreate` failed".to_owned()));
+            }
+            for &(ref icon, delay) in frames {
+                let image = (xcursor.XcursorImageCreate)(icon.width as c_int, icon.height as c_int);
+                if image.is_null() {
+                    (xcursor.XcursorImagesDestroy)(images);
+                    return Err(ExternalError::Os("`XcursorImageCreate` failed".to_owned()));
+                }
+                (*image).xhot = hotspot.0;
+                (*image).yhot = hotspot.1;
+                (*image).delay = delay.as_secs() as u32 * 1000 + delay.subsec_nanos() / 1_000_000;
+                let pixels = icon.to_premultiplied_argb();
+                ptr::copy_nonoverlapping(pixels.as_ptr(), (*image).pixels, pixels.len());
+                // The images are destroyed along with `images`.
+                *(*images).images.offset((*images).nimage as isize) = image;
+                (*images).nimage += 1;
+            }
+            // When the server doesn't support animated cursors, this gives a static cursor showing
+            // the first frame.
+            let cursor = (xcursor.XcursorImagesLoadCursor)(self.xconn.display, images);
+            (xcursor.XcursorImagesDestroy)(images);
             if cursor != 0 {
-                (self.xconn.xlib.XFreeCursor)(self.xconn.display, cursor);
+                Ok(cursor)
+            } else {
+                Err(ExternalError::Os("`XcursorImagesLoadCursor` failed".to_owned()))
             }
-            self.xconn.flush_requests().expect("Failed to set or free the cursor");
         }
     }
 
+    // Cursors other than those created by `set_animated_cursor` are cached, so they're never freed
+    // here.
+    fn update_cursor(&self, cursor: ffi::Cursor) {
+        unsafe {
+            (self.xconn.xlib.XDefineCursor)(self.xconn.display, self.xwindow, cursor);
+            self.xconn.flush_requests().expect("Failed to set the cursor");
+        }
+        *self.defined_cursor.lock() = cursor;
+    }
+
+    // Defines the cursor again from the stored state, after operations that may have caused the
+    // cursor to revert to the parent's, or after the cursor theme changed.
+    pub fn reapply_cursor(&self) {
+        if self.is_destroyed() {
+            return;
+        }
+        let cursor = if *self.cursor_state.lock() == CursorState::Hide {
+            self.get_cursor(MouseCursor::NoneCursor)
+        } else {
+            self.get_current_cursor()
+        };
+        self.update_cursor(cursor);
+    }
+
     #[inline]
     pub fn set_cursor(&self, cursor: MouseCursor) {
-        *self.cursor.lock() = cursor;
+        if self.is_destroyed() {
+            return;
+        }
+
+        self.replace_cursor(CursorId::Standard(cursor));
         if *self.cursor_state.lock() != CursorState::Hide {
             self.update_cursor(self.get_cursor(cursor));
         }
     }
 
-    // TODO: This could maybe be cached. I don't think it's worth
-    // the complexity, since cursor changes are not so common,
-    // and this is just allocating a 1x1 pixmap...
+    pub fn set_cursor_by_name(&self, name: &str) -> Result<(), ExternalError> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+
+        let c_name = CString::new(name)
+            .map_err(|_| ExternalError::Os("cursor names can't contain nul bytes".to_owned()))?;
+        let xcursor = self.get_named_cursor(&c_name);
+        if xcursor == 0 {
+            return Err(ExternalError::Os(
+                format!("the cursor theme doesn't have a cursor named `{}`", name),
+            ));
+        }
+
+        self.replace_cursor(CursorId::Named(name.to_owned()));
+        if *self.cursor_state.lock() != CursorState::Hide {
+            self.update_cursor(xcursor);
+        }
+        Ok(())
+    }
+
+    pub fn set_animated_cursor(
+        &self,
+        frames: Vec<(Icon, Duration)>,
+        hotspot: (u32, u32),
+    ) -> Result<(), ExternalError> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+
+        let xcursor = self.create_animated_cursor(&frames, hotspot)?;
+        self.replace_cursor(CursorId::Custom(xcursor));
+        if *self.cursor_state.lock() != CursorState::Hide {
+            self.update_cursor(xcursor);
+        }
+        Ok(())
+    }
+
+    pub fn set_cursor_image(&self, image: Icon, hotspot: (u32, u32)) -> Result<(), ExternalError> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+
+        let xcursor = self.create_image_cursor(&image, hotspot)?;
+        self.replace_cursor(CursorId::Image(image, hotspot, xcursor));
+        if *self.cursor_state.lock() != CursorState::Hide {
+            self.update_cursor(xcursor);
+        }
+        Ok(())
+    }
+
+    // X cursors are drawn in physical pixels, so the image, which is given at logical size, is
+    // scaled by the DPI factor of the window.
+    fn create_image_cursor(&self, image: &Icon, hotspot: (u32, u32)) -> Result<ffi::Cursor, ExternalError> {
+        let (image, hotspot) = image.scale_cursor(hotspot, self.get_hidpi_factor());
+        // A single frame makes for a static cursor.
+        self.create_animated_cursor(&[(image, Duration::from_secs(0))], hotspot)
+    }
+
+    // Draws the cursor image again at the new DPI factor, after the window moved to another
+    // monitor.
+    pub fn rescale_cursor_image(&self) {
+        if self.is_destroyed() {
+            return;
+        }
+
+        let (image, hotspot) = match *self.cursor.lock() {
+            CursorId::Image(ref image, hotspot, _) => (image.clone(), hotspot),
+            _ => return,
+        };
+        match self.create_image_cursor(&image, hotspot) {
+            Ok(xcursor) => {
+                self.replace_cursor(CursorId::Image(image, hotspot, xcursor));
+                self.reapply_cursor();
+            },
+            // The image stays at the previous size.
+            Err(err) => eprintln!("[winit X11 error] Failed to scale the cursor image: {:?}", err),
+        }
+    }
+
     fn create_empty_cursor(&self) -> Option<ffi::Cursor> {
         let data = 0;
         let pixmap = unsafe {
@@ -1039,9 +1717,21 @@ impl UnownedWindow {
     }
 
     #[inline]
-    pub fn set_cursor_state(&self, state: CursorState) -> Result<(), String> {
+    pub fn is_cursor_visible(&self) -> bool {
+        *self.cursor_state.lock() != CursorState::Hide
+    }
+
+    #[inline]
+    pub fn set_cursor_state(&self, state: CursorState) -> Result<(), ExternalError> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+
         use CursorState::*;
 
+        // The application changed the grab itself, so it shouldn't be restored on focus anymore.
+        self.shared_state.lock().cursor_grab_suspended = false;
+
         let mut cursor_state_lock = self.cursor_state.lock();
 
         match (state, *cursor_state_lock) {
@@ -1051,13 +1741,15 @@ impl UnownedWindow {
 
         match *cursor_state_lock {
             Grab => {
-                unsafe {
-                    (self.xconn.xlib.XUngrabPointer)(self.xconn.display, ffi::CurrentTime);
-                    self.xconn.flush_requests().expect("Failed to call XUngrabPointer");
+                if !self.destroy_pointer_barriers() {
+                    unsafe {
+                        (self.xconn.xlib.XUngrabPointer)(self.xconn.display, ffi::CurrentTime);
+                        self.xconn.flush_requests().expect("Failed to call XUngrabPointer");
+                    }
                 }
             },
             Normal => {},
-            Hide => self.update_cursor(self.get_cursor(*self.cursor.lock())),
+            Hide => self.update_cursor(self.get_current_cursor()),
         }
 
         match state {
@@ -1067,12 +1759,17 @@ impl UnownedWindow {
             },
             Hide => {
                 *cursor_state_lock = state;
-                self.update_cursor(
-                    self.create_empty_cursor().expect("Failed to create empty cursor")
-                );
+                self.update_cursor(self.get_cursor(MouseCursor::NoneCursor));
                 Ok(())
             },
             Grab => {
+                // Pointer barriers confine the cursor without taking it away from the window
+                // manager, so they're preferred over a grab when available.
+                if self.create_pointer_barriers() {
+                    *cursor_state_lock = state;
+                    return Ok(());
+                }
+
                 unsafe {
                     // Ungrab before grabbing to prevent passive grabs
                     // from causing AlreadyGrabbed
@@ -1094,7 +1791,7 @@ impl UnownedWindow {
                         },
                         ffi::AlreadyGrabbed | ffi::GrabInvalidTime |
                         ffi::GrabNotViewable | ffi::GrabFrozen
-                            => Err("cursor could not be grabbed".to_string()),
+                            => Err(ExternalError::Os("cursor could not be grabbed".to_string())),
                         _ => unreachable!(),
                     }
                 }
@@ -1102,13 +1799,343 @@ impl UnownedWindow {
         }
     }
 
+    // Surrounds the inner area of the window with pointer barriers. Returns `false` if XFixes
+    // isn't available or the window's geometry couldn't be queried.
+    fn create_pointer_barriers(&self) -> bool {
+        let xfixes = match self.xconn.xfixes {
+            Some(ref xfixes) => xfixes,
+            None => return false,
+        };
+        let ((x, y), (width, height)) = match (
+            self.get_inner_position_physical(),
+            self.get_inner_size_physical(),
+        ) {
+            (Some(position), Some(size)) => (position, size),
+            _ => return false,
+        };
+        let (right, bottom) = (x + width as i32, y + height as i32);
+
+        // Barriers only block motion across them, so the cursor has to be moved inside first.
+        // 2 is the virtual core pointer.
+        let is_inside = self.xconn.query_pointer(self.xwindow, 2)
+            .map(|pointer_state| {
+                let (win_x, win_y) = pointer_state.get_window_position();
+                win_x >= 0.0 && win_y >= 0.0 && win_x < width as f64 && win_y < height as f64
+            })
+            .unwrap_or(false);
+        if !is_inside {
+            let _ = self.set_cursor_position_physical(width as i32 / 2, height as i32 / 2);
+        }
+
+        match self.create_pointer_barriers_around(xfixes, (x, y, right, bottom)) {
+            Ok(barriers) => {
+                self.pointer_barriers.lock().extend(barriers);
+                true
+            },
+            Err(_) => false,
+        }
+    }
+
+    // Creates barriers along the edges of the rectangle, which is in root coordinates. If any of
+    // them can't be created, the others are destroyed again.
+    fn create_pointer_barriers_around(
+        &self,
+        xfixes: &ffi::XFixes,
+        (x, y, right, bottom): (i32, i32, i32, i32),
+    ) -> Result<Vec<ffi::PointerBarrier>, XError> {
+        let lines = [
+            (x, y, right, y),
+            (x, bottom, right, bottom),
+            (x, y, x, bottom),
+            (right, y, right, bottom),
+        ];
+        let barriers = lines.iter()
+            .map(|&(x1, y1, x2, y2)| unsafe {
+                (xfixes.XFixesCreatePointerBarrier)(
+                    self.xconn.display,
+                    self.root,
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    0, // Block motion in every direction
+                    0, // Apply to all devices
+                    ptr::null_mut(),
+                )
+            })
+            .collect::<Vec<_>>();
+        // A barrier that couldn't be created is only reported as an error, so we have to wait for
+        // the server to know whether they all were.
+        match self.xconn.sync_with_server() {
+            Ok(()) => Ok(barriers),
+            Err(err) => {
+                for &barrier in &barriers {
+                    unsafe { (xfixes.XFixesDestroyPointerBarrier)(self.xconn.display, barrier) };
+                }
+                // Destroying the barriers that weren't created fails as well.
+                let _ = self.xconn.sync_with_server();
+                Err(err)
+            },
+        }
+    }
+
+    pub fn confine_cursor_to_monitor(&self, confine: bool) -> Result<(), ExternalError> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+
+        let xfixes = match self.xconn.xfixes {
+            Some(ref xfixes) => xfixes,
+            None => return Err(ExternalError::NotSupported(NotSupportedError::new())),
+        };
+        // As with the grab, the application's choice overrides whatever focus changes would restore.
+        self.shared_state.lock().monitor_confinement_suspended = false;
+        self.destroy_monitor_barriers();
+        if confine {
+            let monitor = self.get_current_monitor();
+            let (x, y): (i32, i32) = monitor.get_position().into();
+            let (width, height): (u32, u32) = monitor.get_dimensions().into();
+            let (right, bottom) = (x + width as i32, y + height as i32);
+            let barriers = self.create_pointer_barriers_around(xfixes, (x, y, right, bottom))
+                .map_err(|err| ExternalError::Os(format!("Failed to confine cursor to monitor: {:?}", err)))?;
+            *self.monitor_barriers.lock() = barriers;
+        }
+        self.xconn.flush_requests()
+            .map_err(|err| ExternalError::Os(format!("Failed to confine cursor to monitor: {:?}", err)))
+    }
+
+    pub(crate) fn destroy_monitor_barriers(&self) {
+        if let Some(ref xfixes) = self.xconn.xfixes {
+            for barrier in self.monitor_barriers.lock().drain(..) {
+                unsafe { (xfixes.XFixesDestroyPointerBarrier)(self.xconn.display, barrier) };
+            }
+        }
+    }
+
+    // Returns `false` if there weren't any barriers to destroy.
+    pub(crate) fn destroy_pointer_barriers(&self) -> bool {
+        let xfixes = match self.xconn.xfixes {
+            Some(ref xfixes) => xfixes,
+            None => return false,
+        };
+        let mut pointer_barriers = self.pointer_barriers.lock();
+        if pointer_barriers.is_empty() {
+            return false;
+        }
+        for barrier in pointer_barriers.drain(..) {
+            unsafe { (xfixes.XFixesDestroyPointerBarrier)(self.xconn.display, barrier) };
+        }
+        let _ = self.xconn.flush_requests();
+        true
+    }
+
+    // The barriers are placed in root coordinates, so they have to follow the window around.
+    pub(crate) fn update_pointer_barriers(&self) {
+        if self.destroy_pointer_barriers() {
+            self.create_pointer_barriers();
+        }
+    }
+
+    // Releases the cursor grab and the confinement to the monitor when the window loses focus, so
+    // that they can be restored once the window is focused again.
+    pub(crate) fn suspend_cursor_grab(&self) {
+        if *self.cursor_state.lock() == CursorState::Grab
+            && self.set_cursor_state(CursorState::Normal).is_ok()
+        {
+            self.shared_state.lock().cursor_grab_suspended = true;
+        }
+        if !self.monitor_barriers.lock().is_empty() {
+            self.destroy_monitor_barriers();
+            let _ = self.xconn.flush_requests();
+            self.shared_state.lock().monitor_confinement_suspended = true;
+        }
+        if self.shared_state.lock().keyboard_grabbed && self.ungrab_keyboard().is_ok() {
+            self.shared_state.lock().keyboard_grab_suspended = true;
+        }
+    }
+
+    pub(crate) fn resume_cursor_grab(&self) {
+        let suspended = mem::replace(&mut self.shared_state.lock().cursor_grab_suspended, false);
+        if suspended {
+            let _ = self.set_cursor_state(CursorState::Grab);
+        }
+        let suspended = mem::replace(&mut self.shared_state.lock().monitor_confinement_suspended, false);
+        if suspended {
+            // The window may have moved to another monitor in the meantime.
+            let _ = self.confine_cursor_to_monitor(true);
+        }
+        let suspended = mem::replace(&mut self.shared_state.lock().keyboard_grab_suspended, false);
+        if suspended {
+            let _ = self.grab_keyboard();
+        }
+    }
+
+    #[inline]
+    pub fn set_keyboard_grab(&self, grab: bool) -> Result<(), ExternalError> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+
+        // The application changed the grab itself, so it shouldn't be restored on focus anymore.
+        self.shared_state.lock().keyboard_grab_suspended = false;
+        if grab {
+            self.grab_keyboard()?;
+        } else {
+            self.ungrab_keyboard()?;
+        }
+        self.shared_state.lock().keyboard_grabbed = grab;
+        Ok(())
+    }
+
+    fn grab_keyboard(&self) -> Result<(), ExternalError> {
+        let result = unsafe {
+            (self.xconn.xlib.XGrabKeyboard)(
+                self.xconn.display, self.xwindow, ffi::True,
+                ffi::GrabModeAsync, ffi::GrabModeAsync, ffi::CurrentTime
+            )
+        };
+        let reason = match result {
+            ffi::GrabSuccess => return Ok(()),
+            ffi::AlreadyGrabbed => "the keyboard is already grabbed by another client",
+            ffi::GrabInvalidTime => "the keyboard was grabbed more recently",
+            ffi::GrabNotViewable => "the window isn't viewable",
+            ffi::GrabFrozen => "the keyboard is frozen by another client's grab",
+            _ => "unknown error",
+        };
+        Err(ExternalError::Os(format!("Failed to grab the keyboard: {}", reason)))
+    }
+
+    fn ungrab_keyboard(&self) -> Result<(), ExternalError> {
+        unsafe { (self.xconn.xlib.XUngrabKeyboard)(self.xconn.display, ffi::CurrentTime) };
+        self.xconn.flush_requests()
+            .map_err(|err| ExternalError::Os(format!("Failed to call XUngrabKeyboard: {:?}", err)))
+    }
+
     #[inline]
     pub fn get_hidpi_factor(&self) -> f64 {
         self.get_current_monitor().hidpi_factor
     }
 
-    pub(crate) fn set_cursor_position_physical(&self, x: i32, y: i32) -> Result<(), ()> {
+    pub fn show_window_menu(&self, position: LogicalPosition) -> Result<(), ExternalError> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+        let (inner_x, inner_y) = match self.get_inner_position_physical() {
+            Some(inner_position) => inner_position,
+            None => return Ok(()),
+        };
+        let (x, y): (i32, i32) = position.to_physical(self.get_hidpi_factor()).into();
+        // This is the message GTK sends for client-side decorations. It's understood by Mutter,
+        // KWin and most other window managers that support GTK's client-side decorations.
+        let menu_atom = unsafe { self.xconn.get_atom_unchecked(b"_GTK_SHOW_WINDOW_MENU\0") };
+        // The window manager can't grab the pointer to show the menu while we still hold the
+        // implicit grab of the button press that triggered this.
+        self.release_pointer_grab();
+        self.xconn.send_client_msg(
+            self.xwindow,
+            self.root,
+            menu_atom,
+            Some(ffi::SubstructureRedirectMask | ffi::SubstructureNotifyMask),
+            [
+                // The virtual core pointer
+                2,
+                (inner_x + x) as c_long,
+                (inner_y + y) as c_long,
+                0,
+                0,
+            ],
+        ).flush()
+            .map_err(|err| ExternalError::Os(format!("Failed to send window menu request: {:?}", err)))
+    }
+
+    // Called for the button presses and releases the window receives.
+    pub(crate) fn update_pressed_buttons(&self, button: u32, pressed: bool) {
+        if button >= 32 {
+            return;
+        }
+        let mut shared_state = self.shared_state.lock();
+        if pressed {
+            shared_state.pressed_buttons |= 1 << button;
+        } else {
+            shared_state.pressed_buttons &= !(1 << button);
+        }
+    }
+
+    // Whether the window holds the pointer grab, either because of `CursorState::Grab` or because
+    // a button was pressed over it.
+    pub(crate) fn holds_pointer_grab(&self) -> bool {
+        *self.cursor_state.lock() == CursorState::Grab || self.shared_state.lock().pressed_buttons != 0
+    }
+
+    // Releases the pointer grab if this window holds it. Otherwise, the grab belongs to another of
+    // our windows, which keeps it.
+    fn release_pointer_grab(&self) {
+        if self.holds_pointer_grab() {
+            unsafe { (self.xconn.xlib.XUngrabPointer)(self.xconn.display, ffi::CurrentTime) };
+            self.shared_state.lock().pressed_buttons = 0;
+        }
+    }
+
+    pub fn start_drag(&self) -> Result<(), ExternalError> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+        self.move_resize(NET_WM_MOVERESIZE_MOVE)
+    }
+
+    pub fn start_resize(&self, direction: ResizeDirection) -> Result<(), ExternalError> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+        // The `_NET_WM_MOVERESIZE_SIZE_*` directions, clockwise from the top left corner
+        let direction = match direction {
+            ResizeDirection::NorthWest => 0,
+            ResizeDirection::North => 1,
+            ResizeDirection::NorthEast => 2,
+            ResizeDirection::East => 3,
+            ResizeDirection::SouthEast => 4,
+            ResizeDirection::South => 5,
+            ResizeDirection::SouthWest => 6,
+            ResizeDirection::West => 7,
+        };
+        self.move_resize(direction)
+    }
+
+    // Hands the pointer over to the window manager, which moves or resizes the window for as long
+    // as the button that's currently held stays pressed.
+    fn move_resize(&self, direction: c_long) -> Result<(), ExternalError> {
+        // 2 is the virtual core pointer.
+        let pointer_state = self.xconn.query_pointer(self.xwindow, 2)
+            .map_err(|err| ExternalError::Os(format!("Failed to query pointer: {:?}", err)))?;
+        let button = match pointer_state.get_pressed_button() {
+            Some(button) => button,
+            None => return Ok(()),
+        };
+        let (root_x, root_y) = pointer_state.get_root_position();
+        let moveresize_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_MOVERESIZE\0") };
+        // As with the window menu, the window manager has to be able to grab the pointer.
+        self.release_pointer_grab();
+        self.xconn.send_client_msg(
+            self.xwindow,
+            self.root,
+            moveresize_atom,
+            Some(ffi::SubstructureRedirectMask | ffi::SubstructureNotifyMask),
+            [
+                root_x as c_long,
+                root_y as c_long,
+                direction,
+                button as c_long,
+                // Source indication: a normal application
+                1,
+            ],
+        ).flush()
+            .map_err(|err| ExternalError::Os(format!("Failed to send _NET_WM_MOVERESIZE: {:?}", err)))
+    }
+
+    pub(crate) fn set_cursor_position_physical(&self, x: i32, y: i32) -> Result<(), ExternalError> {
         unsafe {
+            let serial = (self.xconn.xlib.XNextRequest)(self.xconn.display);
+            self.shared_state.lock().pending_warp = Some((serial, (x as f64, y as f64)));
             (self.xconn.xlib.XWarpPointer)(
                 self.xconn.display,
                 0,
@@ -1120,12 +2147,17 @@ impl UnownedWindow {
                 x,
                 y,
             );
-            self.xconn.flush_requests().map_err(|_| ())
+            self.xconn.flush_requests()
+                .map_err(|err| ExternalError::Os(format!("Failed to call XWarpPointer: {:?}", err)))
         }
     }
 
     #[inline]
-    pub fn set_cursor_position(&self, logical_position: LogicalPosition) -> Result<(), ()> {
+    pub fn set_cursor_position(&self, logical_position: LogicalPosition) -> Result<(), ExternalError> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+
         let (x, y) = logical_position.to_physical(self.get_hidpi_factor()).into();
         self.set_cursor_position_physical(x, y)
     }
@@ -1133,15 +2165,131 @@ impl UnownedWindow {
     pub(crate) fn set_ime_spot_physical(&self, x: i32, y: i32) {
         let _ = self.ime_sender
             .lock()
-            .send((self.xwindow, x as i16, y as i16));
+            .send((self.xwindow, ImeRequest::Spot(x as i16, y as i16)));
     }
 
     #[inline]
-    pub fn set_ime_spot(&self, logical_spot: LogicalPosition) {
+    pub fn set_ime_spot(&self, logical_spot: LogicalPosition) -> Result<(), ExternalError> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+
         let (x, y) = logical_spot.to_physical(self.get_hidpi_factor()).into();
         self.set_ime_spot_physical(x, y);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn set_ime_allowed(&self, allowed: bool, commit: bool) -> Result<(), ExternalError> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+
+        let _ = self.ime_sender
+            .lock()
+            .send((self.xwindow, ImeRequest::Allowed { allowed, commit }));
+        Ok(())
+    }
+
+    // Returns whether a motion event is the one generated by `set_cursor_position`, in which case the
+    // cursor position is updated without the event being reported, since it would otherwise look
+    // like a large movement of the mouse.
+    pub(crate) fn is_warp_motion(&self, serial: c_ulong, position: (f64, f64)) -> bool {
+        let mut shared_state_lock = self.shared_state.lock();
+        match shared_state_lock.pending_warp {
+            // Events with an older serial were generated before the server processed the warp.
+            Some((warp_serial, target)) if serial >= warp_serial => {
+                // Anything after the warp's own event is a genuine movement, and the warp doesn't
+                // generate an event at all if the cursor was already there, so the warp is no
+                // longer pending either way.
+                shared_state_lock.pending_warp = None;
+                if position == target {
+                    shared_state_lock.cursor_pos = Some(position);
+                    true
+                } else {
+                    false
+                }
+            },
+            _ => false,
+        }
+    }
+
+    pub(crate) fn mark_destroyed(&self) {
+        self.shared_state.lock().destroyed = true;
+    }
+
+    #[inline]
+    fn is_destroyed(&self) -> bool {
+        self.shared_state.lock().destroyed
     }
 
     #[inline]
     pub fn id(&self) -> WindowId { WindowId(self.xwindow) }
 }
+
+#[cfg(test)]
+mod tests {
+    use {EventsLoop, MouseCursor, WindowBuilder};
+    use os::unix::EventsLoopExt;
+    use platform::Window as LinuxWindow;
+    use super::SharedState;
+
+    #[test]
+    fn maximizing_twice_keeps_the_original_restore_geometry() {
+        let mut shared_state = SharedState::default();
+        shared_state.save_restore_geometry((10, 20), (800, 600));
+        shared_state.save_restore_geometry((0, 0), (1920, 1080));
+        assert_eq!(shared_state.restore_geometry.take(), Some(((10, 20), (800, 600))));
+        // Once restored, the next maximization saves the geometry again.
+        shared_state.save_restore_geometry((30, 40), (640, 480));
+        assert_eq!(shared_state.restore_geometry, Some(((30, 40), (640, 480))));
+    }
+
+    #[test]
+    fn restore_geometry_is_applied_once_unmaximized() {
+        let mut shared_state = SharedState::default();
+        shared_state.save_restore_geometry((10, 20), (800, 600));
+        shared_state.restore_requested = true;
+        // The WM hasn't processed the request yet.
+        assert_eq!(shared_state.maximized_changed(true), None);
+        assert_eq!(shared_state.maximized_changed(false), Some(((10, 20), (800, 600))));
+        assert_eq!(shared_state.restore_geometry, None);
+        assert!(!shared_state.restore_requested);
+    }
+
+    #[test]
+    fn restore_geometry_is_dropped_when_the_wm_unmaximizes() {
+        let mut shared_state = SharedState::default();
+        shared_state.save_restore_geometry((10, 20), (800, 600));
+        assert_eq!(shared_state.maximized_changed(false), None);
+        // The next maximization saves the geometry the window has by then.
+        shared_state.save_restore_geometry((30, 40), (640, 480));
+        assert_eq!(shared_state.restore_geometry, Some(((30, 40), (640, 480))));
+    }
+
+    // Needs an X server, so it's skipped without one.
+    #[test]
+    fn toggling_decorations_preserves_the_cursor() {
+        let events_loop = match EventsLoop::new_x11() {
+            Ok(events_loop) => events_loop,
+            Err(_) => return,
+        };
+        let window = WindowBuilder::new().with_visibility(false).build(&events_loop).unwrap();
+        let window = match window.window {
+            LinuxWindow::X(ref window) => window,
+            LinuxWindow::Wayland(_) => unreachable!(),
+        };
+        // The window has a cursor of its own from the start.
+        assert_eq!(*window.defined_cursor.lock(), window.get_cursor(MouseCursor::Default));
+        window.set_cursor(MouseCursor::Hand);
+        let hand = window.get_cursor(MouseCursor::Hand);
+        window.set_decorations(false);
+        assert_eq!(*window.defined_cursor.lock(), hand);
+        window.set_decorations(true);
+        assert_eq!(*window.defined_cursor.lock(), hand);
+        // Hiding the cursor is preserved as well.
+        window.set_cursor_state(::CursorState::Hide).unwrap();
+        window.set_decorations(false);
+        assert_eq!(*window.defined_cursor.lock(), window.get_cursor(MouseCursor::NoneCursor));
+    }
+}
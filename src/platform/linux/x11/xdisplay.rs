@@ -1,10 +1,12 @@
 use std::ptr;
 use std::fmt;
+use std::collections::HashMap;
 use std::error::Error;
 
 use libc;
 use parking_lot::Mutex;
 
+use {Icon, MouseCursor};
 use super::ffi;
 
 /// A connection to an X server.
@@ -15,10 +17,34 @@ pub struct XConnection {
     /// Exposes XRandR functions from version = 1.5
     pub xrandr_1_5: Option<ffi::Xrandr>,
     pub xcursor: ffi::Xcursor,
+    /// Only present if the server supports XFixes >= 5.0, which introduced pointer barriers
+    pub xfixes: Option<ffi::XFixes>,
     pub xinput2: ffi::XInput2,
     pub xlib_xcb: ffi::Xlib_xcb,
     pub display: *mut ffi::Display,
     pub latest_error: Mutex<Option<XError>>,
+    /// Cursors that were already loaded, which are shared by all windows
+    pub cursor_cache: Mutex<HashMap<CursorId, ffi::Cursor>>,
+}
+
+/// Identifies a cursor in `XConnection::cursor_cache`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CursorId {
+    Standard(MouseCursor),
+    /// A cursor loaded from the theme by name, using `WindowExt::set_cursor_by_name`
+    Named(String),
+    /// A cursor created from an image and its hotspot by `Window::set_cursor_image`, which belongs
+    /// to a single window and thus is never cached
+    Image(Icon, (u32, u32), ffi::Cursor),
+    /// A cursor created by `Window::set_animated_cursor`, which belongs to a single window and
+    /// thus is never cached
+    Custom(ffi::Cursor),
+}
+
+impl Default for CursorId {
+    fn default() -> Self {
+        CursorId::Standard(Default::default())
+    }
 }
 
 unsafe impl Send for XConnection {}
@@ -48,15 +74,29 @@ impl XConnection {
             display
         };
 
+        // XFixes requests can't be used before the version we support has been announced.
+        let xfixes = ffi::XFixes::open().ok().and_then(|xfixes| {
+            let mut major = 5;
+            let mut minor = 0;
+            let status = unsafe { (xfixes.XFixesQueryVersion)(display, &mut major, &mut minor) };
+            if status != 0 && major >= 5 {
+                Some(xfixes)
+            } else {
+                None
+            }
+        });
+
         Ok(XConnection {
             xlib,
             xrandr,
             xrandr_1_5,
             xcursor,
+            xfixes,
             xinput2,
             xlib_xcb,
             display,
             latest_error: Mutex::new(None),
+            cursor_cache: Mutex::new(HashMap::new()),
         })
     }
 
@@ -87,6 +127,7 @@ impl fmt::Debug for XConnection {
 impl Drop for XConnection {
     #[inline]
     fn drop(&mut self) {
+        // The cursors are freed along with the rest of the resources when closing the display.
         unsafe { (self.xlib.XCloseDisplay)(self.display) };
     }
 }
@@ -0,0 +1,54 @@
+//! Both X11 and Wayland load cursors from Xcursor themes, which name them inconsistently.
+
+use MouseCursor;
+
+// Cursor themes don't agree on names, so every cursor has a list of names to try, in order of
+// preference. This covers the X core cursor font names, the CSS names used by freedesktop themes,
+// and the names used by Qt and KDE themes. The names are nul-terminated, as Xlib needs them.
+pub fn cursor_names(cursor: MouseCursor) -> &'static [&'static [u8]] {
+    match cursor {
+        MouseCursor::Alias => &[b"link\0", b"alias\0", b"dnd-link\0"],
+        MouseCursor::Arrow => &[b"arrow\0", b"left_ptr\0"],
+        MouseCursor::Cell => &[b"plus\0", b"cell\0", b"crosshair\0"],
+        MouseCursor::Copy => &[b"copy\0", b"dnd-copy\0"],
+        MouseCursor::Crosshair => &[b"crosshair\0", b"cross\0", b"tcross\0"],
+        MouseCursor::Default => &[b"left_ptr\0", b"default\0", b"arrow\0"],
+        MouseCursor::Hand => &[b"hand2\0", b"hand1\0", b"pointer\0", b"pointing_hand\0"],
+        MouseCursor::Help => &[b"question_arrow\0", b"help\0", b"whats_this\0", b"left_ptr_help\0"],
+        MouseCursor::Move => &[b"move\0", b"fleur\0", b"size_all\0"],
+        MouseCursor::Grab => &[b"openhand\0", b"grab\0", b"hand1\0"],
+        MouseCursor::Grabbing => &[b"closedhand\0", b"grabbing\0", b"dnd-move\0"],
+        MouseCursor::Progress => &[b"left_ptr_watch\0", b"progress\0", b"half-busy\0"],
+        MouseCursor::AllScroll => &[b"all-scroll\0", b"fleur\0"],
+        MouseCursor::ContextMenu => &[b"context-menu\0"],
+
+        MouseCursor::NoDrop => &[b"no-drop\0", b"dnd-no-drop\0", b"circle\0"],
+        MouseCursor::NotAllowed => &[b"crossed_circle\0", b"not-allowed\0", b"forbidden\0", b"circle\0"],
+
+        // Resize cursors
+        MouseCursor::EResize => &[b"right_side\0", b"e-resize\0"],
+        MouseCursor::NResize => &[b"top_side\0", b"n-resize\0"],
+        MouseCursor::NeResize => &[b"top_right_corner\0", b"ne-resize\0"],
+        MouseCursor::NwResize => &[b"top_left_corner\0", b"nw-resize\0"],
+        MouseCursor::SResize => &[b"bottom_side\0", b"s-resize\0"],
+        MouseCursor::SeResize => &[b"bottom_right_corner\0", b"se-resize\0"],
+        MouseCursor::SwResize => &[b"bottom_left_corner\0", b"sw-resize\0"],
+        MouseCursor::WResize => &[b"left_side\0", b"w-resize\0"],
+        MouseCursor::EwResize => &[b"h_double_arrow\0", b"ew-resize\0", b"sb_h_double_arrow\0", b"size_hor\0"],
+        MouseCursor::NsResize => &[b"v_double_arrow\0", b"ns-resize\0", b"sb_v_double_arrow\0", b"size_ver\0"],
+        MouseCursor::NwseResize => &[b"bd_double_arrow\0", b"size_bdiag\0", b"nwse-resize\0"],
+        MouseCursor::NeswResize => &[b"fd_double_arrow\0", b"size_fdiag\0", b"nesw-resize\0"],
+        MouseCursor::ColResize => &[b"split_h\0", b"col-resize\0", b"sb_h_double_arrow\0", b"h_double_arrow\0"],
+        MouseCursor::RowResize => &[b"split_v\0", b"row-resize\0", b"sb_v_double_arrow\0", b"v_double_arrow\0"],
+
+        MouseCursor::Text => &[b"text\0", b"xterm\0", b"ibeam\0"],
+        MouseCursor::VerticalText => &[b"vertical-text\0", b"text\0", b"xterm\0"],
+
+        MouseCursor::Wait => &[b"watch\0", b"wait\0"],
+
+        MouseCursor::ZoomIn => &[b"zoom-in\0", b"zoom_in\0"],
+        MouseCursor::ZoomOut => &[b"zoom-out\0", b"zoom_out\0"],
+
+        MouseCursor::NoneCursor => &[],
+    }
+}
@@ -1,17 +1,24 @@
 use {ControlFlow, EventsLoopClosed};
 use cocoa::{self, appkit, foundation};
 use cocoa::appkit::{NSApplication, NSEvent, NSEventMask, NSEventModifierFlags, NSEventPhase, NSView, NSWindow};
+use core_graphics::display::CGDisplay;
 use events::{self, ElementState, Event, TouchPhase, WindowEvent, DeviceEvent, ModifiersState, KeyboardInput};
 use std::collections::VecDeque;
 use std::sync::{Arc, Mutex, Weak};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use super::window::Window2;
 use std;
 use std::os::raw::*;
 use super::DeviceId;
+use timestamp::EventTimestamps;
 
 pub struct EventsLoop {
     modifiers: Modifiers,
     pub shared: Arc<Shared>,
+    // Set by the `Proxy` while a wakeup event is queued, so that several calls to `wakeup` only
+    // produce a single `Awakened` event.
+    pending_wakeup: Arc<AtomicBool>,
 }
 
 // State shared between the `EventsLoop` and its registered windows.
@@ -30,7 +37,9 @@ pub struct Shared {
 }
 
 #[derive(Clone)]
-pub struct Proxy {}
+pub struct Proxy {
+    pending_wakeup: Arc<AtomicBool>,
+}
 
 struct Modifiers {
     shift_pressed: bool,
@@ -170,9 +179,13 @@ impl EventsLoop {
         // marked as the main thread.
         unsafe { appkit::NSApp(); }
 
+        // The timestamps are relative to the creation of the first `EventsLoop`.
+        ::lazy_static::initialize(&TIMESTAMPS);
+
         EventsLoop {
             shared: Arc::new(Shared::new()),
             modifiers: Modifiers::new(),
+            pending_wakeup: Default::default(),
         }
     }
 
@@ -228,9 +241,13 @@ impl EventsLoop {
 
         // Track whether or not control flow has changed.
         let control_flow = std::cell::Cell::new(ControlFlow::Continue);
+        // The deadline returned with `WaitUntil` for the latest event.
+        let deadline = std::cell::Cell::new(None);
 
         let mut callback = |event| {
-            if let ControlFlow::Break = callback(event) {
+            let flow = callback(event);
+            deadline.set(flow.wait_deadline());
+            if let ControlFlow::Break = flow {
                 control_flow.set(ControlFlow::Break);
             }
         };
@@ -245,12 +262,31 @@ impl EventsLoop {
                     break;
                 }
 
+                let wait_deadline = deadline.get();
+                if wait_deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+                    self.shared.user_callback.call_with_event(Event::Awakened);
+                    if let ControlFlow::Break = control_flow.get() {
+                        break;
+                    }
+                    continue;
+                }
+
                 let pool = foundation::NSAutoreleasePool::new(cocoa::base::nil);
 
+                let until_date: cocoa::base::id = match wait_deadline {
+                    Some(deadline) => {
+                        let now = Instant::now();
+                        let timeout = if deadline > now { deadline - now } else { Duration::from_secs(0) };
+                        let seconds = timeout.as_secs() as f64 + timeout.subsec_nanos() as f64 * 1e-9;
+                        msg_send![cocoa::base::class("NSDate"), dateWithTimeIntervalSinceNow:seconds]
+                    },
+                    None => foundation::NSDate::distantFuture(cocoa::base::nil),
+                };
+
                 // Wait for the next event. Note that this function blocks during resize.
                 let ns_event = appkit::NSApp().nextEventMatchingMask_untilDate_inMode_dequeue_(
                     NSEventMask::NSAnyEventMask.bits() | NSEventMask::NSEventMaskPressure.bits(),
-                    foundation::NSDate::distantFuture(cocoa::base::nil),
+                    until_date,
                     foundation::NSDefaultRunLoopMode,
                     cocoa::base::YES);
 
@@ -381,10 +417,14 @@ impl EventsLoop {
                 let view_rect = NSView::frame(*window.view);
                 let x = view_point.x as f64;
                 let y = (view_rect.size.height - view_point.y) as f64;
+                let position = ::LogicalPosition::new(x, y);
+                let dpi_factor = NSWindow::backingScaleFactor(*window.window) as f64;
                 let window_event = WindowEvent::CursorMoved {
                     device_id: DEVICE_ID,
-                    position: (x, y).into(),
+                    position,
+                    physical_position: position.to_physical(dpi_factor),
                     modifiers: event_mods(ns_event),
+                    timestamp: event_timestamp(ns_event),
                 };
                 let event = Event::WindowEvent { window_id: ::WindowId(window.id()), event: window_event };
                 self.shared.pending_events.lock().unwrap().push_back(event);
@@ -471,7 +511,13 @@ impl EventsLoop {
                         },
                     }
                 });
-                let window_event = WindowEvent::MouseWheel { device_id: DEVICE_ID, delta: delta, phase: phase, modifiers: event_mods(ns_event) };
+                let window_event = WindowEvent::MouseWheel {
+                    device_id: DEVICE_ID,
+                    delta: delta,
+                    phase: phase,
+                    modifiers: event_mods(ns_event),
+                    timestamp: event_timestamp(ns_event),
+                };
                 Some(into_event(window_event))
             },
 
@@ -484,6 +530,7 @@ impl EventsLoop {
 
             appkit::NSApplicationDefined => match ns_event.subtype() {
                 appkit::NSEventSubtype::NSApplicationActivatedEventType => {
+                    self.pending_wakeup.store(false, Ordering::Relaxed);
                     Some(Event::Awakened)
                 },
                 _ => None,
@@ -493,14 +540,27 @@ impl EventsLoop {
         }
     }
 
+    pub fn release_grabs(&self) {
+        let _ = CGDisplay::associate_mouse_and_mouse_cursor_position(true);
+    }
+
+    pub fn set_redraw_vsync(&self, _enabled: bool) {
+        // Not implemented
+    }
+
     pub fn create_proxy(&self) -> Proxy {
-        Proxy {}
+        Proxy { pending_wakeup: self.pending_wakeup.clone() }
     }
 
 }
 
 impl Proxy {
     pub fn wakeup(&self) -> Result<(), EventsLoopClosed> {
+        if self.pending_wakeup.swap(true, Ordering::Relaxed) {
+            // The previous wakeup hasn't been received yet, so there's no need to post another.
+            return Ok(());
+        }
+
         // Awaken the event loop by triggering `NSApplicationActivatedEventType`.
         unsafe {
             let pool = foundation::NSAutoreleasePool::new(cocoa::base::nil);
@@ -523,6 +583,19 @@ impl Proxy {
     }
 }
 
+pub fn to_key_location(code: c_ushort) -> events::KeyLocation {
+    match code {
+        // The keypad's keys
+        0x41 | 0x43 | 0x45 | 0x47 | 0x4b | 0x4c | 0x4e | 0x51 | 0x52 | 0x53 | 0x54 | 0x55 | 0x56
+        | 0x57 | 0x58 | 0x59 | 0x5b | 0x5c => events::KeyLocation::Numpad,
+        // Left Command, Shift, Option and Control
+        0x37 | 0x38 | 0x3a | 0x3b => events::KeyLocation::Left,
+        // Right Command, Shift, Option and Control
+        0x36 | 0x3c | 0x3d | 0x3e => events::KeyLocation::Right,
+        _ => events::KeyLocation::Standard,
+    }
+}
+
 pub fn to_virtual_key_code(code: c_ushort) -> Option<events::VirtualKeyCode> {
     Some(match code {
         0x00 => events::VirtualKeyCode::A,
@@ -659,6 +732,36 @@ pub fn to_virtual_key_code(code: c_ushort) -> Option<events::VirtualKeyCode> {
     })
 }
 
+/// Returns the key produced by the current layout for a `keyDown`/`keyUp` event.
+///
+/// `charactersIgnoringModifiers` still takes Shift into account, as the other platforms do. Keys
+/// that don't produce text, such as the arrows, fall back to the key at that position.
+pub unsafe fn to_logical_key(event: cocoa::base::id, code: c_ushort) -> Option<events::LogicalKey> {
+    use cocoa::foundation::NSString;
+
+    let characters = NSEvent::charactersIgnoringModifiers(event);
+    let slice = std::slice::from_raw_parts(characters.UTF8String() as *const c_uchar, characters.len());
+    std::str::from_utf8(slice)
+        .ok()
+        .and_then(|string| string.chars().next())
+        // The function keys are given characters from the private use area.
+        .and_then(|character| match character as u32 {
+            0xf700...0xf8ff => None,
+            _ => events::LogicalKey::from_char(character),
+        })
+        .or_else(|| to_virtual_key_code(code).map(events::LogicalKey::Named))
+}
+
+lazy_static! {
+    static ref TIMESTAMPS: EventTimestamps = EventTimestamps::new();
+}
+
+// `NSEvent` timestamps are in seconds since the system started.
+pub fn event_timestamp(event: cocoa::base::id) -> Duration {
+    let time: f64 = unsafe { msg_send![event, timestamp] };
+    TIMESTAMPS.from_millis((time * 1000.0) as u64 as u32)
+}
+
 pub fn event_mods(event: cocoa::base::id) -> ModifiersState {
     let flags = unsafe {
         NSEvent::modifierFlags(event)
@@ -687,9 +790,15 @@ unsafe fn modifier_event(
             input: KeyboardInput {
                 state,
                 scancode,
+                // `flagsChanged` events have no characters, and modifiers are the same on every
+                // layout anyway
+                physical_key: virtual_keycode,
+                logical_key: virtual_keycode.map(events::LogicalKey::Named),
                 virtual_keycode,
+                location: to_key_location(keycode),
                 modifiers: event_mods(ns_event),
             },
+            timestamp: event_timestamp(ns_event),
         })
     } else {
         None
@@ -2,12 +2,30 @@
 
 #![allow(dead_code, non_snake_case, non_upper_case_globals)]
 
+use std::os::raw::c_void;
+
 use cocoa::base::{class, id};
 use cocoa::foundation::{NSInteger, NSUInteger};
+use core_foundation::array::CFArrayRef;
+use core_foundation::dictionary::CFDictionaryRef;
+use core_graphics::display::CGDirectDisplayID;
 use objc;
 
 pub const NSNotFound: NSInteger = NSInteger::max_value();
 
+pub type CGDisplayModeRef = *mut c_void;
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    pub fn CGDisplayCopyAllDisplayModes(
+        display: CGDirectDisplayID,
+        options: CFDictionaryRef,
+    ) -> CFArrayRef;
+    pub fn CGDisplayModeGetPixelWidth(mode: CGDisplayModeRef) -> usize;
+    pub fn CGDisplayModeGetPixelHeight(mode: CGDisplayModeRef) -> usize;
+    pub fn CGDisplayModeGetRefreshRate(mode: CGDisplayModeRef) -> f64;
+}
+
 #[repr(C)]
 pub struct NSRange {
     pub location: NSUInteger,
@@ -0,0 +1,111 @@
+use cocoa::appkit::NSEventModifierFlags;
+use cocoa::base::{class, id, nil};
+use cocoa::foundation::{NSString, NSUInteger};
+use objc::runtime::Sel;
+
+use platform::platform::window::IdRef;
+
+/// Installs a standard menu bar, unless the application already set one.
+///
+/// Without a menu bar, the usual shortcuts such as Cmd+Q and Cmd+C don't do anything, since
+/// they're dispatched through the key equivalents of menu items.
+pub fn initialize(app: id) {
+    unsafe {
+        let main_menu: id = msg_send![app, mainMenu];
+        if main_menu != nil {
+            return;
+        }
+
+        let process_info: id = msg_send![class("NSProcessInfo"), processInfo];
+        let process_name: id = msg_send![process_info, processName];
+        let process_name = {
+            let slice = ::std::slice::from_raw_parts(
+                process_name.UTF8String() as *const u8,
+                process_name.len(),
+            );
+            ::std::str::from_utf8(slice).unwrap_or("").to_owned()
+        };
+
+        let main_menu = new_menu("");
+
+        // The app menu, whose title is replaced by the name of the application
+        let app_menu = new_menu("");
+        add_item(app_menu, &format!("Hide {}", process_name), sel!(hide:), "h", None);
+        add_item(
+            app_menu,
+            "Hide Others",
+            sel!(hideOtherApplications:),
+            "h",
+            Some(NSEventModifierFlags::NSCommandKeyMask | NSEventModifierFlags::NSAlternateKeyMask),
+        );
+        add_item(app_menu, "Show All", sel!(unhideAllApplications:), "", None);
+        add_separator(app_menu);
+        add_item(app_menu, &format!("Quit {}", process_name), sel!(terminate:), "q", None);
+        add_submenu(main_menu, app_menu);
+
+        // The edit menu, which makes copy and paste work in text fields, including the IME's
+        let edit_menu = new_menu("Edit");
+        add_item(edit_menu, "Undo", sel!(undo:), "z", None);
+        add_item(
+            edit_menu,
+            "Redo",
+            sel!(redo:),
+            "z",
+            Some(NSEventModifierFlags::NSCommandKeyMask | NSEventModifierFlags::NSShiftKeyMask),
+        );
+        add_separator(edit_menu);
+        add_item(edit_menu, "Cut", sel!(cut:), "x", None);
+        add_item(edit_menu, "Copy", sel!(copy:), "c", None);
+        add_item(edit_menu, "Paste", sel!(paste:), "v", None);
+        add_item(edit_menu, "Select All", sel!(selectAll:), "a", None);
+        add_submenu(main_menu, edit_menu);
+
+        // The window menu, which AppKit fills with the list of open windows
+        let window_menu = new_menu("Window");
+        add_item(window_menu, "Minimize", sel!(performMiniaturize:), "m", None);
+        add_item(window_menu, "Zoom", sel!(performZoom:), "", None);
+        add_submenu(main_menu, window_menu);
+
+        let _: () = msg_send![app, setMainMenu:main_menu];
+        let _: () = msg_send![app, setWindowsMenu:window_menu];
+    }
+}
+
+unsafe fn new_menu(title: &str) -> id {
+    let title = IdRef::new(NSString::alloc(nil).init_str(title));
+    let menu: id = msg_send![class("NSMenu"), alloc];
+    let menu: id = msg_send![menu, initWithTitle:*title];
+    msg_send![menu, autorelease]
+}
+
+unsafe fn add_item(
+    menu: id,
+    title: &str,
+    action: Sel,
+    key_equivalent: &str,
+    modifiers: Option<NSEventModifierFlags>,
+) {
+    let title = IdRef::new(NSString::alloc(nil).init_str(title));
+    let key_equivalent = IdRef::new(NSString::alloc(nil).init_str(key_equivalent));
+    let item: id = msg_send![class("NSMenuItem"), alloc];
+    let item: id = msg_send![item, initWithTitle:*title action:action keyEquivalent:*key_equivalent];
+    if let Some(modifiers) = modifiers {
+        let modifiers = modifiers.bits() as NSUInteger;
+        let _: () = msg_send![item, setKeyEquivalentModifierMask:modifiers];
+    }
+    let _: () = msg_send![menu, addItem:item];
+    let _: () = msg_send![item, release];
+}
+
+unsafe fn add_separator(menu: id) {
+    let item: id = msg_send![class("NSMenuItem"), separatorItem];
+    let _: () = msg_send![menu, addItem:item];
+}
+
+unsafe fn add_submenu(menu: id, submenu: id) {
+    let item: id = msg_send![class("NSMenuItem"), alloc];
+    let item: id = msg_send![item, init];
+    let _: () = msg_send![item, setSubmenu:submenu];
+    let _: () = msg_send![menu, addItem:item];
+    let _: () = msg_send![item, release];
+}
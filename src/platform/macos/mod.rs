@@ -6,8 +6,15 @@ pub use self::window::{Id as WindowId, PlatformSpecificWindowBuilderAttributes,
 use std::sync::Arc;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct DeviceId;
 
+impl DeviceId {
+    pub unsafe fn dummy() -> Self {
+        DeviceId
+    }
+}
+
 use {CreationError};
 
 pub struct Window {
@@ -39,6 +46,7 @@ impl Window {
 
 mod events_loop;
 mod ffi;
+mod menu;
 mod monitor;
 mod util;
 mod view;
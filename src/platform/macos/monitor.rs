@@ -1,13 +1,18 @@
 use std::collections::VecDeque;
-use std::fmt;
+use std::ffi::CStr;
+use std::{fmt, ptr};
 
 use cocoa::appkit::NSScreen;
 use cocoa::base::{id, nil};
 use cocoa::foundation::{NSString, NSUInteger};
+use core_foundation::array::{CFArrayGetCount, CFArrayGetValueAtIndex};
+use core_foundation::base::{CFRelease, CFTypeRef};
 use core_graphics::display::{CGDirectDisplayID, CGDisplay, CGDisplayBounds};
+use objc::runtime::{BOOL, YES};
 
-use {PhysicalPosition, PhysicalSize};
-use super::EventsLoop;
+use {Orientation, PhysicalPosition, PhysicalSize, VideoMode};
+use dpi::calc_dpi;
+use super::{ffi, EventsLoop};
 use super::window::{IdRef, Window2};
 
 #[derive(Clone, PartialEq)]
@@ -84,6 +89,19 @@ impl fmt::Debug for MonitorId {
 
 impl MonitorId {
     pub fn get_name(&self) -> Option<String> {
+        // `localizedName` is the name System Preferences shows, such as "Built-in Retina Display".
+        // It was introduced in 10.15.
+        if let Some(screen) = self.get_nsscreen() {
+            unsafe {
+                let responds: BOOL = msg_send![screen, respondsToSelector:sel!(localizedName)];
+                if responds == YES {
+                    let name: id = msg_send![screen, localizedName];
+                    if name != nil {
+                        return Some(CStr::from_ptr(NSString::UTF8String(name)).to_string_lossy().into_owned());
+                    }
+                }
+            }
+        }
         let MonitorId(display_id) = *self;
         let screen_num = CGDisplay::new(display_id).model_number();
         Some(format!("Monitor #{}", screen_num))
@@ -122,6 +140,67 @@ impl MonitorId {
         unsafe { NSScreen::backingScaleFactor(screen) as f64 }
     }
 
+    pub fn get_dpi(&self) -> Option<f64> {
+        let display = CGDisplay::new(self.0);
+        let physical_size = display.screen_size();
+        let dimensions = self.get_dimensions().into();
+        calc_dpi(dimensions, (physical_size.width as f64, physical_size.height as f64))
+    }
+
+    #[inline]
+    pub fn is_primary(&self) -> bool {
+        self.0 == CGDisplay::main().id
+    }
+
+    pub fn get_current_video_mode(&self) -> Option<VideoMode> {
+        let display = CGDisplay::new(self.0);
+        let mode = display.display_mode()?;
+        Some(VideoMode {
+            dimensions: self.get_dimensions().into(),
+            bit_depth: display.bits_per_pixel() as u16,
+            // Built-in displays commonly report a refresh rate of 0.
+            refresh_rate: mode.refresh_rate().round() as u16,
+        })
+    }
+
+    pub fn get_video_modes(&self) -> Vec<VideoMode> {
+        // The bit depth can only be queried for the display's current mode.
+        let bit_depth = CGDisplay::new(self.0).bits_per_pixel() as u16;
+        let mut video_modes = Vec::new();
+        unsafe {
+            let modes = ffi::CGDisplayCopyAllDisplayModes(self.0, ptr::null());
+            if modes.is_null() {
+                return video_modes;
+            }
+            for i in 0..CFArrayGetCount(modes) {
+                let mode = CFArrayGetValueAtIndex(modes, i) as ffi::CGDisplayModeRef;
+                let video_mode = VideoMode {
+                    dimensions: (
+                        ffi::CGDisplayModeGetPixelWidth(mode) as u32,
+                        ffi::CGDisplayModeGetPixelHeight(mode) as u32,
+                    ),
+                    bit_depth,
+                    refresh_rate: ffi::CGDisplayModeGetRefreshRate(mode).round() as u16,
+                };
+                if !video_modes.contains(&video_mode) {
+                    video_modes.push(video_mode);
+                }
+            }
+            CFRelease(modes as CFTypeRef);
+        }
+        video_modes
+    }
+
+    pub fn get_orientation(&self) -> Orientation {
+        // The rotation is clockwise, in degrees.
+        match CGDisplay::new(self.0).rotation().round() as u32 {
+            90 => Orientation::Portrait,
+            180 => Orientation::LandscapeFlipped,
+            270 => Orientation::PortraitFlipped,
+            _ => Orientation::Landscape,
+        }
+    }
+
     pub(crate) fn get_nsscreen(&self) -> Option<id> {
         unsafe {
             let native_id = self.get_native_identifier();
@@ -13,8 +13,16 @@ use cocoa::foundation::{NSPoint, NSRect, NSSize, NSString, NSUInteger};
 use objc::declare::ClassDecl;
 use objc::runtime::{Class, Object, Protocol, Sel, BOOL};
 
-use {ElementState, Event, KeyboardInput, MouseButton, WindowEvent, WindowId};
-use platform::platform::events_loop::{DEVICE_ID, event_mods, Shared, to_virtual_key_code};
+use {ElementState, Event, KeyboardInput, LogicalPosition, MouseButton, WindowEvent, WindowId};
+use platform::platform::events_loop::{
+    DEVICE_ID,
+    event_mods,
+    event_timestamp,
+    Shared,
+    to_key_location,
+    to_logical_key,
+    to_virtual_key_code,
+};
 use platform::platform::util;
 use platform::platform::ffi::*;
 use platform::platform::window::{get_window_id, IdRef};
@@ -23,6 +31,7 @@ struct ViewState {
     window: id,
     shared: Weak<Shared>,
     ime_spot: Option<(f64, f64)>,
+    ime_allowed: bool,
     raw_characters: Option<String>,
     last_insert: Option<String>,
 }
@@ -32,6 +41,7 @@ pub fn new_view(window: id, shared: Weak<Shared>) -> IdRef {
         window,
         shared,
         ime_spot: None,
+        ime_allowed: true,
         raw_characters: None,
         last_insert: None,
     };
@@ -58,6 +68,32 @@ pub fn set_ime_spot(view: id, input_context: id, x: f64, y: f64) {
     }
 }
 
+pub fn set_ime_allowed(view: id, input_context: id, allowed: bool, commit: bool) {
+    unsafe {
+        {
+            let state_ptr: *mut c_void = *(*view).get_mut_ivar("winitState");
+            let state = &mut *(state_ptr as *mut ViewState);
+            if state.ime_allowed == allowed {
+                return;
+            }
+            state.ime_allowed = allowed;
+            state.last_insert = None;
+        }
+        if allowed {
+            let _: () = msg_send![input_context, activate];
+            return;
+        }
+        let marked_text: id = *(*view).get_ivar("markedText");
+        if commit && marked_text.length() > 0 {
+            let string: id = msg_send![marked_text, string];
+            insert_text(&*view, sel!(insertText:replacementRange:), string, NSRange::new(0, 0));
+        }
+        // This clears the marked text and tells the input method to discard its composition
+        unmark_text(&*view, sel!(unmarkText));
+        let _: () = msg_send![input_context, deactivate];
+    }
+}
+
 struct ViewClass(*const Class);
 unsafe impl Send for ViewClass {}
 unsafe impl Sync for ViewClass {}
@@ -71,6 +107,7 @@ lazy_static! {
             sel!(initWithWinit:),
             init_with_winit as extern fn(&Object, Sel, *mut c_void) -> id,
         );
+        decl.add_method(sel!(drawRect:), draw_rect as extern fn(&Object, Sel, NSRect));
         decl.add_method(sel!(hasMarkedText), has_marked_text as extern fn(&Object, Sel) -> BOOL);
         decl.add_method(
             sel!(markedRange),
@@ -362,9 +399,13 @@ extern fn key_down(this: &Object, _sel: Sel, event: id) {
                 input: KeyboardInput {
                     state: ElementState::Pressed,
                     scancode,
+                    physical_key: virtual_keycode,
+                    logical_key: to_logical_key(event, keycode),
                     virtual_keycode,
+                    location: to_key_location(keycode),
                     modifiers: event_mods(event),
                 },
+                timestamp: event_timestamp(event),
             },
         };
 
@@ -399,8 +440,23 @@ extern fn key_down(this: &Object, _sel: Sel, event: id) {
             }
         }
 
-        let array: id = msg_send![class("NSArray"), arrayWithObject:event];
-        let (): _ = msg_send![this, interpretKeyEvents:array];
+        if state.ime_allowed {
+            let array: id = msg_send![class("NSArray"), arrayWithObject:event];
+            let (): _ = msg_send![this, interpretKeyEvents:array];
+        } else if let Some(shared) = state.shared.upgrade() {
+            // Without the input method, the characters are delivered as-is
+            if let Some(ref raw_characters) = state.raw_characters {
+                for character in raw_characters.chars() {
+                    shared.pending_events
+                        .lock()
+                        .unwrap()
+                        .push_back(Event::WindowEvent {
+                            window_id,
+                            event: WindowEvent::ReceivedCharacter(character),
+                        });
+                }
+            }
+        }
     }
 }
 
@@ -422,9 +478,13 @@ extern fn key_up(this: &Object, _sel: Sel, event: id) {
                 input: KeyboardInput {
                     state: ElementState::Released,
                     scancode,
+                    physical_key: virtual_keycode,
+                    logical_key: to_logical_key(event, keycode),
                     virtual_keycode,
+                    location: to_key_location(keycode),
                     modifiers: event_mods(event),
                 },
+                timestamp: event_timestamp(event),
             },
         };
 
@@ -471,6 +531,7 @@ fn mouse_click(this: &Object, event: id, button: MouseButton, button_state: Elem
                 state: button_state,
                 button,
                 modifiers: event_mods(event),
+                timestamp: event_timestamp(event),
             },
         };
 
@@ -529,13 +590,17 @@ fn mouse_motion(this: &Object, event: id) {
 
         let x = view_point.x as f64;
         let y = view_rect.size.height as f64 - view_point.y as f64;
+        let position = LogicalPosition::new(x, y);
+        let dpi_factor = NSWindow::backingScaleFactor(state.window) as f64;
 
         let window_event = Event::WindowEvent {
             window_id: WindowId(get_window_id(state.window)),
             event: WindowEvent::CursorMoved {
                 device_id: DEVICE_ID,
-                position: (x, y).into(),
+                position,
+                physical_position: position.to_physical(dpi_factor),
                 modifiers: event_mods(event),
+                timestamp: event_timestamp(event),
             },
         };
 
@@ -548,6 +613,21 @@ fn mouse_motion(this: &Object, event: id) {
     }
 }
 
+extern fn draw_rect(this: &Object, _sel: Sel, _rect: NSRect) {
+    unsafe {
+        let state_ptr: *mut c_void = *this.get_ivar("winitState");
+        let state = &mut *(state_ptr as *mut ViewState);
+
+        // AppKit draws while we wait for the next event, so this has to be delivered right away.
+        if let Some(shared) = state.shared.upgrade() {
+            shared.call_user_callback_with_event_or_store_in_pending(Event::WindowEvent {
+                window_id: WindowId(get_window_id(state.window)),
+                event: WindowEvent::RedrawRequested,
+            });
+        }
+    }
+}
+
 extern fn mouse_moved(this: &Object, _sel: Sel, event: id) {
     mouse_motion(this, event);
 }
@@ -2,6 +2,8 @@ use std;
 use std::ops::Deref;
 use std::os::raw::c_void;
 use std::sync::Weak;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::time::Duration;
 use std::cell::{Cell, RefCell};
 
 use cocoa;
@@ -17,10 +19,24 @@ use cocoa::appkit::{
     NSWindowStyleMask,
 };
 use cocoa::base::{id, nil};
-use cocoa::foundation::{NSAutoreleasePool, NSDictionary, NSPoint, NSRect, NSSize, NSString};
+use cocoa::foundation::{
+    NSAutoreleasePool,
+    NSDictionary,
+    NSInteger,
+    NSPoint,
+    NSRect,
+    NSSize,
+    NSString,
+    NSUInteger,
+};
 
 use core_graphics::display::CGDisplay;
 
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::RawWindowHandle;
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::macos::MacOSHandle;
+
 use objc;
 use objc::runtime::{Class, Object, Sel, BOOL, YES, NO};
 use objc::declare::ClassDecl;
@@ -29,23 +45,38 @@ use {
     CreationError,
     CursorState,
     Event,
+    ExternalError,
+    Fullscreen,
+    Insets,
     LogicalPosition,
     LogicalSize,
     MouseCursor,
+    NotSupportedError,
+    ResizeDirection,
+    ScaleFactorPolicy,
+    StackOrder,
+    UserAttentionType,
     WindowAttributes,
     WindowEvent,
     WindowId,
 };
 use CreationError::OsError;
-use os::macos::{ActivationPolicy, WindowExt};
-use platform::platform::{ffi, util};
+use os::macos::{ActivationPolicy, BlurMaterial, WindowExt};
+use platform::platform::{ffi, menu, util};
 use platform::platform::events_loop::{EventsLoop, Shared};
-use platform::platform::view::{new_view, set_ime_spot};
+use platform::platform::view::{new_view, set_ime_allowed, set_ime_spot};
 use window::MonitorId as RootMonitorId;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Id(pub usize);
 
+impl Id {
+    pub unsafe fn dummy() -> Self {
+        Id(0)
+    }
+}
+
 pub struct DelegateState {
     view: IdRef,
     window: IdRef,
@@ -64,6 +95,14 @@ pub struct DelegateState {
 
     // Used to prevent redundant events.
     previous_dpi_factor: f64,
+
+    cursor_state: Cell<CursorState>,
+    // Set when the cursor grab was released because the window lost focus.
+    cursor_grab_suspended: Cell<bool>,
+
+    // Set by `with_traffic_light_inset`/`set_traffic_light_inset`. AppKit puts the buttons back
+    // in their default position whenever it lays out the titlebar, so we reapply it after that.
+    traffic_light_inset: Cell<Option<LogicalPosition>>,
 }
 
 impl DelegateState {
@@ -183,6 +222,30 @@ impl WindowDelegate {
         WindowDelegate::emit_event(state, WindowEvent::Resized(size));
     }
 
+    // Emits `HiDpiFactorChanged` if the backing scale factor changed. Windows are sized in points,
+    // so they keep their logical size unless `ScaleFactorPolicy::PreservePhysical` is used.
+    pub fn update_dpi_factor(state: &mut DelegateState) {
+        let dpi_factor = unsafe { NSWindow::backingScaleFactor(*state.window) as f64 };
+        if state.previous_dpi_factor == dpi_factor {
+            return;
+        }
+        let previous_dpi_factor = state.previous_dpi_factor;
+        state.previous_dpi_factor = dpi_factor;
+        WindowDelegate::emit_event(state, WindowEvent::HiDpiFactorChanged(dpi_factor));
+        let policy = state.win_attribs.borrow().scale_factor_policy;
+        if policy == ScaleFactorPolicy::PreservePhysical {
+            let scale_factor = (previous_dpi_factor / dpi_factor) as CGFloat;
+            unsafe {
+                let size = NSView::frame(*state.view).size;
+                NSWindow::setContentSize_(
+                    *state.window,
+                    NSSize::new(size.width * scale_factor, size.height * scale_factor),
+                );
+            }
+        }
+        WindowDelegate::emit_resize_event(state);
+    }
+
     pub fn emit_move_event(state: &mut DelegateState) {
         let rect = unsafe { NSWindow::frame(*state.window) };
         let x = rect.origin.x as f64;
@@ -226,6 +289,9 @@ impl WindowDelegate {
             unsafe {
                 let state: *mut c_void = *this.get_ivar("winitState");
                 let state = &mut *(state as *mut DelegateState);
+                if let Some(inset) = state.traffic_light_inset.get() {
+                    set_traffic_light_inset(*state.window, inset);
+                }
                 WindowDelegate::emit_resize_event(state);
                 WindowDelegate::emit_move_event(state);
             }
@@ -244,12 +310,7 @@ impl WindowDelegate {
             unsafe {
                 let state: *mut c_void = *this.get_ivar("winitState");
                 let state = &mut *(state as *mut DelegateState);
-                let dpi_factor = NSWindow::backingScaleFactor(*state.window) as f64;
-                if state.previous_dpi_factor != dpi_factor {
-                    state.previous_dpi_factor = dpi_factor;
-                    WindowDelegate::emit_event(state, WindowEvent::HiDpiFactorChanged(dpi_factor));
-                    WindowDelegate::emit_resize_event(state);
-                }
+                WindowDelegate::update_dpi_factor(state);
             }
         }
 
@@ -258,12 +319,7 @@ impl WindowDelegate {
             unsafe {
                 let state: *mut c_void = *this.get_ivar("winitState");
                 let state = &mut *(state as *mut DelegateState);
-                let dpi_factor = NSWindow::backingScaleFactor(*state.window) as f64;
-                if state.previous_dpi_factor != dpi_factor {
-                    state.previous_dpi_factor = dpi_factor;
-                    WindowDelegate::emit_event(state, WindowEvent::HiDpiFactorChanged(dpi_factor));
-                    WindowDelegate::emit_resize_event(state);
-                }
+                WindowDelegate::update_dpi_factor(state);
             }
         }
 
@@ -273,6 +329,9 @@ impl WindowDelegate {
                 // lost focus
                 let state: *mut c_void = *this.get_ivar("winitState");
                 let state = &mut *(state as *mut DelegateState);
+                if state.cursor_grab_suspended.replace(false) {
+                    apply_cursor_state(CursorState::Grab);
+                }
                 WindowDelegate::emit_event(state, WindowEvent::Focused(true));
             }
         }
@@ -281,10 +340,32 @@ impl WindowDelegate {
             unsafe {
                 let state: *mut c_void = *this.get_ivar("winitState");
                 let state = &mut *(state as *mut DelegateState);
+                // The grab is released before the application is told, so that it doesn't end up
+                // fighting us over it.
+                if state.cursor_state.get() == CursorState::Grab {
+                    apply_cursor_state(CursorState::Normal);
+                    state.cursor_grab_suspended.set(true);
+                }
                 WindowDelegate::emit_event(state, WindowEvent::Focused(false));
             }
         }
 
+        extern fn window_did_miniaturize(this: &Object, _: Sel, _: id) {
+            unsafe {
+                let state: *mut c_void = *this.get_ivar("winitState");
+                let state = &mut *(state as *mut DelegateState);
+                WindowDelegate::emit_event(state, WindowEvent::Minimized(true));
+            }
+        }
+
+        extern fn window_did_deminiaturize(this: &Object, _: Sel, _: id) {
+            unsafe {
+                let state: *mut c_void = *this.get_ivar("winitState");
+                let state = &mut *(state as *mut DelegateState);
+                WindowDelegate::emit_event(state, WindowEvent::Minimized(false));
+            }
+        }
+
         /// Invoked when the dragged image enters destination bounds or frame
         extern fn dragging_entered(this: &Object, _: Sel, sender: id) -> BOOL {
             use cocoa::appkit::NSPasteboard;
@@ -357,7 +438,8 @@ impl WindowDelegate {
             unsafe {
                 let state: *mut c_void = *this.get_ivar("winitState");
                 let state = &mut *(state as *mut DelegateState);
-                state.win_attribs.borrow_mut().fullscreen = Some(get_current_monitor(*state.window));
+                let monitor = get_current_monitor(*state.window);
+                state.win_attribs.borrow_mut().fullscreen = Some(Fullscreen::Borderless(Some(monitor)));
 
                 state.handle_with_fullscreen = false;
             }
@@ -382,6 +464,9 @@ impl WindowDelegate {
             };
 
             state.restore_state_from_fullscreen();
+            if let Some(inset) = state.traffic_light_inset.get() {
+                unsafe { set_traffic_light_inset(*state.window, inset) };
+            }
         }
 
         /// Invoked when fail to enter fullscreen
@@ -442,6 +527,10 @@ impl WindowDelegate {
                 window_did_become_key as extern fn(&Object, Sel, id));
             decl.add_method(sel!(windowDidResignKey:),
                 window_did_resign_key as extern fn(&Object, Sel, id));
+            decl.add_method(sel!(windowDidMiniaturize:),
+                window_did_miniaturize as extern fn(&Object, Sel, id));
+            decl.add_method(sel!(windowDidDeminiaturize:),
+                window_did_deminiaturize as extern fn(&Object, Sel, id));
 
             // callbacks for drag and drop events
             decl.add_method(sel!(draggingEntered:),
@@ -520,18 +609,44 @@ pub struct PlatformSpecificWindowBuilderAttributes {
     pub titlebar_buttons_hidden: bool,
     pub fullsize_content_view: bool,
     pub resize_increments: Option<LogicalSize>,
+    pub traffic_light_inset: Option<LogicalPosition>,
+    pub blur: Option<BlurMaterial>,
+    pub disable_default_menu: bool,
 }
 
+const NO_ATTENTION_REQUEST: isize = -1;
+
 pub struct Window2 {
     pub view: IdRef,
     pub window: IdRef,
     pub delegate: WindowDelegate,
     pub input_context: IdRef,
+    // The identifier of the last `requestUserAttention:`, or `NO_ATTENTION_REQUEST`.
+    attention_request: AtomicIsize,
+    // The cursor created by `set_animated_cursor`, which is kept alive while it may be shown.
+    custom_cursor: RefCell<Option<IdRef>>,
 }
 
 unsafe impl Send for Window2 {}
 unsafe impl Sync for Window2 {}
 
+fn apply_cursor_state(state: CursorState) {
+    let cls = Class::get("NSCursor").unwrap();
+    match state {
+        CursorState::Normal => {
+            let _: () = unsafe { msg_send![cls, unhide] };
+            let _ = CGDisplay::associate_mouse_and_mouse_cursor_position(true);
+        },
+        CursorState::Hide => {
+            let _: () = unsafe { msg_send![cls, hide] };
+        },
+        CursorState::Grab => {
+            let _: () = unsafe { msg_send![cls, hide] };
+            let _ = CGDisplay::associate_mouse_and_mouse_cursor_position(false);
+        },
+    }
+}
+
 unsafe fn get_current_monitor(window: id) -> RootMonitorId {
     let screen: id = msg_send![window, screen];
     let desc = NSScreen::deviceDescription(screen);
@@ -554,6 +669,12 @@ impl Drop for Window2 {
             shared.find_and_remove_window(id);
         }
 
+        // The mouse would otherwise stay dissociated from the cursor after the window is gone.
+        let state = &self.delegate.state;
+        if state.cursor_state.get() == CursorState::Grab && !state.cursor_grab_suspended.get() {
+            apply_cursor_state(CursorState::Normal);
+        }
+
         // nswindow::close uses autorelease
         // so autorelease pool
         let autoreleasepool = unsafe {
@@ -582,6 +703,12 @@ impl WindowExt for Window2 {
     fn get_nsview(&self) -> *mut c_void {
         *self.view as *mut c_void
     }
+
+    #[inline]
+    fn set_traffic_light_inset(&self, inset: LogicalPosition) {
+        self.delegate.state.traffic_light_inset.set(Some(inset));
+        unsafe { set_traffic_light_inset(*self.window, inset) };
+    }
 }
 
 impl Window2 {
@@ -596,6 +723,11 @@ impl Window2 {
             }
         }
 
+        // Switching the video mode isn't implemented.
+        if let Some(Fullscreen::Exclusive(..)) = win_attribs.fullscreen {
+            return Err(CreationError::NotSupported);
+        }
+
         // Might as well save some RAM...
         win_attribs.window_icon.take();
 
@@ -603,7 +735,7 @@ impl Window2 {
             NSAutoreleasePool::new(nil)
         };
 
-        let app = match Window2::create_app(pl_attribs.activation_policy) {
+        let app = match Window2::create_app(pl_attribs.activation_policy, !pl_attribs.disable_default_menu) {
             Some(app) => app,
             None => {
                 let _: () = unsafe { msg_send![autoreleasepool, drain] };
@@ -632,6 +764,10 @@ impl Window2 {
             if win_attribs.transparent {
                 (*window as id).setOpaque_(NO);
                 (*window as id).setBackgroundColor_(NSColor::clearColor(nil));
+                // Without transparency, the view would be drawn over the blur.
+                if let Some(material) = pl_attribs.blur {
+                    add_visual_effect_view(*window, *view, material);
+                }
             }
 
             app.activateIgnoringOtherApps_(YES);
@@ -661,29 +797,39 @@ impl Window2 {
             handle_with_fullscreen: win_attribs.fullscreen.is_some(),
             previous_position: None,
             previous_dpi_factor: dpi_factor,
+            cursor_state: Cell::new(CursorState::Normal),
+            cursor_grab_suspended: Cell::new(false),
+            traffic_light_inset: Cell::new(pl_attribs.traffic_light_inset),
         };
+        if let Some(inset) = pl_attribs.traffic_light_inset {
+            unsafe { set_traffic_light_inset(*window, inset) };
+        }
         delegate_state.win_attribs.borrow_mut().fullscreen = None;
 
         if dpi_factor != 1.0 {
             WindowDelegate::emit_event(&mut delegate_state, WindowEvent::HiDpiFactorChanged(dpi_factor));
-            WindowDelegate::emit_resize_event(&mut delegate_state);
         }
+        WindowDelegate::emit_resize_event(&mut delegate_state);
 
         let window = Window2 {
             view: view,
             window: window,
             delegate: WindowDelegate::new(delegate_state),
             input_context,
+            attention_request: AtomicIsize::new(NO_ATTENTION_REQUEST),
+            custom_cursor: RefCell::new(None),
         };
 
         // Set fullscreen mode after we setup everything
-        if let Some(ref monitor) = win_attribs.fullscreen {
-            unsafe {
-                if monitor.inner != get_current_monitor(*window.window).inner {
-                    unimplemented!();
+        if let Some(ref fullscreen) = win_attribs.fullscreen {
+            if let Some(monitor) = fullscreen.get_monitor() {
+                unsafe {
+                    if monitor.inner != get_current_monitor(*window.window).inner {
+                        unimplemented!();
+                    }
                 }
             }
-            window.set_fullscreen(Some(monitor.clone()));
+            let _ = window.set_fullscreen(Some(fullscreen.clone()));
         }
 
         // Make key have to be after set fullscreen
@@ -709,13 +855,16 @@ impl Window2 {
         get_window_id(*self.window)
     }
 
-    fn create_app(activation_policy: ActivationPolicy) -> Option<id> {
+    fn create_app(activation_policy: ActivationPolicy, default_menu: bool) -> Option<id> {
         unsafe {
             let app = appkit::NSApp();
             if app == nil {
                 None
             } else {
                 app.setActivationPolicy_(activation_policy.into());
+                if default_menu {
+                    menu::initialize(app);
+                }
                 app.finishLaunching();
                 Some(app)
             }
@@ -745,12 +894,13 @@ impl Window2 {
     ) -> Option<IdRef> {
         unsafe {
             let autoreleasepool = NSAutoreleasePool::new(nil);
-            let screen = match attrs.fullscreen {
-                Some(ref monitor_id) => {
-                    let monitor_screen = monitor_id.inner.get_nsscreen();
+            let screen = match attrs.fullscreen.as_ref().and_then(Fullscreen::get_monitor) {
+                Some(monitor) => {
+                    let monitor_screen = monitor.inner.get_nsscreen();
                     Some(monitor_screen.unwrap_or(appkit::NSScreen::mainScreen(nil)))
                 },
-                _ => None,
+                // `Fullscreen::Borderless(None)` goes fullscreen wherever the window is placed.
+                None => None,
             };
             let frame = match screen {
                 Some(screen) => appkit::NSScreen::frame(screen),
@@ -823,6 +973,11 @@ impl Window2 {
                     window.setMovableByWindowBackground_(YES);
                 }
 
+                // This has to be done before the window is ordered front.
+                if attrs.content_protected {
+                    set_sharing_type(*window, true);
+                }
+
                 if attrs.always_on_top {
                     let _: () = msg_send![*window, setLevel:ffi::NSWindowLevel::NSFloatingWindowLevel];
                 }
@@ -872,6 +1027,14 @@ impl Window2 {
         unsafe { NSWindow::orderOut_(*self.window, nil); }
     }
 
+    // The view sends `RedrawRequested` from `drawRect:`, which AppKit calls once however many times
+    // the view was marked as needing display.
+    pub fn request_redraw(&self) {
+        unsafe {
+            let _: () = msg_send![*self.view, setNeedsDisplay:YES];
+        }
+    }
+
     pub fn get_position(&self) -> Option<LogicalPosition> {
         let frame_rect = unsafe { NSWindow::frame(*self.window) };
         Some((
@@ -914,12 +1077,40 @@ impl Window2 {
         Some((view_frame.size.width as f64, view_frame.size.height as f64).into())
     }
 
+    #[inline]
+    pub fn get_inner_size_physical(&self) -> Option<(u32, u32)> {
+        let view_frame = unsafe { NSView::frame(*self.view) };
+        let backing_frame: NSRect = unsafe { msg_send![*self.view, convertRectToBacking:view_frame] };
+        Some((backing_frame.size.width.round() as u32, backing_frame.size.height.round() as u32))
+    }
+
     #[inline]
     pub fn get_outer_size(&self) -> Option<LogicalSize> {
         let view_frame = unsafe { NSWindow::frame(*self.window) };
         Some((view_frame.size.width as f64, view_frame.size.height as f64).into())
     }
 
+    #[inline]
+    pub fn get_outer_size_physical(&self) -> Option<(u32, u32)> {
+        self.get_outer_size()
+            .map(|logical| logical.to_physical(self.get_hidpi_factor()).into())
+    }
+
+    #[inline]
+    pub fn get_decoration_insets(&self) -> Option<Insets> {
+        unsafe {
+            let frame = NSWindow::frame(*self.window);
+            // Both are in window coordinates, whose origin is the bottom left corner.
+            let layout_rect: NSRect = msg_send![*self.window, contentLayoutRect];
+            Some(Insets {
+                top: (frame.size.height - (layout_rect.origin.y + layout_rect.size.height)) as f64,
+                left: layout_rect.origin.x as f64,
+                right: (frame.size.width - (layout_rect.origin.x + layout_rect.size.width)) as f64,
+                bottom: layout_rect.origin.y as f64,
+            })
+        }
+    }
+
     #[inline]
     pub fn set_inner_size(&self, size: LogicalSize) {
         unsafe {
@@ -956,6 +1147,11 @@ impl Window2 {
         } // Otherwise, we don't change the mask until we exit fullscreen.
     }
 
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        self.delegate.state.win_attribs.borrow().resizable
+    }
+
     pub fn set_cursor(&self, cursor: MouseCursor) {
         let cursor_name = match cursor {
             MouseCursor::Arrow | MouseCursor::Default => "arrowCursor",
@@ -992,28 +1188,69 @@ impl Window2 {
             let cursor: id = cls.send_message(sel, ()).unwrap();
             let _: () = msg_send![cursor, set];
         }
+        *self.custom_cursor.borrow_mut() = None;
     }
 
-    pub fn set_cursor_state(&self, state: CursorState) -> Result<(), String> {
-        let cls = Class::get("NSCursor").unwrap();
+    // Animated cursors aren't supported by AppKit, so we show the first frame as a static cursor.
+    #[inline]
+    pub fn set_cursor_image(&self, image: ::Icon, hotspot: (u32, u32)) -> Result<(), ExternalError> {
+        self.set_animated_cursor(vec![(image, Duration::from_secs(0))], hotspot)
+    }
 
-        // TODO: Check for errors.
-        match state {
-            CursorState::Normal => {
-                let _: () = unsafe { msg_send![cls, unhide] };
-                let _ = CGDisplay::associate_mouse_and_mouse_cursor_position(true);
-                Ok(())
-            },
-            CursorState::Hide => {
-                let _: () = unsafe { msg_send![cls, hide] };
-                Ok(())
-            },
-            CursorState::Grab => {
-                let _: () = unsafe { msg_send![cls, hide] };
-                let _ = CGDisplay::associate_mouse_and_mouse_cursor_position(false);
-                Ok(())
+    pub fn set_animated_cursor(
+        &self,
+        frames: Vec<(::Icon, Duration)>,
+        hotspot: (u32, u32),
+    ) -> Result<(), ExternalError> {
+        let icon = &frames[0].0;
+        unsafe {
+            let color_space = IdRef::new(NSString::alloc(nil).init_str("NSDeviceRGBColorSpace"));
+            // Passing null planes makes the bitmap allocate its own buffer, which we copy into.
+            let bitmap: id = msg_send![Class::get("NSBitmapImageRep").unwrap(), alloc];
+            let bitmap: id = msg_send![bitmap,
+                initWithBitmapDataPlanes:std::ptr::null_mut::<*mut u8>()
+                pixelsWide:icon.width as NSInteger
+                pixelsHigh:icon.height as NSInteger
+                bitsPerSample:8 as NSInteger
+                samplesPerPixel:4 as NSInteger
+                hasAlpha:YES
+                isPlanar:NO
+                colorSpaceName:*color_space
+                bytesPerRow:(icon.width * 4) as NSInteger
+                bitsPerPixel:32 as NSInteger];
+            if bitmap == nil {
+                return Err(ExternalError::Os("Failed to create the cursor image".to_owned()));
             }
+            let bitmap = IdRef::new(bitmap);
+            let data: *mut u8 = msg_send![*bitmap, bitmapData];
+            std::ptr::copy_nonoverlapping(icon.rgba.as_ptr(), data, icon.rgba.len());
+
+            let size = NSSize::new(icon.width as CGFloat, icon.height as CGFloat);
+            let image: id = msg_send![Class::get("NSImage").unwrap(), alloc];
+            let image = IdRef::new(msg_send![image, initWithSize:size]);
+            let _: () = msg_send![*image, addRepresentation:*bitmap];
+
+            let hotspot = NSPoint::new(hotspot.0 as CGFloat, hotspot.1 as CGFloat);
+            let cursor: id = msg_send![Class::get("NSCursor").unwrap(), alloc];
+            let cursor = IdRef::new(msg_send![cursor, initWithImage:*image hotSpot:hotspot]);
+            let _: () = msg_send![*cursor, set];
+            *self.custom_cursor.borrow_mut() = Some(cursor);
         }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn is_cursor_visible(&self) -> bool {
+        self.delegate.state.cursor_state.get() != CursorState::Hide
+    }
+
+    pub fn set_cursor_state(&self, state: CursorState) -> Result<(), ExternalError> {
+        // The application changed the grab itself, so it shouldn't be restored on focus anymore.
+        self.delegate.state.cursor_grab_suspended.set(false);
+        self.delegate.state.cursor_state.set(state);
+        apply_cursor_state(state);
+        // TODO: Check for errors.
+        Ok(())
     }
 
     #[inline]
@@ -1024,17 +1261,56 @@ impl Window2 {
     }
 
     #[inline]
-    pub fn set_cursor_position(&self, cursor_position: LogicalPosition) -> Result<(), ()> {
+    pub fn set_keyboard_grab(&self, _grab: bool) -> Result<(), ExternalError> {
+        Err(ExternalError::NotSupported(NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn confine_cursor_to_monitor(&self, _confine: bool) -> Result<(), ExternalError> {
+        Err(ExternalError::NotSupported(NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn set_cursor_position(&self, cursor_position: LogicalPosition) -> Result<(), ExternalError> {
         let window_position = self.get_inner_position()
-            .expect("`get_inner_position` failed");
+            .ok_or_else(|| ExternalError::Os("`get_inner_position` failed".to_owned()))?;
         let point = appkit::CGPoint {
             x: (cursor_position.x + window_position.x) as CGFloat,
             y: (cursor_position.y + window_position.y) as CGFloat,
         };
         CGDisplay::warp_mouse_cursor_position(point)
-            .expect("`CGWarpMouseCursorPosition` failed");
-        CGDisplay::associate_mouse_and_mouse_cursor_position(true)
-            .expect("`CGAssociateMouseAndMouseCursorPosition` failed");
+            .map_err(|err| ExternalError::Os(format!("`CGWarpMouseCursorPosition` failed: {:?}", err)))?;
+        // Warping suppresses mouse input for a short while, unless the mouse is (re)associated
+        // with the cursor, or has been dissociated from it. While the cursor is grabbed, it stays
+        // dissociated so that mouse-look isn't interrupted after recentering the cursor.
+        let state = &self.delegate.state;
+        let is_grabbed = state.cursor_state.get() == CursorState::Grab && !state.cursor_grab_suspended.get();
+        CGDisplay::associate_mouse_and_mouse_cursor_position(!is_grabbed)
+            .map_err(|err| {
+                ExternalError::Os(format!("`CGAssociateMouseAndMouseCursorPosition` failed: {:?}", err))
+            })?;
+        Ok(())
+    }
+
+    #[cfg(feature = "raw-window-handle")]
+    #[inline]
+    pub fn raw_window_handle(&self) -> RawWindowHandle {
+        RawWindowHandle::MacOS(MacOSHandle {
+            ns_window: *self.window as *mut _,
+            ns_view: *self.view as *mut _,
+            ..MacOSHandle::empty()
+        })
+    }
+
+    #[inline]
+    pub fn set_minimized(&self, minimized: bool) -> Result<(), ExternalError> {
+        unsafe {
+            if minimized {
+                let _: () = msg_send![*self.window, miniaturize:nil];
+            } else {
+                let _: () = msg_send![*self.window, deminiaturize:nil];
+            }
+        }
         Ok(())
     }
 
@@ -1046,21 +1322,27 @@ impl Window2 {
     #[inline]
     /// TODO: Right now set_fullscreen do not work on switching monitors
     /// in fullscreen mode
-    pub fn set_fullscreen(&self, monitor: Option<RootMonitorId>) {
+    pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) -> Result<(), ExternalError> {
+        // Switching the video mode isn't implemented.
+        if let Some(Fullscreen::Exclusive(..)) = fullscreen {
+            return Err(ExternalError::NotSupported(NotSupportedError::new()));
+        }
         let state = &self.delegate.state;
         let current = {
             let win_attribs = state.win_attribs.borrow_mut();
 
             let current = win_attribs.fullscreen.clone();
-            match (&current, monitor) {
+            match (&current, fullscreen) {
                 (&None, None) => {
-                    return;
+                    return Ok(());
                 }
-                (&Some(ref a), Some(ref b)) if a.inner != b.inner => {
-                    unimplemented!();
+                // `Fullscreen::Borderless(None)` means the monitor the window is already on.
+                (&Some(ref a), Some(ref b)) if b.get_monitor().is_some()
+                    && a.get_monitor().map(|m| &m.inner) != b.get_monitor().map(|m| &m.inner) => {
+                    return Err(ExternalError::NotSupported(NotSupportedError::new()));
                 }
                 (&Some(_), Some(_)) => {
-                    return;
+                    return Ok(());
                 }
                 _ => (),
             }
@@ -1083,6 +1365,17 @@ impl Window2 {
 
             self.window.toggleFullScreen_(nil);
         }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn get_current_fullscreen(&self) -> Option<Fullscreen> {
+        self.delegate.state.win_attribs.borrow().fullscreen.clone()
+    }
+
+    #[inline]
+    pub fn is_decorated(&self) -> bool {
+        self.delegate.state.win_attribs.borrow().decorations
     }
 
     #[inline]
@@ -1120,7 +1413,7 @@ impl Window2 {
     }
 
     #[inline]
-    pub fn set_always_on_top(&self, always_on_top: bool) {
+    pub fn set_always_on_top(&self, always_on_top: bool) -> Result<(), ExternalError> {
         unsafe {
             let level = if always_on_top {
                 ffi::NSWindowLevel::NSFloatingWindowLevel
@@ -1129,6 +1422,67 @@ impl Window2 {
             };
             let _: () = msg_send![*self.window, setLevel:level];
         }
+        self.delegate.state.win_attribs.borrow_mut().always_on_top = always_on_top;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn is_always_on_top(&self) -> bool {
+        self.delegate.state.win_attribs.borrow().always_on_top
+    }
+
+    #[inline]
+    pub fn restack(&self, relative_to: Id, order: StackOrder) -> Result<(), ExternalError> {
+        // These are `NSWindowAbove` and `NSWindowBelow`.
+        let mode: NSInteger = match order {
+            StackOrder::Above => 1,
+            StackOrder::Below => -1,
+        };
+        // The id may belong to a window that was closed since, so it's only dereferenced once it's
+        // found among the live windows.
+        let relative_to = self.delegate.state.shared.upgrade()
+            .and_then(|shared| {
+                shared.windows.lock().unwrap().iter()
+                    .filter_map(Weak::upgrade)
+                    .find(|window| window.id() == relative_to)
+                    .map(|window| *window.window)
+            })
+            .ok_or_else(|| ExternalError::Os("The window to restack relative to doesn't exist".to_string()))?;
+        unsafe {
+            let window_number: NSInteger = msg_send![relative_to, windowNumber];
+            let _: () = msg_send![*self.window, orderWindow:mode relativeTo:window_number];
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn set_content_protected(&self, protected: bool) -> Result<(), ExternalError> {
+        unsafe { set_sharing_type(*self.window, protected) };
+        Ok(())
+    }
+
+    #[inline]
+    pub fn set_background_color(&self, color: Option<(u8, u8, u8, u8)>) {
+        unsafe {
+            let color: id = match color {
+                Some((red, green, blue, alpha)) => {
+                    let channel = |value: u8| value as CGFloat / 255.0;
+                    let ns_color = Class::get("NSColor").unwrap();
+                    msg_send![ns_color, colorWithSRGBRed:channel(red)
+                                                  green:channel(green)
+                                                   blue:channel(blue)
+                                                  alpha:channel(alpha)]
+                },
+                None if self.delegate.state.win_attribs.borrow().transparent => {
+                    NSColor::clearColor(nil)
+                },
+                None => {
+                    let ns_color = Class::get("NSColor").unwrap();
+                    msg_send![ns_color, windowBackgroundColor]
+                },
+            };
+            (*self.window as id).setBackgroundColor_(color);
+        }
     }
 
     #[inline]
@@ -1143,8 +1497,68 @@ impl Window2 {
     }
 
     #[inline]
-    pub fn set_ime_spot(&self, logical_spot: LogicalPosition) {
+    pub fn set_ime_spot(&self, logical_spot: LogicalPosition) -> Result<(), ExternalError> {
         set_ime_spot(*self.view, *self.input_context, logical_spot.x, logical_spot.y);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn set_ime_allowed(&self, allowed: bool, commit: bool) -> Result<(), ExternalError> {
+        set_ime_allowed(*self.view, *self.input_context, allowed, commit);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn show_window_menu(&self, _position: LogicalPosition) -> Result<(), ExternalError> {
+        // macOS windows don't have a window menu
+        Err(ExternalError::NotSupported(NotSupportedError::new()))
+    }
+
+    pub fn start_drag(&self) -> Result<(), ExternalError> {
+        unsafe {
+            let pressed_buttons: NSUInteger = msg_send![Class::get("NSEvent").unwrap(), pressedMouseButtons];
+            if pressed_buttons == 0 {
+                return Ok(());
+            }
+            // `performWindowDragWithEvent:` was introduced in 10.11.
+            let responds: BOOL = msg_send![*self.window,
+                respondsToSelector:sel!(performWindowDragWithEvent:)];
+            if responds == NO {
+                return Ok(());
+            }
+            let event: id = msg_send![cocoa::appkit::NSApp(), currentEvent];
+            if event != nil {
+                let _: () = msg_send![*self.window, performWindowDragWithEvent:event];
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn start_resize(&self, _direction: ResizeDirection) -> Result<(), ExternalError> {
+        // AppKit has no way to start a resize on our behalf
+        Err(ExternalError::NotSupported(NotSupportedError::new()))
+    }
+
+    #[inline]
+    pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
+        unsafe {
+            let app = cocoa::appkit::NSApp();
+            // Attention is requested on behalf of the whole application, so only one request is
+            // kept track of.
+            let request_id = self.attention_request.swap(NO_ATTENTION_REQUEST, Ordering::SeqCst);
+            if request_id != NO_ATTENTION_REQUEST {
+                let () = msg_send![app, cancelUserAttentionRequest:request_id as NSInteger];
+            }
+            if let Some(request_type) = request_type {
+                let request_type: NSInteger = match request_type {
+                    UserAttentionType::Critical => 0, // NSCriticalRequest
+                    UserAttentionType::Informational => 10, // NSInformationalRequest
+                };
+                let request_id: NSInteger = msg_send![app, requestUserAttention:request_type];
+                self.attention_request.store(request_id as isize, Ordering::SeqCst);
+            }
+        }
     }
 
     #[inline]
@@ -1161,6 +1575,81 @@ pub fn get_window_id(window_cocoa_id: id) -> Id {
     Id(window_cocoa_id as *const objc::runtime::Object as usize)
 }
 
+// Moves the close, miniaturize and zoom buttons so that the close button sits `inset` away from
+// the top-left corner of the window.
+unsafe fn set_traffic_light_inset(window: id, inset: LogicalPosition) {
+    let close = window.standardWindowButton_(NSWindowButton::NSWindowCloseButton);
+    let miniaturize = window.standardWindowButton_(NSWindowButton::NSWindowMiniaturizeButton);
+    let zoom = window.standardWindowButton_(NSWindowButton::NSWindowZoomButton);
+    if close == nil || miniaturize == nil || zoom == nil {
+        // The window has no titlebar
+        return;
+    }
+
+    // The buttons are laid out at the bottom of the titlebar container, so the container is made
+    // taller to move them down.
+    let superview: id = msg_send![close, superview];
+    let container: id = msg_send![superview, superview];
+    let close_frame = NSView::frame(close);
+    let container_height = close_frame.size.height + inset.y as CGFloat;
+    let mut container_frame = NSView::frame(container);
+    container_frame.size.height = container_height;
+    container_frame.origin.y = NSWindow::frame(window).size.height - container_height;
+    let () = msg_send![container, setFrame:container_frame];
+
+    let spacing = NSView::frame(miniaturize).origin.x - close_frame.origin.x;
+    for (i, &button) in [close, miniaturize, zoom].iter().enumerate() {
+        let mut origin = NSView::frame(button).origin;
+        origin.x = inset.x as CGFloat + i as CGFloat * spacing;
+        let () = msg_send![button, setFrameOrigin:origin];
+    }
+}
+
+unsafe fn set_sharing_type(window: id, protected: bool) {
+    // `NSWindowSharingNone` and `NSWindowSharingReadOnly`, the default
+    let sharing_type: NSUInteger = if protected { 0 } else { 1 };
+    let () = msg_send![window, setSharingType:sharing_type];
+}
+
+// The `NSVisualEffectView` needs to be behind our view, so it becomes the content view and our
+// view is moved into it.
+unsafe fn add_visual_effect_view(window: id, view: id, material: BlurMaterial) {
+    // `NSVisualEffectBlendingModeBehindWindow`
+    const BLENDING_MODE_BEHIND_WINDOW: NSInteger = 0;
+    // `NSVisualEffectStateActive`, so that the blur doesn't go away when the window loses focus
+    const STATE_ACTIVE: NSInteger = 1;
+    // `NSViewWidthSizable | NSViewHeightSizable`
+    const AUTORESIZE_TO_FILL: NSUInteger = 2 | 16;
+
+    let mut frame = NSView::frame(view);
+    frame.origin = NSPoint::new(0.0, 0.0);
+    let effect_view: id = msg_send![cocoa::base::class("NSVisualEffectView"), alloc];
+    let effect_view: id = msg_send![effect_view, initWithFrame:frame];
+    if effect_view == nil {
+        // `NSVisualEffectView` needs macOS 10.10
+        return;
+    }
+    let () = msg_send![effect_view, setMaterial:material as NSInteger];
+    let () = msg_send![effect_view, setBlendingMode:BLENDING_MODE_BEHIND_WINDOW];
+    let () = msg_send![effect_view, setState:STATE_ACTIVE];
+    let () = msg_send![effect_view, setAutoresizingMask:AUTORESIZE_TO_FILL];
+
+    // The window retains its content view, and `Window2` holds its own reference to our view.
+    window.setContentView_(effect_view);
+    let () = msg_send![effect_view, release];
+    let () = msg_send![view, setFrame:frame];
+    let () = msg_send![view, setAutoresizingMask:AUTORESIZE_TO_FILL];
+    let () = msg_send![effect_view, addSubview:view];
+
+    // Our view needs a transparent layer for the blur to show through.
+    let () = msg_send![view, setWantsLayer:YES];
+    let layer: id = msg_send![view, layer];
+    let () = msg_send![layer, setOpaque:NO];
+    let () = msg_send![layer, setBackgroundColor:nil];
+
+    window.makeFirstResponder_(view);
+}
+
 unsafe fn nswindow_set_min_dimensions<V: NSWindow + Copy>(window: V, mut min_size: LogicalSize) {
     let mut current_rect = NSWindow::frame(window);
     let content_rect = NSWindow::contentRectForFrameRect_(window, NSWindow::frame(window));
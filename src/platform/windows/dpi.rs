@@ -1,7 +1,7 @@
 #![allow(non_snake_case, unused_unsafe)]
 
-use std::mem;
-use std::os::raw::c_void;
+use std::{mem, ptr};
+use std::os::raw::{c_int, c_void};
 use std::sync::{Once, ONCE_INIT};
 
 use winapi::shared::minwindef::{BOOL, UINT, FALSE};
@@ -16,6 +16,7 @@ use winapi::shared::winerror::S_OK;
 use winapi::um::libloaderapi::{GetProcAddress, LoadLibraryA};
 use winapi::um::shellscalingapi::{
     MDT_EFFECTIVE_DPI,
+    MDT_RAW_DPI,
     MONITOR_DPI_TYPE,
     PROCESS_DPI_AWARENESS,
     PROCESS_PER_MONITOR_DPI_AWARE,
@@ -41,6 +42,7 @@ type GetDpiForMonitor = unsafe extern "system" fn (
     dpi_y: *mut UINT,
 ) -> HRESULT;
 type EnableNonClientDpiScaling = unsafe extern "system" fn (hwnd: HWND) -> BOOL;
+type GetSystemMetricsForDpi = unsafe extern "system" fn (index: c_int, dpi: UINT) -> c_int;
 
 // Helper function to dynamically load function pointer.
 // `library` and `function` must be zero-terminated.
@@ -82,6 +84,10 @@ lazy_static! {
         "user32.dll",
         EnableNonClientDpiScaling
     );
+    static ref GET_SYSTEM_METRICS_FOR_DPI: Option<GetSystemMetricsForDpi> = get_function!(
+        "user32.dll",
+        GetSystemMetricsForDpi
+    );
 }
 
 pub fn become_dpi_aware(enable: bool) {
@@ -124,12 +130,21 @@ pub fn enable_non_client_dpi_scaling(hwnd: HWND) {
 }
 
 pub fn get_monitor_dpi(hmonitor: HMONITOR) -> Option<u32> {
+    get_dpi_for_monitor(hmonitor, MDT_EFFECTIVE_DPI)
+}
+
+// Unlike `get_monitor_dpi`, this is the density of the screen, regardless of the user's scaling settings.
+pub fn get_monitor_raw_dpi(hmonitor: HMONITOR) -> Option<u32> {
+    get_dpi_for_monitor(hmonitor, MDT_RAW_DPI)
+}
+
+fn get_dpi_for_monitor(hmonitor: HMONITOR, dpi_type: MONITOR_DPI_TYPE) -> Option<u32> {
     unsafe {
         if let Some(GetDpiForMonitor) = *GET_DPI_FOR_MONITOR {
             // We are on Windows 8.1 or later.
             let mut dpi_x = 0;
             let mut dpi_y = 0;
-            if GetDpiForMonitor(hmonitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) == S_OK {
+            if GetDpiForMonitor(hmonitor, dpi_type, &mut dpi_x, &mut dpi_y) == S_OK {
                 // MSDN says that "the values of *dpiX and *dpiY are identical. You only need to
                 // record one of the values to determine the DPI and respond appropriately".
                 // https://msdn.microsoft.com/en-us/library/windows/desktop/dn280510(v=vs.85).aspx
@@ -181,6 +196,29 @@ pub unsafe fn get_window_dpi(hwnd: HWND, hdc: HDC) -> u32 {
     }
 }
 
+// Returns the size of cursors on a monitor with the given DPI. `GetSystemMetrics` only gives the
+// size for the DPI of the primary monitor at the time the process started.
+pub fn get_cursor_size(dpi: u32) -> c_int {
+    unsafe {
+        if let Some(GetSystemMetricsForDpi) = *GET_SYSTEM_METRICS_FOR_DPI {
+            // We are on Windows 10 Anniversary Update (1607) or later.
+            return GetSystemMetricsForDpi(winuser::SM_CXCURSOR, dpi);
+        }
+        let size = winuser::GetSystemMetrics(winuser::SM_CXCURSOR);
+        let hdc = winuser::GetDC(ptr::null_mut());
+        if hdc.is_null() {
+            return size;
+        }
+        let system_dpi = GetDeviceCaps(hdc, LOGPIXELSX);
+        winuser::ReleaseDC(ptr::null_mut(), hdc);
+        if system_dpi <= 0 {
+            size
+        } else {
+            size * dpi as c_int / system_dpi
+        }
+    }
+}
+
 // Use this when you have both the HWND and HDC on hand (i.e. window methods)
 pub fn get_window_scale_factor(hwnd: HWND, hdc: HDC) -> f64 {
     dpi_to_scale_factor(unsafe { get_window_dpi(hwnd, hdc) })
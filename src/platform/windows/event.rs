@@ -1,7 +1,7 @@
 use std::char;
 use std::os::raw::c_int;
 
-use events::VirtualKeyCode;
+use events::{KeyLocation, LogicalKey, VirtualKeyCode};
 use events::ModifiersState;
 
 use winapi::shared::minwindef::{WPARAM, LPARAM, UINT};
@@ -243,11 +243,206 @@ pub fn handle_extended_keys(vkey: c_int, mut scancode: UINT, extended: bool) ->
     Some((vkey, scancode))
 }
 
-pub fn process_key_params(wparam: WPARAM, lparam: LPARAM) -> Option<(ScanCode, Option<VirtualKeyCode>)> {
+// `vkey` must have gone through `handle_extended_keys` first, so that the left and right
+// modifiers are told apart.
+pub fn vkey_to_location(vkey: c_int, extended: bool) -> KeyLocation {
+    match vkey {
+        winuser::VK_LSHIFT | winuser::VK_LCONTROL | winuser::VK_LMENU | winuser::VK_LWIN
+            => KeyLocation::Left,
+        winuser::VK_RSHIFT | winuser::VK_RCONTROL | winuser::VK_RMENU | winuser::VK_RWIN
+            => KeyLocation::Right,
+        // VK_NUMPAD0 to VK_NUMPAD9, then the numpad's operators
+        vkey if vkey >= winuser::VK_NUMPAD0 && vkey <= winuser::VK_DIVIDE => KeyLocation::Numpad,
+        // The numpad's Enter is the extended version of the main one.
+        winuser::VK_RETURN if extended => KeyLocation::Numpad,
+        // With Num Lock off, the numpad sends the same keys as the navigation cluster, except
+        // without the extended flag.
+        winuser::VK_INSERT | winuser::VK_DELETE | winuser::VK_HOME | winuser::VK_END
+        | winuser::VK_PRIOR | winuser::VK_NEXT | winuser::VK_LEFT | winuser::VK_RIGHT
+        | winuser::VK_UP | winuser::VK_DOWN | winuser::VK_CLEAR if !extended => KeyLocation::Numpad,
+        _ => KeyLocation::Standard,
+    }
+}
+
+// Maps a set 1 scancode to the key at the same position on a US QWERTY keyboard. `vkey` must have
+// gone through `handle_extended_keys` first, since Pause and Num Lock share a scancode.
+pub fn scancode_to_physical_key(scancode: UINT, extended: bool, vkey: c_int) -> Option<VirtualKeyCode> {
+    use events::VirtualKeyCode::*;
+    match vkey {
+        winuser::VK_PAUSE => return Some(Pause),
+        winuser::VK_NUMLOCK => return Some(Numlock),
+        _ => (),
+    }
+    Some(match (scancode, extended) {
+        (0x01, _) => Escape,
+        (0x02, _) => Key1,
+        (0x03, _) => Key2,
+        (0x04, _) => Key3,
+        (0x05, _) => Key4,
+        (0x06, _) => Key5,
+        (0x07, _) => Key6,
+        (0x08, _) => Key7,
+        (0x09, _) => Key8,
+        (0x0A, _) => Key9,
+        (0x0B, _) => Key0,
+        (0x0C, _) => Minus,
+        (0x0D, _) => Equals,
+        (0x0E, _) => Back,
+        (0x0F, _) => Tab,
+        (0x10, _) => Q,
+        (0x11, _) => W,
+        (0x12, _) => E,
+        (0x13, _) => R,
+        (0x14, _) => T,
+        (0x15, _) => Y,
+        (0x16, _) => U,
+        (0x17, _) => I,
+        (0x18, _) => O,
+        (0x19, _) => P,
+        (0x1A, _) => LBracket,
+        (0x1B, _) => RBracket,
+        (0x1C, false) => Return,
+        (0x1C, true) => NumpadEnter,
+        (0x1D, false) => LControl,
+        (0x1D, true) => RControl,
+        (0x1E, _) => A,
+        (0x1F, _) => S,
+        (0x20, _) => D,
+        (0x21, _) => F,
+        (0x22, _) => G,
+        (0x23, _) => H,
+        (0x24, _) => J,
+        (0x25, _) => K,
+        (0x26, _) => L,
+        (0x27, _) => Semicolon,
+        (0x28, _) => Apostrophe,
+        (0x29, _) => Grave,
+        (0x2A, _) => LShift,
+        (0x2B, _) => Backslash,
+        (0x2C, _) => Z,
+        (0x2D, _) => X,
+        (0x2E, _) => C,
+        (0x2F, _) => V,
+        (0x30, _) => B,
+        (0x31, _) => N,
+        (0x32, _) => M,
+        (0x33, _) => Comma,
+        (0x34, _) => Period,
+        (0x35, false) => Slash,
+        (0x35, true) => Divide,
+        (0x36, _) => RShift,
+        (0x37, false) => Multiply,
+        (0x37, true) => Snapshot,
+        (0x38, false) => LAlt,
+        (0x38, true) => RAlt,
+        (0x39, _) => Space,
+        (0x3A, _) => Capital,
+        (0x3B, _) => F1,
+        (0x3C, _) => F2,
+        (0x3D, _) => F3,
+        (0x3E, _) => F4,
+        (0x3F, _) => F5,
+        (0x40, _) => F6,
+        (0x41, _) => F7,
+        (0x42, _) => F8,
+        (0x43, _) => F9,
+        (0x44, _) => F10,
+        (0x46, _) => Scroll,
+        // The numpad and the navigation cluster share scancodes, the latter being extended.
+        (0x47, false) => Numpad7,
+        (0x47, true) => Home,
+        (0x48, false) => Numpad8,
+        (0x48, true) => Up,
+        (0x49, false) => Numpad9,
+        (0x49, true) => PageUp,
+        (0x4A, _) => Subtract,
+        (0x4B, false) => Numpad4,
+        (0x4B, true) => Left,
+        (0x4C, _) => Numpad5,
+        (0x4D, false) => Numpad6,
+        (0x4D, true) => Right,
+        (0x4E, _) => Add,
+        (0x4F, false) => Numpad1,
+        (0x4F, true) => End,
+        (0x50, false) => Numpad2,
+        (0x50, true) => Down,
+        (0x51, false) => Numpad3,
+        (0x51, true) => PageDown,
+        (0x52, false) => Numpad0,
+        (0x52, true) => Insert,
+        (0x53, false) => Decimal,
+        (0x53, true) => Delete,
+        (0x56, _) => OEM102,
+        (0x57, _) => F11,
+        (0x58, _) => F12,
+        (0x59, _) => NumpadEquals,
+        (0x5B, true) => LWin,
+        (0x5C, true) => RWin,
+        (0x5D, true) => Apps,
+        (0x64, _) => F13,
+        (0x65, _) => F14,
+        (0x66, _) => F15,
+        (0x70, _) => Kana,
+        (0x73, _) => AbntC1,
+        (0x79, _) => Convert,
+        (0x7B, _) => NoConvert,
+        (0x7D, _) => Yen,
+        (0x7E, _) => AbntC2,
+        _ => return None,
+    })
+}
+
+// Returns what the layout produces for the key. Shift and AltGr are taken into account, as they
+// are on the other platforms, but Ctrl isn't. Keys that don't produce text fall back to their name.
+pub fn vkey_to_logical_key(vkey: c_int, scancode: UINT) -> Option<LogicalKey> {
+    let mut state = [0u8; 256];
+    let mut buffer = [0u16; 8];
+    let written = unsafe {
+        if winuser::GetKeyboardState(state.as_mut_ptr()) == 0 {
+            0
+        } else {
+            // AltGr is reported as Ctrl+Alt, so Ctrl is only left out when Alt isn't held.
+            if state[winuser::VK_MENU as usize] & 0x80 == 0 {
+                state[winuser::VK_CONTROL as usize] = 0;
+                state[winuser::VK_LCONTROL as usize] = 0;
+                state[winuser::VK_RCONTROL as usize] = 0;
+            }
+            // The flag keeps `ToUnicode` from consuming a dead key the user has typed.
+            winuser::ToUnicode(
+                vkey as UINT,
+                scancode,
+                state.as_ptr(),
+                buffer.as_mut_ptr(),
+                buffer.len() as c_int,
+                1 << 2,
+            )
+        }
+    };
+    // Dead keys return a negative count, but their character is written out all the same.
+    let written = (written.abs() as usize).min(buffer.len());
+    char::decode_utf16(buffer[..written].iter().cloned())
+        .next()
+        .and_then(Result::ok)
+        .and_then(LogicalKey::from_char)
+        .or_else(|| vkey_to_winit_vkey(vkey).map(LogicalKey::Named))
+}
+
+pub fn process_key_params(
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> Option<(ScanCode, Option<VirtualKeyCode>, Option<LogicalKey>, Option<VirtualKeyCode>, KeyLocation)> {
     let scancode = ((lparam >> 16) & 0xff) as UINT;
     let extended = (lparam & 0x01000000) != 0;
     handle_extended_keys(wparam as _, scancode, extended)
-        .map(|(vkey, scancode)| (scancode, vkey_to_winit_vkey(vkey)))
+        .map(|(vkey, scancode)| {
+            (
+                scancode,
+                scancode_to_physical_key(scancode, extended, vkey),
+                vkey_to_logical_key(vkey, scancode),
+                vkey_to_winit_vkey(vkey),
+                vkey_to_location(vkey, extended),
+            )
+        })
 }
 
 // This is needed as windows doesn't properly distinguish
@@ -265,3 +460,24 @@ fn map_text_keys(win_virtual_key: i32) -> Option<VirtualKeyCode> {
         _ => None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::scancode_to_physical_key;
+    use events::VirtualKeyCode;
+    use winapi::um::winuser;
+
+    #[test]
+    fn scancodes_map_to_us_qwerty_positions() {
+        assert_eq!(scancode_to_physical_key(0x10, false, 0x51), Some(VirtualKeyCode::Q));
+        assert_eq!(scancode_to_physical_key(0x27, false, winuser::VK_OEM_1), Some(VirtualKeyCode::Semicolon));
+        // The extended flag tells the navigation cluster apart from the numpad.
+        assert_eq!(scancode_to_physical_key(0x48, false, winuser::VK_NUMPAD8), Some(VirtualKeyCode::Numpad8));
+        assert_eq!(scancode_to_physical_key(0x48, true, winuser::VK_UP), Some(VirtualKeyCode::Up));
+        assert_eq!(scancode_to_physical_key(0x1C, true, winuser::VK_RETURN), Some(VirtualKeyCode::NumpadEnter));
+        // Pause and Num Lock share a scancode.
+        assert_eq!(scancode_to_physical_key(0x45, false, winuser::VK_PAUSE), Some(VirtualKeyCode::Pause));
+        assert_eq!(scancode_to_physical_key(0x45, false, winuser::VK_NUMLOCK), Some(VirtualKeyCode::Numlock));
+        assert_eq!(scancode_to_physical_key(0x00, false, 0), None);
+    }
+}
@@ -13,12 +13,15 @@
 //! add a `WindowState` entry to a list of window to be used by the callback.
 
 use std::{mem, ptr, thread};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::ffi::OsString;
 use std::os::windows::ffi::OsStringExt;
 use std::os::windows::io::AsRawHandle;
 use std::sync::{Arc, Barrier, Condvar, mpsc, Mutex};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
 use winapi::ctypes::c_int;
 use winapi::shared::minwindef::{
@@ -33,9 +36,9 @@ use winapi::shared::minwindef::{
     UINT,
     WPARAM,
 };
-use winapi::shared::windef::{HWND, POINT, RECT};
+use winapi::shared::windef::{COLORREF, HDC, HHOOK, HWND, POINT, RECT};
 use winapi::shared::windowsx;
-use winapi::um::{winuser, shellapi, processthreadsapi};
+use winapi::um::{dwmapi, winuser, shellapi, processthreadsapi, libloaderapi, wingdi};
 use winapi::um::winnt::{LONG, LPCSTR, SHORT};
 
 use {
@@ -46,21 +49,33 @@ use {
     KeyboardInput,
     LogicalPosition,
     LogicalSize,
+    PhysicalPosition,
     PhysicalSize,
+    ScaleFactorPolicy,
     WindowEvent,
     WindowId as SuperWindowId,
 };
 use events::{DeviceEvent, Touch, TouchPhase};
 use platform::platform::{event, Cursor, WindowId, DEVICE_ID, wrap_device_id, util};
+use platform::platform::icon::AnimatedCursor;
 use platform::platform::dpi::{
+    BASE_DPI,
     become_dpi_aware,
     dpi_to_scale_factor,
     enable_non_client_dpi_scaling,
     get_hwnd_scale_factor,
 };
-use platform::platform::event::{handle_extended_keys, process_key_params, vkey_to_winit_vkey};
+use platform::platform::event::{
+    handle_extended_keys,
+    process_key_params,
+    scancode_to_physical_key,
+    vkey_to_logical_key,
+    vkey_to_location,
+    vkey_to_winit_vkey,
+};
 use platform::platform::raw_input::{get_raw_input_data, get_raw_mouse_button_state};
 use platform::platform::window::adjust_size;
+use timestamp::EventTimestamps;
 
 /// Contains saved window info for switching between fullscreen
 #[derive(Clone)]
@@ -95,6 +110,34 @@ pub struct WindowState {
     // This is different from the value in `SavedWindowInfo`! That one represents the DPI saved upon entering
     // fullscreen. This will always be the most recent DPI for the window.
     pub dpi_factor: f64,
+    pub scale_factor_policy: ScaleFactorPolicy,
+    /// Used by `WM_ERASEBKGND`.
+    pub background_color: Option<COLORREF>,
+    /// Transparent windows don't have their background erased by `WM_ERASEBKGND`.
+    pub transparent: bool,
+    /// Set when the cursor grab was released because the window lost focus, so that it's restored
+    /// by `WM_SETFOCUS`.
+    pub cursor_grab_suspended: bool,
+    /// Set by `confine_cursor_to_monitor`. This only applies while the window is focused, and a grab
+    /// takes precedence over it.
+    pub cursor_confined_to_monitor: bool,
+    /// The client position `set_cursor_position` moved the cursor to, until the next
+    /// `WM_MOUSEMOVE` is received.
+    pub pending_warp: Option<(i32, i32)>,
+    /// The position requested by `set_position`, until the window is next moved.
+    pub pending_move: Option<(i32, i32)>,
+    /// Set for undecorated windows created with `with_undecorated_shadow`, which keep their frame
+    /// styles but have their non-client area removed by `WM_NCCALCSIZE`.
+    pub frame_hidden: bool,
+    /// Set by `set_animated_cursor`, and shown instead of `cursor` by `WM_SETCURSOR`.
+    pub animated_cursor: Option<Arc<AnimatedCursor>>,
+    /// The frame of `animated_cursor` being shown, which is advanced by `WM_TIMER`.
+    pub animated_cursor_frame: usize,
+    /// When `false`, `WM_CLOSE` is left to `DefWindowProcW` instead of being sent as
+    /// `CloseRequested`.
+    pub handle_close_requests: bool,
+    /// Tracked by `WM_SIZE`, to send `Minimized` when it changes.
+    pub minimized: bool,
 }
 
 impl WindowState {
@@ -135,6 +178,14 @@ pub struct EventsLoop {
     // The mutex's value is `true` when it's blocked, and should be set to false when it's done
     // blocking. That's done by the parent thread when it receives a Resized event.
     win32_block_loop: Arc<(Mutex<bool>, Condvar)>,
+    // Set by the `EventsLoopProxy` when a wakeup message has been posted and not yet received, so
+    // that several calls to `wakeup` only produce a single `Awakened` event.
+    pending_wakeup: Arc<AtomicBool>,
+    // Copied to the `WindowState` of the windows created afterwards.
+    handle_close_requests: Cell<bool>,
+    // Shared with the background thread, which waits for the next composition after each batch of
+    // redraws while it's set.
+    redraw_vsync: Arc<AtomicBool>,
 }
 
 impl EventsLoop {
@@ -149,6 +200,8 @@ impl EventsLoop {
         let (tx, rx) = mpsc::channel();
         let win32_block_loop = Arc::new((Mutex::new(false), Condvar::new()));
         let win32_block_loop_child = win32_block_loop.clone();
+        let redraw_vsync = Arc::new(AtomicBool::new(false));
+        let redraw_vsync_child = redraw_vsync.clone();
 
         // Local barrier in order to block the `new()` function until the background thread has
         // an events queue.
@@ -161,7 +214,8 @@ impl EventsLoop {
                     sender: tx,
                     windows: HashMap::with_capacity(4),
                     win32_block_loop: win32_block_loop_child,
-                    mouse_buttons_down: 0
+                    mouse_buttons_down: 0,
+                    timestamps: EventTimestamps::new(),
                 });
             });
 
@@ -176,6 +230,8 @@ impl EventsLoop {
                 drop(barrier_clone);
 
                 let mut msg = mem::uninitialized();
+                // Whether `RedrawRequested` was sent since the last `RedrawEventsCleared`.
+                let mut painted = false;
 
                 loop {
                     if winuser::GetMessageW(&mut msg, ptr::null_mut(), 0, 0) == 0 {
@@ -198,6 +254,32 @@ impl EventsLoop {
                             winuser::DispatchMessageW(&msg);
                         }
                     }
+
+                    // `WM_PAINT` is only retrieved once no other message is queued, so the redraws
+                    // are done when there are no more windows to paint.
+                    if msg.message == winuser::WM_PAINT {
+                        painted = true;
+                    }
+                    if painted {
+                        let mut next_msg = mem::uninitialized();
+                        let paint_pending = winuser::PeekMessageW(
+                            &mut next_msg,
+                            ptr::null_mut(),
+                            winuser::WM_PAINT,
+                            winuser::WM_PAINT,
+                            winuser::PM_NOREMOVE,
+                        ) != 0;
+                        if !paint_pending {
+                            send_event(Event::RedrawEventsCleared);
+                            painted = false;
+                            if redraw_vsync_child.load(Ordering::Relaxed) {
+                                // The windows invalidated until the desktop is composited again
+                                // are painted together afterwards. This returns right away if
+                                // composition is disabled.
+                                dwmapi::DwmFlush();
+                            }
+                        }
+                    }
                 }
             }
         });
@@ -214,6 +296,9 @@ impl EventsLoop {
             thread_id,
             receiver: rx,
             win32_block_loop,
+            pending_wakeup: Default::default(),
+            handle_close_requests: Cell::new(true),
+            redraw_vsync,
         }
     }
 
@@ -227,6 +312,10 @@ impl EventsLoop {
             };
             let is_resize = match event {
                 Event::WindowEvent{ event: WindowEvent::Resized(..), .. } => true,
+                Event::Awakened => {
+                    self.pending_wakeup.store(false, Ordering::Relaxed);
+                    false
+                },
                 _ => false
             };
 
@@ -243,13 +332,43 @@ impl EventsLoop {
     pub fn run_forever<F>(&mut self, mut callback: F)
         where F: FnMut(Event) -> ControlFlow
     {
+        // The deadline returned with `WaitUntil` for the latest event.
+        let mut deadline = None;
         loop {
-            let event = match self.receiver.recv() {
-                Ok(e) => e,
-                Err(_) => return
+            let event = match deadline {
+                None => match self.receiver.recv() {
+                    Ok(e) => e,
+                    Err(_) => return
+                },
+                Some(wait_deadline) => {
+                    let now = Instant::now();
+                    // Check the deadline first, so that a steady stream of events can't delay it.
+                    let received = if wait_deadline > now {
+                        self.receiver.recv_timeout(wait_deadline - now)
+                    } else {
+                        Err(RecvTimeoutError::Timeout)
+                    };
+                    match received {
+                        Ok(e) => e,
+                        // Unlike a wakeup from the proxy, this doesn't go through the background
+                        // thread, so `pending_wakeup` must be left alone.
+                        Err(RecvTimeoutError::Timeout) => match callback(Event::Awakened) {
+                            ControlFlow::Break | ControlFlow::ExitWithCode(_) => break,
+                            flow => {
+                                deadline = flow.wait_deadline();
+                                continue;
+                            },
+                        },
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                },
             };
             let is_resize = match event {
                 Event::WindowEvent{ event: WindowEvent::Resized(..), .. } => true,
+                Event::Awakened => {
+                    self.pending_wakeup.store(false, Ordering::Relaxed);
+                    false
+                },
                 _ => false
             };
 
@@ -261,15 +380,34 @@ impl EventsLoop {
                 cvar.notify_all();
             }
             match flow {
-                ControlFlow::Continue => continue,
-                ControlFlow::Break => break,
+                ControlFlow::Break | ControlFlow::ExitWithCode(_) => break,
+                flow => deadline = flow.wait_deadline(),
             }
         }
     }
 
+    pub fn release_grabs(&self) {
+        unsafe { winuser::ClipCursor(ptr::null()) };
+        // The keyboard hook belongs to the background thread.
+        self.execute_in_thread(|_| unsafe { release_keyboard_grab() });
+    }
+
+    pub fn set_redraw_vsync(&self, enabled: bool) {
+        self.redraw_vsync.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn set_handle_close_requests(&self, handle: bool) {
+        self.handle_close_requests.set(handle);
+    }
+
+    pub fn handles_close_requests(&self) -> bool {
+        self.handle_close_requests.get()
+    }
+
     pub fn create_proxy(&self) -> EventsLoopProxy {
         EventsLoopProxy {
             thread_id: self.thread_id,
+            pending_wakeup: self.pending_wakeup.clone(),
         }
     }
 
@@ -299,14 +437,20 @@ impl Drop for EventsLoop {
 #[derive(Clone)]
 pub struct EventsLoopProxy {
     thread_id: DWORD,
+    pending_wakeup: Arc<AtomicBool>,
 }
 
 impl EventsLoopProxy {
     pub fn wakeup(&self) -> Result<(), EventsLoopClosed> {
+        if self.pending_wakeup.swap(true, Ordering::Relaxed) {
+            // A wakeup is already on its way, and will be delivered as a single `Awakened`.
+            return Ok(());
+        }
         unsafe {
             if winuser::PostThreadMessageA(self.thread_id, *WAKEUP_MSG_ID, 0, 0) != 0 {
                 Ok(())
             } else {
+                self.pending_wakeup.store(false, Ordering::Relaxed);
                 // https://msdn.microsoft.com/fr-fr/library/windows/desktop/ms644946(v=vs.85).aspx
                 // > If the function fails, the return value is zero. To get extended error
                 // > information, call GetLastError. GetLastError returns ERROR_INVALID_THREAD_ID
@@ -381,6 +525,9 @@ lazy_static! {
     };
 }
 
+// The ID of the timer that shows the frames of a window's animated cursor.
+pub const ANIMATED_CURSOR_TIMER_ID: WPARAM = 1;
+
 // There's no parameters passed to the callback function, so it needs to get its context stashed
 // in a thread-local variable.
 thread_local!(static CONTEXT_STASH: RefCell<Option<ThreadLocalData>> = RefCell::new(None));
@@ -388,7 +535,20 @@ struct ThreadLocalData {
     sender: mpsc::Sender<Event>,
     windows: HashMap<HWND, Arc<Mutex<WindowState>>>,
     win32_block_loop: Arc<(Mutex<bool>, Condvar)>,
-    mouse_buttons_down: u32
+    mouse_buttons_down: u32,
+    timestamps: EventTimestamps,
+}
+
+// Converts a time in milliseconds since the system started, as used by the messages.
+fn get_timestamp(time: DWORD) -> Duration {
+    CONTEXT_STASH.with(|context_stash| {
+        context_stash.borrow().as_ref().unwrap().timestamps.from_millis(time)
+    })
+}
+
+// Returns when the message being processed was posted.
+fn get_message_timestamp() -> Duration {
+    get_timestamp(unsafe { winuser::GetMessageTime() } as DWORD)
 }
 
 // Utility function that dispatches an event on the current thread.
@@ -400,6 +560,51 @@ fn send_event(event: Event) {
     });
 }
 
+/// Whether `WM_CLOSE` is sent as `CloseRequested`, see `WindowState::handle_close_requests`.
+fn handles_close_requests(window: HWND) -> bool {
+    CONTEXT_STASH.with(|context_stash| {
+        context_stash.borrow()
+            .as_ref()
+            .and_then(|cstash| cstash.windows.get(&window))
+            .map_or(true, |wstash| wstash.lock().unwrap().handle_close_requests)
+    })
+}
+
+/// Whether the frame of `window` is hidden by `WM_NCCALCSIZE`, see `WindowState::frame_hidden`.
+fn is_frame_hidden(window: HWND) -> bool {
+    CONTEXT_STASH.with(|context_stash| {
+        context_stash.borrow()
+            .as_ref()
+            .and_then(|cstash| cstash.windows.get(&window))
+            .map_or(false, |wstash| wstash.lock().unwrap().frame_hidden)
+    })
+}
+
+/// Shows the next frame of the animated cursor of `window`, and schedules the one after it. The
+/// timer is stopped once the animated cursor has been replaced.
+unsafe fn advance_animated_cursor(window: HWND, window_state: &mut WindowState) {
+    let delay = match window_state.animated_cursor {
+        Some(ref animated_cursor) => {
+            let frame = (window_state.animated_cursor_frame + 1) % animated_cursor.frames.len();
+            window_state.animated_cursor_frame = frame;
+            let (cursor, delay) = animated_cursor.frames[frame];
+            if window_state.mouse_in_window {
+                winuser::SetCursor(cursor);
+            }
+            Some(delay)
+        },
+        None => None,
+    };
+    match delay {
+        Some(delay) => {
+            winuser::SetTimer(window, ANIMATED_CURSOR_TIMER_ID, delay, None);
+        },
+        None => {
+            winuser::KillTimer(window, ANIMATED_CURSOR_TIMER_ID);
+        },
+    }
+}
+
 /// Capture mouse input, allowing `window` to receive mouse events when the cursor is outside of
 /// the window.
 unsafe fn capture_mouse(window: HWND) {
@@ -426,6 +631,107 @@ unsafe fn release_mouse() {
     });
 }
 
+/// Releases mouse input regardless of how many buttons are held, for when the system takes over the
+/// mouse and the button releases won't reach us.
+pub(crate) unsafe fn reset_mouse_capture() {
+    CONTEXT_STASH.with(|context_stash| {
+        if let Some(context_stash) = context_stash.borrow_mut().as_mut() {
+            context_stash.mouse_buttons_down = 0;
+        }
+    });
+    winuser::ReleaseCapture();
+}
+
+// The low-level keyboard hook installed by `Window::set_keyboard_grab`, along with the window that
+// grabbed the keyboard. Like the windows themselves, this lives in the background thread.
+thread_local!(static KEYBOARD_GRAB: Cell<Option<(HHOOK, HWND)>> = Cell::new(None));
+
+/// Starts or stops redirecting system keyboard shortcuts to `window`.
+///
+/// Only one window can grab the keyboard at a time; grabbing takes it over from any other window.
+/// Must be called from the background thread.
+pub unsafe fn set_keyboard_grab(window: HWND, grab: bool) -> Result<(), util::WinError> {
+    KEYBOARD_GRAB.with(|keyboard_grab| {
+        match (keyboard_grab.get(), grab) {
+            (Some((hook, _)), true) => keyboard_grab.set(Some((hook, window))),
+            (Some((hook, grab_window)), false) => if grab_window == window {
+                winuser::UnhookWindowsHookEx(hook);
+                keyboard_grab.set(None);
+            },
+            (None, true) => {
+                let hook = winuser::SetWindowsHookExW(
+                    winuser::WH_KEYBOARD_LL,
+                    Some(keyboard_hook_callback),
+                    libloaderapi::GetModuleHandleW(ptr::null()),
+                    0,
+                );
+                if hook.is_null() {
+                    return Err(util::WinError::from_last_error());
+                }
+                keyboard_grab.set(Some((hook, window)));
+            },
+            (None, false) => (),
+        }
+        Ok(())
+    })
+}
+
+/// Stops redirecting system keyboard shortcuts, whichever window grabbed the keyboard.
+///
+/// Must be called from the background thread.
+unsafe fn release_keyboard_grab() {
+    KEYBOARD_GRAB.with(|keyboard_grab| {
+        if let Some((hook, _)) = keyboard_grab.take() {
+            winuser::UnhookWindowsHookEx(hook);
+        }
+    })
+}
+
+// Whether the system would handle this key press itself instead of sending it to the window.
+unsafe fn is_system_shortcut(info: &winuser::KBDLLHOOKSTRUCT) -> bool {
+    let alt_down = info.flags & winuser::LLKHF_ALTDOWN != 0;
+    let ctrl_down = winuser::GetAsyncKeyState(winuser::VK_CONTROL) as u16 & 0x8000 != 0;
+    match info.vkCode as c_int {
+        winuser::VK_LWIN | winuser::VK_RWIN | winuser::VK_APPS => true,
+        winuser::VK_TAB => alt_down,
+        winuser::VK_ESCAPE => alt_down || ctrl_down,
+        _ => false,
+    }
+}
+
+// Called for every key event on the system while a window has grabbed the keyboard. System
+// shortcuts are swallowed and posted to the grabbing window instead, but only while it has focus,
+// so the grab is effectively released whenever the window loses focus.
+unsafe extern "system" fn keyboard_hook_callback(
+    code: c_int,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if code == winuser::HC_ACTION {
+        let grab_window = KEYBOARD_GRAB.with(|keyboard_grab| keyboard_grab.get())
+            .map(|(_, window)| window);
+        if let Some(window) = grab_window {
+            let info = &*(lparam as *const winuser::KBDLLHOOKSTRUCT);
+            if winuser::GetForegroundWindow() == window && is_system_shortcut(info) {
+                // Rebuild the LPARAM of a regular keyboard message (see `process_key_params`).
+                let mut key_lparam: DWORD = 1 | ((info.scanCode & 0xff) << 16);
+                if info.flags & winuser::LLKHF_EXTENDED != 0 {
+                    key_lparam |= 1 << 24;
+                }
+                if info.flags & winuser::LLKHF_ALTDOWN != 0 {
+                    key_lparam |= 1 << 29;
+                }
+                if info.flags & winuser::LLKHF_UP != 0 {
+                    key_lparam |= (1 << 30) | (1 << 31);
+                }
+                winuser::PostMessageW(window, wparam as UINT, info.vkCode as WPARAM, key_lparam as LPARAM);
+                return 1;
+            }
+        }
+    }
+    winuser::CallNextHookEx(ptr::null_mut(), code, wparam, lparam)
+}
+
 /// Any window whose callback is configured to this function will have its events propagated
 /// through the events loop of the thread the window was created in.
 //
@@ -445,6 +751,12 @@ pub unsafe extern "system" fn callback(
             winuser::DefWindowProcW(window, msg, wparam, lparam)
         },
 
+        // The application handles the request itself, usually by subclassing the window, and
+        // otherwise the window is destroyed.
+        winuser::WM_CLOSE if !handles_close_requests(window) => {
+            winuser::DefWindowProcW(window, msg, wparam, lparam)
+        },
+
         winuser::WM_CLOSE => {
             use events::WindowEvent::CloseRequested;
             send_event(Event::WindowEvent {
@@ -460,6 +772,7 @@ pub unsafe extern "system" fn callback(
                 let mut context_stash = context_stash.borrow_mut();
                 context_stash.as_mut().unwrap().windows.remove(&window);
             });
+            let _ = set_keyboard_grab(window, false);
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
                 event: Destroyed
@@ -467,11 +780,85 @@ pub unsafe extern "system" fn callback(
             0
         },
 
+        winuser::WM_NCCALCSIZE if wparam != 0 && is_frame_hidden(window) => {
+            // Maximized windows extend past the monitor by the width of their frame, which would
+            // otherwise be cut off.
+            if winuser::IsZoomed(window) != 0 {
+                let params = &mut *(lparam as *mut winuser::NCCALCSIZE_PARAMS);
+                let (border_x, border_y) = util::get_frame_thickness();
+                params.rgrc[0].left += border_x;
+                params.rgrc[0].top += border_y;
+                params.rgrc[0].right -= border_x;
+                params.rgrc[0].bottom -= border_y;
+            }
+            // Returning 0 without changing the proposed rectangle makes the whole window the client
+            // area.
+            0
+        },
+
+        winuser::WM_NCHITTEST if is_frame_hidden(window) => {
+            // The resize border is part of the client area now, so we have to report it ourselves.
+            let style = winuser::GetWindowLongW(window, winuser::GWL_STYLE) as DWORD;
+            let resizable = style & winuser::WS_SIZEBOX != 0 && winuser::IsZoomed(window) == 0;
+            if let (true, Some(rect)) = (resizable, util::get_window_rect(window)) {
+                let x = windowsx::GET_X_LPARAM(lparam);
+                let y = windowsx::GET_Y_LPARAM(lparam);
+                let (border_x, border_y) = util::get_frame_thickness();
+                let left = x < rect.left + border_x;
+                let right = x >= rect.right - border_x;
+                let top = y < rect.top + border_y;
+                let bottom = y >= rect.bottom - border_y;
+                let hit = match (left, right, top, bottom) {
+                    (true, _, true, _) => winuser::HTTOPLEFT,
+                    (_, true, true, _) => winuser::HTTOPRIGHT,
+                    (true, _, _, true) => winuser::HTBOTTOMLEFT,
+                    (_, true, _, true) => winuser::HTBOTTOMRIGHT,
+                    (true, _, _, _) => winuser::HTLEFT,
+                    (_, true, _, _) => winuser::HTRIGHT,
+                    (_, _, true, _) => winuser::HTTOP,
+                    (_, _, _, true) => winuser::HTBOTTOM,
+                    _ => winuser::HTCLIENT,
+                };
+                if hit != winuser::HTCLIENT {
+                    return hit as LRESULT;
+                }
+            }
+            winuser::DefWindowProcW(window, msg, wparam, lparam)
+        },
+
+        winuser::WM_ERASEBKGND => {
+            let (background_color, transparent) = CONTEXT_STASH.with(|context_stash| {
+                context_stash.borrow()
+                    .as_ref()
+                    .and_then(|cstash| cstash.windows.get(&window))
+                    .map(|wstash| {
+                        let wstash = wstash.lock().unwrap();
+                        (wstash.background_color, wstash.transparent)
+                    })
+                    .unwrap_or((None, false))
+            });
+            if transparent && background_color.is_none() {
+                // Claim that the background was erased without painting anything, so the window
+                // doesn't flash before the first frame is drawn.
+                1
+            } else if let Some(background_color) = background_color {
+                let mut rect: RECT = mem::uninitialized();
+                winuser::GetClientRect(window, &mut rect);
+                let brush = wingdi::CreateSolidBrush(background_color);
+                winuser::FillRect(wparam as HDC, &rect, brush);
+                wingdi::DeleteObject(brush as _);
+                // The background has been erased
+                1
+            } else {
+                winuser::DefWindowProcW(window, msg, wparam, lparam)
+            }
+        },
+
         winuser::WM_PAINT => {
-            use events::WindowEvent::Refresh;
+            use events::WindowEvent::RedrawRequested;
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
-                event: Refresh,
+                event: RedrawRequested,
             });
             winuser::DefWindowProcW(window, msg, wparam, lparam)
         },
@@ -482,11 +869,23 @@ pub unsafe extern "system" fn callback(
 
             let windowpos = lparam as *const winuser::WINDOWPOS;
             if (*windowpos).flags & winuser::SWP_NOMOVE != winuser::SWP_NOMOVE {
+                let position = ((*windowpos).x, (*windowpos).y);
+                // The move requested by `set_position` isn't reported.
+                let requested = CONTEXT_STASH.with(|context_stash| {
+                    let mut context_stash = context_stash.borrow_mut();
+                    if let Some(context_stash) = context_stash.as_mut() {
+                        if let Some(w) = context_stash.windows.get_mut(&window) {
+                            let mut w = w.lock().unwrap();
+                            return w.pending_move.take() == Some(position);
+                        }
+                    }
+                    false
+                });
+                if requested {
+                    return winuser::DefWindowProcW(window, msg, wparam, lparam);
+                }
                 let dpi_factor = get_hwnd_scale_factor(window);
-                let logical_position = LogicalPosition::from_physical(
-                    ((*windowpos).x, (*windowpos).y),
-                    dpi_factor,
-                );
+                let logical_position = LogicalPosition::from_physical(position, dpi_factor);
                 send_event(Event::WindowEvent {
                     window_id: SuperWindowId(WindowId(window)),
                     event: Moved(logical_position),
@@ -508,6 +907,18 @@ pub unsafe extern "system" fn callback(
                 let mut context_stash = context_stash.borrow_mut();
                 let cstash = context_stash.as_mut().unwrap();
 
+                if let Some(wstash) = cstash.windows.get(&window) {
+                    let minimized = wparam == winuser::SIZE_MINIMIZED;
+                    let mut window_state = wstash.lock().unwrap();
+                    if window_state.minimized != minimized {
+                        window_state.minimized = minimized;
+                        cstash.sender.send(Event::WindowEvent {
+                            window_id: SuperWindowId(WindowId(window)),
+                            event: WindowEvent::Minimized(minimized),
+                        }).ok();
+                    }
+                }
+
                 let dpi_factor = get_hwnd_scale_factor(window);
                 let logical_size = LogicalSize::from_physical((w, h), dpi_factor);
                 let event = Event::WindowEvent {
@@ -516,8 +927,8 @@ pub unsafe extern "system" fn callback(
                 };
 
                 // If this window has been inserted into the window map, the resize event happened
-                // during the event loop. If it hasn't, the event happened on window creation and
-                // should be ignored.
+                // during the event loop. If it hasn't, the event happened on window creation, and
+                // is the initial `Resized`, which the window's thread isn't waiting for.
                 if cstash.windows.get(&window).is_some() {
                     let (ref mutex, ref cvar) = *cstash.win32_block_loop;
                     let mut block_thread = mutex.lock().unwrap();
@@ -588,14 +999,40 @@ pub unsafe extern "system" fn callback(
                 });
             }
 
-            let x = windowsx::GET_X_LPARAM(lparam) as f64;
-            let y = windowsx::GET_Y_LPARAM(lparam) as f64;
+            let x = windowsx::GET_X_LPARAM(lparam);
+            let y = windowsx::GET_Y_LPARAM(lparam);
+
+            // `WM_MOUSEMOVE` is generated from the current cursor position when it's retrieved, so
+            // the first one after `SetCursorPos` is either at the position we moved the cursor to,
+            // or the result of the user moving the mouse afterwards. The former isn't reported,
+            // since it would look like a large movement of the mouse.
+            let warped = CONTEXT_STASH.with(|context_stash| {
+                let mut context_stash = context_stash.borrow_mut();
+                if let Some(context_stash) = context_stash.as_mut() {
+                    if let Some(w) = context_stash.windows.get_mut(&window) {
+                        let mut w = w.lock().unwrap();
+                        return w.pending_warp.take() == Some((x, y));
+                    }
+                }
+                false
+            });
+            if warped {
+                return 0;
+            }
+
             let dpi_factor = get_hwnd_scale_factor(window);
-            let position = LogicalPosition::from_physical((x, y), dpi_factor);
+            let physical_position = PhysicalPosition::new(x as f64, y as f64);
+            let position = physical_position.to_logical(dpi_factor);
 
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
-                event: CursorMoved { device_id: DEVICE_ID, position, modifiers: event::get_key_mods() },
+                event: CursorMoved {
+                    device_id: DEVICE_ID,
+                    position,
+                    physical_position,
+                    modifiers: event::get_key_mods(),
+                    timestamp: get_message_timestamp(),
+                },
             });
 
             0
@@ -638,7 +1075,7 @@ pub unsafe extern "system" fn callback(
 
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
-                event: WindowEvent::MouseWheel { device_id: DEVICE_ID, delta: LineDelta(0.0, value), phase: TouchPhase::Moved, modifiers: event::get_key_mods() },
+                event: WindowEvent::MouseWheel { device_id: DEVICE_ID, delta: LineDelta(0.0, value), phase: TouchPhase::Moved, modifiers: event::get_key_mods(), timestamp: get_message_timestamp() },
             });
 
             0
@@ -650,7 +1087,7 @@ pub unsafe extern "system" fn callback(
             if msg == winuser::WM_SYSKEYDOWN && wparam as i32 == winuser::VK_F4 {
                 winuser::DefWindowProcW(window, msg, wparam, lparam)
             } else {
-                if let Some((scancode, vkey)) = process_key_params(wparam, lparam) {
+                if let Some((scancode, physical_key, logical_key, vkey, location)) = process_key_params(wparam, lparam) {
                     send_event(Event::WindowEvent {
                         window_id: SuperWindowId(WindowId(window)),
                         event: WindowEvent::KeyboardInput {
@@ -658,9 +1095,13 @@ pub unsafe extern "system" fn callback(
                             input: KeyboardInput {
                                 state: Pressed,
                                 scancode: scancode,
+                                physical_key,
+                                logical_key,
                                 virtual_keycode: vkey,
+                                location,
                                 modifiers: event::get_key_mods(),
-                            }
+                            },
+                            timestamp: get_message_timestamp(),
                         }
                     });
                     // Windows doesn't emit a delete character by default, but in order to make it
@@ -678,7 +1119,7 @@ pub unsafe extern "system" fn callback(
 
         winuser::WM_KEYUP | winuser::WM_SYSKEYUP => {
             use events::ElementState::Released;
-            if let Some((scancode, vkey)) = process_key_params(wparam, lparam) {
+            if let Some((scancode, physical_key, logical_key, vkey, location)) = process_key_params(wparam, lparam) {
                 send_event(Event::WindowEvent {
                     window_id: SuperWindowId(WindowId(window)),
                     event: WindowEvent::KeyboardInput {
@@ -686,9 +1127,13 @@ pub unsafe extern "system" fn callback(
                         input: KeyboardInput {
                             state: Released,
                             scancode: scancode,
+                            physical_key,
+                            logical_key,
                             virtual_keycode: vkey,
+                            location,
                             modifiers: event::get_key_mods(),
                         },
+                        timestamp: get_message_timestamp(),
                     }
                 });
             }
@@ -704,7 +1149,7 @@ pub unsafe extern "system" fn callback(
 
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
-                event: MouseInput { device_id: DEVICE_ID, state: Pressed, button: Left, modifiers: event::get_key_mods() }
+                event: MouseInput { device_id: DEVICE_ID, state: Pressed, button: Left, modifiers: event::get_key_mods(), timestamp: get_message_timestamp() }
             });
             0
         },
@@ -718,7 +1163,7 @@ pub unsafe extern "system" fn callback(
 
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
-                event: MouseInput { device_id: DEVICE_ID, state: Released, button: Left, modifiers: event::get_key_mods() }
+                event: MouseInput { device_id: DEVICE_ID, state: Released, button: Left, modifiers: event::get_key_mods(), timestamp: get_message_timestamp() }
             });
             0
         },
@@ -732,7 +1177,7 @@ pub unsafe extern "system" fn callback(
 
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
-                event: MouseInput { device_id: DEVICE_ID, state: Pressed, button: Right, modifiers: event::get_key_mods() }
+                event: MouseInput { device_id: DEVICE_ID, state: Pressed, button: Right, modifiers: event::get_key_mods(), timestamp: get_message_timestamp() }
             });
             0
         },
@@ -746,7 +1191,7 @@ pub unsafe extern "system" fn callback(
 
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
-                event: MouseInput { device_id: DEVICE_ID, state: Released, button: Right, modifiers: event::get_key_mods() }
+                event: MouseInput { device_id: DEVICE_ID, state: Released, button: Right, modifiers: event::get_key_mods(), timestamp: get_message_timestamp() }
             });
             0
         },
@@ -760,7 +1205,7 @@ pub unsafe extern "system" fn callback(
 
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
-                event: MouseInput { device_id: DEVICE_ID, state: Pressed, button: Middle, modifiers: event::get_key_mods() }
+                event: MouseInput { device_id: DEVICE_ID, state: Pressed, button: Middle, modifiers: event::get_key_mods(), timestamp: get_message_timestamp() }
             });
             0
         },
@@ -774,7 +1219,7 @@ pub unsafe extern "system" fn callback(
 
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
-                event: MouseInput { device_id: DEVICE_ID, state: Released, button: Middle, modifiers: event::get_key_mods() }
+                event: MouseInput { device_id: DEVICE_ID, state: Released, button: Middle, modifiers: event::get_key_mods(), timestamp: get_message_timestamp() }
             });
             0
         },
@@ -789,7 +1234,7 @@ pub unsafe extern "system" fn callback(
 
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
-                event: MouseInput { device_id: DEVICE_ID, state: Pressed, button: Other(xbutton as u8), modifiers: event::get_key_mods() }
+                event: MouseInput { device_id: DEVICE_ID, state: Pressed, button: Other(xbutton as u8), modifiers: event::get_key_mods(), timestamp: get_message_timestamp() }
             });
             0
         },
@@ -804,7 +1249,7 @@ pub unsafe extern "system" fn callback(
 
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
-                event: MouseInput { device_id: DEVICE_ID, state: Released, button: Other(xbutton as u8), modifiers: event::get_key_mods() }
+                event: MouseInput { device_id: DEVICE_ID, state: Released, button: Other(xbutton as u8), modifiers: event::get_key_mods(), timestamp: get_message_timestamp() }
             });
             0
         },
@@ -910,13 +1355,17 @@ pub unsafe extern "system" fn callback(
                             extended,
                         ) {
                             let virtual_keycode = vkey_to_winit_vkey(vkey);
+                            let location = vkey_to_location(vkey, extended);
 
                             send_event(Event::DeviceEvent {
                                 device_id,
                                 event: Key(KeyboardInput {
                                     scancode,
                                     state,
+                                    physical_key: scancode_to_physical_key(scancode, extended, vkey),
+                                    logical_key: vkey_to_logical_key(vkey, scancode),
                                     virtual_keycode,
+                                    location,
                                     modifiers: event::get_key_mods(),
                                 }),
                             });
@@ -960,6 +1409,11 @@ pub unsafe extern "system" fn callback(
                             location,
                             id: input.dwID as u64,
                             device_id: DEVICE_ID,
+                            timestamp: if input.dwTime != 0 {
+                                get_timestamp(input.dwTime)
+                            } else {
+                                get_message_timestamp()
+                            },
                         })
                     });
                 }
@@ -970,6 +1424,19 @@ pub unsafe extern "system" fn callback(
 
         winuser::WM_SETFOCUS => {
             use events::WindowEvent::{Focused, CursorMoved};
+            CONTEXT_STASH.with(|context_stash| {
+                if let Some(cstash) = context_stash.borrow().as_ref() {
+                    if let Some(wstash) = cstash.windows.get(&window) {
+                        let mut window_state = wstash.lock().unwrap();
+                        if window_state.cursor_grab_suspended {
+                            window_state.cursor_grab_suspended = false;
+                            let _ = util::clip_cursor_to_client_area(window);
+                        } else if window_state.cursor_confined_to_monitor {
+                            let _ = util::clip_cursor_to_monitor(window);
+                        }
+                    }
+                }
+            });
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
                 event: Focused(true)
@@ -978,11 +1445,18 @@ pub unsafe extern "system" fn callback(
             let x = windowsx::GET_X_LPARAM(lparam) as f64;
             let y = windowsx::GET_Y_LPARAM(lparam) as f64;
             let dpi_factor = get_hwnd_scale_factor(window);
-            let position = LogicalPosition::from_physical((x, y), dpi_factor);
+            let physical_position = PhysicalPosition::new(x, y);
+            let position = physical_position.to_logical(dpi_factor);
 
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
-                event: CursorMoved { device_id: DEVICE_ID, position, modifiers: event::get_key_mods() },
+                event: CursorMoved {
+                    device_id: DEVICE_ID,
+                    position,
+                    physical_position,
+                    modifiers: event::get_key_mods(),
+                    timestamp: get_message_timestamp(),
+                },
             });
 
             0
@@ -990,6 +1464,21 @@ pub unsafe extern "system" fn callback(
 
         winuser::WM_KILLFOCUS => {
             use events::WindowEvent::Focused;
+            // The grab is released before the application is told, so that it doesn't end up
+            // fighting us over it.
+            CONTEXT_STASH.with(|context_stash| {
+                if let Some(cstash) = context_stash.borrow().as_ref() {
+                    if let Some(wstash) = cstash.windows.get(&window) {
+                        let mut window_state = wstash.lock().unwrap();
+                        if window_state.cursor_state == CursorState::Grab {
+                            winuser::ClipCursor(ptr::null());
+                            window_state.cursor_grab_suspended = true;
+                        } else if window_state.cursor_confined_to_monitor {
+                            winuser::ClipCursor(ptr::null());
+                        }
+                    }
+                }
+            });
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
                 event: Focused(false)
@@ -1006,10 +1495,16 @@ pub unsafe extern "system" fn callback(
                     .map(|window_state_mutex| {
                         let window_state = window_state_mutex.lock().unwrap();
                         if window_state.mouse_in_window {
-                            let cursor = winuser::LoadCursorW(
-                                ptr::null_mut(),
-                                window_state.cursor.0,
-                            );
+                            let cursor = match window_state.animated_cursor {
+                                Some(ref animated_cursor) => {
+                                    animated_cursor.frames[window_state.animated_cursor_frame].0
+                                },
+                                None => {
+                                    // The cursor follows the DPI of the monitor the window is on.
+                                    let dpi = (window_state.dpi_factor * BASE_DPI as f64).round() as u32;
+                                    util::load_cursor(window_state.cursor.0, dpi)
+                                },
+                            };
                             winuser::SetCursor(cursor);
                             false
                         } else {
@@ -1026,6 +1521,19 @@ pub unsafe extern "system" fn callback(
             }
         },
 
+        winuser::WM_TIMER if wparam == ANIMATED_CURSOR_TIMER_ID => {
+            CONTEXT_STASH.with(|context_stash| {
+                let context_stash = context_stash.borrow();
+                let window_state_mutex = context_stash
+                    .as_ref()
+                    .and_then(|cstash| cstash.windows.get(&window));
+                if let Some(window_state_mutex) = window_state_mutex {
+                    advance_animated_cursor(window, &mut window_state_mutex.lock().unwrap());
+                }
+            });
+            0
+        },
+
         winuser::WM_DROPFILES => {
             use events::WindowEvent::DroppedFile;
 
@@ -1061,12 +1569,13 @@ pub unsafe extern "system" fn callback(
                         if window_state.min_size.is_some() || window_state.max_size.is_some() {
                             let style = winuser::GetWindowLongA(window, winuser::GWL_STYLE) as DWORD;
                             let ex_style = winuser::GetWindowLongA(window, winuser::GWL_EXSTYLE) as DWORD;
+                            let frame_hidden = window_state.frame_hidden;
                             if let Some(min_size) = window_state.min_size {
-                                let (width, height) = adjust_size(min_size, style, ex_style);
+                                let (width, height) = adjust_size(min_size, style, ex_style, frame_hidden);
                                 (*mmi).ptMinTrackSize = POINT { x: width as i32, y: height as i32 };
                             }
                             if let Some(max_size) = window_state.max_size {
-                                let (width, height) = adjust_size(max_size, style, ex_style);
+                                let (width, height) = adjust_size(max_size, style, ex_style, frame_hidden);
                                 (*mmi).ptMaxTrackSize = POINT { x: width as i32, y: height as i32 };
                             }
                         }
@@ -1089,7 +1598,7 @@ pub unsafe extern "system" fn callback(
             let new_dpi_x = u32::from(LOWORD(wparam as DWORD));
             let new_dpi_factor = dpi_to_scale_factor(new_dpi_x);
 
-            let suppress_resize = CONTEXT_STASH.with(|context_stash| {
+            let (suppress_resize, scale_factor_policy) = CONTEXT_STASH.with(|context_stash| {
                 context_stash
                     .borrow()
                     .as_ref()
@@ -1113,16 +1622,21 @@ pub unsafe extern "system" fn callback(
                             window_state.update_min_max(old_dpi_factor, new_dpi_factor);
                         }
                         window_state.dpi_factor = new_dpi_factor;
-                        suppress_resize
+                        (suppress_resize, window_state.scale_factor_policy)
                     })
-                    .unwrap_or(false)
+                    .unwrap_or((false, ScaleFactorPolicy::default()))
             });
 
             // This prevents us from re-applying DPI adjustment to the restored size after exiting
             // fullscreen (the restored size is already DPI adjusted).
             if !suppress_resize {
-                // Resize window to the size suggested by Windows.
+                // Windows suggests a rect that preserves the logical size, so we only use its
+                // position when preserving the physical size.
                 let rect = &*(lparam as *const RECT);
+                let flags = match scale_factor_policy {
+                    ScaleFactorPolicy::PreserveLogical => 0,
+                    ScaleFactorPolicy::PreservePhysical => winuser::SWP_NOSIZE,
+                };
                 winuser::SetWindowPos(
                     window,
                     ptr::null_mut(),
@@ -1130,7 +1644,7 @@ pub unsafe extern "system" fn callback(
                     rect.top,
                     rect.right - rect.left,
                     rect.bottom - rect.top,
-                    winuser::SWP_NOZORDER | winuser::SWP_NOACTIVATE,
+                    winuser::SWP_NOZORDER | winuser::SWP_NOACTIVATE | flags,
                 );
             }
 
@@ -1170,7 +1684,9 @@ pub unsafe extern "system" fn callback(
                 let dw_style = winuser::GetWindowLongA(window, winuser::GWL_STYLE) as DWORD;
                 let b_menu = !winuser::GetMenu(window).is_null() as BOOL;
                 let dw_style_ex = winuser::GetWindowLongA(window, winuser::GWL_EXSTYLE) as DWORD;
-                winuser::AdjustWindowRectEx(&mut rect, dw_style, b_menu, dw_style_ex);
+                if !is_frame_hidden(window) {
+                    winuser::AdjustWindowRectEx(&mut rect, dw_style, b_menu, dw_style_ex);
+                }
                 let outer_x = (rect.right - rect.left).abs() as c_int;
                 let outer_y = (rect.top - rect.bottom).abs() as c_int;
                 winuser::SetWindowPos(
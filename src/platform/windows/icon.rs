@@ -1,13 +1,15 @@
 use std::{self, mem, ptr};
 use std::os::windows::ffi::OsStrExt;
 use std::path::Path;
+use std::time::Duration;
 
 use winapi::ctypes::{c_int, wchar_t};
-use winapi::shared::minwindef::{BYTE, LPARAM, WPARAM};
-use winapi::shared::windef::{HICON, HWND};
-use winapi::um::winuser;
+use winapi::shared::minwindef::{BYTE, FALSE, LPARAM, UINT, WPARAM};
+use winapi::shared::windef::{HCURSOR, HICON, HWND};
+use winapi::um::{wingdi, winuser};
 
-use {Pixel, PIXEL_SIZE, Icon};
+use {PIXEL_SIZE, Icon};
+use icon::Pixel;
 use platform::platform::util;
 
 impl Pixel {
@@ -82,6 +84,29 @@ impl WinIcon {
         }
     }
 
+    // Creates a cursor showing the icon, with its click point at `hotspot`.
+    pub fn to_cursor(&self, hotspot: (u32, u32)) -> Result<HCURSOR, util::WinError> {
+        unsafe {
+            let mut icon_info: winuser::ICONINFO = mem::uninitialized();
+            if winuser::GetIconInfo(self.handle, &mut icon_info) == 0 {
+                return Err(util::WinError::from_last_error());
+            }
+            icon_info.fIcon = FALSE;
+            icon_info.xHotspot = hotspot.0;
+            icon_info.yHotspot = hotspot.1;
+            let handle = winuser::CreateIconIndirect(&mut icon_info) as HCURSOR;
+            let result = if !handle.is_null() {
+                Ok(handle)
+            } else {
+                Err(util::WinError::from_last_error())
+            };
+            // `GetIconInfo` gives us copies of the bitmaps, which we have to delete.
+            wingdi::DeleteObject(icon_info.hbmMask as _);
+            wingdi::DeleteObject(icon_info.hbmColor as _);
+            result
+        }
+    }
+
     pub fn set_for_window(&self, hwnd: HWND, icon_type: IconType) {
         unsafe {
             winuser::SendMessageW(
@@ -100,6 +125,37 @@ impl Drop for WinIcon {
     }
 }
 
+/// The frames of a cursor set with `Window::set_animated_cursor`, which are shown in turn by
+/// `WM_TIMER`.
+#[derive(Debug)]
+pub struct AnimatedCursor {
+    /// Each frame, along with how many milliseconds it's shown for.
+    pub frames: Vec<(HCURSOR, UINT)>,
+}
+
+unsafe impl Send for AnimatedCursor {}
+unsafe impl Sync for AnimatedCursor {}
+
+impl AnimatedCursor {
+    pub fn new(frames: Vec<(Icon, Duration)>, hotspot: (u32, u32)) -> Result<Self, util::WinError> {
+        let mut animated_cursor = AnimatedCursor { frames: Vec::with_capacity(frames.len()) };
+        for (icon, delay) in frames {
+            let cursor = WinIcon::from_icon(icon)?.to_cursor(hotspot)?;
+            let delay = delay.as_secs() as UINT * 1000 + delay.subsec_nanos() / 1_000_000;
+            animated_cursor.frames.push((cursor, delay));
+        }
+        Ok(animated_cursor)
+    }
+}
+
+impl Drop for AnimatedCursor {
+    fn drop(&mut self) {
+        for &(cursor, _) in &self.frames {
+            unsafe { winuser::DestroyCursor(cursor) };
+        }
+    }
+}
+
 pub fn unset_for_window(hwnd: HWND, icon_type: IconType) {
     unsafe {
         winuser::SendMessageW(
@@ -11,6 +11,11 @@ pub use self::window::Window;
 pub struct PlatformSpecificWindowBuilderAttributes {
     pub parent: Option<HWND>,
     pub taskbar_icon: Option<::Icon>,
+    pub undecorated_shadow: bool,
+    pub corner_preference: ::os::windows::CornerPreference,
+    pub border_color: ::os::windows::FrameColor,
+    pub title_background_color: ::os::windows::FrameColor,
+    pub title_text_color: ::os::windows::FrameColor,
 }
 
 unsafe impl Send for PlatformSpecificWindowBuilderAttributes {}
@@ -23,9 +28,14 @@ unsafe impl Send for Cursor {}
 unsafe impl Sync for Cursor {}
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct DeviceId(u32);
 
 impl DeviceId {
+    pub unsafe fn dummy() -> Self {
+        DeviceId(0)
+    }
+
     pub fn get_persistent_identifier(&self) -> Option<String> {
         if self.0 != 0 {
             raw_input::get_raw_input_device_name(self.0 as _)
@@ -47,6 +57,20 @@ pub struct WindowId(HWND);
 unsafe impl Send for WindowId {}
 unsafe impl Sync for WindowId {}
 
+// `HWND` is a pointer, so it's serialized as an integer.
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for WindowId {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ::serde::Serialize::serialize(&(self.0 as usize), serializer)
+    }
+}
+
+impl WindowId {
+    pub unsafe fn dummy() -> Self {
+        WindowId(::std::ptr::null_mut())
+    }
+}
+
 mod dpi;
 mod event;
 mod events_loop;
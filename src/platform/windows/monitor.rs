@@ -1,13 +1,16 @@
 use winapi::shared::minwindef::{BOOL, DWORD, LPARAM, TRUE};
 use winapi::shared::windef::{HDC, HMONITOR, HWND, LPRECT, POINT};
-use winapi::um::winuser;
+use winapi::um::{wingdi, winuser};
 
 use std::{mem, ptr};
 use std::collections::VecDeque;
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
 
 use super::{EventsLoop, util};
 use dpi::{PhysicalPosition, PhysicalSize};
-use platform::platform::dpi::{dpi_to_scale_factor, get_monitor_dpi};
+use window::{Orientation, VideoMode};
+use platform::platform::dpi::{dpi_to_scale_factor, get_monitor_dpi, get_monitor_raw_dpi};
 use platform::platform::window::Window;
 
 /// Win32 implementation of the main `MonitorId` object.
@@ -77,6 +80,8 @@ impl EventsLoop {
     }
 
     pub fn get_current_monitor(hwnd: HWND) -> MonitorId {
+        // This picks the monitor the window overlaps the most, regardless of where the cursor is.
+        // If the window is entirely off-screen, the nearest monitor is used.
         let hmonitor = unsafe {
             winuser::MonitorFromWindow(hwnd, winuser::MONITOR_DEFAULTTONEAREST)
         };
@@ -114,6 +119,15 @@ fn get_monitor_info(hmonitor: HMONITOR) -> Result<winuser::MONITORINFOEXW, util:
     }
 }
 
+fn video_mode_from_settings(mode: &wingdi::DEVMODEW) -> VideoMode {
+    VideoMode {
+        dimensions: (mode.dmPelsWidth, mode.dmPelsHeight),
+        bit_depth: mode.dmBitsPerPel as u16,
+        // Values of 0 and 1 both mean the hardware's default rate, which isn't known to us.
+        refresh_rate: if mode.dmDisplayFrequency > 1 { mode.dmDisplayFrequency as u16 } else { 0 },
+    }
+}
+
 impl MonitorId {
     pub(crate) fn from_hmonitor(hmonitor: HMONITOR) -> Self {
         let monitor_info = get_monitor_info(hmonitor).expect("`GetMonitorInfoW` failed");
@@ -132,9 +146,23 @@ impl MonitorId {
         }
     }
 
-    #[inline]
+    // The name of the monitor attached to the display device, such as `DELL U2415`, which Windows
+    // takes from the monitor's EDID. Drivers that don't provide one get the device's name instead.
     pub fn get_name(&self) -> Option<String> {
-        Some(self.monitor_name.clone())
+        let device_name = self.get_wide_device_name();
+        let mut device: wingdi::DISPLAY_DEVICEW = unsafe { mem::zeroed() };
+        device.cb = mem::size_of::<wingdi::DISPLAY_DEVICEW>() as DWORD;
+        let status = unsafe { winuser::EnumDisplayDevicesW(device_name.as_ptr(), 0, &mut device, 0) };
+        let name = if status != 0 {
+            util::wchar_ptr_to_string(device.DeviceString.as_ptr())
+        } else {
+            String::new()
+        };
+        if name.is_empty() {
+            Some(self.monitor_name.clone())
+        } else {
+            Some(name)
+        }
     }
 
     #[inline]
@@ -161,4 +189,77 @@ impl MonitorId {
     pub fn get_hidpi_factor(&self) -> f64 {
         self.hidpi_factor
     }
+
+    #[inline]
+    pub fn get_dpi(&self) -> Option<f64> {
+        get_monitor_raw_dpi(self.hmonitor.0).map(|dpi| dpi as f64)
+    }
+
+    #[inline]
+    pub fn is_primary(&self) -> bool {
+        self.primary
+    }
+
+    // The name of the display device, such as `\\.\DISPLAY1`, as a nul-terminated wide string.
+    fn get_wide_device_name(&self) -> Vec<u16> {
+        OsStr::new(&self.monitor_name)
+            .encode_wide()
+            .chain(Some(0).into_iter())
+            .collect()
+    }
+
+    fn get_current_settings(&self) -> Option<wingdi::DEVMODEW> {
+        self.get_settings(winuser::ENUM_CURRENT_SETTINGS)
+    }
+
+    // `mode_num` is either an index into the display's list of modes or `ENUM_CURRENT_SETTINGS`.
+    fn get_settings(&self, mode_num: DWORD) -> Option<wingdi::DEVMODEW> {
+        let device_name = self.get_wide_device_name();
+        let mut mode: wingdi::DEVMODEW = unsafe { mem::zeroed() };
+        mode.dmSize = mem::size_of::<wingdi::DEVMODEW>() as _;
+        let status = unsafe {
+            winuser::EnumDisplaySettingsW(
+                device_name.as_ptr(),
+                mode_num,
+                &mut mode,
+            )
+        };
+        if status == 0 {
+            None
+        } else {
+            Some(mode)
+        }
+    }
+
+    pub fn get_current_video_mode(&self) -> Option<VideoMode> {
+        self.get_current_settings().map(|mode| video_mode_from_settings(&mode))
+    }
+
+    pub fn get_video_modes(&self) -> Vec<VideoMode> {
+        let mut video_modes = Vec::new();
+        let mut mode_num = 0;
+        while let Some(mode) = self.get_settings(mode_num) {
+            let video_mode = video_mode_from_settings(&mode);
+            // Modes that only differ in settings we don't expose, like scaling, are reported once.
+            if !video_modes.contains(&video_mode) {
+                video_modes.push(video_mode);
+            }
+            mode_num += 1;
+        }
+        video_modes
+    }
+
+    pub fn get_orientation(&self) -> Orientation {
+        let mode = match self.get_current_settings() {
+            Some(mode) => mode,
+            None => return Orientation::Landscape,
+        };
+        // The rotations are clockwise.
+        match unsafe { mode.u1.s2().dmDisplayOrientation } {
+            wingdi::DMDO_90 => Orientation::Portrait,
+            wingdi::DMDO_180 => Orientation::LandscapeFlipped,
+            wingdi::DMDO_270 => Orientation::PortraitFlipped,
+            _ => Orientation::Landscape,
+        }
+    }
 }
@@ -1,9 +1,13 @@
 use std::{self, mem, ptr, slice};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::ops::BitAnd;
+use std::os::raw::c_int;
 
 use winapi::ctypes::wchar_t;
-use winapi::shared::minwindef::DWORD;
-use winapi::shared::windef::{HWND, RECT};
+use winapi::shared::minwindef::{BOOL, DWORD};
+use winapi::shared::windef::{HCURSOR, HWND, LPPOINT, POINT, RECT};
+use winapi::um::imm::HIMC;
 use winapi::um::errhandlingapi::GetLastError;
 use winapi::um::winbase::{
     FormatMessageW,
@@ -21,6 +25,8 @@ use winapi::um::winnt::{
 };
 use winapi::um::winuser;
 
+use platform::platform::dpi::get_cursor_size;
+
 pub fn has_flag<T>(bitset: T, flag: T) -> bool
 where T:
     Copy + PartialEq + BitAnd<T, Output = T>
@@ -47,6 +53,48 @@ pub fn get_window_rect(hwnd: HWND) -> Option<RECT> {
     }
 }
 
+thread_local! {
+    // Cursors loaded by `load_cursor`, by name and size. They're shared system resources, so the
+    // system owns them and they must not be destroyed.
+    static CURSORS: RefCell<HashMap<(usize, c_int), HCURSOR>> = RefCell::new(HashMap::new());
+}
+
+// Loads the system cursor `name` at the size used on monitors with the given DPI. `LoadCursorW`
+// always gives the size for the primary monitor, so cursors would be tiny on hidpi monitors.
+pub fn load_cursor(name: *const wchar_t, dpi: u32) -> HCURSOR {
+    let size = get_cursor_size(dpi);
+    CURSORS.with(|cursors| {
+        *cursors.borrow_mut().entry((name as usize, size)).or_insert_with(|| unsafe {
+            let cursor = winuser::LoadImageW(
+                ptr::null_mut(),
+                name,
+                winuser::IMAGE_CURSOR,
+                size,
+                size,
+                // System cursors can only be loaded as shared images.
+                winuser::LR_SHARED,
+            ) as HCURSOR;
+            if cursor.is_null() {
+                // Fall back to the default size rather than not showing any cursor
+                winuser::LoadCursorW(ptr::null_mut(), name)
+            } else {
+                cursor
+            }
+        })
+    })
+}
+
+// The width and height of the resize border of windows with `WS_THICKFRAME`.
+pub fn get_frame_thickness() -> (i32, i32) {
+    unsafe {
+        let padding = winuser::GetSystemMetrics(winuser::SM_CXPADDEDBORDER);
+        (
+            winuser::GetSystemMetrics(winuser::SM_CXFRAME) + padding,
+            winuser::GetSystemMetrics(winuser::SM_CYFRAME) + padding,
+        )
+    }
+}
+
 // This won't be needed anymore if we just add a derive to winapi.
 pub fn rect_eq(a: &RECT, b: &RECT) -> bool {
     let left_eq = a.left == b.left;
@@ -56,6 +104,39 @@ pub fn rect_eq(a: &RECT, b: &RECT) -> bool {
     left_eq && right_eq && top_eq && bottom_eq
 }
 
+// Confines the cursor to the client area of `hwnd`.
+pub unsafe fn clip_cursor_to_client_area(hwnd: HWND) -> Result<(), String> {
+    let mut rect: RECT = mem::uninitialized();
+    if winuser::GetClientRect(hwnd, &mut rect) == 0 {
+        return Err("`GetClientRect` failed".to_owned());
+    }
+    // A `POINT` is two `LONG`s (x, y), and the `RECT` field after `left` is `top`.
+    if winuser::ClientToScreen(hwnd, &mut rect.left as *mut _ as LPPOINT) == 0 {
+        return Err("`ClientToScreen` (left, top) failed".to_owned());
+    }
+    if winuser::ClientToScreen(hwnd, &mut rect.right as *mut _ as LPPOINT) == 0 {
+        return Err("`ClientToScreen` (right, bottom) failed".to_owned());
+    }
+    if winuser::ClipCursor(&rect) == 0 {
+        return Err("`ClipCursor` failed".to_owned());
+    }
+    Ok(())
+}
+
+// Confines the cursor to the monitor that `hwnd` is on.
+pub unsafe fn clip_cursor_to_monitor(hwnd: HWND) -> Result<(), String> {
+    let hmonitor = winuser::MonitorFromWindow(hwnd, winuser::MONITOR_DEFAULTTONEAREST);
+    let mut monitor_info: winuser::MONITORINFO = mem::zeroed();
+    monitor_info.cbSize = mem::size_of::<winuser::MONITORINFO>() as _;
+    if winuser::GetMonitorInfoW(hmonitor, &mut monitor_info) == 0 {
+        return Err("`GetMonitorInfoW` failed".to_owned());
+    }
+    if winuser::ClipCursor(&monitor_info.rcMonitor) == 0 {
+        return Err("`ClipCursor` failed".to_owned());
+    }
+    Ok(())
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct WinError(Option<String>);
 
@@ -94,3 +175,24 @@ pub unsafe fn get_last_error() -> Option<String> {
     }
     None
 }
+
+// These aren't exposed by `winapi`.
+pub const NI_COMPOSITIONSTR: DWORD = 0x0015;
+pub const CPS_COMPLETE: DWORD = 0x0001;
+pub const CPS_CANCEL: DWORD = 0x0004;
+pub const IACE_DEFAULT: DWORD = 0x0010;
+pub const CFS_POINT: DWORD = 0x0002;
+
+#[repr(C)]
+pub struct COMPOSITIONFORM {
+    pub style: DWORD,
+    pub current_pos: POINT,
+    pub area: RECT,
+}
+
+#[link(name = "imm32")]
+extern "system" {
+    pub fn ImmNotifyIME(himc: HIMC, action: DWORD, index: DWORD, value: DWORD) -> BOOL;
+    pub fn ImmAssociateContextEx(hwnd: HWND, himc: HIMC, flags: DWORD) -> BOOL;
+    pub fn ImmSetCompositionWindow(himc: HIMC, form: *mut COMPOSITIONFORM) -> BOOL;
+}
@@ -6,32 +6,62 @@ use std::{io, mem, ptr};
 use std::os::windows::ffi::OsStrExt;
 use std::sync::{Arc, Mutex};
 use std::sync::mpsc::channel;
+use std::time::Duration;
 
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::RawWindowHandle;
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::windows::WindowsHandle;
 use winapi::ctypes::c_int;
-use winapi::shared::minwindef::{BOOL, DWORD, FALSE, LPARAM, TRUE, UINT, WORD, WPARAM};
-use winapi::shared::windef::{HDC, HWND, LPPOINT, POINT, RECT};
-use winapi::um::{combaseapi, dwmapi, libloaderapi, winuser};
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE, LPARAM, LPCVOID, LRESULT, TRUE, UINT, WORD, WPARAM};
+use winapi::shared::windef::{COLORREF, HDC, HWND, LPPOINT, POINT, RECT};
+use winapi::um::{combaseapi, dwmapi, imm, libloaderapi, wingdi, winuser};
 use winapi::um::objbase::{COINIT_MULTITHREADED};
 use winapi::um::shobjidl_core::{CLSID_TaskbarList, ITaskbarList2};
+use winapi::um::uxtheme::MARGINS;
 use winapi::um::winnt::{LONG, LPCWSTR};
 
 use {
     CreationError,
     CursorState,
+    ExternalError,
+    Fullscreen,
     Icon,
+    Insets,
     LogicalPosition,
     LogicalSize,
     MonitorId as RootMonitorId,
     MouseCursor,
+    NotSupportedError,
     PhysicalSize,
+    ResizeDirection,
+    StackOrder,
+    UserAttentionType,
     WindowAttributes,
 };
 use platform::platform::{Cursor, EventsLoop, PlatformSpecificWindowBuilderAttributes, WindowId};
 use platform::platform::dpi::{BASE_DPI, dpi_to_scale_factor, get_window_dpi, get_window_scale_factor};
 use platform::platform::events_loop::{self, DESTROY_MSG_ID, INITIAL_DPI_MSG_ID};
-use platform::platform::icon::{self, IconType, WinIcon};
+use platform::platform::icon::{self, AnimatedCursor, IconType, WinIcon};
 use platform::platform::raw_input::register_all_mice_and_keyboards_for_raw_input;
 use platform::platform::util;
+use os::windows::{CornerPreference, FrameColor};
+
+// The extended styles that make up the window frame. These are the only ones we change when
+// entering/leaving fullscreen or toggling decorations; the others (`WS_EX_TOPMOST`,
+// `WS_EX_LAYERED`, `WS_EX_TRANSPARENT`, `WS_EX_TOOLWINDOW`, ...) are owned by other features and
+// are always read back from the window, so that overlay windows keep all of them at once.
+const FRAME_EX_STYLES: DWORD = winuser::WS_EX_DLGMODALFRAME
+    | winuser::WS_EX_WINDOWEDGE
+    | winuser::WS_EX_CLIENTEDGE
+    | winuser::WS_EX_STATICEDGE;
+
+// Combines the frame bits of `frame_ex_style` with the other extended styles currently set on
+// `hwnd`.
+unsafe fn merge_frame_ex_style(hwnd: HWND, frame_ex_style: LONG) -> LONG {
+    let current = winuser::GetWindowLongW(hwnd, winuser::GWL_EXSTYLE) as DWORD;
+    ((current & !FRAME_EX_STYLES) | (frame_ex_style as DWORD & FRAME_EX_STYLES)) as LONG
+}
 
 /// The Win32 implementation of the main `Window` object.
 pub struct Window {
@@ -41,8 +71,11 @@ pub struct Window {
     decorations: Cell<bool>,
     maximized: Cell<bool>,
     resizable: Cell<bool>,
-    fullscreen: RefCell<Option<::MonitorId>>,
+    fullscreen: RefCell<Option<Fullscreen>>,
     always_on_top: Cell<bool>,
+    // Whether removing the decorations hides the frame instead of removing it, see
+    // `WindowState::frame_hidden`.
+    undecorated_shadow: bool,
 
     /// The current window state.
     window_state: Arc<Mutex<events_loop::WindowState>>,
@@ -85,12 +118,20 @@ impl Window {
         w_attr: WindowAttributes,
         pl_attr: PlatformSpecificWindowBuilderAttributes,
     ) -> Result<Window, CreationError> {
+        // Switching the video mode isn't implemented.
+        if let Some(Fullscreen::Exclusive(..)) = w_attr.fullscreen {
+            return Err(CreationError::NotSupported);
+        }
+
         let (tx, rx) = channel();
         let proxy = events_loop.create_proxy();
+        let handle_close_requests = events_loop.handles_close_requests();
         events_loop.execute_in_thread(move |inserter| {
             // We dispatch an `init` function because of code style.
             // First person to remove the need for cloning here gets a cookie!
-            let win = unsafe { init(w_attr.clone(), pl_attr.clone(), inserter, proxy.clone()) };
+            let win = unsafe {
+                init(w_attr.clone(), pl_attr.clone(), inserter, proxy.clone(), handle_close_requests)
+            };
             let _ = tx.send(win);
         });
         rx.recv().unwrap()
@@ -120,6 +161,20 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn request_redraw(&self) {
+        unsafe {
+            // This makes Windows send `WM_PAINT` even though nothing was invalidated. It's only sent
+            // once there are no other messages, and only once however many times this is called.
+            winuser::RedrawWindow(
+                self.window.0,
+                ptr::null(),
+                ptr::null_mut(),
+                winuser::RDW_INTERNALPAINT,
+            );
+        }
+    }
+
     pub(crate) fn get_position_physical(&self) -> Option<(i32, i32)> {
         util::get_window_rect(self.window.0)
             .map(|rect| (rect.left as i32, rect.top as i32))
@@ -170,6 +225,7 @@ impl Window {
     pub fn set_position(&self, logical_position: LogicalPosition) {
         let dpi_factor = self.get_hidpi_factor();
         let (x, y) = logical_position.to_physical(dpi_factor).into();
+        self.window_state.lock().unwrap().pending_move = Some((x, y));
         self.set_position_physical(x, y);
     }
 
@@ -210,6 +266,29 @@ impl Window {
             })
     }
 
+    #[inline]
+    pub fn get_decoration_insets(&self) -> Option<Insets> {
+        let window_rect = util::get_window_rect(self.window.0)?;
+        let mut client_rect: RECT = unsafe { mem::uninitialized() };
+        let mut client_origin = POINT { x: 0, y: 0 };
+        unsafe {
+            if winuser::GetClientRect(self.window.0, &mut client_rect) == 0 {
+                return None;
+            }
+            if winuser::ClientToScreen(self.window.0, &mut client_origin) == 0 {
+                return None;
+            }
+        }
+        let dpi_factor = self.get_hidpi_factor();
+        let logicalize = |value: LONG| value as f64 / dpi_factor;
+        Some(Insets {
+            top: logicalize(client_origin.y - window_rect.top),
+            left: logicalize(client_origin.x - window_rect.left),
+            right: logicalize(window_rect.right - (client_origin.x + client_rect.right)),
+            bottom: logicalize(window_rect.bottom - (client_origin.y + client_rect.bottom)),
+        })
+    }
+
     pub(crate) fn set_inner_size_physical(&self, x: u32, y: u32) {
         unsafe {
             let mut rect = RECT {
@@ -221,7 +300,9 @@ impl Window {
             let dw_style = winuser::GetWindowLongA(self.window.0, winuser::GWL_STYLE) as DWORD;
             let b_menu = !winuser::GetMenu(self.window.0).is_null() as BOOL;
             let dw_style_ex = winuser::GetWindowLongA(self.window.0, winuser::GWL_EXSTYLE) as DWORD;
-            winuser::AdjustWindowRectEx(&mut rect, dw_style, b_menu, dw_style_ex);
+            if !self.window_state.lock().unwrap().frame_hidden {
+                winuser::AdjustWindowRectEx(&mut rect, dw_style, b_menu, dw_style_ex);
+            }
             let outer_x = (rect.right - rect.left).abs() as c_int;
             let outer_y = (rect.top - rect.bottom).abs() as c_int;
             winuser::SetWindowPos(
@@ -309,12 +390,28 @@ impl Window {
         self.resizable.replace(resizable);
     }
 
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        self.resizable.get()
+    }
+
     /// Returns the `hwnd` of this window.
     #[inline]
     pub fn hwnd(&self) -> HWND {
         self.window.0
     }
 
+    #[cfg(feature = "raw-window-handle")]
+    #[inline]
+    pub fn raw_window_handle(&self) -> RawWindowHandle {
+        RawWindowHandle::Windows(WindowsHandle {
+            hwnd: self.window.0 as *mut _,
+            // The window class is registered with the executable's module.
+            hinstance: unsafe { libloaderapi::GetModuleHandleW(ptr::null()) } as *mut _,
+            ..WindowsHandle::empty()
+        })
+    }
+
     #[inline]
     pub fn set_cursor(&self, cursor: MouseCursor) {
         let cursor_id = match cursor {
@@ -342,6 +439,45 @@ impl Window {
 
         let mut cur = self.window_state.lock().unwrap();
         cur.cursor = Cursor(cursor_id);
+        // The timer stops itself once it sees that the animated cursor is gone.
+        cur.animated_cursor = None;
+    }
+
+    pub fn set_animated_cursor(
+        &self,
+        frames: Vec<(Icon, Duration)>,
+        hotspot: (u32, u32),
+    ) -> Result<(), ExternalError> {
+        let animated_cursor = AnimatedCursor::new(frames, hotspot)
+            .map(Arc::new)
+            .map_err(|err| ExternalError::Os(format!("Failed to create the cursor: {:?}", err)))?;
+        let window = self.window.clone();
+        let window_state = Arc::clone(&self.window_state);
+        // Timers belong to the thread that created the window.
+        self.events_loop_proxy.execute_in_thread(move |_| unsafe {
+            let mut window_state = window_state.lock().unwrap();
+            let (cursor, delay) = animated_cursor.frames[0];
+            window_state.animated_cursor = Some(Arc::clone(&animated_cursor));
+            window_state.animated_cursor_frame = 0;
+            if window_state.mouse_in_window {
+                winuser::SetCursor(cursor);
+            }
+            // A single frame makes for a static cursor, which doesn't need the timer.
+            if animated_cursor.frames.len() > 1 {
+                winuser::SetTimer(window.0, events_loop::ANIMATED_CURSOR_TIMER_ID, delay, None);
+            } else {
+                winuser::KillTimer(window.0, events_loop::ANIMATED_CURSOR_TIMER_ID);
+            }
+        });
+        Ok(())
+    }
+
+    // Cursors are drawn in physical pixels, so the image, which is given at logical size, is
+    // scaled by the DPI factor of the window.
+    #[inline]
+    pub fn set_cursor_image(&self, image: Icon, hotspot: (u32, u32)) -> Result<(), ExternalError> {
+        let (image, hotspot) = image.scale_cursor(hotspot, self.get_hidpi_factor());
+        self.set_animated_cursor(vec![(image, Duration::from_secs(0))], hotspot)
     }
 
     unsafe fn cursor_is_grabbed(&self) -> Result<bool, String> {
@@ -389,19 +525,7 @@ impl Window {
 
             (CursorState::Normal, CursorState::Grab)
             | (CursorState::Hide, CursorState::Grab) => unsafe {
-                let mut rect = mem::uninitialized();
-                if winuser::GetClientRect(window.0, &mut rect) == 0 {
-                    return Err("`GetClientRect` failed".to_owned());
-                }
-                if winuser::ClientToScreen(window.0, &mut rect.left as *mut _ as LPPOINT) == 0 {
-                    return Err("`ClientToScreen` (left, top) failed".to_owned());
-                }
-                if winuser::ClientToScreen(window.0, &mut rect.right as *mut _ as LPPOINT) == 0 {
-                    return Err("`ClientToScreen` (right, bottom) failed".to_owned());
-                }
-                if winuser::ClipCursor(&rect) == 0 {
-                    return Err("`ClipCursor` failed".to_owned());
-                }
+                util::clip_cursor_to_client_area(window.0)?;
                 if current_state != CursorState::Hide {
                     winuser::ShowCursor(FALSE);
                 }
@@ -417,13 +541,20 @@ impl Window {
         Ok(state)
     }
 
-    pub fn set_cursor_state(&self, state: CursorState) -> Result<(), String> {
-        let is_grabbed = unsafe { self.cursor_is_grabbed() }?;
+    #[inline]
+    pub fn is_cursor_visible(&self) -> bool {
+        self.window_state.lock().unwrap().cursor_state != CursorState::Hide
+    }
+
+    pub fn set_cursor_state(&self, state: CursorState) -> Result<(), ExternalError> {
+        let is_grabbed = unsafe { self.cursor_is_grabbed() }.map_err(ExternalError::Os)?;
         let (tx, rx) = channel();
         let window = self.window.clone();
         let window_state = Arc::clone(&self.window_state);
         self.events_loop_proxy.execute_in_thread(move |_| {
             let mut window_state_lock = window_state.lock().unwrap();
+            // The application changed the grab itself, so it shouldn't be restored on focus anymore.
+            window_state_lock.cursor_grab_suspended = false;
             // We should probably also check if the cursor is hidden,
             // but `GetCursorInfo` isn't in winapi-rs yet, and it doesn't seem to matter as much.
             let current_state = match window_state_lock.cursor_state {
@@ -435,6 +566,50 @@ impl Window {
                 .map(|_| {
                     window_state_lock.cursor_state = state;
                 });
+            // Releasing the grab also released the confinement to the monitor.
+            if result.is_ok() && state != CursorState::Grab && window_state_lock.cursor_confined_to_monitor {
+                let _ = unsafe { util::clip_cursor_to_monitor(window.0) };
+            }
+            let _ = tx.send(result);
+        });
+        rx.recv().unwrap().map_err(ExternalError::Os)
+    }
+
+    pub fn confine_cursor_to_monitor(&self, confine: bool) -> Result<(), ExternalError> {
+        let (tx, rx) = channel();
+        let window = self.window.clone();
+        let window_state = Arc::clone(&self.window_state);
+        self.events_loop_proxy.execute_in_thread(move |_| {
+            let mut window_state_lock = window_state.lock().unwrap();
+            window_state_lock.cursor_confined_to_monitor = confine;
+            // The confinement is applied by `WM_SETFOCUS` otherwise, and a grab already confines
+            // the cursor further.
+            let is_focused = unsafe { winuser::GetForegroundWindow() } == window.0;
+            if !is_focused || window_state_lock.cursor_state == CursorState::Grab {
+                let _ = tx.send(Ok(()));
+                return;
+            }
+            let result = unsafe {
+                if confine {
+                    util::clip_cursor_to_monitor(window.0)
+                } else if winuser::ClipCursor(ptr::null()) == 0 {
+                    Err("`ClipCursor` failed".to_owned())
+                } else {
+                    Ok(())
+                }
+            };
+            let _ = tx.send(result);
+        });
+        rx.recv().unwrap().map_err(ExternalError::Os)
+    }
+
+    #[inline]
+    pub fn set_keyboard_grab(&self, grab: bool) -> Result<(), ExternalError> {
+        let (tx, rx) = channel();
+        let window = self.window.clone();
+        self.events_loop_proxy.execute_in_thread(move |_| {
+            let result = unsafe { events_loop::set_keyboard_grab(window.0, grab) }
+                .map_err(|err| ExternalError::Os(format!("Failed to set keyboard grab: {:?}", err)));
             let _ = tx.send(result);
         });
         rx.recv().unwrap()
@@ -445,21 +620,23 @@ impl Window {
         get_window_scale_factor(self.window.0, self.window.1)
     }
 
-    fn set_cursor_position_physical(&self, x: i32, y: i32) -> Result<(), ()> {
+    fn set_cursor_position_physical(&self, x: i32, y: i32) -> Result<(), ExternalError> {
         let mut point = POINT { x, y };
         unsafe {
             if winuser::ClientToScreen(self.window.0, &mut point) == 0 {
-                return Err(());
+                return Err(ExternalError::Os("`ClientToScreen` failed".to_owned()));
             }
+            self.window_state.lock().unwrap().pending_warp = Some((x, y));
             if winuser::SetCursorPos(point.x, point.y) == 0 {
-                return Err(());
+                self.window_state.lock().unwrap().pending_warp = None;
+                return Err(ExternalError::Os("`SetCursorPos` failed".to_owned()));
             }
         }
         Ok(())
     }
 
     #[inline]
-    pub fn set_cursor_position(&self, logical_position: LogicalPosition) -> Result<(), ()> {
+    pub fn set_cursor_position(&self, logical_position: LogicalPosition) -> Result<(), ExternalError> {
         let dpi_factor = self.get_hidpi_factor();
         let (x, y) = logical_position.to_physical(dpi_factor).into();
         self.set_cursor_position_physical(x, y)
@@ -492,6 +669,21 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn set_minimized(&self, minimized: bool) -> Result<(), ExternalError> {
+        let window = self.window.clone();
+        self.events_loop_proxy.execute_in_thread(move |_| unsafe {
+            if minimized {
+                winuser::ShowWindow(window.0, winuser::SW_MINIMIZE);
+            } else if winuser::IsIconic(window.0) != 0 {
+                // This brings the window back to its maximized state if it was maximized, but
+                // would unmaximize a window that isn't minimized.
+                winuser::ShowWindow(window.0, winuser::SW_RESTORE);
+            }
+        });
+        Ok(())
+    }
+
     unsafe fn set_fullscreen_style(&self) -> (LONG, LONG) {
         let mut window_state = self.window_state.lock().unwrap();
 
@@ -551,6 +743,9 @@ impl Window {
                 style &= !winuser::WS_SIZEBOX as LONG;
             }
             winuser::SetWindowLongW(window.0, winuser::GWL_STYLE, style);
+            // The saved extended style is only trusted for the frame, since e.g. `WS_EX_TOPMOST`
+            // may have changed while we were fullscreen.
+            let ex_style = merge_frame_ex_style(window.0, ex_style);
             winuser::SetWindowLongW(window.0, winuser::GWL_EXSTYLE, ex_style);
 
             winuser::SetWindowPos(
@@ -581,10 +776,18 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_fullscreen(&self, monitor: Option<RootMonitorId>) {
+    pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) -> Result<(), ExternalError> {
+        // Switching the video mode isn't implemented.
+        if let Some(Fullscreen::Exclusive(..)) = fullscreen {
+            return Err(ExternalError::NotSupported(NotSupportedError::new()));
+        }
+        let fullscreen = match fullscreen {
+            Some(Fullscreen::Borderless(None)) => Some(Fullscreen::Borderless(Some(self.get_current_monitor()))),
+            fullscreen => fullscreen,
+        };
         unsafe {
-            match &monitor {
-                &Some(RootMonitorId { ref inner }) => {
+            match fullscreen.as_ref().and_then(Fullscreen::get_monitor) {
+                Some(&RootMonitorId { ref inner }) => {
                     let (x, y): (i32, i32) = inner.get_position().into();
                     let (width, height): (u32, u32) = inner.get_dimensions().into();
                     let window = self.window.clone();
@@ -602,11 +805,10 @@ impl Window {
                         winuser::SetWindowLongW(
                             window.0,
                             winuser::GWL_EXSTYLE,
-                            ((ex_style as DWORD)
-                                & !(winuser::WS_EX_DLGMODALFRAME | winuser::WS_EX_WINDOWEDGE
-                                    | winuser::WS_EX_CLIENTEDGE
-                                    | winuser::WS_EX_STATICEDGE))
-                                as LONG,
+                            merge_frame_ex_style(
+                                window.0,
+                                ((ex_style as DWORD) & !FRAME_EX_STYLES) as LONG,
+                            ),
                         );
 
                         winuser::SetWindowPos(
@@ -624,13 +826,24 @@ impl Window {
                         mark_fullscreen(window.0, true);
                     });
                 }
-                &None => {
+                None => {
                     self.restore_saved_window();
                 }
             }
         }
 
-        self.fullscreen.replace(monitor);
+        self.fullscreen.replace(fullscreen);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn get_current_fullscreen(&self) -> Option<Fullscreen> {
+        self.fullscreen.borrow().clone()
+    }
+
+    #[inline]
+    pub fn is_decorated(&self) -> bool {
+        self.decorations.get()
     }
 
     #[inline]
@@ -639,6 +852,39 @@ impl Window {
             return;
         }
 
+        if self.undecorated_shadow {
+            let inner_size = self.get_inner_size_physical();
+            self.window_state.lock().unwrap().frame_hidden = !decorations;
+            let window = self.window.clone();
+            unsafe {
+                self.events_loop_proxy.execute_in_thread(move |_| {
+                    set_frame_shadow(window.0, !decorations);
+                    // Makes the window receive `WM_NCCALCSIZE` again.
+                    winuser::SetWindowPos(
+                        window.0,
+                        ptr::null_mut(),
+                        0,
+                        0,
+                        0,
+                        0,
+                        winuser::SWP_NOMOVE
+                        | winuser::SWP_NOSIZE
+                        | winuser::SWP_NOZORDER
+                        | winuser::SWP_NOACTIVATE
+                        | winuser::SWP_FRAMECHANGED,
+                    );
+                });
+            }
+            // Keep the size of the client area, like when the styles are changed.
+            if let Some((width, height)) = inner_size {
+                if self.fullscreen.borrow().is_none() {
+                    self.set_inner_size_physical(width, height);
+                }
+            }
+            self.decorations.replace(decorations);
+            return;
+        }
+
         let style_flags = (winuser::WS_CAPTION | winuser::WS_THICKFRAME) as LONG;
         let ex_style_flags = (winuser::WS_EX_WINDOWEDGE) as LONG;
 
@@ -694,6 +940,8 @@ impl Window {
 
             self.events_loop_proxy.execute_in_thread(move |_| {
                 winuser::SetWindowLongW(window.0, winuser::GWL_STYLE, style);
+                // Other extended styles may have been changed since we read them above.
+                let ex_style = merge_frame_ex_style(window.0, ex_style);
                 winuser::SetWindowLongW(window.0, winuser::GWL_EXSTYLE, ex_style);
                 winuser::AdjustWindowRectEx(&mut rect, style as _, 0, ex_style as _);
 
@@ -716,9 +964,9 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_always_on_top(&self, always_on_top: bool) {
+    pub fn set_always_on_top(&self, always_on_top: bool) -> Result<(), ExternalError> {
         if self.always_on_top.get() == always_on_top {
-            return;
+            return Ok(());
         }
 
         let window = self.window.clone();
@@ -743,6 +991,48 @@ impl Window {
         });
 
         self.always_on_top.replace(always_on_top);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn is_always_on_top(&self) -> bool {
+        self.always_on_top.get()
+    }
+
+    pub fn restack(&self, relative_to: WindowId, order: StackOrder) -> Result<(), ExternalError> {
+        // `SetWindowPos` places the window below `insert_after`, so to be placed above a window,
+        // we go below the window that's currently above it.
+        let insert_after = match order {
+            StackOrder::Below => relative_to.0,
+            StackOrder::Above => {
+                let above = unsafe { winuser::GetWindow(relative_to.0, winuser::GW_HWNDPREV) };
+                if above == self.window.0 {
+                    return Ok(());
+                } else if above.is_null() {
+                    winuser::HWND_TOP
+                } else {
+                    above
+                }
+            },
+        };
+        let success = unsafe {
+            winuser::SetWindowPos(
+                self.window.0,
+                insert_after,
+                0,
+                0,
+                0,
+                0,
+                winuser::SWP_ASYNCWINDOWPOS
+                    | winuser::SWP_NOACTIVATE
+                    | winuser::SWP_NOMOVE
+                    | winuser::SWP_NOSIZE,
+            )
+        };
+        if success == 0 {
+            return Err(ExternalError::Os(format!("{}", io::Error::last_os_error())));
+        }
+        Ok(())
     }
 
     #[inline]
@@ -752,6 +1042,28 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn set_content_protected(&self, protected: bool) -> Result<(), ExternalError> {
+        let window = self.window.clone();
+        self.events_loop_proxy.execute_in_thread(move |_| {
+            let affinity = if protected {
+                winuser::WDA_MONITOR
+            } else {
+                winuser::WDA_NONE
+            };
+            unsafe { winuser::SetWindowDisplayAffinity(window.0, affinity) };
+        });
+        Ok(())
+    }
+
+    #[inline]
+    pub fn set_background_color(&self, color: Option<(u8, u8, u8, u8)>) {
+        // GDI has no notion of alpha, so only the color is used.
+        let color = color.map(|(red, green, blue, _)| wingdi::RGB(red, green, blue));
+        self.window_state.lock().unwrap().background_color = color;
+        unsafe { winuser::InvalidateRect(self.window.0, ptr::null(), TRUE) };
+    }
+
     #[inline]
     pub fn set_window_icon(&self, mut window_icon: Option<Icon>) {
         let window_icon = window_icon
@@ -779,8 +1091,169 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_ime_spot(&self, _logical_spot: LogicalPosition) {
-        unimplemented!();
+    pub fn set_ime_spot(&self, logical_spot: LogicalPosition) -> Result<(), ExternalError> {
+        let (x, y) = logical_spot.to_physical(self.get_hidpi_factor()).into();
+        let window = self.window.clone();
+        self.events_loop_proxy.execute_in_thread(move |_| unsafe {
+            let himc = imm::ImmGetContext(window.0);
+            if !himc.is_null() {
+                let mut form = util::COMPOSITIONFORM {
+                    style: util::CFS_POINT,
+                    current_pos: POINT { x, y },
+                    area: mem::zeroed(),
+                };
+                util::ImmSetCompositionWindow(himc, &mut form);
+                imm::ImmReleaseContext(window.0, himc);
+            }
+        });
+        Ok(())
+    }
+
+    pub fn set_ime_allowed(&self, allowed: bool, commit: bool) -> Result<(), ExternalError> {
+        let window = self.window.clone();
+        self.events_loop_proxy.execute_in_thread(move |_| unsafe {
+            if allowed {
+                util::ImmAssociateContextEx(window.0, ptr::null_mut(), util::IACE_DEFAULT);
+                return;
+            }
+            let himc = imm::ImmGetContext(window.0);
+            if !himc.is_null() {
+                // Completing the composition makes the IME send the result string, which ends up
+                // as `WM_CHAR` messages.
+                let action = if commit { util::CPS_COMPLETE } else { util::CPS_CANCEL };
+                util::ImmNotifyIME(himc, util::NI_COMPOSITIONSTR, action, 0);
+                imm::ImmReleaseContext(window.0, himc);
+            }
+            util::ImmAssociateContextEx(window.0, ptr::null_mut(), 0);
+        });
+        Ok(())
+    }
+
+    #[inline]
+    pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
+        match request_type {
+            // With a count of 0, `flash_frame` keeps flashing until the window is focused.
+            Some(UserAttentionType::Critical) => self.flash_frame(0, 0),
+            Some(UserAttentionType::Informational) => self.flash_frame(1, 0),
+            None => self.flash(winuser::FLASHW_STOP, 0, 0),
+        }
+    }
+
+    #[inline]
+    pub fn set_corner_preference(&self, preference: CornerPreference) {
+        unsafe { set_corner_preference(self.window.0, preference) };
+    }
+
+    #[inline]
+    pub fn set_border_color(&self, color: FrameColor) {
+        unsafe { set_frame_color(self.window.0, DWMWA_BORDER_COLOR, color) };
+    }
+
+    #[inline]
+    pub fn set_title_background_color(&self, color: FrameColor) {
+        unsafe { set_frame_color(self.window.0, DWMWA_CAPTION_COLOR, color) };
+    }
+
+    #[inline]
+    pub fn set_title_text_color(&self, color: FrameColor) {
+        unsafe { set_frame_color(self.window.0, DWMWA_TEXT_COLOR, color) };
+    }
+
+    #[inline]
+    pub fn flash_frame(&self, count: u32, interval_ms: u32) {
+        let flags = if count == 0 {
+            winuser::FLASHW_ALL | winuser::FLASHW_TIMERNOFG
+        } else {
+            winuser::FLASHW_ALL
+        };
+        self.flash(flags, count, interval_ms)
+    }
+
+    fn flash(&self, flags: DWORD, count: u32, interval_ms: u32) {
+        let mut flash_info = winuser::FLASHWINFO {
+            cbSize: mem::size_of::<winuser::FLASHWINFO>() as UINT,
+            hwnd: self.window.0,
+            dwFlags: flags,
+            uCount: count,
+            dwTimeout: interval_ms,
+        };
+        unsafe { winuser::FlashWindowEx(&mut flash_info) };
+    }
+
+    pub fn show_window_menu(&self, position: LogicalPosition) -> Result<(), ExternalError> {
+        let window = self.window.clone();
+        let (x, y): (i32, i32) = position.to_physical(self.get_hidpi_factor()).into();
+        self.events_loop_proxy.execute_in_thread(move |_| unsafe {
+            let mut point = POINT { x, y };
+            if winuser::ClientToScreen(window.0, &mut point) == 0 {
+                return;
+            }
+            let menu = winuser::GetSystemMenu(window.0, FALSE);
+            if menu.is_null() {
+                return;
+            }
+            // With `TPM_RETURNCMD`, the selected command is returned instead of being sent to the
+            // window as a `WM_COMMAND`, which `DefWindowProc` wouldn't know what to do with.
+            let command = winuser::TrackPopupMenu(
+                menu,
+                winuser::TPM_RETURNCMD | winuser::TPM_RIGHTBUTTON,
+                point.x,
+                point.y,
+                0,
+                window.0,
+                ptr::null(),
+            );
+            if command != 0 {
+                winuser::PostMessageW(window.0, winuser::WM_SYSCOMMAND, command as WPARAM, 0);
+            }
+        });
+        Ok(())
+    }
+
+    #[inline]
+    pub fn start_drag(&self) -> Result<(), ExternalError> {
+        self.press_non_client_area(winuser::HTCAPTION)
+    }
+
+    #[inline]
+    pub fn start_resize(&self, direction: ResizeDirection) -> Result<(), ExternalError> {
+        let hit_test = match direction {
+            ResizeDirection::East => winuser::HTRIGHT,
+            ResizeDirection::North => winuser::HTTOP,
+            ResizeDirection::NorthEast => winuser::HTTOPRIGHT,
+            ResizeDirection::NorthWest => winuser::HTTOPLEFT,
+            ResizeDirection::South => winuser::HTBOTTOM,
+            ResizeDirection::SouthEast => winuser::HTBOTTOMRIGHT,
+            ResizeDirection::SouthWest => winuser::HTBOTTOMLEFT,
+            ResizeDirection::West => winuser::HTLEFT,
+        };
+        self.press_non_client_area(hit_test)
+    }
+
+    // Makes the window believe the left button was pressed on the given part of its frame, which
+    // starts the same move or resize loop as pressing it would.
+    fn press_non_client_area(&self, hit_test: LRESULT) -> Result<(), ExternalError> {
+        let window = self.window.clone();
+        self.events_loop_proxy.execute_in_thread(move |_| unsafe {
+            if winuser::GetKeyState(winuser::VK_LBUTTON) & (1 << 15) == 0 {
+                return;
+            }
+            let mut point = POINT { x: 0, y: 0 };
+            if winuser::GetCursorPos(&mut point) == 0 {
+                return;
+            }
+            // The move loop swallows the button release, so our capture has to be given up now.
+            events_loop::reset_mouse_capture();
+            // The position is in screen coordinates.
+            let position = (point.x as WORD as LPARAM) | ((point.y as WORD as LPARAM) << 16);
+            winuser::PostMessageW(
+                window.0,
+                winuser::WM_NCLBUTTONDOWN,
+                hit_test as WPARAM,
+                position,
+            );
+        });
+        Ok(())
     }
 }
 
@@ -806,18 +1279,76 @@ pub struct WindowWrapper(HWND, HDC);
 // https://github.com/retep998/winapi-rs/issues/396
 unsafe impl Send for WindowWrapper {}
 
-pub unsafe fn adjust_size(physical_size: PhysicalSize, style: DWORD, ex_style: DWORD) -> (LONG, LONG) {
+pub unsafe fn adjust_size(
+    physical_size: PhysicalSize,
+    style: DWORD,
+    ex_style: DWORD,
+    frame_hidden: bool,
+) -> (LONG, LONG) {
     let (width, height): (u32, u32) = physical_size.into();
     let mut rect = RECT { left: 0, right: width as LONG, top: 0, bottom: height as LONG };
-    winuser::AdjustWindowRectEx(&mut rect, style, 0, ex_style);
+    if !frame_hidden {
+        winuser::AdjustWindowRectEx(&mut rect, style, 0, ex_style);
+    }
     (rect.right - rect.left, rect.bottom - rect.top)
 }
 
+// DWM only draws the shadow of windows that have part of their frame extended into the client area,
+// so we extend it by a pixel on every side.
+unsafe fn set_frame_shadow(hwnd: HWND, enabled: bool) {
+    let width = if enabled { 1 } else { 0 };
+    let margins = MARGINS {
+        cxLeftWidth: width,
+        cxRightWidth: width,
+        cyTopHeight: width,
+        cyBottomHeight: width,
+    };
+    dwmapi::DwmExtendFrameIntoClientArea(hwnd, &margins);
+}
+
+// Windows 11's `DWMWA_WINDOW_CORNER_PREFERENCE` and frame color attributes, which aren't in
+// winapi yet.
+const DWMWA_WINDOW_CORNER_PREFERENCE: DWORD = 33;
+const DWMWA_BORDER_COLOR: DWORD = 34;
+const DWMWA_CAPTION_COLOR: DWORD = 35;
+const DWMWA_TEXT_COLOR: DWORD = 36;
+
+// The `COLORREF` sentinels accepted by the frame color attributes.
+const DWMWA_COLOR_DEFAULT: COLORREF = 0xFFFF_FFFF;
+const DWMWA_COLOR_NONE: COLORREF = 0xFFFF_FFFE;
+
+// Earlier versions of Windows don't know the attribute, and fail the call without side effects.
+unsafe fn set_corner_preference(hwnd: HWND, preference: CornerPreference) {
+    let preference = preference as DWORD;
+    dwmapi::DwmSetWindowAttribute(
+        hwnd,
+        DWMWA_WINDOW_CORNER_PREFERENCE,
+        &preference as *const DWORD as LPCVOID,
+        mem::size_of::<DWORD>() as DWORD,
+    );
+}
+
+// Like `set_corner_preference`, this fails without side effects before Windows 11.
+unsafe fn set_frame_color(hwnd: HWND, attribute: DWORD, color: FrameColor) {
+    let color = match color {
+        FrameColor::Default => DWMWA_COLOR_DEFAULT,
+        FrameColor::None => DWMWA_COLOR_NONE,
+        FrameColor::Rgb(r, g, b) => wingdi::RGB(r, g, b),
+    };
+    dwmapi::DwmSetWindowAttribute(
+        hwnd,
+        attribute,
+        &color as *const COLORREF as LPCVOID,
+        mem::size_of::<COLORREF>() as DWORD,
+    );
+}
+
 unsafe fn init(
     mut attributes: WindowAttributes,
     mut pl_attribs: PlatformSpecificWindowBuilderAttributes,
     inserter: events_loop::Inserter,
     events_loop_proxy: events_loop::EventsLoopProxy,
+    handle_close_requests: bool,
 ) -> Result<Window, CreationError> {
     let title = OsStr::new(&attributes.title)
         .encode_wide()
@@ -863,8 +1394,12 @@ unsafe fn init(
         bottom: height as LONG,
     };
 
+    // Undecorated windows with a shadow keep the styles of decorated ones, and hide their frame in
+    // `WM_NCCALCSIZE` instead.
+    let frame_hidden = !attributes.decorations && pl_attribs.undecorated_shadow;
+
     // computing the style and extended style of the window
-    let (mut ex_style, style) = if !attributes.decorations {
+    let (mut ex_style, style) = if !attributes.decorations && !frame_hidden {
         (winuser::WS_EX_APPWINDOW,
             //winapi::WS_POPUP is incompatible with winapi::WS_CHILD
             if pl_attribs.parent.is_some() {
@@ -884,18 +1419,20 @@ unsafe fn init(
     }
 
     // adjusting the window coordinates using the style
-    winuser::AdjustWindowRectEx(&mut rect, style, 0, ex_style);
+    if !frame_hidden {
+        winuser::AdjustWindowRectEx(&mut rect, style, 0, ex_style);
+    }
 
     // creating the real window this time, by using the functions in `extra_functions`
     let real_window = {
         let (adjusted_width, adjusted_height) = if attributes.dimensions.is_some() {
             let min_dimensions = attributes.min_dimensions
                 .map(|logical_size| PhysicalSize::from_logical(logical_size, 1.0))
-                .map(|physical_size| adjust_size(physical_size, style, ex_style))
+                .map(|physical_size| adjust_size(physical_size, style, ex_style, frame_hidden))
                 .unwrap_or((0, 0));
             let max_dimensions = attributes.max_dimensions
                 .map(|logical_size| PhysicalSize::from_logical(logical_size, 1.0))
-                .map(|physical_size| adjust_size(physical_size, style, ex_style))
+                .map(|physical_size| adjust_size(physical_size, style, ex_style, frame_hidden))
                 .unwrap_or((c_int::max_value(), c_int::max_value()));
             (
                 Some((rect.right - rect.left).min(max_dimensions.0).max(min_dimensions.0)),
@@ -937,6 +1474,11 @@ unsafe fn init(
                                               format!("{}", io::Error::last_os_error()))));
         }
 
+        // This has to be done before the window is shown, so that it's never captured.
+        if attributes.content_protected {
+            winuser::SetWindowDisplayAffinity(handle, winuser::WDA_MONITOR);
+        }
+
         let hdc = winuser::GetDC(handle);
         if hdc.is_null() {
             return Err(CreationError::OsError(format!("GetDC function failed: {}",
@@ -986,6 +1528,18 @@ unsafe fn init(
             mouse_in_window: false,
             saved_window_info: None,
             dpi_factor,
+            scale_factor_policy: attributes.scale_factor_policy,
+            background_color: None,
+            transparent: attributes.transparent,
+            cursor_grab_suspended: false,
+            cursor_confined_to_monitor: false,
+            pending_warp: None,
+            pending_move: None,
+            frame_hidden,
+            animated_cursor: None,
+            animated_cursor_frame: 0,
+            handle_close_requests,
+            minimized: false,
         };
         // Creating a mutex to track the current window state
         Arc::new(Mutex::new(window_state))
@@ -1011,6 +1565,7 @@ unsafe fn init(
         resizable: Cell::new(attributes.resizable.clone()),
         fullscreen: RefCell::new(attributes.fullscreen.clone()),
         always_on_top: Cell::new(attributes.always_on_top),
+        undecorated_shadow: pl_attribs.undecorated_shadow,
         window_icon: Cell::new(window_icon),
         taskbar_icon: Cell::new(taskbar_icon),
         events_loop_proxy,
@@ -1018,12 +1573,45 @@ unsafe fn init(
 
     win.set_maximized(attributes.maximized);
     if let Some(_) = attributes.fullscreen {
-        win.set_fullscreen(attributes.fullscreen);
+        // Exclusive fullscreen was rejected by `Window::new`.
+        let _ = win.set_fullscreen(attributes.fullscreen);
         force_window_active(win.window.0);
     }
 
     inserter.insert(win.window.0, win.window_state.clone());
 
+    if pl_attribs.corner_preference != CornerPreference::Default {
+        set_corner_preference(win.window.0, pl_attribs.corner_preference);
+    }
+    if pl_attribs.border_color != FrameColor::Default {
+        set_frame_color(win.window.0, DWMWA_BORDER_COLOR, pl_attribs.border_color);
+    }
+    if pl_attribs.title_background_color != FrameColor::Default {
+        set_frame_color(win.window.0, DWMWA_CAPTION_COLOR, pl_attribs.title_background_color);
+    }
+    if pl_attribs.title_text_color != FrameColor::Default {
+        set_frame_color(win.window.0, DWMWA_TEXT_COLOR, pl_attribs.title_text_color);
+    }
+
+    // `WM_NCCALCSIZE` was sent before the window state existed, so it has to be sent again for the
+    // frame to be hidden.
+    if frame_hidden {
+        set_frame_shadow(win.window.0, true);
+        winuser::SetWindowPos(
+            win.window.0,
+            ptr::null_mut(),
+            0,
+            0,
+            0,
+            0,
+            winuser::SWP_NOMOVE
+            | winuser::SWP_NOSIZE
+            | winuser::SWP_NOZORDER
+            | winuser::SWP_NOACTIVATE
+            | winuser::SWP_FRAMECHANGED,
+        );
+    }
+
     Ok(win)
 }
 
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use {Event, LogicalSize, WindowEvent, WindowId};
+
+/// Holds back `Resized` events that differ from the last reported size by less than a threshold.
+///
+/// The latest size held back for a window still has to be reported with `take_held` before any
+/// other event, and at the end of a batch of events, so that the reported size catches up once the
+/// window stops being resized.
+#[derive(Debug, Default)]
+pub struct ResizeDebounce {
+    threshold: f64,
+    last_sizes: HashMap<WindowId, LogicalSize>,
+    held_sizes: HashMap<WindowId, LogicalSize>,
+}
+
+impl ResizeDebounce {
+    pub fn set_threshold(&mut self, threshold: f64) {
+        self.threshold = threshold;
+        if threshold <= 0.0 {
+            self.last_sizes.clear();
+        }
+    }
+
+    /// Returns whether `event` should be delivered to the user.
+    pub fn filter<T>(&mut self, event: &Event<T>) -> bool {
+        if self.threshold <= 0.0 {
+            return true;
+        }
+        match *event {
+            Event::WindowEvent { window_id, event: WindowEvent::Resized(size) } => {
+                if let Some(last_size) = self.last_sizes.get(&window_id) {
+                    let delta_width = (size.width - last_size.width).abs();
+                    let delta_height = (size.height - last_size.height).abs();
+                    if delta_width < self.threshold && delta_height < self.threshold {
+                        self.held_sizes.insert(window_id, size);
+                        return false;
+                    }
+                }
+                self.held_sizes.remove(&window_id);
+                self.last_sizes.insert(window_id, size);
+                true
+            },
+            Event::WindowEvent { window_id, event: WindowEvent::Destroyed } => {
+                self.last_sizes.remove(&window_id);
+                self.held_sizes.remove(&window_id);
+                true
+            },
+            _ => true,
+        }
+    }
+
+    /// Returns a `Resized` event for one of the windows whose latest size was held back, which then
+    /// counts as reported.
+    pub fn take_held<T>(&mut self) -> Option<Event<T>> {
+        let window_id = match self.held_sizes.keys().next() {
+            Some(&window_id) => window_id,
+            None => return None,
+        };
+        let size = self.held_sizes.remove(&window_id).unwrap();
+        self.last_sizes.insert(window_id, size);
+        Some(Event::WindowEvent { window_id, event: WindowEvent::Resized(size) })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::ResizeDebounce;
+    use {Event, LogicalSize, WindowEvent, WindowId};
+
+    fn resized(width: f64, height: f64) -> Event {
+        let window_id = unsafe { WindowId::dummy() };
+        Event::WindowEvent { window_id, event: WindowEvent::Resized(LogicalSize::new(width, height)) }
+    }
+
+    fn held_size(debounce: &mut ResizeDebounce) -> Option<LogicalSize> {
+        match debounce.take_held::<()>() {
+            Some(Event::WindowEvent { event: WindowEvent::Resized(size), .. }) => Some(size),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn small_resizes_are_held_until_taken() {
+        let mut debounce = ResizeDebounce::default();
+        debounce.set_threshold(4.0);
+        assert!(debounce.filter(&resized(800.0, 600.0)));
+        assert!(!debounce.filter(&resized(801.0, 600.0)));
+        assert!(!debounce.filter(&resized(802.0, 601.0)));
+        // Only the latest size is reported, and only once.
+        assert_eq!(held_size(&mut debounce), Some(LogicalSize::new(802.0, 601.0)));
+        assert_eq!(held_size(&mut debounce), None);
+        // The held size counts as reported.
+        assert!(!debounce.filter(&resized(805.0, 601.0)));
+        assert!(debounce.filter(&resized(806.0, 601.0)));
+    }
+
+    #[test]
+    fn large_resize_replaces_held_size() {
+        let mut debounce = ResizeDebounce::default();
+        debounce.set_threshold(4.0);
+        assert!(debounce.filter(&resized(800.0, 600.0)));
+        assert!(!debounce.filter(&resized(802.0, 600.0)));
+        assert!(debounce.filter(&resized(900.0, 600.0)));
+        assert_eq!(held_size(&mut debounce), None);
+    }
+
+    #[test]
+    fn held_size_is_dropped_with_window() {
+        let mut debounce = ResizeDebounce::default();
+        debounce.set_threshold(4.0);
+        assert!(debounce.filter(&resized(800.0, 600.0)));
+        assert!(!debounce.filter(&resized(802.0, 600.0)));
+        let window_id = unsafe { WindowId::dummy() };
+        let destroyed: Event = Event::WindowEvent { window_id, event: WindowEvent::Destroyed };
+        assert!(debounce.filter(&destroyed));
+        assert_eq!(held_size(&mut debounce), None);
+    }
+}
@@ -0,0 +1,353 @@
+use std::env;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use platform;
+
+pub use self::imp::Server;
+
+/// Claims `app_id` for this instance, or sends the arguments of this instance to the one that
+/// claimed it first.
+///
+/// Returns `None` if another instance is running, in which case it was sent the arguments.
+pub fn claim(app_id: &str) -> io::Result<Option<Server>> {
+    let endpoint = imp::endpoint(app_id);
+    match imp::connect_or_listen(&endpoint)? {
+        imp::Instance::Primary(server) => Ok(Some(server)),
+        imp::Instance::Secondary(mut connection) => {
+            let args: Vec<String> = env::args_os()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect();
+            connection.write_all(&encode_args(&args))?;
+            Ok(None)
+        },
+    }
+}
+
+/// Receives the arguments of the instances launched after this one, on a thread that wakes up
+/// the `EventsLoop` for each of them.
+pub struct InstanceListener {
+    receiver: Receiver<Vec<String>>,
+    endpoint: imp::Endpoint,
+    closed: Arc<AtomicBool>,
+}
+
+impl InstanceListener {
+    pub fn spawn(mut server: Server, proxy: platform::EventsLoopProxy) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let endpoint = server.endpoint().clone();
+        let closed = Arc::new(AtomicBool::new(false));
+        {
+            let closed = closed.clone();
+            thread::spawn(move || {
+                while let Ok(mut connection) = server.accept() {
+                    if closed.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    // Each client is read on its own thread, so that one that connects and never
+                    // finishes writing doesn't keep the others from being heard.
+                    let sender = sender.clone();
+                    let proxy = proxy.clone();
+                    thread::spawn(move || {
+                        // A client that went away before sending anything isn't a launch.
+                        let mut data = Vec::new();
+                        if connection.read_to_end(&mut data).is_err() || data.is_empty() {
+                            return;
+                        }
+                        if sender.send(decode_args(&data)).is_ok() {
+                            let _ = proxy.wakeup();
+                        }
+                    });
+                }
+            });
+        }
+        InstanceListener { receiver, endpoint, closed }
+    }
+
+    pub fn try_recv(&self) -> Option<Vec<String>> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for InstanceListener {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Relaxed);
+        // Unblocks the thread, which is waiting for a connection.
+        let _ = imp::connect(&self.endpoint);
+        imp::release(&self.endpoint);
+    }
+}
+
+// Each argument is terminated by a NUL byte, which can't appear in arguments.
+fn encode_args(args: &[String]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for arg in args {
+        data.extend_from_slice(arg.as_bytes());
+        data.push(0);
+    }
+    data
+}
+
+fn decode_args(data: &[u8]) -> Vec<String> {
+    let mut args: Vec<String> = data
+        .split(|&byte| byte == 0)
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+    // What follows the last terminator is empty.
+    args.pop();
+    args
+}
+
+// A Unix domain socket is used on every Unix platform, since it works the same whether the
+// application runs on X11 or Wayland.
+#[cfg(all(unix, not(target_os = "emscripten")))]
+mod imp {
+    use std::{env, fs, io};
+    use std::fs::{File, OpenOptions};
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::PathBuf;
+
+    use libc;
+
+    pub type Endpoint = PathBuf;
+
+    pub enum Instance {
+        Primary(Server),
+        Secondary(UnixStream),
+    }
+
+    pub struct Server {
+        path: PathBuf,
+        listener: UnixListener,
+    }
+
+    impl Server {
+        pub fn endpoint(&self) -> &Endpoint {
+            &self.path
+        }
+
+        pub fn accept(&mut self) -> io::Result<UnixStream> {
+            self.listener.accept().map(|(stream, _)| stream)
+        }
+    }
+
+    pub fn endpoint(app_id: &str) -> Endpoint {
+        // Unlike the temporary directory, `XDG_RUNTIME_DIR` is private to the user.
+        match env::var_os("XDG_RUNTIME_DIR") {
+            Some(dir) => PathBuf::from(dir).join(format!("{}.instance", app_id)),
+            None => {
+                let uid = unsafe { libc::getuid() };
+                env::temp_dir().join(format!("{}-{}.instance", app_id, uid))
+            },
+        }
+    }
+
+    pub fn connect_or_listen(path: &Endpoint) -> io::Result<Instance> {
+        // Removing a socket left behind by a crashed instance and binding a new one isn't atomic,
+        // so instances started at the same time take turns. The lock goes away with the file
+        // descriptor, even if the process crashes.
+        let _lock = lock(path)?;
+        if let Ok(stream) = connect(path) {
+            return Ok(Instance::Secondary(stream));
+        }
+        // Nobody is listening, so the socket was left behind by an instance that crashed.
+        let _ = fs::remove_file(path);
+        let listener = UnixListener::bind(path)?;
+        Ok(Instance::Primary(Server { path: path.clone(), listener }))
+    }
+
+    // The lock file is left in place, since removing it would let an instance lock a new file
+    // while another one still holds the lock on the old one.
+    fn lock(path: &Endpoint) -> io::Result<File> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .open(path.with_extension("lock"))?;
+        loop {
+            if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+                return Ok(file);
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+
+    pub fn connect(path: &Endpoint) -> io::Result<UnixStream> {
+        UnixStream::connect(path)
+    }
+
+    pub fn release(path: &Endpoint) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+// A named pipe is used on Windows. `FILE_FLAG_FIRST_PIPE_INSTANCE` makes creating the pipe fail
+// if another instance already did.
+#[cfg(windows)]
+mod imp {
+    use std::{io, mem, ptr};
+    use std::ffi::OsStr;
+    use std::fs::{File, OpenOptions};
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::FromRawHandle;
+
+    use winapi::shared::minwindef::DWORD;
+    use winapi::shared::winerror::{ERROR_ACCESS_DENIED, ERROR_NO_DATA, ERROR_PIPE_CONNECTED};
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+    use winapi::um::namedpipeapi::ConnectNamedPipe;
+    use winapi::um::winbase::{
+        self,
+        FILE_FLAG_FIRST_PIPE_INSTANCE,
+        PIPE_ACCESS_INBOUND,
+        PIPE_TYPE_BYTE,
+        PIPE_UNLIMITED_INSTANCES,
+        PIPE_WAIT,
+    };
+    use winapi::um::winnt::HANDLE;
+
+    pub type Endpoint = String;
+
+    pub enum Instance {
+        Primary(Server),
+        Secondary(File),
+    }
+
+    pub struct Server {
+        name: String,
+        // The instance of the pipe waiting for the next client.
+        handle: HANDLE,
+    }
+
+    unsafe impl Send for Server {}
+
+    impl Server {
+        pub fn endpoint(&self) -> &Endpoint {
+            &self.name
+        }
+
+        pub fn accept(&mut self) -> io::Result<File> {
+            if unsafe { ConnectNamedPipe(self.handle, ptr::null_mut()) } == 0 {
+                // The client connected before we started waiting, and may even be gone already.
+                match unsafe { GetLastError() } {
+                    ERROR_PIPE_CONNECTED | ERROR_NO_DATA => (),
+                    _ => return Err(io::Error::last_os_error()),
+                }
+            }
+            // A new instance of the pipe takes over, so that the next client can connect while
+            // this one is being read from.
+            let next = create_pipe(&self.name, 0)?;
+            let connected = mem::replace(&mut self.handle, next);
+            Ok(unsafe { File::from_raw_handle(connected as _) })
+        }
+    }
+
+    impl Drop for Server {
+        fn drop(&mut self) {
+            unsafe { CloseHandle(self.handle) };
+        }
+    }
+
+    pub fn endpoint(app_id: &str) -> Endpoint {
+        format!(r"\\.\pipe\{}", app_id)
+    }
+
+    pub fn connect_or_listen(name: &Endpoint) -> io::Result<Instance> {
+        match create_pipe(name, FILE_FLAG_FIRST_PIPE_INSTANCE) {
+            Ok(handle) => Ok(Instance::Primary(Server { name: name.clone(), handle })),
+            Err(ref err) if err.raw_os_error() == Some(ERROR_ACCESS_DENIED as i32) => {
+                connect(name).map(Instance::Secondary)
+            },
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn connect(name: &Endpoint) -> io::Result<File> {
+        OpenOptions::new().write(true).open(name)
+    }
+
+    pub fn release(_name: &Endpoint) {
+        // The pipe goes away with its last handle.
+    }
+
+    fn create_pipe(name: &str, flags: DWORD) -> io::Result<HANDLE> {
+        let name: Vec<u16> = OsStr::new(name)
+            .encode_wide()
+            .chain(Some(0).into_iter())
+            .collect();
+        let handle = unsafe {
+            winbase::CreateNamedPipeW(
+                name.as_ptr(),
+                PIPE_ACCESS_INBOUND | flags,
+                PIPE_TYPE_BYTE | PIPE_WAIT,
+                PIPE_UNLIMITED_INSTANCES,
+                0,
+                4096,
+                0,
+                ptr::null_mut(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(handle)
+        }
+    }
+}
+
+#[cfg(target_os = "emscripten")]
+mod imp {
+    use std::io;
+
+    pub type Endpoint = ();
+
+    pub enum Instance {
+        Primary(Server),
+        Secondary(io::Sink),
+    }
+
+    pub struct Server;
+
+    impl Server {
+        pub fn endpoint(&self) -> &Endpoint {
+            &()
+        }
+
+        pub fn accept(&mut self) -> io::Result<io::Empty> {
+            Err(unsupported())
+        }
+    }
+
+    pub fn endpoint(_app_id: &str) -> Endpoint {}
+
+    pub fn connect_or_listen(_endpoint: &Endpoint) -> io::Result<Instance> {
+        Err(unsupported())
+    }
+
+    pub fn connect(_endpoint: &Endpoint) -> io::Result<io::Sink> {
+        Err(unsupported())
+    }
+
+    pub fn release(_endpoint: &Endpoint) {}
+
+    fn unsupported() -> io::Error {
+        io::Error::new(io::ErrorKind::Other, "single instance applications aren't supported")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_args, encode_args};
+
+    #[test]
+    fn args_round_trip() {
+        let args = vec!["app".to_owned(), String::new(), "--open=a b".to_owned()];
+        assert_eq!(decode_args(&encode_args(&args)), args);
+    }
+}
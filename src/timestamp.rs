@@ -0,0 +1,95 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Converts the timestamps of input events to durations since the creation of the `EventsLoop`.
+///
+/// Most platforms timestamp input events in milliseconds from an arbitrary epoch, such as the
+/// start of the X server or the boot of the system, with a 32-bit counter that wraps around every
+/// 49 days or so. The platform time matching the creation of the loop is estimated from the first
+/// event converted, and the wraparounds are undone by comparing the timestamps to the time that
+/// actually elapsed since then.
+#[derive(Debug)]
+pub struct EventTimestamps {
+    created: Instant,
+    epoch: Mutex<Option<u32>>,
+}
+
+impl EventTimestamps {
+    pub fn new() -> Self {
+        EventTimestamps {
+            created: Instant::now(),
+            epoch: Mutex::new(None),
+        }
+    }
+
+    /// Converts a timestamp in milliseconds, from the platform's epoch.
+    pub fn from_millis(&self, time: u32) -> Duration {
+        let epoch = *self.epoch.lock().unwrap().get_or_insert_with(|| {
+            time.wrapping_sub(as_millis(self.created.elapsed()) as u32)
+        });
+        Duration::from_millis(unwrap_millis(epoch, time, as_millis(self.created.elapsed())))
+    }
+
+    /// Returns the current time, for platforms that don't timestamp their events.
+    pub fn now(&self) -> Duration {
+        self.created.elapsed()
+    }
+}
+
+fn as_millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + (duration.subsec_nanos() / 1_000_000) as u64
+}
+
+// Returns the milliseconds from `epoch` to `time`, which have wrapped around every 2^32 ms. Out of
+// the times that `time` can stand for, this picks the one closest to `now_ms`, the milliseconds
+// elapsed since the epoch, since events are converted soon after they occur.
+fn unwrap_millis(epoch: u32, time: u32, now_ms: u64) -> u64 {
+    const PERIOD: u64 = 1 << 32;
+    let elapsed_ms = (now_ms & !(PERIOD - 1)) + time.wrapping_sub(epoch) as u64;
+    if elapsed_ms > now_ms + PERIOD / 2 {
+        // Events that were queued before the first one converted can predate the epoch, in which
+        // case this saturates.
+        elapsed_ms.saturating_sub(PERIOD)
+    } else if elapsed_ms + PERIOD / 2 < now_ms {
+        elapsed_ms + PERIOD
+    } else {
+        elapsed_ms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unwrap_millis;
+
+    const PERIOD: u64 = 1 << 32;
+
+    #[test]
+    fn timestamps_keep_increasing_across_wraparounds() {
+        let epoch = u32::max_value() - 1000;
+        assert_eq!(unwrap_millis(epoch, epoch, 0), 0);
+        assert_eq!(unwrap_millis(epoch, epoch.wrapping_add(500), 510), 500);
+        // The platform's counter wraps around shortly after the loop is created.
+        assert_eq!(unwrap_millis(epoch, 2000, 3005), 3001);
+        // 25 days later, past the point where the difference overflows an `i32`.
+        let days_25 = 25 * 24 * 3600 * 1000;
+        assert_eq!(unwrap_millis(epoch, epoch.wrapping_add(days_25 as u32), days_25 + 4), days_25);
+        // And past the first wraparound of the difference itself, after about 49.7 days.
+        let days_50 = 50 * 24 * 3600 * 1000;
+        assert!(days_50 > PERIOD);
+        assert_eq!(unwrap_millis(epoch, epoch.wrapping_add(days_50 as u32), days_50 + 2), days_50);
+        assert_eq!(unwrap_millis(epoch, epoch.wrapping_add(u32::max_value()), PERIOD + 3), PERIOD - 1);
+    }
+
+    #[test]
+    fn timestamps_slightly_ahead_of_now() {
+        // The platform's clock can drift from ours.
+        assert_eq!(unwrap_millis(0, 10, 5), 10);
+        assert_eq!(unwrap_millis(0, 5, PERIOD - 2), PERIOD + 5);
+    }
+
+    #[test]
+    fn timestamps_before_epoch_are_zero() {
+        assert_eq!(unwrap_millis(1000, 990, 0), 0);
+        assert_eq!(unwrap_millis(1000, 990, 20), 0);
+    }
+}
@@ -0,0 +1,41 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// The events sent with `EventsLoopProxy::send_event`. Each of them comes with a wakeup of the
+/// backend, and they're delivered when the resulting `Awakened` event is received.
+pub struct UserEvents<T> {
+    sender: Sender<T>,
+    receiver: Receiver<T>,
+    // Set by `EventsLoopProxy::wakeup`, so that its wakeups can be told apart from those that only
+    // carry user events.
+    wakeup_requested: Arc<AtomicBool>,
+}
+
+impl<T> UserEvents<T> {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        UserEvents {
+            sender,
+            receiver,
+            wakeup_requested: Default::default(),
+        }
+    }
+
+    pub fn sender(&self) -> Sender<T> {
+        self.sender.clone()
+    }
+
+    pub fn wakeup_requested(&self) -> Arc<AtomicBool> {
+        self.wakeup_requested.clone()
+    }
+
+    pub fn try_recv(&self) -> Option<T> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Returns whether `EventsLoopProxy::wakeup` was called since the last call.
+    pub fn take_wakeup_request(&self) -> bool {
+        self.wakeup_requested.swap(false, Ordering::Relaxed)
+    }
+}
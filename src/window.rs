@@ -1,16 +1,26 @@
+use std::any::Any;
 use std::collections::vec_deque::IntoIter as VecDequeIter;
+use std::vec::IntoIter as VecIntoIter;
+use std::sync::Mutex;
+use std::time::Duration;
 
 use {
     CreationError,
     CursorState,
     EventsLoop,
+    ExternalError,
     Icon,
+    Insets,
     LogicalPosition,
     LogicalSize,
     MouseCursor,
     PhysicalPosition,
     PhysicalSize,
     platform,
+    ResizeDirection,
+    ScaleFactorPolicy,
+    StackOrder,
+    UserAttentionType,
     Window,
     WindowBuilder,
     WindowId,
@@ -41,6 +51,8 @@ impl WindowBuilder {
     }
 
     /// Sets a maximum dimension size for the window
+    ///
+    /// If it's smaller than the minimum in either dimension, it's raised to the minimum.
     #[inline]
     pub fn with_max_dimensions(mut self, max_size: LogicalSize) -> WindowBuilder {
         self.window.max_dimensions = Some(max_size);
@@ -70,11 +82,15 @@ impl WindowBuilder {
         self
     }
 
-    /// Sets the window fullscreen state. None means a normal window, Some(MonitorId)
-    /// means a fullscreen window on that specific monitor
+    /// Sets the window fullscreen state. None means a normal window, Some(Fullscreen)
+    /// means a fullscreen window on the monitor it describes.
+    ///
+    /// See `Fullscreen` for the difference between borderless and exclusive fullscreen.
+    /// `Fullscreen::Borderless(None)` makes the window fullscreen on the monitor it's created on,
+    /// saving you from looking up a `MonitorId`.
     #[inline]
-    pub fn with_fullscreen(mut self, monitor: Option<MonitorId>) -> WindowBuilder {
-        self.window.fullscreen = monitor;
+    pub fn with_fullscreen(mut self, fullscreen: Option<Fullscreen>) -> WindowBuilder {
+        self.window.fullscreen = fullscreen;
         self
     }
 
@@ -93,12 +109,58 @@ impl WindowBuilder {
     }
 
     /// Sets whether the background of the window should be transparent.
+    ///
+    /// This can't be changed once the window has been created.
+    ///
+    /// Every platform composites transparent windows with premultiplied alpha, so what's drawn in
+    /// the window must have its color channels already multiplied by its alpha channel. Drawing
+    /// with straight alpha gives partially transparent areas dark edges, since their colors end
+    /// up being multiplied by the alpha channel twice.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** The window's visual can't be changed after creation, so transparency requires
+    ///   creating the window with a 32-bit ARGB visual using `WindowBuilderExt::with_x11_visual`.
+    ///   Compositing managers treat the contents of ARGB visuals as premultiplied.
+    /// - **Wayland:** The ARGB formats of `wl_shm` and EGL surfaces are premultiplied. The
+    ///   decorations are opaque, so they aren't blended with the window's contents.
+    /// - **Windows:** Transparency is done with `DwmEnableBlurBehindWindow`, and DWM treats the
+    ///   window's contents as premultiplied.
+    /// - **macOS:** Core Animation treats the window's contents as premultiplied.
     #[inline]
     pub fn with_transparency(mut self, transparent: bool) -> WindowBuilder {
         self.window.transparent = transparent;
         self
     }
 
+    /// Sets how the window is resized when its DPI factor changes. By default, the logical size
+    /// is preserved.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This has no effect on iOS, Android and Emscripten.
+    #[inline]
+    pub fn with_scale_factor_policy(mut self, policy: ScaleFactorPolicy) -> WindowBuilder {
+        self.window.scale_factor_policy = policy;
+        self
+    }
+
+    /// Sets whether the contents of the window should be protected from being captured by other
+    /// applications.
+    ///
+    /// Unlike calling `Window::set_content_protected` after creation, this ensures that the
+    /// contents can't be captured before the protection is applied.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This only has an effect on Windows and macOS. Use `Window::set_content_protected` to find
+    /// out whether the platform supports it.
+    #[inline]
+    pub fn with_content_protected(mut self, protected: bool) -> WindowBuilder {
+        self.window.content_protected = protected;
+        self
+    }
+
     /// Sets whether the window should have a border, a title bar, etc.
     #[inline]
     pub fn with_decorations(mut self, decorations: bool) -> WindowBuilder {
@@ -143,23 +205,42 @@ impl WindowBuilder {
     /// Error should be very rare and only occur in case of permission denied, incompatible system,
     /// out of memory, etc.
     #[inline]
-    pub fn build(mut self, events_loop: &EventsLoop) -> Result<Window, CreationError> {
+    pub fn build<T>(mut self, events_loop: &EventsLoop<T>) -> Result<Window, CreationError> {
+        let platform_events_loop = match events_loop.events_loop {
+            Some(ref events_loop) => events_loop,
+            None => return Err(CreationError::OsError("a headless `EventsLoop` can't create windows".to_owned())),
+        };
         self.window.dimensions = Some(self.window.dimensions.unwrap_or_else(|| {
-            if let Some(ref monitor) = self.window.fullscreen {
-                // resizing the window to the dimensions of the monitor when fullscreen
-                LogicalSize::from_physical(monitor.get_dimensions(), 1.0)
-            } else {
+            // resizing the window to the dimensions of the monitor when fullscreen
+            let dimensions = match self.window.fullscreen {
+                Some(Fullscreen::Borderless(Some(ref monitor))) => Some(monitor.get_dimensions()),
+                Some(Fullscreen::Exclusive(_, video_mode)) => Some(video_mode.get_dimensions()),
+                _ => None,
+            };
+            match dimensions {
+                Some(dimensions) => LogicalSize::from_physical(dimensions, 1.0),
                 // default dimensions
-                (1024, 768).into()
+                None => (1024, 768).into(),
             }
         }));
 
+        let dimension_limits = (self.window.min_dimensions, self.window.max_dimensions);
+        self.window.max_dimensions = clamp_max_dimensions(
+            self.window.min_dimensions,
+            self.window.max_dimensions,
+        );
+
         // building
         platform::Window::new(
-            &events_loop.events_loop,
+            platform_events_loop,
             self.window,
             self.platform_specific,
-        ).map(|window| Window { window })
+        ).map(|window| Window {
+            window,
+            user_data: None,
+            dimension_limits: Mutex::new(dimension_limits),
+            cursor: Mutex::new((UserCursor::Standard(MouseCursor::Default), 0)),
+        })
     }
 }
 
@@ -171,7 +252,7 @@ impl Window {
     /// Error should be very rare and only occur in case of permission denied, incompatible system,
     ///  out of memory, etc.
     #[inline]
-    pub fn new(events_loop: &EventsLoop) -> Result<Window, CreationError> {
+    pub fn new<T>(events_loop: &EventsLoop<T>) -> Result<Window, CreationError> {
         let builder = WindowBuilder::new();
         builder.build(events_loop)
     }
@@ -206,6 +287,26 @@ impl Window {
         self.window.hide()
     }
 
+    /// Requests a `WindowEvent::RedrawRequested` event, so that the application draws the
+    /// contents of the window only when needed instead of every time it polls for events.
+    ///
+    /// Requests are coalesced, so at most one `RedrawRequested` is delivered for the window per
+    /// iteration of the events loop. A redraw requested while handling an event, including
+    /// `RedrawRequested` itself, is delivered on the next iteration. This can be called from any
+    /// thread, and wakes up `run_forever` if it's waiting for events. See
+    /// `EventsLoop::set_redraw_vsync` to deliver them no faster than the display refreshes.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** The event is delivered once there are no other messages to process, like
+    ///   `WM_PAINT`.
+    /// - **macOS:** The event is delivered when AppKit next draws the window.
+    /// - **iOS / Android:** Unsupported.
+    #[inline]
+    pub fn request_redraw(&self) {
+        self.window.request_redraw()
+    }
+
     /// Returns the position of the top-left hand corner of the window relative to the
     ///  top-left hand corner of the desktop.
     ///
@@ -236,6 +337,10 @@ impl Window {
     /// See `get_position` for more information about the coordinates.
     ///
     /// This is a no-op if the window has already been closed.
+    ///
+    /// On X11 and Windows, the `Moved` event caused by this call isn't emitted, so that code
+    /// saving the window's position when it's moved doesn't react to its own moves. If the window
+    /// manager places the window elsewhere, the position it chose is reported as usual.
     #[inline]
     pub fn set_position(&self, position: LogicalPosition) {
         self.window.set_position(position)
@@ -246,6 +351,8 @@ impl Window {
     /// The client area is the content of the window, excluding the title bar and borders.
     ///
     /// Converting the returned `LogicalSize` to `PhysicalSize` produces the size your framebuffer should be.
+    /// Prefer `get_inner_size_physical` for that, since it's the size reported by the system, so it isn't
+    /// subject to rounding.
     ///
     /// Returns `None` if the window no longer exists.
     #[inline]
@@ -253,6 +360,17 @@ impl Window {
         self.window.get_inner_size()
     }
 
+    /// Returns the physical size of the window's client area.
+    ///
+    /// This is the size your framebuffer should be. Use `get_inner_size` instead for laying out UI, which
+    /// should stay the same size across DPI factors.
+    ///
+    /// Returns `None` if the window no longer exists.
+    #[inline]
+    pub fn get_inner_size_physical(&self) -> Option<PhysicalSize> {
+        self.window.get_inner_size_physical().map(Into::into)
+    }
+
     /// Returns the logical size of the entire window.
     ///
     /// These dimensions include the title bar and borders. If you don't want that (and you usually don't),
@@ -264,6 +382,34 @@ impl Window {
         self.window.get_outer_size()
     }
 
+    /// Returns the physical size of the entire window.
+    ///
+    /// These dimensions include the title bar and borders. If you don't want that (and you usually don't),
+    /// use `get_inner_size_physical` instead.
+    ///
+    /// Returns `None` if the window no longer exists.
+    #[inline]
+    pub fn get_outer_size_physical(&self) -> Option<PhysicalSize> {
+        self.window.get_outer_size_physical().map(Into::into)
+    }
+
+    /// Returns the space taken up by the title bar and borders on each side of the window, which is
+    /// the difference between the outer and inner size. This is zero on every side if the window
+    /// isn't decorated.
+    ///
+    /// Returns `None` if the window no longer exists.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **macOS:** This is the area not covered by the content layout rect, which includes the
+    ///   title bar even if the content view is full-size.
+    /// - **Wayland:** These are the sizes of the decorations drawn by winit.
+    /// - **Android / iOS / Emscripten:** Always zero.
+    #[inline]
+    pub fn get_decoration_insets(&self) -> Option<Insets> {
+        self.window.get_decoration_insets()
+    }
+
     /// Modifies the inner size of the window.
     ///
     /// See `get_inner_size` for more information about the values.
@@ -275,15 +421,44 @@ impl Window {
     }
 
     /// Sets a minimum dimension size for the window.
+    ///
+    /// If the minimum is larger than the maximum in either dimension, the maximum is raised to the
+    /// minimum, since platforms behave erratically when given contradictory limits. The maximum
+    /// given to `set_max_dimensions` is restored once the minimum is lowered again.
     #[inline]
     pub fn set_min_dimensions(&self, dimensions: Option<LogicalSize>) {
-        self.window.set_min_dimensions(dimensions)
+        let mut limits = self.dimension_limits.lock().unwrap();
+        let old_min = limits.0;
+        limits.0 = dimensions;
+        self.apply_dimension_limits(old_min, *limits);
     }
 
     /// Sets a maximum dimension size for the window.
+    ///
+    /// If the maximum is smaller than the minimum in either dimension, it's raised to the minimum.
     #[inline]
     pub fn set_max_dimensions(&self, dimensions: Option<LogicalSize>) {
-        self.window.set_max_dimensions(dimensions)
+        let mut limits = self.dimension_limits.lock().unwrap();
+        let old_min = limits.0;
+        limits.1 = dimensions;
+        self.apply_dimension_limits(old_min, *limits);
+    }
+
+    fn apply_dimension_limits(
+        &self,
+        old_min: Option<LogicalSize>,
+        (min, max): (Option<LogicalSize>, Option<LogicalSize>),
+    ) {
+        let max = clamp_max_dimensions(min, max);
+        // The limits are set one after the other, so we pick the order that doesn't make the new
+        // maximum smaller than the old minimum in between.
+        if clamp_max_dimensions(old_min, max) == max {
+            self.window.set_max_dimensions(max);
+            self.window.set_min_dimensions(min);
+        } else {
+            self.window.set_min_dimensions(min);
+            self.window.set_max_dimensions(max);
+        }
     }
 
     /// Sets whether the window is resizable or not.
@@ -301,6 +476,19 @@ impl Window {
         self.window.set_resizable(resizable)
     }
 
+    /// Returns whether the window is resizable or not.
+    ///
+    /// This reflects the value last passed to `set_resizable` (or `WindowBuilder::with_resizable`),
+    /// rather than querying the window manager.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Always returns `false` on Android, iOS and Emscripten.
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        self.window.is_resizable()
+    }
+
     /// Returns the DPI factor that can be used to map logical pixels to physical pixels, and vice versa.
     ///
     /// See the [`dpi`](dpi/index.html) module for more information.
@@ -316,35 +504,281 @@ impl Window {
 
     /// Modifies the mouse cursor of the window.
     /// Has no effect on Android.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** The cursor theme and size are read from the `XCURSOR_THEME` and `XCURSOR_SIZE`
+    ///   environment variables, and the cursor is scaled according to the window's DPI factor.
+    ///
+    /// While the wait cursor is shown by `set_wait_cursor`, the new cursor only appears once it's
+    /// turned off.
     #[inline]
     pub fn set_cursor(&self, cursor: MouseCursor) {
-        self.window.set_cursor(cursor);
+        let _ = self.set_user_cursor(UserCursor::Standard(cursor));
+    }
+
+    /// Shows `MouseCursor::Wait` while the application is busy, then restores the previous cursor.
+    ///
+    /// Calls nest: the previous cursor is restored once every `set_wait_cursor(true)` has been
+    /// matched by a `set_wait_cursor(false)`, so a blocking operation can turn on the wait cursor
+    /// without knowing whether its caller already did. Extra `set_wait_cursor(false)` calls are
+    /// ignored. The cursor restored is the one last set, whether with `set_cursor`,
+    /// `set_cursor_image`, `set_animated_cursor` or `WindowExt::set_cursor_by_name`, even if that
+    /// happened while the wait cursor was shown.
+    pub fn set_wait_cursor(&self, busy: bool) {
+        let mut state = self.cursor.lock().unwrap();
+        if busy {
+            state.1 += 1;
+            if state.1 == 1 {
+                self.window.set_cursor(MouseCursor::Wait);
+            }
+        } else if state.1 > 0 {
+            state.1 -= 1;
+            if state.1 == 0 {
+                // A cursor set while the wait cursor was shown hasn't been checked yet.
+                if self.apply_cursor(&state.0).is_err() {
+                    state.0 = UserCursor::Standard(MouseCursor::Default);
+                    self.window.set_cursor(MouseCursor::Default);
+                }
+            }
+        }
+    }
+
+    /// Sets an animated cursor, which shows each frame for its duration and then loops.
+    ///
+    /// `hotspot` is the position of the click point within the frames, in pixels from their
+    /// top-left corner. Calling `set_cursor` replaces the animated cursor.
+    ///
+    /// While the wait cursor is shown by `set_wait_cursor`, the animated cursor only appears once
+    /// it's turned off.
+    ///
+    /// Returns an error if `frames` is empty, or if `hotspot` lies outside of one of the frames.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **macOS:** Animated cursors aren't supported, so the first frame is shown as a static
+    ///   cursor.
+    /// - **Wayland:** The events loop shows each frame in turn, so the cursor only animates while
+    ///   it's running.
+    /// - **iOS / Android / Emscripten:** Unsupported.
+    pub fn set_animated_cursor(
+        &self,
+        frames: Vec<(Icon, Duration)>,
+        hotspot: (u32, u32),
+    ) -> Result<(), ExternalError> {
+        if frames.is_empty() {
+            return Err(ExternalError::Os("an animated cursor needs at least one frame".to_owned()));
+        }
+        if frames.iter().any(|&(ref icon, _)| hotspot.0 >= icon.width || hotspot.1 >= icon.height) {
+            return Err(ExternalError::Os(
+                "the hotspot of an animated cursor must lie within each of its frames".to_owned(),
+            ));
+        }
+        self.set_user_cursor(UserCursor::Animated(frames, hotspot))
+    }
+
+    /// Sets a cursor drawn from an image, such as a brush outline in a drawing application.
+    ///
+    /// `rgba` holds `width * height` pixels of 8-bit RGBA, row by row from the top-left corner,
+    /// like `Icon::from_rgba`. The color channels are *not* premultiplied by the alpha channel:
+    /// winit premultiplies them itself where the platform requires it. `hotspot` is the position
+    /// of the click point within the image, in pixels from its top-left corner. Calling
+    /// `set_cursor` replaces the image. While the wait cursor is shown by `set_wait_cursor`, the
+    /// image only appears once it's turned off.
+    ///
+    /// The image is given at logical size, and winit scales it and the hotspot by the DPI factor
+    /// of the window, so that the cursor has the same size and clicks land on the same point on
+    /// every monitor.
+    ///
+    /// Returns an error if the length of `rgba` doesn't match the dimensions, or if `hotspot` lies
+    /// outside of the image.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** The cursor is freed as soon as it's replaced, so setting an image again creates
+    ///   a new cursor. It's scaled again when the window moves to a monitor with another DPI
+    ///   factor.
+    /// - **Wayland:** The image is drawn at the buffer scale of the window, so it stays crisp on
+    ///   hidpi outputs.
+    /// - **Windows:** The image is scaled by the DPI factor the window has when the cursor is set.
+    /// - **macOS:** AppKit scales the image itself.
+    /// - **iOS / Android / Emscripten:** Unsupported.
+    pub fn set_cursor_image(
+        &self,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+        hotspot: (u32, u32),
+    ) -> Result<(), ExternalError> {
+        let image = Icon::from_rgba(rgba.to_vec(), width, height)
+            .map_err(|err| ExternalError::Os(err.to_string()))?;
+        if hotspot.0 >= width || hotspot.1 >= height {
+            return Err(ExternalError::Os("the hotspot of a cursor image must lie within it".to_owned()));
+        }
+        self.set_user_cursor(UserCursor::Image(image, hotspot))
+    }
+
+    // Remembers the cursor, so that `set_wait_cursor(false)` can restore it, and shows it unless
+    // the wait cursor is shown.
+    pub(crate) fn set_user_cursor(&self, cursor: UserCursor) -> Result<(), ExternalError> {
+        let mut state = self.cursor.lock().unwrap();
+        if state.1 == 0 {
+            self.apply_cursor(&cursor)?;
+        }
+        state.0 = cursor;
+        Ok(())
+    }
+
+    fn apply_cursor(&self, cursor: &UserCursor) -> Result<(), ExternalError> {
+        match *cursor {
+            UserCursor::Standard(cursor) => {
+                self.window.set_cursor(cursor);
+                Ok(())
+            },
+            UserCursor::Image(ref image, hotspot) => self.window.set_cursor_image(image.clone(), hotspot),
+            UserCursor::Animated(ref frames, hotspot) => self.window.set_animated_cursor(frames.clone(), hotspot),
+            #[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd"))]
+            UserCursor::Named(ref name) => self.window.set_cursor_by_name(name),
+        }
     }
 
     /// Changes the position of the cursor in window coordinates.
+    ///
+    /// This doesn't emit a `CursorMoved` event, so that recentering the cursor doesn't look like a
+    /// movement of the mouse. Movements made by the user afterwards are reported as usual.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** The cursor can't be moved. While it's grabbed, this sets where the cursor
+    ///   will reappear once it's released, and returns `ExternalError::NotSupported` otherwise.
+    /// - **macOS:** While the cursor is grabbed, the mouse stays dissociated from it, so mouse
+    ///   input isn't suppressed for a fraction of a second after each move like it normally is.
+    /// - **iOS / Android / Emscripten:** Unsupported.
     #[inline]
-    pub fn set_cursor_position(&self, position: LogicalPosition) -> Result<(), ()> {
+    pub fn set_cursor_position(&self, position: LogicalPosition) -> Result<(), ExternalError> {
         self.window.set_cursor_position(position)
     }
 
     /// Sets how winit handles the cursor. See the documentation of `CursorState` for details.
     ///
     /// Has no effect on Android.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** `CursorState::Grab` locks the cursor in place, and requires the compositor to
+    ///   support the pointer constraints protocol. `DeviceEvent::MouseMotion` keeps being
+    ///   delivered while it's locked.
+    /// - **X11:** `CursorState::Grab` confines the cursor using XFixes pointer barriers when the
+    ///   server supports them, which unlike a pointer grab doesn't prevent the window manager
+    ///   from receiving input. Otherwise, the pointer is grabbed.
+    /// - **macOS:** `CursorState::Grab` dissociates the mouse from the cursor, which is
+    ///   reassociated when the grab is released, when the window loses focus and when it's
+    ///   dropped.
     #[inline]
-    pub fn set_cursor_state(&self, state: CursorState) -> Result<(), String> {
+    pub fn set_cursor_state(&self, state: CursorState) -> Result<(), ExternalError> {
         self.window.set_cursor_state(state)
     }
 
+    /// Returns `false` if the cursor was hidden with `CursorState::Hide`.
+    ///
+    /// This is the state last requested with `set_cursor_state`, rather than what the system is
+    /// currently showing. A `CursorState::Grab` counts as visible, even on platforms where the
+    /// grab hides the cursor.
+    #[inline]
+    pub fn is_cursor_visible(&self) -> bool {
+        self.window.is_cursor_visible()
+    }
+
+    /// Grabs the keyboard, so that the window receives all keyboard input, including shortcuts
+    /// that would otherwise be handled by the system or window manager (such as Alt+Tab).
+    ///
+    /// Returns an `Err` if the keyboard couldn't be grabbed.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** The grab is released when the window is hidden or destroyed. It's also released
+    ///   while the window doesn't have focus, and restored when it's focused again.
+    /// - **Windows:** Implemented with a low-level keyboard hook, which redirects the Windows keys,
+    ///   Alt+Tab, Alt+Esc and Ctrl+Esc to the window while it has focus. Ctrl+Alt+Del can't be
+    ///   grabbed. Only one window can grab the keyboard at a time.
+    /// - **Wayland / macOS / iOS / Android / Emscripten:** Unsupported.
+    #[inline]
+    pub fn set_keyboard_grab(&self, grab: bool) -> Result<(), ExternalError> {
+        self.window.set_keyboard_grab(grab)
+    }
+
+    /// Confines the cursor to the monitor the window is on, which keeps it from sliding onto another
+    /// monitor while playing a fullscreen game. Unlike `CursorState::Grab`, the cursor can still
+    /// move outside of the window.
+    ///
+    /// This isn't tied to the fullscreen state, so you have to call this after `set_fullscreen`,
+    /// and call it again with `false` when leaving fullscreen.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Requires the XFixes extension. The cursor isn't moved onto the monitor if it's
+    ///   elsewhere, and the confinement doesn't follow the window to another monitor.
+    /// - **Windows:** The confinement only applies while the window is focused.
+    /// - **Wayland / macOS / iOS / Android / Emscripten:** Unsupported.
+    #[inline]
+    pub fn confine_cursor_to_monitor(&self, confine: bool) -> Result<(), ExternalError> {
+        self.window.confine_cursor_to_monitor(confine)
+    }
+
+    /// Minimizes the window, or restores it to the maximized or normal state it was in before.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Only the compositor can restore a minimized window, so `false` returns
+    ///   `ExternalError::NotSupported`.
+    /// - **iOS / Android / Emscripten:** Unsupported.
+    #[inline]
+    pub fn set_minimized(&self, minimized: bool) -> Result<(), ExternalError> {
+        self.window.set_minimized(minimized)
+    }
+
     /// Sets the window to maximized or back
     #[inline]
     pub fn set_maximized(&self, maximized: bool) {
         self.window.set_maximized(maximized)
     }
 
-    /// Sets the window to fullscreen or back
+    /// Sets the window to fullscreen or back.
+    ///
+    /// Leaving exclusive fullscreen, or dropping the window while in it, restores the video mode
+    /// the monitor was using beforehand.
+    ///
+    /// Returns `ExternalError::NotSupported` if the platform can't make the window fullscreen in
+    /// the requested way, in which case the fullscreen state is left unchanged.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows / macOS / Wayland:** `Fullscreen::Exclusive` is unsupported.
+    /// - **macOS:** Moving a fullscreen window to another monitor is unsupported.
+    /// - **iOS / Android / Emscripten:** Unsupported.
     #[inline]
-    pub fn set_fullscreen(&self, monitor: Option<MonitorId>) {
-        self.window.set_fullscreen(monitor)
+    pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) -> Result<(), ExternalError> {
+        self.window.set_fullscreen(fullscreen)
+    }
+
+    /// Returns the fullscreen state that was applied by the last call to `set_fullscreen`, or
+    /// `None` if the window isn't fullscreen.
+    ///
+    /// This can differ from what was requested on X11: `Fullscreen::Exclusive` is returned only if
+    /// the video mode was actually switched, and is replaced with `Fullscreen::Borderless`
+    /// otherwise. It holds the mode the monitor is actually using, which the X server may have
+    /// substituted for the requested one. `Fullscreen::Borderless(None)` is returned with the
+    /// monitor the window was made fullscreen on, except on Wayland.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **macOS:** Also reflects the user entering or leaving fullscreen.
+    /// - **X11 / Wayland:** Doesn't reflect the window manager or compositor changing the
+    ///   fullscreen state by itself.
+    /// - **Android / iOS / Emscripten:** Always returns `None`.
+    #[inline]
+    pub fn get_current_fullscreen(&self) -> Option<Fullscreen> {
+        self.window.get_current_fullscreen()
     }
 
     /// Turn window decorations on or off.
@@ -353,12 +787,56 @@ impl Window {
         self.window.set_decorations(decorations)
     }
 
+    /// Returns whether the window has decorations or not.
+    ///
+    /// This reflects the value last passed to `set_decorations` (or
+    /// `WindowBuilder::with_decorations`), rather than querying the window manager.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Always returns `false` on Android, iOS and Emscripten.
+    #[inline]
+    pub fn is_decorated(&self) -> bool {
+        self.window.is_decorated()
+    }
+
     /// Change whether or not the window will always be on top of other windows.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland / iOS / Android / Emscripten:** Unsupported.
     #[inline]
-    pub fn set_always_on_top(&self, always_on_top: bool) {
+    pub fn set_always_on_top(&self, always_on_top: bool) -> Result<(), ExternalError> {
         self.window.set_always_on_top(always_on_top)
     }
 
+    /// Returns whether the window is always on top of other windows.
+    ///
+    /// Like `is_decorated`, this reflects the value last passed to `set_always_on_top` (or
+    /// `WindowBuilder::with_always_on_top`), rather than querying the window manager.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Always returns `false` on Wayland, Android, iOS and Emscripten.
+    #[inline]
+    pub fn is_always_on_top(&self) -> bool {
+        self.window.is_always_on_top()
+    }
+
+    /// Places the window directly above or below the window with the given ID.
+    ///
+    /// Unlike `set_always_on_top`, this only orders the window relative to `relative_to`, so it can be used to
+    /// keep a palette above its document window without also covering other applications.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** The window manager is asked to restack the window, and may not honor the request.
+    /// - **Wayland / iOS / Android / Emscripten:** Unsupported.
+    #[inline]
+    pub fn restack(&self, relative_to: WindowId, order: StackOrder) -> Result<(), ExternalError> {
+        self.window.restack(relative_to.0, order)
+    }
+
     /// Sets the window icon. On Windows and X11, this is typically the small icon in the top-left
     /// corner of the titlebar.
     ///
@@ -372,13 +850,157 @@ impl Window {
         self.window.set_window_icon(window_icon)
     }
 
+    /// Sets whether the contents of the window should be protected from being captured by other
+    /// applications, such as screenshots and screen recordings.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11 / Wayland / iOS / Android / Emscripten:** Unsupported. Nothing prevents other
+    ///   clients from capturing the window.
+    #[inline]
+    pub fn set_content_protected(&self, protected: bool) -> Result<(), ExternalError> {
+        self.window.set_content_protected(protected)
+    }
+
+    /// Sets the color the window is filled with before its contents are drawn, as
+    /// `(red, green, blue, alpha)`. `None` restores the default background.
+    ///
+    /// Setting this before showing the window avoids briefly displaying uninitialized or gray
+    /// content before the first frame is rendered. The alpha channel only has an effect if the
+    /// window is transparent. Unlike the window's contents, the color uses straight alpha; it's
+    /// premultiplied where the platform needs it.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** The alpha channel is ignored.
+    /// - **Wayland:** The window's contents are entirely up to the buffers attached to it, so the
+    ///   color is drawn into a buffer that's shown when the window gets its initial size, until
+    ///   the application draws into the window. Setting the color after the first
+    ///   `WindowEvent::RedrawRequested` has no effect.
+    /// - **Android / iOS / Emscripten:** Has no effect.
+    #[inline]
+    pub fn set_background_color(&self, color: Option<(u8, u8, u8, u8)>) {
+        self.window.set_background_color(color)
+    }
+
     /// Sets location of IME candidate box in client area coordinates relative to the top left.
+    ///
+    /// Returns `ExternalError::NotSupported` if the platform has no IME support, in which case
+    /// applications may want to show their own hint of where text is being composed.
+    ///
+    /// ## Platform-specific
+    ///
+    /// The IME is supported on Windows, macOS and X11.
     #[inline]
-    pub fn set_ime_spot(&self, position: LogicalPosition) {
+    pub fn set_ime_spot(&self, position: LogicalPosition) -> Result<(), ExternalError> {
         self.window.set_ime_spot(position)
     }
 
-    /// Returns the monitor on which the window currently resides
+    /// Sets whether the IME can be used to input text in this window. It's allowed by default.
+    ///
+    /// If a composition is in progress when the IME is disallowed, the text being composed is
+    /// committed and delivered as `ReceivedCharacter` events, so that it isn't lost. Use
+    /// `set_ime_allowed_discard` to drop it instead.
+    ///
+    /// Returns `ExternalError::NotSupported` if the platform has no IME support.
+    ///
+    /// ## Platform-specific
+    ///
+    /// The IME is supported on Windows, macOS and X11.
+    #[inline]
+    pub fn set_ime_allowed(&self, allowed: bool) -> Result<(), ExternalError> {
+        self.window.set_ime_allowed(allowed, true)
+    }
+
+    /// Disallows the IME in this window, discarding any composition in progress.
+    ///
+    /// See `set_ime_allowed` for more details.
+    #[inline]
+    pub fn set_ime_allowed_discard(&self) -> Result<(), ExternalError> {
+        self.window.set_ime_allowed(false, false)
+    }
+
+    /// Opens the system menu of the window (with entries such as move, minimize, maximize and
+    /// close) at the given position, in client area coordinates relative to the top left.
+    ///
+    /// This is meant for windows without decorations, which would typically call this when their
+    /// custom titlebar is right-clicked.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Requires a window manager that supports `_GTK_SHOW_WINDOW_MENU`, which most
+    ///   major ones do.
+    /// - **Wayland:** Unsupported, since the version of smithay-client-toolkit winit uses doesn't
+    ///   give access to `xdg_toplevel.show_window_menu`.
+    /// - **macOS / Android / iOS / Emscripten:** Unsupported.
+    #[inline]
+    pub fn show_window_menu(&self, position: LogicalPosition) -> Result<(), ExternalError> {
+        self.window.show_window_menu(position)
+    }
+
+    /// Starts moving the window with the pointer, as if its titlebar was being dragged, until the
+    /// mouse button is released.
+    ///
+    /// This is meant for windows without decorations, and should be called while a mouse button
+    /// is held, typically from the `MouseInput` event that pressed it. Nothing happens otherwise.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Requires a window manager that supports `_NET_WM_MOVERESIZE`.
+    /// - **Windows:** Only the left mouse button can be used.
+    /// - **macOS:** Requires macOS 10.11 or later, and does nothing on older versions.
+    /// - **Android / iOS / Emscripten:** Unsupported.
+    #[inline]
+    pub fn start_drag(&self) -> Result<(), ExternalError> {
+        self.window.start_drag()
+    }
+
+    /// Starts resizing the window with the pointer from the given edge or corner, as if its
+    /// border was being dragged, until the mouse button is released.
+    ///
+    /// Like `start_drag`, this should be called while a mouse button is held, and nothing
+    /// happens otherwise.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Requires a window manager that supports `_NET_WM_MOVERESIZE`.
+    /// - **Windows:** Only the left mouse button can be used.
+    /// - **macOS / Android / iOS / Emscripten:** Unsupported.
+    #[inline]
+    pub fn start_resize(&self, direction: ResizeDirection) -> Result<(), ExternalError> {
+        self.window.start_resize(direction)
+    }
+
+    /// Requests the user's attention, typically by flashing the window's taskbar entry. Passing
+    /// `None` cancels a previous request.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** `Critical` flashes the window until it's focused, and `Informational`
+    ///   flashes it once, even if the window is focused. See `WindowExt::flash_frame` for finer
+    ///   control.
+    /// - **macOS:** Has no effect while the application is active. Otherwise `Critical` bounces
+    ///   the dock icon until the application is activated, and `Informational` bounces it once.
+    /// - **X11:** Sets `_NET_WM_STATE_DEMANDS_ATTENTION`, even if the window is focused. There's
+    ///   no distinction between the two request types, so how attention is drawn, and whether the
+    ///   hint is cleared once the window is focused, is up to the window manager.
+    /// - **Wayland / Android / iOS / Emscripten:** Has no effect.
+    #[inline]
+    pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
+        self.window.request_user_attention(request_type)
+    }
+
+    /// Returns the monitor on which the window currently resides.
+    ///
+    /// This is based on the window's position, not the cursor's. If the window spans several
+    /// monitors, the one it overlaps the most is returned. Right after the window is built, this
+    /// is the monitor it was created on, whose properties should be used for the first frame.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** The compositor only tells which output the window is on once it's been
+    ///   drawn. Until then, the primary monitor is returned. If the window later spans several
+    ///   monitors, the one it entered last is returned.
     #[inline]
     pub fn get_current_monitor(&self) -> MonitorId {
         self.window.get_current_monitor()
@@ -405,6 +1027,57 @@ impl Window {
     pub fn id(&self) -> WindowId {
         WindowId(self.window.id())
     }
+
+    /// Attaches arbitrary data to the window, returning the data that was previously attached.
+    ///
+    /// This saves you from keeping a separate map from `WindowId` to your own per-window state.
+    /// Use `get_user_data` to retrieve it, and downcast it to its original type.
+    #[inline]
+    pub fn set_user_data(
+        &mut self,
+        data: Option<Box<Any + Send + Sync>>,
+    ) -> Option<Box<Any + Send + Sync>> {
+        ::std::mem::replace(&mut self.user_data, data)
+    }
+
+    /// Returns the data attached with `set_user_data`, if any.
+    ///
+    /// ```no_run
+    /// # use winit::{EventsLoop, Window};
+    /// # let events_loop = EventsLoop::new();
+    /// let mut window = Window::new(&events_loop).unwrap();
+    /// window.set_user_data(Some(Box::new(42u32)));
+    /// let data = window.get_user_data().and_then(|data| data.downcast_ref::<u32>());
+    /// assert_eq!(data, Some(&42));
+    /// ```
+    #[inline]
+    pub fn get_user_data(&self) -> Option<&Any> {
+        self.user_data.as_ref().map(|data| &**data as &Any)
+    }
+
+    /// Returns a mutable reference to the data attached with `set_user_data`, if any.
+    #[inline]
+    pub fn get_user_data_mut(&mut self) -> Option<&mut Any> {
+        self.user_data.as_mut().map(|data| &mut **data as &mut Any)
+    }
+}
+
+/// Gives graphics libraries the native handles of the window, without going through the
+/// platform-specific `WindowExt` traits.
+///
+/// ## Platform-specific
+///
+/// - **Linux / BSDs:** `RawWindowHandle::Xlib` or `RawWindowHandle::Wayland`, depending on the
+///   backend in use.
+/// - **Emscripten:** Not implemented, since there's no native handle for the canvas.
+///
+/// This requires the `raw-window-handle` feature.
+#[cfg(all(feature = "raw-window-handle", not(target_os = "emscripten")))]
+unsafe impl raw_window_handle::HasRawWindowHandle for Window {
+    #[inline]
+    fn raw_window_handle(&self) -> raw_window_handle::RawWindowHandle {
+        self.window.raw_window_handle()
+    }
 }
 
 /// An iterator for the list of available monitors.
@@ -438,12 +1111,36 @@ pub struct MonitorId {
 impl MonitorId {
     /// Returns a human-readable name of the monitor.
     ///
+    /// The name isn't necessarily unique if several identical monitors are connected, and isn't
+    /// guaranteed to stay the same when the monitor is reconnected.
+    ///
     /// Returns `None` if the monitor doesn't exist anymore.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** The name of the XRandR output, such as `HDMI-1`.
+    /// - **Wayland:** The model and make advertised by the `wl_output`.
+    /// - **Windows:** The name of the monitor, such as `DELL U2415`, or the name of the display
+    ///   device, such as `\\.\DISPLAY1`, if the driver doesn't provide one.
+    /// - **macOS:** The name shown in System Preferences on 10.15 and later. On older versions, it's
+    ///   based on the model number of the display.
     #[inline]
     pub fn get_name(&self) -> Option<String> {
         self.inner.get_name()
     }
 
+    /// Returns the refresh rate of the monitor's current video mode, in Hz.
+    ///
+    /// Returns `None` if the refresh rate is unknown, or if the current video mode couldn't be
+    /// determined. See `get_current_video_mode` for details.
+    #[inline]
+    pub fn get_refresh_rate(&self) -> Option<u16> {
+        self.get_current_video_mode().and_then(|mode| match mode.get_refresh_rate() {
+            0 => None,
+            refresh_rate => Some(refresh_rate),
+        })
+    }
+
     /// Returns the monitor's resolution.
     #[inline]
     pub fn get_dimensions(&self) -> PhysicalSize {
@@ -469,4 +1166,230 @@ impl MonitorId {
     pub fn get_hidpi_factor(&self) -> f64 {
         self.inner.get_hidpi_factor()
     }
+
+    /// Returns the number of physical pixels per inch of the monitor, which is useful for sizing text in points.
+    ///
+    /// This is the actual density of the screen, and unlike the DPI factor, isn't rounded to a value chosen by the
+    /// user. The DPI factor is roughly this divided by 96.
+    ///
+    /// Returns `None` if the physical size of the monitor is unknown.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Only supported on Windows 8.1 and later.
+    /// - **iOS / Android / Emscripten:** Always returns `None`.
+    #[inline]
+    pub fn get_dpi(&self) -> Option<f64> {
+        self.inner.get_dpi()
+    }
+
+    /// Returns whether this is the primary monitor of the system.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** There's no concept of a primary monitor, so the first monitor advertised by
+    ///   the compositor is considered primary, the same as with `get_primary_monitor`.
+    /// - **X11:** If no monitor is marked as primary by XRandR, the first one is considered primary.
+    /// - **Android / iOS / Emscripten:** Always returns `true`.
+    #[inline]
+    pub fn is_primary(&self) -> bool {
+        self.inner.is_primary()
+    }
+
+    /// Returns the video mode the monitor is currently using, which is the desktop's mode unless
+    /// a fullscreen application changed it.
+    ///
+    /// Returns `None` if the mode couldn't be determined, for instance if the monitor was
+    /// disconnected.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** The mode is read when the monitor list is queried, and is refreshed whenever
+    ///   XRandR reports a change.
+    /// - **Wayland:** The bit depth isn't exposed by the protocol, so it's always reported as 32.
+    /// - **Android / iOS / Emscripten:** Always returns `None`.
+    #[inline]
+    pub fn get_current_video_mode(&self) -> Option<VideoMode> {
+        self.inner.get_current_video_mode()
+    }
+
+    /// Returns the video modes supported by the monitor, which can be used with
+    /// `Fullscreen::Exclusive`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** The modes are read when the monitor list is queried. They have the monitor's
+    ///   current rotation applied, so their dimensions are comparable with `get_dimensions`.
+    /// - **Wayland:** The bit depth isn't exposed by the protocol, so it's always reported as 32.
+    /// - **macOS:** Every mode is reported with the current bit depth of the display.
+    /// - **Android / iOS / Emscripten:** Always empty.
+    #[inline]
+    pub fn get_video_modes(&self) -> VideoModesIter {
+        VideoModesIter { data: self.inner.get_video_modes().into_iter() }
+    }
+
+    /// Returns how the monitor is rotated, for instance when a tablet is turned on its side.
+    ///
+    /// `Landscape` is returned if the platform doesn't report the rotation.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Flipped (mirrored) transforms are reported as their rotation alone.
+    /// - **Android / iOS / Emscripten:** Always returns `Landscape`.
+    #[inline]
+    pub fn get_orientation(&self) -> Orientation {
+        self.inner.get_orientation()
+    }
+}
+
+/// An iterator over the video modes supported by a monitor.
+#[derive(Debug)]
+pub struct VideoModesIter {
+    data: VecIntoIter<VideoMode>,
+}
+
+impl Iterator for VideoModesIter {
+    type Item = VideoMode;
+
+    #[inline]
+    fn next(&mut self) -> Option<VideoMode> {
+        self.data.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.data.size_hint()
+    }
+}
+
+/// Describes how a window is made fullscreen.
+#[derive(Debug, Clone)]
+pub enum Fullscreen {
+    /// The window covers the monitor without changing its video mode.
+    ///
+    /// With `None`, the window covers the monitor it's currently on, as returned by
+    /// `Window::get_current_monitor`. For a window that is being created, that's the monitor the
+    /// system places it on, which is typically the primary monitor or the one containing the
+    /// cursor.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** With `None`, the compositor chooses the output.
+    Borderless(Option<MonitorId>),
+
+    /// The monitor is switched to the given video mode for as long as the window is fullscreen.
+    /// The mode should be one of those returned by `MonitorId::get_video_modes`.
+    ///
+    /// Switching the mode resizes the window, which is reported with `WindowEvent::Resized`, and
+    /// with `WindowEvent::HiDpiFactorChanged` if the DPI factor of the monitor changed along with
+    /// its resolution. `Window::get_current_fullscreen` tells whether the mode was switched.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** The mode is switched using XRandR, and the original mode is restored when the
+    ///   window leaves fullscreen or is dropped. If the switch fails, the window is still made
+    ///   fullscreen on the monitor.
+    /// - **Windows / macOS / Wayland:** Unsupported, so `Window::set_fullscreen` returns
+    ///   `ExternalError::NotSupported` and `WindowBuilder::build` returns
+    ///   `CreationError::NotSupported`. Wayland doesn't let clients change the video mode at all.
+    /// - **Android / iOS / Emscripten:** Has no effect, like `Borderless`.
+    Exclusive(MonitorId, VideoMode),
+}
+
+impl Fullscreen {
+    /// Returns the monitor the window is made fullscreen on, or `None` for
+    /// `Fullscreen::Borderless(None)`.
+    #[inline]
+    pub fn get_monitor(&self) -> Option<&MonitorId> {
+        match *self {
+            Fullscreen::Borderless(ref monitor) => monitor.as_ref(),
+            Fullscreen::Exclusive(ref monitor, _) => Some(monitor),
+        }
+    }
+}
+
+/// Describes a video mode of a monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VideoMode {
+    pub(crate) dimensions: (u32, u32),
+    pub(crate) bit_depth: u16,
+    pub(crate) refresh_rate: u16,
+}
+
+impl VideoMode {
+    /// Returns the resolution of this video mode.
+    #[inline]
+    pub fn get_dimensions(&self) -> PhysicalSize {
+        self.dimensions.into()
+    }
+
+    /// Returns the bit depth of this video mode, as in how many bits are used to represent a
+    /// single pixel. This is typically 24 or 32, and can be used to filter out legacy low-depth
+    /// modes.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** The bits per pixel of the mode, as reported by the display driver.
+    /// - **macOS:** The bits per pixel of the display.
+    /// - **X11:** The depth of the X screen, which is shared by every mode and doesn't count
+    ///   padding bits, so this is 24 for a 32-bit framebuffer.
+    /// - **Wayland:** The bit depth isn't exposed by the protocol, so it's always 32.
+    #[inline]
+    pub fn get_bit_depth(&self) -> u16 {
+        self.bit_depth
+    }
+
+    /// Returns the refresh rate of this video mode, in Hz.
+    ///
+    /// This is `0` if the refresh rate is unknown, which can happen with some built-in displays.
+    #[inline]
+    pub fn get_refresh_rate(&self) -> u16 {
+        self.refresh_rate
+    }
+}
+
+/// Describes how a monitor is rotated from its natural orientation.
+///
+/// Monitors are assumed to be landscape in their natural orientation, so a portrait panel that
+/// isn't rotated is reported as `Landscape`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    /// Not rotated.
+    Landscape,
+    /// Rotated by 90 degrees clockwise.
+    Portrait,
+    /// Rotated by 180 degrees.
+    LandscapeFlipped,
+    /// Rotated by 270 degrees clockwise, or 90 degrees counter-clockwise.
+    PortraitFlipped,
+}
+
+impl Default for Orientation {
+    #[inline]
+    fn default() -> Self {
+        Orientation::Landscape
+    }
+}
+
+// The cursors the user can set on a window, which `set_wait_cursor(false)` brings back.
+#[derive(Debug, Clone)]
+pub(crate) enum UserCursor {
+    Standard(MouseCursor),
+    Image(Icon, (u32, u32)),
+    Animated(Vec<(Icon, Duration)>, (u32, u32)),
+    // Set with `WindowExt::set_cursor_by_name`.
+    #[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd"))]
+    Named(String),
+}
+
+// Raises `max` to `min` in the dimensions where it's smaller, so the backends are never given
+// contradictory limits.
+fn clamp_max_dimensions(min: Option<LogicalSize>, max: Option<LogicalSize>) -> Option<LogicalSize> {
+    match (min, max) {
+        (Some(min), Some(max)) => Some(LogicalSize::new(
+            max.width.max(min.width),
+            max.height.max(min.height),
+        )),
+        (_, max) => max,
+    }
 }
@@ -0,0 +1,70 @@
+#![cfg(feature = "testing")]
+
+extern crate winit;
+
+use winit::{ControlFlow, Event, EventsLoop, WindowEvent, WindowId};
+use winit::dpi::LogicalSize;
+
+#[test]
+fn injected_events_are_delivered() {
+    let mut events_loop = EventsLoop::new_headless();
+    let window_id = unsafe { WindowId::dummy() };
+    events_loop.inject_event(Event::WindowEvent { window_id, event: WindowEvent::Focused(true) });
+    events_loop.inject_event(Event::WindowEvent { window_id, event: WindowEvent::CloseRequested });
+
+    let mut events = Vec::new();
+    events_loop.poll_events(|event| events.push(event));
+    assert_eq!(events.len(), 2);
+    match events[0] {
+        Event::WindowEvent { window_id: id, event: WindowEvent::Focused(focused) } => {
+            assert_eq!(id, window_id);
+            assert!(focused);
+        },
+        ref event => panic!("unexpected event: {:?}", event),
+    }
+    match events[1] {
+        Event::WindowEvent { window_id: id, event: WindowEvent::CloseRequested } => assert_eq!(id, window_id),
+        ref event => panic!("unexpected event: {:?}", event),
+    }
+}
+
+#[test]
+fn run_forever_returns_once_events_are_delivered() {
+    let mut events_loop = EventsLoop::new_headless();
+    events_loop.inject_event(Event::Suspended(false));
+    events_loop.inject_event(Event::Suspended(true));
+
+    let mut suspended = Vec::new();
+    let exit_code = events_loop.run_forever(|event| {
+        match event {
+            Event::Suspended(value) => suspended.push(value),
+            event => panic!("unexpected event: {:?}", event),
+        }
+        ControlFlow::Continue
+    });
+    assert_eq!(exit_code, 0);
+    assert_eq!(suspended, vec![false, true]);
+}
+
+#[test]
+fn run_forever_reports_held_resizes_before_returning() {
+    let mut events_loop = EventsLoop::new_headless();
+    events_loop.set_resize_debounce(4.0);
+    let window_id = unsafe { WindowId::dummy() };
+    let resized = |width, height| {
+        Event::WindowEvent { window_id, event: WindowEvent::Resized(LogicalSize::new(width, height)) }
+    };
+    events_loop.inject_event(resized(800.0, 600.0));
+    // Too small to be reported right away.
+    events_loop.inject_event(resized(802.0, 600.0));
+
+    let mut sizes = Vec::new();
+    events_loop.run_forever(|event| {
+        match event {
+            Event::WindowEvent { event: WindowEvent::Resized(size), .. } => sizes.push(size),
+            event => panic!("unexpected event: {:?}", event),
+        }
+        ControlFlow::Continue
+    });
+    assert_eq!(sizes, vec![LogicalSize::new(800.0, 600.0), LogicalSize::new(802.0, 600.0)]);
+}
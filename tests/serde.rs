@@ -0,0 +1,46 @@
+#![cfg(feature = "serde")]
+
+extern crate serde_json;
+extern crate winit;
+
+use winit::{
+    ElementState,
+    Event,
+    KeyLocation,
+    KeyboardInput,
+    LogicalKey,
+    ModifiersState,
+    VirtualKeyCode,
+    WindowEvent,
+    WindowId,
+};
+
+#[test]
+fn keyboard_input_round_trips() {
+    let input = KeyboardInput {
+        scancode: 39,
+        state: ElementState::Pressed,
+        physical_key: Some(VirtualKeyCode::Semicolon),
+        logical_key: Some(LogicalKey::Character("é".to_owned())),
+        virtual_keycode: None,
+        location: KeyLocation::Standard,
+        modifiers: ModifiersState { shift: true, ..Default::default() },
+    };
+    let json = serde_json::to_string(&input).unwrap();
+    let output: KeyboardInput = serde_json::from_str(&json).unwrap();
+    assert_eq!(output.scancode, input.scancode);
+    assert_eq!(output.state, input.state);
+    assert_eq!(output.physical_key, input.physical_key);
+    assert_eq!(output.logical_key, input.logical_key);
+    assert_eq!(output.virtual_keycode, input.virtual_keycode);
+    assert_eq!(output.location, input.location);
+    assert_eq!(output.modifiers, input.modifiers);
+}
+
+#[test]
+fn events_serialize() {
+    let window_id = unsafe { WindowId::dummy() };
+    let event: Event = Event::WindowEvent { window_id, event: WindowEvent::Focused(true) };
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(json["WindowEvent"]["event"]["Focused"], serde_json::Value::Bool(true));
+}